@@ -1,7 +1,9 @@
+use crate::serve;
 use clap::{Parser, Subcommand};
 use rnote_engine::engine::export::{DocExportFormat, DocExportPrefs};
-use rnote_engine::engine::EngineSnapshot;
-use rnote_engine::RnoteEngine;
+use rnote_engine::engine::{EngineSnapshot, ReplayExportPrefs, SearchOptions};
+use rnote_engine::strokes::Stroke;
+use rnote_engine::{RnoteEngine, WidgetFlags};
 use smol::fs::File;
 use smol::io::{AsyncReadExt, AsyncWriteExt};
 use std::path::{Path, PathBuf};
@@ -24,14 +26,14 @@ pub(crate) enum Commands {
         rnote_files: Vec<PathBuf>,
     },
     /// Imports the specified input file and saves it as a rnote save file.{n}
-    /// Currently only `.xopp` files can be imported.
+    /// Currently `.xopp`, legacy Xournal `.xoj`, `.inkml` and `.excalidraw` files can be imported.
     Import {
         /// the rnote save file
         rnote_file: PathBuf,
         /// the import input file
         #[arg(short = 'i', long)]
         input_file: PathBuf,
-        /// When importing a .xopp file, the import dpi can be specified.{n}
+        /// When importing a .xopp or .xoj file, the import dpi can be specified.{n}
         /// Else the default (96) is used.
         #[arg(long)]
         xopp_dpi: Option<f64>,
@@ -41,10 +43,11 @@ pub(crate) enum Commands {
     /// The export format is recognized from the file extension of the output file.{n}
     /// When using --output-format, the same file name is used with the extension changed.{n}
     /// --output-file and --output-format are mutually exclusive but one of them is required.{n}
-    /// Currently `.svg`, `.xopp` and `.pdf` are supported.{n}
+    /// Currently `.svg`, `.xopp`, `.pdf`, `.png`, `.jpg`, `.inkml` and `.excalidraw` are supported.{n}
+    /// The output format `annotated-pdf` writes the strokes as native annotations into the Pdf that is embedded with the pages.{n}
     /// Usages: {n}
-    /// rnote-cli export --output-file [filename.(svg|xopp|pdf)] [1 file]{n}
-    /// rnote-cli export --output-format [svg|xopp|pdf] [list of files]
+    /// rnote-cli export --output-file [filename.(svg|xopp|pdf|png|jpg|inkml|excalidraw)] [1 file]{n}
+    /// rnote-cli export --output-format [svg|xopp|pdf|annotated-pdf|png|jpg|inkml|excalidraw] [list of files]
     Export {
         /// the rnote save file
         rnote_files: Vec<PathBuf>,
@@ -60,6 +63,109 @@ pub(crate) enum Commands {
         /// export with background pattern
         #[arg(short = 'p', long)]
         with_pattern: Option<bool>,
+        /// the bitmap scale-factor in relation to the actual size, when exporting as `.png` or `.jpg`.
+        #[arg(long)]
+        bitmap_scalefactor: Option<f64>,
+        /// the quality (1 - 100), when exporting as `.jpg`.
+        #[arg(long)]
+        jpeg_quality: Option<u8>,
+    },
+    /// Searches the text of the specified Rnote files and prints the lines that match.{n}
+    /// Typed text and the text of imported Pdf pages are searched.{n}
+    /// The search is case-insensitive by default.{n}
+    /// Usage: rnote-cli search [query] [list of files]
+    Search {
+        /// the search query
+        query: String,
+        /// the rnote save files
+        rnote_files: Vec<PathBuf>,
+        /// search case-sensitive
+        #[arg(short = 'c', long)]
+        case_sensitive: bool,
+        /// interpret the query as a regular expression
+        #[arg(short = 'r', long)]
+        regex: bool,
+    },
+    /// Modifies the pages of the specified Rnote file, which must be in the fixed-size layout.{n}
+    /// The pages are numbered starting from 1.{n}
+    /// Usage: rnote-cli pages [count|insert|delete|duplicate|move] [rnote file] [options]
+    Pages {
+        #[command(subcommand)]
+        command: PagesCommands,
+    },
+    /// Exports a replay of the specified Rnote file, revealing the strokes in the order they were drawn.{n}
+    /// The export format is recognized from the file extension of the output file.{n}
+    /// `.svg` exports an animated Svg, `.png` exports a sequence of frames{n}
+    /// that are saved next to the output file as `[filename]-[frame number].png`.{n}
+    /// Usage: rnote-cli replay --output-file [filename.(svg|png)] [rnote file]
+    Replay {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the export output file
+        #[arg(short = 'o', long)]
+        output_file: PathBuf,
+        /// the replay speed in steps per second. Every stroke is one step, brush strokes take one step per segment.
+        #[arg(short = 's', long)]
+        speed: Option<f64>,
+        /// the frames per second, when exporting as `.png` frames.
+        #[arg(long)]
+        fps: Option<f64>,
+        /// export with background
+        #[arg(short = 'b', long)]
+        with_background: Option<bool>,
+        /// export with background pattern
+        #[arg(short = 'p', long)]
+        with_pattern: Option<bool>,
+        /// the bitmap scale-factor in relation to the actual size, when exporting as `.png` frames.
+        #[arg(long)]
+        bitmap_scalefactor: Option<f64>,
+    },
+    /// Serves JSON-RPC 2.0 requests on stdin against a single document and writes the responses to stdout.{n}
+    /// Every request and response is on its own line. The server exits when stdin is closed.{n}
+    /// Methods: `open`, `save`, `export`, `insert_text`, `insert_stroke_content`, `change_pen_style`,{n}
+    /// `handle_pen_events`, `bounds`, `undo`, `redo`.{n}
+    /// Usage: rnote-cli serve
+    Serve,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum PagesCommands {
+    /// Prints the number of pages.
+    Count {
+        /// the rnote save file
+        rnote_file: PathBuf,
+    },
+    /// Inserts a blank page after the given page. Use 0 to insert it before the first page.
+    Insert {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the page after which the blank page is inserted
+        #[arg(short = 'a', long)]
+        after: u32,
+    },
+    /// Deletes the given page together with its strokes.
+    Delete {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the page to delete
+        page: u32,
+    },
+    /// Duplicates the given page, inserting the copy after it.
+    Duplicate {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the page to duplicate
+        page: u32,
+    },
+    /// Moves the given page to a new position.
+    Move {
+        /// the rnote save file
+        rnote_file: PathBuf,
+        /// the page to move
+        page: u32,
+        /// the new position of the page
+        #[arg(short = 't', long)]
+        to: u32,
     },
 }
 
@@ -145,6 +251,8 @@ pub(crate) async fn run() -> anyhow::Result<()> {
             output_format,
             with_background,
             with_pattern,
+            bitmap_scalefactor,
+            jpeg_quality,
         } => {
             println!("Exporting..");
 
@@ -154,6 +262,8 @@ pub(crate) async fn run() -> anyhow::Result<()> {
                 output_format.as_deref(),
                 with_background,
                 with_pattern,
+                bitmap_scalefactor,
+                jpeg_quality,
             )?;
 
             match output_file {
@@ -241,6 +351,135 @@ pub(crate) async fn run() -> anyhow::Result<()> {
 
             println!("Export Finished!");
         }
+        Commands::Search {
+            query,
+            rnote_files,
+            case_sensitive,
+            regex,
+        } => {
+            let search_options = SearchOptions {
+                case_sensitive,
+                regex,
+            };
+            let mut n_matches = 0;
+
+            for rnote_file in rnote_files.iter() {
+                let rnote_file_disp = rnote_file.display().to_string();
+
+                match search_file(&mut engine, rnote_file, &query, search_options).await {
+                    Ok(matched_lines) => {
+                        for line in matched_lines.iter() {
+                            println!("{rnote_file_disp}: {line}");
+                        }
+                        n_matches += matched_lines.len();
+                    }
+                    Err(e) => {
+                        eprintln!("Searching \"{rnote_file_disp}\" failed, Err: {e:?}");
+                    }
+                }
+            }
+
+            println!("Search finished, found {n_matches} matches.");
+        }
+        Commands::Pages { command } => match command {
+            PagesCommands::Count { rnote_file } => {
+                load_file(&mut engine, &rnote_file).await?;
+                println!("{}", engine.doc_n_pages_fixed_size());
+            }
+            PagesCommands::Insert { rnote_file, after } => {
+                modify_pages_of_file(&mut engine, &rnote_file, |engine| {
+                    engine.doc_insert_page_fixed_size(after)
+                })
+                .await?;
+                println!("Inserted a page after page {after}.");
+            }
+            PagesCommands::Delete { rnote_file, page } => {
+                let index = page_index(page)?;
+                modify_pages_of_file(&mut engine, &rnote_file, |engine| {
+                    engine.doc_delete_page_fixed_size(index)
+                })
+                .await?;
+                println!("Deleted page {page}.");
+            }
+            PagesCommands::Duplicate { rnote_file, page } => {
+                let index = page_index(page)?;
+                modify_pages_of_file(&mut engine, &rnote_file, |engine| {
+                    engine.doc_duplicate_page_fixed_size(index)
+                })
+                .await?;
+                println!("Duplicated page {page}.");
+            }
+            PagesCommands::Move {
+                rnote_file,
+                page,
+                to,
+            } => {
+                let (index, to_index) = (page_index(page)?, page_index(to)?);
+                modify_pages_of_file(&mut engine, &rnote_file, |engine| {
+                    engine.doc_move_page_fixed_size(index, to_index)
+                })
+                .await?;
+                println!("Moved page {page} to {to}.");
+            }
+        },
+        Commands::Replay {
+            rnote_file,
+            output_file,
+            speed,
+            fps,
+            with_background,
+            with_pattern,
+            bitmap_scalefactor,
+        } => {
+            let mut replay_export_prefs = ReplayExportPrefs::default();
+            if let Some(speed) = speed {
+                replay_export_prefs.speed = speed;
+            }
+            if let Some(fps) = fps {
+                replay_export_prefs.fps = fps;
+            }
+            if let Some(with_background) = with_background {
+                replay_export_prefs.with_background = with_background;
+            }
+            if let Some(with_pattern) = with_pattern {
+                replay_export_prefs.with_pattern = with_pattern;
+            }
+            if let Some(bitmap_scalefactor) = bitmap_scalefactor {
+                replay_export_prefs.bitmap_scalefactor = bitmap_scalefactor;
+            }
+
+            let rnote_file_disp = rnote_file.display().to_string();
+            let output_file_disp = output_file.display().to_string();
+            let pb = indicatif::ProgressBar::new_spinner().with_message(format!(
+                "Exporting replay of \"{rnote_file_disp}\" to: \"{output_file_disp}\""
+            ));
+            pb.set_draw_target(indicatif::ProgressDrawTarget::stdout());
+            pb.enable_steady_tick(Duration::from_millis(8));
+
+            // export
+            if let Err(e) =
+                export_replay_to_file(&mut engine, &rnote_file, &output_file, replay_export_prefs)
+                    .await
+            {
+                let msg = format!("Exporting replay of \"{rnote_file_disp}\" to: \"{output_file_disp}\" failed, Err {e:?}");
+                if pb.is_hidden() {
+                    println!("{msg}")
+                }
+                pb.abandon_with_message(msg);
+                return Err(e);
+            } else {
+                let msg = format!(
+                    "Exporting replay of \"{rnote_file_disp}\" to: \"{output_file_disp}\" succeeded"
+                );
+                if pb.is_hidden() {
+                    println!("{msg}")
+                }
+                pb.finish_with_message(msg);
+            }
+        }
+        Commands::Serve => {
+            serve::run(&mut engine).await?;
+        }
     }
 
     Ok(())
@@ -271,14 +510,33 @@ pub(crate) async fn import_file(
         return Err(anyhow::anyhow!("Failed to get filename from rnote_file"));
     };
 
-    let mut ifh = File::open(input_file).await?;
+    let mut ifh = File::open(&input_file).await?;
     ifh.read_to_end(&mut input_bytes).await?;
 
-    let snapshot =
-        EngineSnapshot::load_from_xopp_bytes(input_bytes, engine.import_prefs.xopp_import_prefs)
+    match input_file.extension().and_then(|ext| ext.to_str()) {
+        Some("inkml") => {
+            let strokes = engine
+                .generate_inkml_strokes_from_bytes(Stroke::IMPORT_OFFSET_DEFAULT, input_bytes)
+                .await??;
+            let _ = engine.import_generated_strokes(strokes);
+        }
+        Some("excalidraw") => {
+            let strokes = engine
+                .generate_excalidraw_strokes_from_bytes(Stroke::IMPORT_OFFSET_DEFAULT, input_bytes)
+                .await??;
+            let _ = engine.import_generated_strokes(strokes);
+        }
+        _ => {
+            let snapshot = EngineSnapshot::load_from_xopp_bytes(
+                input_bytes,
+                engine.import_prefs.xopp_import_prefs,
+                Some(input_file),
+            )
             .await?;
 
-    let _ = engine.load_snapshot(snapshot);
+            let _ = engine.load_snapshot(snapshot);
+        }
+    }
 
     let rnote_bytes = engine.save_as_rnote_bytes(rnote_file_name).await??;
 
@@ -294,6 +552,11 @@ fn get_export_format(format: &str) -> anyhow::Result<DocExportFormat> {
         "svg" => Ok(DocExportFormat::Svg),
         "xopp" => Ok(DocExportFormat::Xopp),
         "pdf" => Ok(DocExportFormat::Pdf),
+        "png" => Ok(DocExportFormat::Png),
+        "jpg" | "jpeg" => Ok(DocExportFormat::Jpeg),
+        "inkml" => Ok(DocExportFormat::Inkml),
+        "excalidraw" => Ok(DocExportFormat::Excalidraw),
+        "annotated-pdf" => Ok(DocExportFormat::AnnotatedPdf),
         ext => Err(anyhow::anyhow!(
             "Could not create doc export prefs, unsupported export file extension `{ext}`"
         )),
@@ -305,6 +568,8 @@ pub(crate) fn create_doc_export_prefs_from_args(
     output_format: Option<&str>,
     with_background: Option<bool>,
     with_pattern: Option<bool>,
+    bitmap_scalefactor: Option<f64>,
+    jpeg_quality: Option<u8>,
 ) -> anyhow::Result<DocExportPrefs> {
     let format = match (output_file, output_format) {
         (Some(file), None) => match file.as_ref().extension().and_then(|ext| ext.to_str()) {
@@ -341,6 +606,12 @@ pub(crate) fn create_doc_export_prefs_from_args(
     if let Some(with_pattern) = with_pattern {
         prefs.with_pattern = with_pattern;
     }
+    if let Some(bitmap_scalefactor) = bitmap_scalefactor {
+        prefs.bitmap_scalefactor = bitmap_scalefactor;
+    }
+    if let Some(jpeg_quality) = jpeg_quality {
+        prefs.jpeg_quality = jpeg_quality.clamp(1, 100);
+    }
 
     Ok(prefs)
 }
@@ -372,3 +643,138 @@ pub(crate) async fn export_to_file(
 
     Ok(())
 }
+
+/// Searches the text of the rnote file and returns the lines that contain matches.
+pub(crate) async fn search_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    query: &str,
+    search_options: SearchOptions,
+) -> anyhow::Result<Vec<String>> {
+    load_file(engine, rnote_file).await?;
+
+    let matched_lines = engine
+        .find_matches(query, search_options)?
+        .into_iter()
+        .filter_map(|search_match| {
+            let text = engine
+                .store
+                .get_stroke_ref(search_match.key)?
+                .text_content()?;
+
+            Some(
+                search_match
+                    .ranges
+                    .into_iter()
+                    .map(|range| {
+                        let line_start =
+                            text[..range.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+                        let line_end = text[range.end..]
+                            .find('\n')
+                            .map(|i| range.end + i)
+                            .unwrap_or(text.len());
+                        text[line_start..line_end].trim().to_string()
+                    })
+                    .collect::<Vec<String>>(),
+            )
+        })
+        .flatten()
+        .collect();
+
+    Ok(matched_lines)
+}
+
+/// Converts the page number starting from 1 to the page index starting from 0.
+fn page_index(page: u32) -> anyhow::Result<u32> {
+    page.checked_sub(1)
+        .ok_or_else(|| anyhow::anyhow!("Page numbers start from 1"))
+}
+
+/// Loads the rnote file into the engine.
+pub(crate) async fn load_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+) -> anyhow::Result<()> {
+    let mut rnote_bytes = vec![];
+    File::open(rnote_file)
+        .await?
+        .read_to_end(&mut rnote_bytes)
+        .await?;
+
+    let engine_snapshot = EngineSnapshot::load_from_rnote_bytes(rnote_bytes).await?;
+    let _ = engine.load_snapshot(engine_snapshot);
+
+    Ok(())
+}
+
+/// Loads the rnote file, modifies its pages and saves it.
+pub(crate) async fn modify_pages_of_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    modify: impl FnOnce(&mut RnoteEngine) -> anyhow::Result<WidgetFlags>,
+) -> anyhow::Result<()> {
+    let Some(rnote_file_name) = rnote_file.as_ref().file_name().map(|s| s.to_string_lossy().to_string()) else {
+        return Err(anyhow::anyhow!("Failed to get filename from rnote_file"));
+    };
+
+    load_file(engine, &rnote_file).await?;
+    let _ = modify(engine)?;
+
+    let rnote_bytes = engine.save_as_rnote_bytes(rnote_file_name).await??;
+
+    let mut ofh = File::create(rnote_file).await?;
+    ofh.write_all(&rnote_bytes).await?;
+    ofh.sync_all().await?;
+
+    Ok(())
+}
+
+/// Loads the rnote file and exports its replay, as animated Svg or as Png frames depending on the output file extension.
+pub(crate) async fn export_replay_to_file(
+    engine: &mut RnoteEngine,
+    rnote_file: impl AsRef<Path>,
+    output_file: impl AsRef<Path>,
+    replay_export_prefs: ReplayExportPrefs,
+) -> anyhow::Result<()> {
+    let output_file = output_file.as_ref();
+
+    match output_file.extension().and_then(|ext| ext.to_str()) {
+        Some("svg") => {
+            load_file(engine, rnote_file).await?;
+            let svg_bytes = engine
+                .export_replay_as_animated_svg(replay_export_prefs)
+                .await??;
+
+            let mut fh = File::create(output_file).await?;
+            fh.write_all(&svg_bytes).await?;
+            fh.sync_all().await?;
+        }
+        Some("png") => {
+            let Some(file_stem) = output_file.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+                return Err(anyhow::anyhow!("Failed to get file stem from output_file"));
+            };
+
+            load_file(engine, rnote_file).await?;
+            let output_file = output_file.to_path_buf();
+            engine
+                .export_replay_as_png_frames(replay_export_prefs, move |i, frame_bytes| {
+                    let frame_file = output_file.with_file_name(format!("{file_stem}-{i:04}.png"));
+                    std::fs::write(frame_file, frame_bytes)?;
+                    Ok(())
+                })
+                .await??;
+        }
+        Some(ext) => {
+            return Err(anyhow::anyhow!(
+                "Exporting the replay failed, unsupported export file extension `{ext}`"
+            ))
+        }
+        None => {
+            return Err(anyhow::anyhow!(
+                "Output file needs to have an extension to determine the file type"
+            ))
+        }
+    }
+
+    Ok(())
+}
@@ -3,6 +3,11 @@
 //! The cli interface is not (yet) stable and could change at any time.
 
 pub(crate) mod cli;
+pub(crate) mod serve;
+
+// Renames
+extern crate nalgebra as na;
+extern crate parry2d_f64 as p2d;
 
 fn main() -> anyhow::Result<()> {
     smol::block_on(async { cli::run().await })
@@ -0,0 +1,452 @@
+// Imports
+use crate::cli;
+use p2d::bounding_volume::Aabb;
+use rnote_compose::penevents::PenEvent;
+use rnote_engine::engine::{EngineTaskReceiver, StrokeContent};
+use rnote_engine::pens::{PenMode, PenStyle};
+use rnote_engine::RnoteEngine;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use smol::fs::File;
+use smol::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// The JSON-RPC error code when the request is not valid JSON.
+const PARSE_ERROR: i64 = -32700;
+/// The JSON-RPC error code when the request is not a valid request object.
+const INVALID_REQUEST: i64 = -32600;
+/// The JSON-RPC error code when the requested method does not exist.
+const METHOD_NOT_FOUND: i64 = -32601;
+/// The JSON-RPC error code when the method parameters are invalid.
+const INVALID_PARAMS: i64 = -32602;
+/// The JSON-RPC error code when the engine failed to execute the method.
+const ENGINE_ERROR: i64 = -32000;
+
+/// A JSON-RPC 2.0 request.
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(rename = "jsonrpc")]
+    jsonrpc: String,
+    /// Notifications have no id and don't get a response.
+    #[serde(rename = "id", default)]
+    id: Option<Value>,
+    #[serde(rename = "method")]
+    method: String,
+    #[serde(rename = "params", default)]
+    params: Value,
+}
+
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        Self::new(ENGINE_ERROR, format!("{e:?}"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileParams {
+    #[serde(rename = "file")]
+    file: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    #[serde(rename = "file")]
+    file: PathBuf,
+    /// Overrides the export format that is otherwise recognized from the file extension.
+    #[serde(rename = "format", default)]
+    format: Option<String>,
+    #[serde(rename = "with_background", default)]
+    with_background: Option<bool>,
+    #[serde(rename = "with_pattern", default)]
+    with_pattern: Option<bool>,
+    #[serde(rename = "bitmap_scalefactor", default)]
+    bitmap_scalefactor: Option<f64>,
+    #[serde(rename = "jpeg_quality", default)]
+    jpeg_quality: Option<u8>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InsertTextParams {
+    #[serde(rename = "text")]
+    text: String,
+    #[serde(rename = "pos")]
+    pos: na::Vector2<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InsertStrokeContentParams {
+    #[serde(rename = "content")]
+    content: StrokeContent,
+    #[serde(rename = "pos")]
+    pos: na::Vector2<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChangePenStyleParams {
+    #[serde(rename = "style")]
+    style: PenStyle,
+}
+
+#[derive(Debug, Deserialize)]
+struct PenEventsParams {
+    #[serde(rename = "events")]
+    events: Vec<PenEvent>,
+    #[serde(rename = "pen_mode", default)]
+    pen_mode: Option<PenMode>,
+}
+
+/// Serves JSON-RPC 2.0 requests from stdin against a single engine, until stdin is closed.
+///
+/// Every line is expected to contain exactly one request. The responses are written to stdout, one per line.
+pub(crate) async fn run(engine: &mut RnoteEngine) -> anyhow::Result<()> {
+    let mut stdin = BufReader::new(smol::Unblock::new(std::io::stdin()));
+    let mut stdout = smol::Unblock::new(std::io::stdout());
+    let mut line = String::new();
+    let Some(mut tasks_rx) = engine.tasks_rx.take() else {
+        return Err(anyhow::anyhow!(
+            "Taking the engine tasks receiver failed, it was already taken"
+        ));
+    };
+
+    loop {
+        line.clear();
+        if stdin.read_line(&mut line).await? == 0 {
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = handle_line(engine, &line).await;
+        let quit = handle_received_engine_tasks(engine, &mut tasks_rx);
+
+        if let Some(response) = response {
+            stdout.write_all(response.to_string().as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        if quit {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Handles the tasks that the engine has sent to itself and that were already received, without waiting for more.
+///
+/// There is no main loop that receives them, so without this they would pile up,
+/// for example the images of finished rendering tasks.
+///
+/// Returns true when the engine requested to quit.
+fn handle_received_engine_tasks(
+    engine: &mut RnoteEngine,
+    tasks_rx: &mut EngineTaskReceiver,
+) -> bool {
+    let mut quit = false;
+
+    while let Ok(Some(task)) = tasks_rx.try_next() {
+        let (_, task_quit) = engine.handle_engine_task(task);
+        quit |= task_quit;
+    }
+
+    quit
+}
+
+/// Handles one line holding a request and returns the response, or None if the request is a notification.
+pub(crate) async fn handle_line(engine: &mut RnoteEngine, line: &str) -> Option<Value> {
+    let value = match serde_json::from_str::<Value>(line) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(PARSE_ERROR, e.to_string()),
+            ))
+        }
+    };
+    let request = match serde_json::from_value::<Request>(value) {
+        Ok(request) if request.jsonrpc == "2.0" => request,
+        Ok(request) => {
+            return Some(error_response(
+                request.id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "Only JSON-RPC version 2.0 is supported"),
+            ))
+        }
+        Err(e) => {
+            return Some(error_response(
+                Value::Null,
+                RpcError::new(INVALID_REQUEST, e.to_string()),
+            ))
+        }
+    };
+
+    let result = handle_request(engine, &request.method, request.params).await;
+    let id = request.id?;
+
+    Some(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => error_response(id, e),
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e.to_string()))
+}
+
+fn aabb_to_json(bounds: Aabb) -> Value {
+    json!({
+        "mins": [bounds.mins[0], bounds.mins[1]],
+        "maxs": [bounds.maxs[0], bounds.maxs[1]],
+    })
+}
+
+fn file_name(file: &Path) -> Result<String, RpcError> {
+    file.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Failed to get filename from file"))
+}
+
+async fn handle_request(
+    engine: &mut RnoteEngine,
+    method: &str,
+    params: Value,
+) -> Result<Value, RpcError> {
+    match method {
+        "open" => {
+            let params = parse_params::<FileParams>(params)?;
+            cli::load_file(engine, &params.file).await?;
+            Ok(Value::Null)
+        }
+        "save" => {
+            let params = parse_params::<FileParams>(params)?;
+            let rnote_bytes = engine
+                .save_as_rnote_bytes(file_name(&params.file)?)
+                .await
+                .map_err(anyhow::Error::from)??;
+            write_file(&params.file, &rnote_bytes).await?;
+            Ok(Value::Null)
+        }
+        "export" => {
+            let params = parse_params::<ExportParams>(params)?;
+            let doc_export_prefs = cli::create_doc_export_prefs_from_args(
+                params.format.is_none().then_some(&params.file),
+                params.format.as_deref(),
+                params.with_background,
+                params.with_pattern,
+                params.bitmap_scalefactor,
+                params.jpeg_quality,
+            )?;
+            let export_bytes = engine
+                .export_doc(file_name(&params.file)?, Some(doc_export_prefs))
+                .await
+                .map_err(anyhow::Error::from)??;
+            write_file(&params.file, &export_bytes).await?;
+            Ok(Value::Null)
+        }
+        "insert_text" => {
+            let params = parse_params::<InsertTextParams>(params)?;
+            let _ = engine.insert_text(params.text, params.pos)?;
+            Ok(Value::Null)
+        }
+        "insert_stroke_content" => {
+            let params = parse_params::<InsertStrokeContentParams>(params)?;
+            let _ = engine.insert_stroke_content(params.content, params.pos);
+            Ok(Value::Null)
+        }
+        "change_pen_style" => {
+            let params = parse_params::<ChangePenStyleParams>(params)?;
+            let _ = engine.change_pen_style(params.style);
+            Ok(Value::Null)
+        }
+        "handle_pen_events" => {
+            let params = parse_params::<PenEventsParams>(params)?;
+            for event in params.events {
+                let _ = engine.handle_pen_event(event, params.pen_mode, Instant::now());
+            }
+            Ok(Value::Null)
+        }
+        "bounds" => {
+            let content_bounds = engine
+                .store
+                .bounds_for_strokes(&engine.store.stroke_keys_as_rendered());
+            Ok(json!({
+                "doc": aabb_to_json(engine.document.bounds()),
+                "content": content_bounds.map(aabb_to_json),
+            }))
+        }
+        "undo" => {
+            let _ = engine.undo();
+            Ok(history_state(engine))
+        }
+        "redo" => {
+            let _ = engine.redo();
+            Ok(history_state(engine))
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Method `{method}` not found"),
+        )),
+    }
+}
+
+/// Whether undo and redo are possible, with the labels of the operations that would be undone or redone.
+fn history_state(engine: &RnoteEngine) -> Value {
+    json!({
+        "can_undo": engine.can_undo(),
+        "can_redo": engine.can_redo(),
+        "undo_label": engine.undo_label(),
+        "redo_label": engine.redo_label(),
+    })
+}
+
+async fn write_file(file: impl AsRef<Path>, bytes: &[u8]) -> anyhow::Result<()> {
+    let mut fh = File::create(file).await?;
+    fh.write_all(bytes).await?;
+    fh.sync_all().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_text_undo_redo() {
+        smol::block_on(async {
+            let mut engine = RnoteEngine::default();
+
+            let response = handle_line(
+                &mut engine,
+                r#"{"jsonrpc":"2.0","id":1,"method":"insert_text","params":{"text":"Hello","pos":[10.0,20.0]}}"#,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response["result"], Value::Null);
+
+            let response =
+                handle_line(&mut engine, r#"{"jsonrpc":"2.0","id":2,"method":"bounds"}"#)
+                    .await
+                    .unwrap();
+            assert!(response["result"]["content"].is_object());
+
+            let response = handle_line(&mut engine, r#"{"jsonrpc":"2.0","id":3,"method":"undo"}"#)
+                .await
+                .unwrap();
+            assert_eq!(response["result"]["can_redo"], Value::Bool(true));
+            assert_eq!(response["result"]["redo_label"], json!("insert_text"));
+        });
+    }
+
+    #[test]
+    fn save_open_export() {
+        smol::block_on(async {
+            let dir = std::env::temp_dir();
+            let rnote_file = dir.join(format!("rnote-cli-serve-test-{}.rnote", std::process::id()));
+            let svg_file = dir.join(format!("rnote-cli-serve-test-{}.svg", std::process::id()));
+
+            let mut engine = RnoteEngine::default();
+            handle_line(
+                &mut engine,
+                r#"{"jsonrpc":"2.0","id":1,"method":"insert_text","params":{"text":"Hello","pos":[10.0,20.0]}}"#,
+            )
+            .await
+            .unwrap();
+            let response = handle_line(
+                &mut engine,
+                &json!({ "jsonrpc": "2.0", "id": 2, "method": "save", "params": { "file": rnote_file } })
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response["result"], Value::Null);
+
+            let mut engine = RnoteEngine::default();
+            let mut tasks_rx = engine.tasks_rx.take().unwrap();
+            let response = handle_line(
+                &mut engine,
+                &json!({ "jsonrpc": "2.0", "id": 3, "method": "open", "params": { "file": rnote_file } })
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response["result"], Value::Null);
+            assert!(!handle_received_engine_tasks(&mut engine, &mut tasks_rx));
+
+            let response =
+                handle_line(&mut engine, r#"{"jsonrpc":"2.0","id":4,"method":"bounds"}"#)
+                    .await
+                    .unwrap();
+            assert!(response["result"]["content"].is_object());
+
+            let response = handle_line(
+                &mut engine,
+                &json!({ "jsonrpc": "2.0", "id": 5, "method": "export", "params": { "file": svg_file } })
+                    .to_string(),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response["result"], Value::Null);
+            assert!(!handle_received_engine_tasks(&mut engine, &mut tasks_rx));
+            let svg = std::fs::read_to_string(&svg_file).unwrap();
+            assert!(svg.contains("<svg"));
+
+            std::fs::remove_file(rnote_file).unwrap();
+            std::fs::remove_file(svg_file).unwrap();
+        });
+    }
+
+    #[test]
+    fn errors() {
+        smol::block_on(async {
+            let mut engine = RnoteEngine::default();
+
+            let response = handle_line(&mut engine, "{").await.unwrap();
+            assert_eq!(response["error"]["code"], json!(PARSE_ERROR));
+
+            let response = handle_line(&mut engine, r#"{"jsonrpc":"2.0","id":1,"method":"foo"}"#)
+                .await
+                .unwrap();
+            assert_eq!(response["error"]["code"], json!(METHOD_NOT_FOUND));
+
+            let response = handle_line(
+                &mut engine,
+                r#"{"jsonrpc":"2.0","id":2,"method":"insert_text","params":{}}"#,
+            )
+            .await
+            .unwrap();
+            assert_eq!(response["error"]["code"], json!(INVALID_PARAMS));
+
+            // notifications don't get a response
+            assert!(
+                handle_line(&mut engine, r#"{"jsonrpc":"2.0","method":"redo"}"#)
+                    .await
+                    .is_none()
+            );
+        });
+    }
+}
@@ -8,87 +8,122 @@ use serde::{Deserialize, Serialize};
 /// Motion event already encode state as they would only be valid if they are preceded by a down event.
 /// As a result, multiple down events are emitted while the pen is pressed down and being moved.
 /// This should be handled accordingly by the state machines which receive the events.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "pen_event")]
 pub enum PenEvent {
     /// A pen down event. Is repeatedly emitted while the pen is pressed down and moved.
+    #[serde(rename = "down")]
     Down {
         /// The element for the down event.
+        #[serde(rename = "element")]
         element: Element,
         /// Modifier keys pressed during the event.
+        #[serde(rename = "modifier_keys", default)]
         modifier_keys: Vec<ModifierKey>,
     },
     /// A pen up event.
+    #[serde(rename = "up")]
     Up {
         /// The element for the up event.
+        #[serde(rename = "element")]
         element: Element,
         /// Modifier keys pressed during the event.
+        #[serde(rename = "modifier_keys", default)]
         modifier_keys: Vec<ModifierKey>,
     },
     /// A pen down event. Is repeatedly emitted while the pen is in proximity and moved.
+    #[serde(rename = "proximity")]
     Proximity {
         /// The element for the proximity event.
+        #[serde(rename = "element")]
         element: Element,
         /// Modifier keys pressed during the event.
+        #[serde(rename = "modifier_keys", default)]
         modifier_keys: Vec<ModifierKey>,
     },
     /// A keyboard key pressed event.
+    #[serde(rename = "key_pressed")]
     KeyPressed {
         /// the key
+        #[serde(rename = "keyboard_key")]
         keyboard_key: KeyboardKey,
         /// Modifier keys pressed during the event.
+        #[serde(rename = "modifier_keys", default)]
         modifier_keys: Vec<ModifierKey>,
     },
     /// Text input event.
+    #[serde(rename = "text")]
     Text {
         /// The committed text.
+        #[serde(rename = "text")]
         text: String,
     },
     /// Cancel event when the pen vanishes unexpected.
     ///
     /// Should finish all current actions and reset all state.
+    #[serde(rename = "cancel")]
     Cancel,
 }
 
 /// A key on the keyboard.
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename = "keyboard_key")]
 pub enum KeyboardKey {
     /// A Unicode character.
     ///
     /// Expects that control characters are already converted and filtered out with the method [KeyboardKey::filter_convert_unicode_control_chars].
+    #[serde(rename = "unicode")]
     Unicode(char),
     /// Backspace.
+    #[serde(rename = "back_space")]
     BackSpace,
     /// Tab.
+    #[serde(rename = "horizontal_tab")]
     HorizontalTab,
     /// Line feed.
+    #[serde(rename = "linefeed")]
     Linefeed,
     /// Carriage return.
+    #[serde(rename = "carriage_return")]
     CarriageReturn,
     /// Escape.
+    #[serde(rename = "escape")]
     Escape,
     /// Delete.
+    #[serde(rename = "delete")]
     Delete,
     /// Arrow up.
+    #[serde(rename = "nav_up")]
     NavUp,
     /// Arrow down.
+    #[serde(rename = "nav_down")]
     NavDown,
     /// Arrow left.
+    #[serde(rename = "nav_left")]
     NavLeft,
     /// Arrow right.
+    #[serde(rename = "nav_right")]
     NavRight,
     /// Shift left.
+    #[serde(rename = "shift_left")]
     ShiftLeft,
     /// Shift right.
+    #[serde(rename = "shift_right")]
     ShiftRight,
     /// Ctrl left.
+    #[serde(rename = "ctrl_left")]
     CtrlLeft,
     /// Ctrl right.
+    #[serde(rename = "ctrl_right")]
     CtrlRight,
     /// Home.
+    #[serde(rename = "home")]
     Home,
     /// End.
+    #[serde(rename = "end")]
     End,
     /// Unsupported Key.
+    #[serde(rename = "unsupported")]
     Unsupported,
 }
 
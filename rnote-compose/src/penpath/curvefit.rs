@@ -0,0 +1,465 @@
+// Imports
+use super::{Element, Segment};
+use crate::shapes::cubbez::cubbez_calc;
+
+/// The maximum deviation of the pressure from the linearly interpolated pressure along a fitted segment.
+///
+/// Segments interpolate the pressure linearly between their start and end,
+/// so when it deviates more than this the curve is split to preserve the pressure profile.
+const PRESSURE_TOLERANCE: f64 = 0.05;
+/// The number of newton-raphson reparameterization iterations before splitting the curve.
+const MAX_REPARAMETERIZE_ITERATIONS: usize = 4;
+/// Consecutive elements closer than this are merged before fitting.
+const MIN_ELEMENT_DIST: f64 = 1e-3;
+
+/// Fits cubic bezier segments through the elements, with the maximum distance of every element
+/// to the fitted curve being below the tolerance.
+///
+/// Implements "An Algorithm for Automatically Fitting Digitized Curves" by Philip J. Schneider (Graphics Gems, 1990).
+/// The pressure of the segment ends is taken from the elements the curve is split at.
+///
+/// Returns the new start element and the fitted segments, or None if there are no elements.
+pub(crate) fn fit_cubbez_segments(
+    elements: &[Element],
+    tolerance: f64,
+) -> Option<(Element, Vec<Segment>)> {
+    let start = *elements.first()?;
+    let elements = elements.iter().skip(1).fold(vec![start], |mut acc, el| {
+        let last = acc.last_mut().unwrap();
+        if (el.pos - last.pos).magnitude() < MIN_ELEMENT_DIST {
+            last.pressure = last.pressure.max(el.pressure);
+        } else {
+            acc.push(*el);
+        }
+        acc
+    });
+
+    let mut segments = Vec::new();
+    if elements.len() < 2 {
+        return Some((elements[0], segments));
+    }
+
+    let n = elements.len();
+    let tangent_start = tangent(elements[1].pos - elements[0].pos);
+    let tangent_end = tangent(elements[n - 2].pos - elements[n - 1].pos);
+    fit_cubic(
+        &elements,
+        tangent_start,
+        tangent_end,
+        tolerance.max(MIN_ELEMENT_DIST).powi(2),
+        &mut segments,
+    );
+
+    Some((elements[0], segments))
+}
+
+/// Recursively fits a cubic bezier to the elements, splitting at the point of the maximum error
+/// when the tolerance is exceeded.
+///
+/// `tolerance_sq` is the squared tolerance.
+fn fit_cubic(
+    elements: &[Element],
+    tangent_start: na::Vector2<f64>,
+    tangent_end: na::Vector2<f64>,
+    tolerance_sq: f64,
+    segments: &mut Vec<Segment>,
+) {
+    let n = elements.len();
+    let first = elements[0];
+    let last = elements[n - 1];
+
+    // Use a heuristic if there are only two elements
+    if n == 2 {
+        let dist = (last.pos - first.pos).magnitude() / 3.0;
+        segments.push(Segment::CubBezTo {
+            cp1: first.pos + tangent_start * dist,
+            cp2: last.pos + tangent_end * dist,
+            end: last,
+        });
+        return;
+    }
+
+    let mut u = chord_length_parameterize(elements);
+    let mut bez = generate_bezier(elements, &u, tangent_start, tangent_end);
+    let (mut max_error, mut split_i) = compute_max_error(elements, &bez, &u);
+
+    if max_error < tolerance_sq {
+        push_fitted_bezier(elements, &bez, &u, tolerance_sq, split_i, segments);
+        return;
+    }
+
+    // When the error is not too large, try reparameterization
+    if max_error < tolerance_sq * 4.0 {
+        for _ in 0..MAX_REPARAMETERIZE_ITERATIONS {
+            u = reparameterize(elements, &u, &bez);
+            bez = generate_bezier(elements, &u, tangent_start, tangent_end);
+            (max_error, split_i) = compute_max_error(elements, &bez, &u);
+
+            if max_error < tolerance_sq {
+                push_fitted_bezier(elements, &bez, &u, tolerance_sq, split_i, segments);
+                return;
+            }
+        }
+    }
+
+    split_and_fit(
+        elements,
+        split_i,
+        tangent_start,
+        tangent_end,
+        tolerance_sq,
+        segments,
+    );
+}
+
+/// Pushes the fitted bezier as segment when the pressure is preserved, else splits at the largest pressure deviation.
+fn push_fitted_bezier(
+    elements: &[Element],
+    bez: &[na::Vector2<f64>; 4],
+    u: &[f64],
+    tolerance_sq: f64,
+    split_i: usize,
+    segments: &mut Vec<Segment>,
+) {
+    let n = elements.len();
+    let first = elements[0];
+    let last = elements[n - 1];
+
+    let (max_pressure_deviation, pressure_split_i) = elements
+        .iter()
+        .zip(u.iter())
+        .enumerate()
+        .skip(1)
+        .take(n - 2)
+        .map(|(i, (el, &t))| {
+            let interpolated = first.pressure + (last.pressure - first.pressure) * t;
+            ((el.pressure - interpolated).abs(), i)
+        })
+        .fold(
+            (0.0, split_i),
+            |acc, dev| if dev.0 > acc.0 { dev } else { acc },
+        );
+
+    if max_pressure_deviation <= PRESSURE_TOLERANCE {
+        segments.push(Segment::CubBezTo {
+            cp1: bez[1],
+            cp2: bez[2],
+            end: last,
+        });
+    } else {
+        let tangent_start = tangent(bez[1] - bez[0]);
+        let tangent_end = tangent(bez[2] - bez[3]);
+        split_and_fit(
+            elements,
+            pressure_split_i,
+            tangent_start,
+            tangent_end,
+            tolerance_sq,
+            segments,
+        );
+    }
+}
+
+/// Splits the elements at the given index and fits both halves.
+fn split_and_fit(
+    elements: &[Element],
+    split_i: usize,
+    tangent_start: na::Vector2<f64>,
+    tangent_end: na::Vector2<f64>,
+    tolerance_sq: f64,
+    segments: &mut Vec<Segment>,
+) {
+    let n = elements.len();
+    // The split element must be an inner element, else the recursion would not terminate
+    let split_i = split_i.clamp(1, n - 2);
+
+    let tangent_center = {
+        let center = tangent(elements[split_i - 1].pos - elements[split_i + 1].pos);
+        if center == na::Vector2::zeros() {
+            tangent(elements[split_i - 1].pos - elements[split_i].pos)
+        } else {
+            center
+        }
+    };
+
+    fit_cubic(
+        &elements[..=split_i],
+        tangent_start,
+        tangent_center,
+        tolerance_sq,
+        segments,
+    );
+    fit_cubic(
+        &elements[split_i..],
+        -tangent_center,
+        tangent_end,
+        tolerance_sq,
+        segments,
+    );
+}
+
+/// Normalizes the vector, returning the zero vector if it has no length.
+fn tangent(v: na::Vector2<f64>) -> na::Vector2<f64> {
+    v.try_normalize(f64::EPSILON)
+        .unwrap_or_else(na::Vector2::zeros)
+}
+
+/// Assigns parameter values to the elements, using relative distances along the polyline.
+fn chord_length_parameterize(elements: &[Element]) -> Vec<f64> {
+    let mut u = Vec::with_capacity(elements.len());
+    u.push(0.0);
+    for w in elements.windows(2) {
+        let prev = *u.last().unwrap();
+        u.push(prev + (w[1].pos - w[0].pos).magnitude());
+    }
+
+    let total = *u.last().unwrap();
+    if total > 0.0 {
+        u.iter_mut().for_each(|t| *t /= total);
+    }
+    u
+}
+
+/// Least-squares fit of the control points of a cubic bezier for the parameterization,
+/// constrained to the given end tangents.
+fn generate_bezier(
+    elements: &[Element],
+    u: &[f64],
+    tangent_start: na::Vector2<f64>,
+    tangent_end: na::Vector2<f64>,
+) -> [na::Vector2<f64>; 4] {
+    let first = elements[0].pos;
+    let last = elements[elements.len() - 1].pos;
+
+    let mut c = na::Matrix2::<f64>::zeros();
+    let mut x = na::Vector2::<f64>::zeros();
+
+    for (el, &t) in elements.iter().zip(u.iter()) {
+        let mt = 1.0 - t;
+        let b0 = mt.powi(3);
+        let b1 = 3.0 * t * mt.powi(2);
+        let b2 = 3.0 * t.powi(2) * mt;
+        let b3 = t.powi(3);
+
+        let a0 = tangent_start * b1;
+        let a1 = tangent_end * b2;
+
+        c[(0, 0)] += a0.dot(&a0);
+        c[(0, 1)] += a0.dot(&a1);
+        c[(1, 1)] += a1.dot(&a1);
+
+        let tmp = el.pos - (first * (b0 + b1) + last * (b2 + b3));
+        x[0] += a0.dot(&tmp);
+        x[1] += a1.dot(&tmp);
+    }
+    c[(1, 0)] = c[(0, 1)];
+
+    let det_c0_c1 = c[(0, 0)] * c[(1, 1)] - c[(1, 0)] * c[(0, 1)];
+    let det_c0_x = c[(0, 0)] * x[1] - c[(1, 0)] * x[0];
+    let det_x_c1 = x[0] * c[(1, 1)] - x[1] * c[(0, 1)];
+
+    let (alpha_l, alpha_r) = if det_c0_c1 == 0.0 {
+        (0.0, 0.0)
+    } else {
+        (det_x_c1 / det_c0_c1, det_c0_x / det_c0_c1)
+    };
+
+    // Fall back to the heuristic when alpha is negative or degenerate
+    let seg_len = (last - first).magnitude();
+    let epsilon = 1e-6 * seg_len;
+    if alpha_l < epsilon || alpha_r < epsilon || !alpha_l.is_finite() || !alpha_r.is_finite() {
+        let dist = seg_len / 3.0;
+        return [
+            first,
+            first + tangent_start * dist,
+            last + tangent_end * dist,
+            last,
+        ];
+    }
+
+    [
+        first,
+        first + tangent_start * alpha_l,
+        last + tangent_end * alpha_r,
+        last,
+    ]
+}
+
+/// Improves the parameterization with a newton-raphson iteration step for every element.
+fn reparameterize(elements: &[Element], u: &[f64], bez: &[na::Vector2<f64>; 4]) -> Vec<f64> {
+    elements
+        .iter()
+        .zip(u.iter())
+        .map(|(el, &t)| newton_raphson_root_find(bez, el.pos, t))
+        .collect()
+}
+
+fn newton_raphson_root_find(bez: &[na::Vector2<f64>; 4], pos: na::Vector2<f64>, t: f64) -> f64 {
+    let d = bezier_eval(bez, t) - pos;
+
+    let q1 = [
+        (bez[1] - bez[0]) * 3.0,
+        (bez[2] - bez[1]) * 3.0,
+        (bez[3] - bez[2]) * 3.0,
+    ];
+    let q2 = [(q1[1] - q1[0]) * 2.0, (q1[2] - q1[1]) * 2.0];
+
+    let mt = 1.0 - t;
+    let q1_t = q1[0] * mt.powi(2) + q1[1] * 2.0 * t * mt + q1[2] * t.powi(2);
+    let q2_t = q2[0] * mt + q2[1] * t;
+
+    let numerator = d.dot(&q1_t);
+    let denominator = q1_t.dot(&q1_t) + d.dot(&q2_t);
+
+    if denominator == 0.0 {
+        t
+    } else {
+        (t - numerator / denominator).clamp(0.0, 1.0)
+    }
+}
+
+/// The maximum squared distance of the elements to the fitted curve, and the index of the element where it occurs.
+fn compute_max_error(elements: &[Element], bez: &[na::Vector2<f64>; 4], u: &[f64]) -> (f64, usize) {
+    let n = elements.len();
+    let mut max_dist = 0.0;
+    let mut split_i = n / 2;
+
+    for (i, (el, &t)) in elements
+        .iter()
+        .zip(u.iter())
+        .enumerate()
+        .skip(1)
+        .take(n - 2)
+    {
+        let dist = (bezier_eval(bez, t) - el.pos).magnitude_squared();
+        if dist >= max_dist {
+            max_dist = dist;
+            split_i = i;
+        }
+    }
+
+    (max_dist, split_i)
+}
+
+fn bezier_eval(bez: &[na::Vector2<f64>; 4], t: f64) -> na::Vector2<f64> {
+    cubbez_calc(bez[0], bez[1], bez[2], bez[3], t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::penpath::PenPath;
+
+    const TOLERANCE: f64 = 0.5;
+
+    /// The minimum distance of the position to the segments, evaluated at many points along them.
+    fn dist_to_segments(start: Element, segments: &[Segment], pos: na::Vector2<f64>) -> f64 {
+        PenPath::new_w_segments(start, segments.iter().copied())
+            .sample_elements(0.01)
+            .into_iter()
+            .map(|el| (el.pos - pos).magnitude())
+            .fold(f64::MAX, f64::min)
+    }
+
+    fn assert_within_tolerance(elements: &[Element], start: Element, segments: &[Segment]) {
+        for el in elements {
+            let dist = dist_to_segments(start, segments, el.pos);
+            // allow a small error of the sampled distance
+            assert!(
+                dist < TOLERANCE + 0.01,
+                "element {:?} deviates {dist} from the fitted segments",
+                el.pos
+            );
+        }
+    }
+
+    fn segment_end(segment: &Segment) -> Element {
+        match segment {
+            Segment::LineTo { end }
+            | Segment::QuadBezTo { end, .. }
+            | Segment::CubBezTo { end, .. } => *end,
+        }
+    }
+
+    #[test]
+    fn fit_straight_line() {
+        let elements = (0..=50)
+            .map(|i| Element::new(na::vector![i as f64 * 2.0, i as f64], 0.5))
+            .collect::<Vec<Element>>();
+
+        let (start, segments) = fit_cubbez_segments(&elements, TOLERANCE).unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(start.pos, elements[0].pos);
+        assert_eq!(segment_end(&segments[0]).pos, elements[50].pos);
+        assert_within_tolerance(&elements, start, &segments);
+    }
+
+    #[test]
+    fn fit_circle_arc() {
+        let radius = 100.0;
+        let elements = (0..=90)
+            .map(|i| {
+                let angle = (i as f64).to_radians();
+                Element::new(na::vector![angle.cos(), angle.sin()] * radius, 0.5)
+            })
+            .collect::<Vec<Element>>();
+
+        let (start, segments) = fit_cubbez_segments(&elements, TOLERANCE).unwrap();
+
+        // A quarter circle is approximated well by a single cubic bezier
+        assert!(segments.len() <= 2);
+        assert_within_tolerance(&elements, start, &segments);
+    }
+
+    #[test]
+    fn fit_splits_at_pressure_changes() {
+        // A straight line with the pressure rising to the center and then falling again
+        let elements = (0..=50)
+            .map(|i| {
+                let pressure = 1.0 - (i as f64 - 25.0).abs() / 25.0;
+                Element::new(na::vector![i as f64 * 2.0, 0.0], pressure)
+            })
+            .collect::<Vec<Element>>();
+
+        let (start, segments) = fit_cubbez_segments(&elements, TOLERANCE).unwrap();
+
+        assert!(segments.len() >= 2);
+        assert_eq!(start.pressure, 0.0);
+        assert_eq!(segment_end(segments.last().unwrap()).pressure, 0.0);
+        assert!(segments
+            .iter()
+            .any(|seg| segment_end(seg).pressure > 1.0 - PRESSURE_TOLERANCE));
+        assert_within_tolerance(&elements, start, &segments);
+    }
+
+    #[test]
+    fn fit_single_and_duplicate_elements() {
+        let el = Element::new(na::vector![1.0, 1.0], 0.5);
+
+        assert!(fit_cubbez_segments(&[], TOLERANCE).is_none());
+        let (start, segments) = fit_cubbez_segments(&[el, el, el], TOLERANCE).unwrap();
+        assert_eq!(start.pos, el.pos);
+        assert!(segments.is_empty());
+    }
+
+    #[test]
+    fn refitting_curved_path_does_not_drift() {
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 0.0], 0.5),
+            [Segment::CubBezTo {
+                cp1: na::vector![0.0, 100.0],
+                cp2: na::vector![100.0, 100.0],
+                end: Element::new(na::vector![100.0, 0.0], 0.5),
+            }],
+        );
+        let original = path.sample_elements(1.0);
+
+        let mut refitted = path;
+        for _ in 0..5 {
+            refitted = refitted.fit_cubbez(TOLERANCE);
+        }
+
+        assert_within_tolerance(&original, refitted.start, &refitted.segments);
+    }
+}
@@ -1,5 +1,7 @@
 // Modules
+mod curvefit;
 mod element;
+mod recognizer;
 mod segment;
 
 // Re-exports
@@ -8,9 +10,9 @@ pub use segment::Segment;
 
 // Imports
 use crate::helpers::{KurboHelpers, Vector2Helpers};
-use crate::shapes::{CubicBezier, Line, QuadraticBezier, ShapeBehaviour};
+use crate::shapes::{cubbez, quadbez, CubicBezier, Line, QuadraticBezier, Shape, ShapeBehaviour};
 use crate::transform::TransformBehaviour;
-use kurbo::Shape;
+use kurbo::Shape as _;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use serde::{Deserialize, Serialize};
 
@@ -97,6 +99,11 @@ impl TransformBehaviour for PenPath {
 }
 
 impl PenPath {
+    /// The maximum distance of the elements that curved segments are sampled with before refitting.
+    const FIT_CURVE_SAMPLE_DIST: f64 = 2.0;
+    /// The maximum distance of the elements that curved segments are sampled with before recognizing shapes.
+    const RECOGNIZE_CURVE_SAMPLE_DIST: f64 = 5.0;
+
     /// A new pen path
     pub fn new(start: Element) -> Self {
         Self {
@@ -126,6 +133,55 @@ impl PenPath {
         elements
     }
 
+    /// Samples the path into elements, including points on the curved segments, unlike [PenPath::into_elements()].
+    ///
+    /// The curved segments are sampled with elements that are at most `max_dist` apart,
+    /// their pressure is linearly interpolated between the segment start and end.
+    pub fn sample_elements(&self, max_dist: f64) -> Vec<Element> {
+        let mut elements = vec![self.start];
+
+        let mut prev = self.start;
+        for seg in self.segments.iter() {
+            match seg {
+                Segment::LineTo { end } => {
+                    elements.push(*end);
+                    prev = *end;
+                }
+                Segment::QuadBezTo { cp, end } => {
+                    let quadbez = QuadraticBezier {
+                        start: prev.pos,
+                        cp: *cp,
+                        end: end.pos,
+                    };
+
+                    let n_samples =
+                        no_samples_for_segment_len(quadbez.to_kurbo().perimeter(0.25), max_dist);
+                    elements.extend(sample_segment(prev, *end, n_samples, |t| {
+                        quadbez::quadbez_calc(quadbez.start, quadbez.cp, quadbez.end, t)
+                    }));
+                    prev = *end;
+                }
+                Segment::CubBezTo { cp1, cp2, end } => {
+                    let cubbez = CubicBezier {
+                        start: prev.pos,
+                        cp1: *cp1,
+                        cp2: *cp2,
+                        end: end.pos,
+                    };
+
+                    let n_samples =
+                        no_samples_for_segment_len(cubbez.to_kurbo().perimeter(0.25), max_dist);
+                    elements.extend(sample_segment(prev, *end, n_samples, |t| {
+                        cubbez::cubbez_calc(cubbez.start, cubbez.cp1, cubbez.cp2, cubbez.end, t)
+                    }));
+                    prev = *end;
+                }
+            }
+        }
+
+        elements
+    }
+
     /// Try to create a pen path from the elements. the first element will be the start
     pub fn try_from_elements(elements_iter: impl IntoIterator<Item = Element>) -> Option<Self> {
         let mut elements_iter = elements_iter.into_iter();
@@ -138,6 +194,34 @@ impl PenPath {
         Some(Self { start, segments })
     }
 
+    /// Refits the path into cubic bezier segments, with the input elements deviating at most by `tolerance` from the new path.
+    ///
+    /// The pressure is preserved at the segment ends and is linearly interpolated in between.
+    /// This reduces the number of segments and smoothes jitter of the input.
+    pub fn fit_cubbez(&self, tolerance: f64) -> Self {
+        // Sample already curved segments, so that smoothing a path repeatedly does not drift away from its shape
+        let elements = self.sample_elements(Self::FIT_CURVE_SAMPLE_DIST);
+
+        match curvefit::fit_cubbez_segments(&elements, tolerance) {
+            Some((start, segments)) => Self { start, segments },
+            None => self.clone(),
+        }
+    }
+
+    /// Recognizes a line, arrow, rectangle or ellipse shape that the path resembles.
+    ///
+    /// `tolerance` is the maximum deviation of the path from the shape, relative to the size of the path.
+    /// Returns None if the path does not resemble any of the shapes.
+    pub fn recognize_shape(&self, tolerance: f64) -> Option<Shape> {
+        let points = self
+            .sample_elements(Self::RECOGNIZE_CURVE_SAMPLE_DIST)
+            .into_iter()
+            .map(|el| el.pos)
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        recognizer::recognize_shape(&points, tolerance)
+    }
+
     /// Checks whether bounds collide with the path. If it does, it returns the indices of the colliding segments
     ///
     /// `loosened` loosens the segments hitboxes by the value
@@ -262,6 +346,36 @@ impl Extend<Segment> for PenPath {
     }
 }
 
+/// Calculates the number of samples for a curved segment with the given length,
+/// so that the samples are at most `max_dist` apart. Capped for very long segments.
+fn no_samples_for_segment_len(len: f64, max_dist: f64) -> usize {
+    const MAX_SAMPLES: f64 = 1000.0;
+
+    (len / max_dist.max(f64::EPSILON))
+        .ceil()
+        .clamp(1.0, MAX_SAMPLES) as usize
+}
+
+/// Samples the segment from `start` to `end` with `n_samples` elements, excluding the start.
+///
+/// `pos_at` is the position on the segment for t between 0.0 and 1.0. The pressure is linearly interpolated.
+fn sample_segment(
+    start: Element,
+    end: Element,
+    n_samples: usize,
+    pos_at: impl Fn(f64) -> na::Vector2<f64>,
+) -> Vec<Element> {
+    (1..=n_samples)
+        .map(|i| {
+            let t = i as f64 / n_samples as f64;
+            Element::new(
+                pos_at(t),
+                start.pressure + (end.pressure - start.pressure) * t,
+            )
+        })
+        .collect()
+}
+
 /// Calculates the number subsegment elements (for hitboxes/ flattening of bezier curve)
 /// for the given segment length, capped with a maximum no of hitbox elements
 pub(crate) fn no_subsegments_for_segment_len(len: f64) -> i32 {
@@ -0,0 +1,506 @@
+// Imports
+use crate::shapes::{Arrow, Ellipse, Line, Rectangle, Shape};
+use crate::Transform;
+use p2d::bounding_volume::Aabb;
+use std::f64::consts::{FRAC_PI_2, PI};
+
+/// The minimum size of the bounds diagonal of a path for it to be recognized.
+const MIN_SIZE: f64 = 4.0;
+/// The maximum gap between start and end relative to the bounds diagonal for a path to be considered closed.
+const CLOSED_GAP: f64 = 0.2;
+/// Angles closer to an axis than this are snapped to it.
+const AXIS_SNAP_ANGLE: f64 = 8.0 * PI / 180.0;
+/// The maximum deviation of the corner angles of a rectangle from a right angle.
+const RECTANGLE_CORNER_ANGLE_DEVIATION: f64 = 25.0 * PI / 180.0;
+/// Vertices of the simplified polyline which turn less than this are not considered corners.
+const CORNER_MIN_TURN_ANGLE: f64 = 35.0 * PI / 180.0;
+/// The maximum relative difference of the radii of an ellipse for it to become a circle.
+const CIRCLE_RADII_RATIO: f64 = 0.1;
+/// The allowed range of the angle between the arrow stem and its barbs.
+const ARROW_BARB_ANGLE_RANGE: std::ops::RangeInclusive<f64> =
+    (10.0 * PI / 180.0)..=(80.0 * PI / 180.0);
+/// The maximum length of the arrow barbs relative to the stem.
+const ARROW_BARB_MAX_LEN: f64 = 0.6;
+
+/// Recognizes a shape from the positions of a path.
+///
+/// `tolerance` is the maximum deviation of the positions from the recognized shape, relative to the size of the path.
+pub(crate) fn recognize_shape(points: &[na::Vector2<f64>], tolerance: f64) -> Option<Shape> {
+    if points.len() < 2 {
+        return None;
+    }
+    let bounds = Aabb::from_points(
+        &points
+            .iter()
+            .map(|&p| na::Point2::from(p))
+            .collect::<Vec<na::Point2<f64>>>(),
+    );
+    let diag = bounds.extents().magnitude();
+    if diag < MIN_SIZE {
+        return None;
+    }
+    let abs_tolerance = tolerance * diag;
+
+    let start = points[0];
+    let end = points[points.len() - 1];
+
+    if (end - start).magnitude() < CLOSED_GAP * diag {
+        recognize_rectangle(points, abs_tolerance)
+            .map(Shape::Rectangle)
+            .or_else(|| recognize_ellipse(points, abs_tolerance).map(Shape::Ellipse))
+    } else if points
+        .iter()
+        .all(|&p| dist_to_line_segment(p, start, end) < abs_tolerance)
+    {
+        Some(Shape::Line(Line { start, end }))
+    } else {
+        recognize_arrow(points, abs_tolerance).map(Shape::Arrow)
+    }
+}
+
+/// Recognizes an arrow drawn in a single stroke, going from the stem start to the tip, along the first barb
+/// and back to the tip, and then along the second barb.
+fn recognize_arrow(points: &[na::Vector2<f64>], abs_tolerance: f64) -> Option<Arrow> {
+    let vertices = simplify_polyline(points, abs_tolerance);
+    let [stem_start, tip, barb_0, tip_2, barb_1] = vertices[..] else {
+        return None;
+    };
+
+    let stem = stem_start - tip;
+    let stem_len = stem.magnitude();
+    let barb_0 = barb_0 - tip;
+    let barb_1 = barb_1 - tip_2;
+    let barb_max_len = barb_0.magnitude().max(barb_1.magnitude());
+    let barb_min_len = barb_0.magnitude().min(barb_1.magnitude());
+
+    if barb_max_len > ARROW_BARB_MAX_LEN * stem_len
+        || (tip_2 - tip).magnitude() > abs_tolerance.max(0.5 * barb_min_len)
+        || !ARROW_BARB_ANGLE_RANGE.contains(&stem.angle(&barb_0))
+        || !ARROW_BARB_ANGLE_RANGE.contains(&stem.angle(&barb_1))
+        || stem.perp(&barb_0) * stem.perp(&barb_1) > 0.0
+    {
+        return None;
+    }
+
+    Some(Arrow::new(stem_start, tip))
+}
+
+/// Recognizes a (possibly rotated) rectangle from a closed path.
+fn recognize_rectangle(points: &[na::Vector2<f64>], abs_tolerance: f64) -> Option<Rectangle> {
+    let mut vertices = simplify_polyline(points, abs_tolerance);
+    // The path is closed, so the last vertex is the same as the first.
+    vertices.pop();
+    let corners = corners_of_closed_polyline(&vertices);
+    if corners.len() != 4 {
+        return None;
+    }
+
+    for i in 0..4 {
+        let prev = corners[(i + 3) % 4] - corners[i];
+        let next = corners[(i + 1) % 4] - corners[i];
+        if (prev.angle(&next) - FRAC_PI_2).abs() > RECTANGLE_CORNER_ANGLE_DEVIATION {
+            return None;
+        }
+    }
+
+    // Average the edge orientations modulo a right angle, as circular mean of the quadrupled angles.
+    let (sin_sum, cos_sum) = (0..4)
+        .map(|i| {
+            let edge = corners[(i + 1) % 4] - corners[i];
+            let angle = 4.0 * edge[1].atan2(edge[0]);
+            (angle.sin(), angle.cos())
+        })
+        .fold((0.0, 0.0), |acc, (sin, cos)| (acc.0 + sin, acc.1 + cos));
+    let angle = snap_to_axis(sin_sum.atan2(cos_sum) / 4.0);
+
+    let rotation = na::Rotation2::new(-angle);
+    let local_points = points
+        .iter()
+        .map(|&p| rotation * p)
+        .collect::<Vec<na::Vector2<f64>>>();
+    let (mins, maxs) = local_points.iter().fold(
+        (
+            na::vector![f64::MAX, f64::MAX],
+            na::vector![f64::MIN, f64::MIN],
+        ),
+        |(mins, maxs), p| (mins.inf(p), maxs.sup(p)),
+    );
+    let local_center = (mins + maxs) * 0.5;
+    let half_extents = (maxs - mins) * 0.5;
+
+    let fits = local_points.iter().all(|p| {
+        let d = (p - local_center).abs() - half_extents;
+        let dist = if d[0] <= 0.0 && d[1] <= 0.0 {
+            -d[0].max(d[1])
+        } else {
+            d.sup(&na::Vector2::zeros()).magnitude()
+        };
+        dist < abs_tolerance
+    });
+    if !fits {
+        return None;
+    }
+
+    Some(Rectangle {
+        cuboid: p2d::shape::Cuboid::new(half_extents),
+        transform: Transform::new_w_isometry(na::Isometry2::new(
+            rotation.inverse() * local_center,
+            angle,
+        )),
+    })
+}
+
+/// Recognizes a (possibly rotated) ellipse from a closed path.
+fn recognize_ellipse(points: &[na::Vector2<f64>], abs_tolerance: f64) -> Option<Ellipse> {
+    // The points are weighted by the lengths of their adjacent segments, to account for varying input density.
+    let weights = (0..points.len())
+        .map(|i| {
+            let prev = points[i.saturating_sub(1)];
+            let next = points[(i + 1).min(points.len() - 1)];
+            (next - prev).magnitude() * 0.5
+        })
+        .collect::<Vec<f64>>();
+    let total_weight = weights.iter().sum::<f64>();
+    if total_weight <= 0.0 {
+        return None;
+    }
+
+    let center = points
+        .iter()
+        .zip(weights.iter())
+        .map(|(p, w)| p * *w)
+        .sum::<na::Vector2<f64>>()
+        / total_weight;
+    let (cxx, cyy, cxy) = points
+        .iter()
+        .zip(weights.iter())
+        .map(|(p, w)| {
+            let d = p - center;
+            (d[0] * d[0] * w, d[1] * d[1] * w, d[0] * d[1] * w)
+        })
+        .fold((0.0, 0.0, 0.0), |acc, c| {
+            (acc.0 + c.0, acc.1 + c.1, acc.2 + c.2)
+        });
+    let (cxx, cyy, cxy) = (cxx / total_weight, cyy / total_weight, cxy / total_weight);
+
+    // The principal axes of the covariance are the axes of the ellipse.
+    let mut angle = snap_to_axis(0.5 * (2.0 * cxy).atan2(cxx - cyy));
+    let rotation = na::Rotation2::new(-angle);
+    // The radii and the center are taken from the extents along the axes,
+    // because the variance along an axis depends on how the points are distributed on the perimeter.
+    let (mins, maxs) = points.iter().map(|&p| rotation * (p - center)).fold(
+        (
+            na::vector![f64::MAX, f64::MAX],
+            na::vector![f64::MIN, f64::MIN],
+        ),
+        |(mins, maxs), p| (mins.inf(&p), maxs.sup(&p)),
+    );
+    let center = center + rotation.inverse() * ((mins + maxs) * 0.5);
+    let mut radii = (maxs - mins) * 0.5;
+    if radii[0] <= 0.0 || radii[1] <= 0.0 {
+        return None;
+    }
+    let local_points = points
+        .iter()
+        .map(|&p| rotation * (p - center))
+        .collect::<Vec<na::Vector2<f64>>>();
+
+    let fits = local_points.iter().all(|p| {
+        let phi = p[1].atan2(p[0]);
+        let expected = radii[0] * radii[1]
+            / ((radii[1] * phi.cos()).powi(2) + (radii[0] * phi.sin()).powi(2)).sqrt();
+        (p.magnitude() - expected).abs() < abs_tolerance
+    });
+    if !fits {
+        return None;
+    }
+
+    if (radii[0] - radii[1]).abs() < CIRCLE_RADII_RATIO * radii[0].max(radii[1]) {
+        radii = na::Vector2::repeat((radii[0] + radii[1]) * 0.5);
+        angle = 0.0;
+    }
+
+    Some(Ellipse {
+        radii,
+        transform: Transform::new_w_isometry(na::Isometry2::new(center, angle)),
+    })
+}
+
+/// Snaps the angle to the nearest axis, if it is close to it.
+fn snap_to_axis(angle: f64) -> f64 {
+    let nearest_axis = (angle / FRAC_PI_2).round() * FRAC_PI_2;
+    if (angle - nearest_axis).abs() < AXIS_SNAP_ANGLE {
+        nearest_axis
+    } else {
+        angle
+    }
+}
+
+/// The vertices of the closed polyline where the direction changes significantly.
+fn corners_of_closed_polyline(vertices: &[na::Vector2<f64>]) -> Vec<na::Vector2<f64>> {
+    let n = vertices.len();
+    if n < 3 {
+        return vec![];
+    }
+
+    (0..n)
+        .filter_map(|i| {
+            let vertex = vertices[i];
+            let incoming = vertex - vertices[(i + n - 1) % n];
+            let outgoing = vertices[(i + 1) % n] - vertex;
+            (incoming.angle(&outgoing) > CORNER_MIN_TURN_ANGLE).then_some(vertex)
+        })
+        .collect()
+}
+
+/// Simplifies the polyline with the Ramer-Douglas-Peucker algorithm.
+fn simplify_polyline(points: &[na::Vector2<f64>], epsilon: f64) -> Vec<na::Vector2<f64>> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let first = points[0];
+    let last = points[points.len() - 1];
+
+    let (max_i, max_dist) = points
+        .iter()
+        .enumerate()
+        .skip(1)
+        .take(points.len() - 2)
+        .map(|(i, &p)| (i, dist_to_line_segment(p, first, last)))
+        .fold(
+            (0, 0.0),
+            |acc, (i, dist)| {
+                if dist > acc.1 {
+                    (i, dist)
+                } else {
+                    acc
+                }
+            },
+        );
+
+    if max_dist > epsilon {
+        let mut simplified = simplify_polyline(&points[..=max_i], epsilon);
+        simplified.pop();
+        simplified.extend(simplify_polyline(&points[max_i..], epsilon));
+        simplified
+    } else {
+        vec![first, last]
+    }
+}
+
+/// The distance of the point to the line segment.
+fn dist_to_line_segment(
+    point: na::Vector2<f64>,
+    start: na::Vector2<f64>,
+    end: na::Vector2<f64>,
+) -> f64 {
+    let v = end - start;
+    let len_sq = v.magnitude_squared();
+    if len_sq == 0.0 {
+        return (point - start).magnitude();
+    }
+    let t = ((point - start).dot(&v) / len_sq).clamp(0.0, 1.0);
+    (point - (start + v * t)).magnitude()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOLERANCE: f64 = 0.05;
+
+    /// Samples the closed polyline through the corners with the given number of points per edge.
+    fn closed_polyline(corners: &[na::Vector2<f64>], n_per_edge: usize) -> Vec<na::Vector2<f64>> {
+        let n = corners.len();
+        let mut points = (0..n)
+            .flat_map(|i| {
+                let (start, end) = (corners[i], corners[(i + 1) % n]);
+                (0..n_per_edge).map(move |j| start + (end - start) * (j as f64 / n_per_edge as f64))
+            })
+            .collect::<Vec<na::Vector2<f64>>>();
+        points.push(corners[0]);
+        points
+    }
+
+    fn ellipse_points(
+        center: na::Vector2<f64>,
+        radii: na::Vector2<f64>,
+        angle: f64,
+    ) -> Vec<na::Vector2<f64>> {
+        let rotation = na::Rotation2::new(angle);
+        (0..=72)
+            .map(|i| {
+                let phi = (i as f64 * 5.0).to_radians();
+                center + rotation * na::vector![radii[0] * phi.cos(), radii[1] * phi.sin()]
+            })
+            .collect()
+    }
+
+    fn assert_vec_eq(a: na::Vector2<f64>, b: na::Vector2<f64>, epsilon: f64) {
+        assert!((a - b).magnitude() < epsilon, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn recognize_line() {
+        // A slightly shaky line
+        let points = (0..=20)
+            .map(|i| na::vector![i as f64 * 10.0, 50.0 + if i % 2 == 0 { 1.0 } else { -1.0 }])
+            .collect::<Vec<na::Vector2<f64>>>();
+
+        let Some(Shape::Line(line)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as line");
+        };
+        assert_eq!(line.start, points[0]);
+        assert_eq!(line.end, points[20]);
+    }
+
+    #[test]
+    fn recognize_rectangle() {
+        let corners = [
+            na::vector![10.0, 10.0],
+            na::vector![210.0, 10.0],
+            na::vector![210.0, 110.0],
+            na::vector![10.0, 110.0],
+        ];
+        let points = closed_polyline(&corners, 10);
+
+        let Some(Shape::Rectangle(rectangle)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as rectangle");
+        };
+        assert_vec_eq(
+            rectangle.cuboid.half_extents,
+            na::vector![100.0, 50.0],
+            1e-6,
+        );
+        assert_vec_eq(
+            rectangle.transform.translation_part(),
+            na::vector![110.0, 60.0],
+            1e-6,
+        );
+        assert!(rectangle.transform.transform_vec(na::Vector2::x())[1].abs() < 1e-6);
+    }
+
+    #[test]
+    fn recognize_rotated_rectangle() {
+        let rotation = na::Rotation2::new(30.0_f64.to_radians());
+        let corners = [
+            na::vector![-100.0, -50.0],
+            na::vector![100.0, -50.0],
+            na::vector![100.0, 50.0],
+            na::vector![-100.0, 50.0],
+        ]
+        .map(|corner| rotation * corner);
+        let points = closed_polyline(&corners, 10);
+
+        let Some(Shape::Rectangle(rectangle)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as rectangle");
+        };
+        let half_extents = rectangle.cuboid.half_extents;
+        // The orientation is only defined modulo a right angle
+        assert!(
+            (half_extents - na::vector![100.0, 50.0]).magnitude() < 1e-6
+                || (half_extents - na::vector![50.0, 100.0]).magnitude() < 1e-6
+        );
+        assert_vec_eq(
+            rectangle.transform.translation_part(),
+            na::Vector2::zeros(),
+            1e-6,
+        );
+    }
+
+    #[test]
+    fn recognize_ellipse_and_circle() {
+        let points = ellipse_points(na::vector![100.0, 100.0], na::vector![80.0, 40.0], 0.0);
+
+        let Some(Shape::Ellipse(ellipse)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as ellipse");
+        };
+        assert_vec_eq(ellipse.radii, na::vector![80.0, 40.0], 1.0);
+        assert_vec_eq(
+            ellipse.transform.translation_part(),
+            na::vector![100.0, 100.0],
+            1.0,
+        );
+
+        let points = ellipse_points(na::vector![0.0, 0.0], na::vector![50.0, 48.0], 0.0);
+
+        let Some(Shape::Ellipse(circle)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as circle");
+        };
+        assert_eq!(circle.radii[0], circle.radii[1]);
+        assert!((circle.radii[0] - 49.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn recognize_arrow() {
+        // Drawn from the stem start to the tip, along the first barb and back, and then along the second barb
+        let vertices = [
+            na::vector![0.0, 0.0],
+            na::vector![200.0, 0.0],
+            na::vector![170.0, -20.0],
+            na::vector![200.0, 0.0],
+            na::vector![170.0, 20.0],
+        ];
+        let mut points = vertices
+            .windows(2)
+            .flat_map(|w| (0..10).map(move |j| w[0] + (w[1] - w[0]) * (j as f64 / 10.0)))
+            .collect::<Vec<na::Vector2<f64>>>();
+        points.push(vertices[4]);
+
+        let Some(Shape::Arrow(arrow)) = recognize_shape(&points, TOLERANCE) else {
+            panic!("the path was not recognized as arrow");
+        };
+        assert_vec_eq(arrow.start, na::vector![0.0, 0.0], 1e-6);
+        assert_vec_eq(arrow.tip, na::vector![200.0, 0.0], 1e-6);
+
+        // Both barbs on the same side of the stem
+        let points = [
+            na::vector![0.0, 0.0],
+            na::vector![200.0, 0.0],
+            na::vector![170.0, -20.0],
+            na::vector![200.0, 0.0],
+            na::vector![175.0, -25.0],
+        ];
+        assert!(super::recognize_arrow(&points, TOLERANCE * 200.0).is_none());
+    }
+
+    #[test]
+    fn recognize_tolerance_boundary() {
+        // A line with a bump of 5.0 in the middle, the size of the path is ~100.0
+        let points = vec![
+            na::vector![0.0, 0.0],
+            na::vector![50.0, 5.0],
+            na::vector![100.0, 0.0],
+        ];
+        let diag = na::vector![100.0, 5.0].magnitude();
+
+        assert!(matches!(
+            recognize_shape(&points, 5.1 / diag),
+            Some(Shape::Line(_))
+        ));
+        assert!(recognize_shape(&points, 4.9 / diag).is_none());
+    }
+
+    #[test]
+    fn recognize_nothing() {
+        // Too small
+        assert!(
+            recognize_shape(&[na::vector![0.0, 0.0], na::vector![1.0, 1.0]], TOLERANCE).is_none()
+        );
+        assert!(recognize_shape(&[na::vector![0.0, 0.0]], TOLERANCE).is_none());
+
+        // A zigzag
+        let points = (0..=8)
+            .map(|i| na::vector![i as f64 * 20.0, if i % 2 == 0 { 0.0 } else { 40.0 }])
+            .collect::<Vec<na::Vector2<f64>>>();
+        assert!(recognize_shape(&points, TOLERANCE).is_none());
+
+        // A triangle is neither rectangle nor ellipse
+        let corners = [
+            na::vector![0.0, 0.0],
+            na::vector![200.0, 0.0],
+            na::vector![100.0, 150.0],
+        ];
+        assert!(recognize_shape(&closed_polyline(&corners, 10), TOLERANCE).is_none());
+    }
+}
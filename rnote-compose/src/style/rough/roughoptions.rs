@@ -22,6 +22,9 @@ pub struct RoughOptions {
     /// Hachure angle (in radians).
     #[serde(rename = "hachure_angle", with = "crate::serialize::f64_dp3")]
     pub hachure_angle: f64,
+    /// The roughness. `0.0` draws the shapes without any roughness, higher values draw them rougher.
+    #[serde(rename = "roughness", with = "crate::serialize::f64_dp3")]
+    pub roughness: f64,
     /// An optional seed to generate reproducible shapes.
     #[serde(rename = "seed")]
     pub seed: Option<u64>,
@@ -36,6 +39,7 @@ impl Default for RoughOptions {
             fill_style: FillStyle::Hachure,
             // Default hachure angle (in rad). is -41 degrees
             hachure_angle: -0.715585,
+            roughness: 1.0,
             seed: None,
         }
     }
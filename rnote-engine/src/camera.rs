@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 // Imports
+#[cfg(feature = "gtk")]
 use gtk4::{graphene, gsk};
 use p2d::bounding_volume::Aabb;
 use rnote_compose::helpers::AabbHelpers;
@@ -58,8 +59,14 @@ impl Camera {
     pub const ZOOM_TIMEOUT: Duration = Duration::from_millis(400);
     // when performing a drag - zoom 0.5% zoom for every pixel in y dir
     pub const DRAG_ZOOM_MAGN_ZOOM_FACTOR: f64 = 0.005;
+    // The relative zoom change for a single zoom in / out step.
+    pub const ZOOM_STEP: f64 = 0.1;
+    // The margin around the document width when zooming to fit it.
+    pub const ZOOM_FIT_WIDTH_MARGIN: f64 = 32.0;
     pub const OVERSHOOT_HORIZONTAL: f64 = 96.0;
     pub const OVERSHOOT_VERTICAL: f64 = 96.0;
+    // The tolerance, relative to the page height, for considering the viewport aligned to a page
+    const PAGE_ALIGN_TOLERANCE: f64 = 1e-3;
 
     pub fn with_zoom(mut self, zoom: f64) -> Self {
         self.zoom = zoom.clamp(Self::ZOOM_MIN, Self::ZOOM_MAX);
@@ -240,6 +247,31 @@ impl Camera {
         widget_flags
     }
 
+    /// The offset that aligns the top of the viewport with the top of the next (or previous) page of the document.
+    ///
+    /// The returned offset is in surface coordinate space and is clamped to the first and last page.
+    pub fn adjacent_page_offset(&self, doc: &Document, forward: bool) -> na::Vector2<f64> {
+        let page_height = doc.format.height;
+        if page_height <= 0.0 {
+            return self.offset;
+        }
+        let n_pages = (doc.height / page_height).ceil().max(1.0);
+        let current_page = (self.viewport().mins[1] - doc.y) / page_height;
+
+        // the tolerance makes a viewport that is already aligned to a page count as being on that page
+        let target_page = if forward {
+            (current_page + Self::PAGE_ALIGN_TOLERANCE).floor() + 1.0
+        } else {
+            (current_page - Self::PAGE_ALIGN_TOLERANCE).ceil() - 1.0
+        }
+        .clamp(0.0, n_pages - 1.0);
+
+        na::vector![
+            self.offset[0],
+            (doc.y + target_page * page_height) * self.total_zoom()
+        ]
+    }
+
     /// Transform Aabb from document coords to surface coords.
     pub fn transform_bounds(&self, bounds: Aabb) -> Aabb {
         bounds.scale(self.total_zoom()).translate(-self.offset)
@@ -269,6 +301,7 @@ impl Camera {
     /// GTKs transformations are applied on its coordinate system,
     /// so we need to reverse the transformation order (translate, then scale).
     /// To get the inverse, call .invert().
+    #[cfg(feature = "gtk")]
     pub fn transform_for_gtk_snapshot(&self) -> gsk::Transform {
         let total_zoom = self.total_zoom();
 
@@ -283,7 +316,8 @@ impl Camera {
 
 #[cfg(test)]
 mod tests {
-    use crate::Camera;
+    use crate::document::Format;
+    use crate::{Camera, Document};
     use approx::assert_relative_eq;
 
     #[test]
@@ -320,4 +354,45 @@ mod tests {
         assert_relative_eq!(viewport.mins, mins);
         assert_relative_eq!(viewport.maxs, maxs);
     }
+
+    #[test]
+    fn adjacent_page_offset() {
+        let zoom = 2.0;
+        let doc = Document {
+            y: 0.0,
+            height: 300.0,
+            format: Format {
+                height: 100.0,
+                ..Format::default()
+            },
+            ..Document::default()
+        };
+        let camera = Camera::default()
+            .with_zoom(zoom)
+            .with_offset(na::vector![5.0, 150.0 * zoom])
+            .with_size(na::vector![50.0, 50.0]);
+
+        assert_relative_eq!(
+            camera.adjacent_page_offset(&doc, true),
+            na::vector![5.0, 200.0 * zoom]
+        );
+        assert_relative_eq!(
+            camera.adjacent_page_offset(&doc, false),
+            na::vector![5.0, 100.0 * zoom]
+        );
+
+        // aligned to the top of a page
+        let camera = camera.with_offset(na::vector![5.0, 100.0 * zoom]);
+        assert_relative_eq!(
+            camera.adjacent_page_offset(&doc, false),
+            na::vector![5.0, 0.0]
+        );
+
+        // clamped to the last page
+        let camera = camera.with_offset(na::vector![5.0, 200.0 * zoom]);
+        assert_relative_eq!(
+            camera.adjacent_page_offset(&doc, true),
+            na::vector![5.0, 200.0 * zoom]
+        );
+    }
 }
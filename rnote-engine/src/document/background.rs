@@ -32,6 +32,16 @@ pub enum PatternStyle {
     IsometricGrid,
     #[serde(rename = "isometric_dots")]
     IsometricDots,
+    #[serde(rename = "staves")]
+    Staves,
+    #[serde(rename = "cornell_notes")]
+    CornellNotes,
+    #[serde(rename = "grid_major_lines")]
+    GridMajorLines,
+    #[serde(rename = "hexagon_grid")]
+    HexagonGrid,
+    #[serde(rename = "lines_margin")]
+    LinesMargin,
 }
 
 impl Default for PatternStyle {
@@ -190,6 +200,182 @@ pub fn gen_dots_pattern(
     group.into()
 }
 
+pub fn gen_staves_pattern(
+    bounds: Aabb,
+    line_spacing: f64,
+    staff_spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    // line_spacing: distance between the five lines of a staff
+    // staff_spacing: distance between the first lines of two consecutive staves
+
+    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_staves_pattern";
+
+    let line_offset = line_width * 0.5;
+
+    let mut pattern = element::Pattern::new()
+        .set("id", pattern_id.as_str())
+        .set("x", 0_f64)
+        .set("y", 0_f64)
+        .set("width", bounds.extents()[0])
+        .set("height", staff_spacing)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternContentUnits", "userSpaceOnUse");
+
+    for i in 0..5 {
+        let y = line_offset + f64::from(i) * line_spacing;
+        pattern = pattern.add(
+            element::Line::new()
+                .set("stroke-width", line_width)
+                .set("stroke", color.to_css_color_attr())
+                .set("x1", 0_f64)
+                .set("y1", y)
+                .set("x2", bounds.extents()[0])
+                .set("y2", y),
+        );
+    }
+
+    let pattern = element::Definitions::new().add(pattern);
+
+    let mut rect = element::Rectangle::new().set("fill", format!("url(#{pattern_id})"));
+
+    rect.assign("x", format!("{}px", bounds.mins[0]));
+    rect.assign("y", format!("{}px", bounds.mins[1]));
+    rect.assign("width", format!("{}px", bounds.extents()[0]));
+    rect.assign("height", format!("{}px", bounds.extents()[1]));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+pub fn gen_grid_major_lines_pattern(
+    bounds: Aabb,
+    row_spacing: f64,
+    column_spacing: f64,
+    major_line_interval: u32,
+    color: Color,
+    line_width: f64,
+    major_line_width: f64,
+) -> svg::node::element::Element {
+    // Every major_line_interval'th line is a major line, the pattern spans from one major line to the next
+
+    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_grid_major_lines_pattern";
+    let major_line_interval = major_line_interval.max(1);
+    let pattern_width = column_spacing * f64::from(major_line_interval);
+    let pattern_height = row_spacing * f64::from(major_line_interval);
+
+    let line_offset = major_line_width * 0.5;
+
+    let mut pattern = element::Pattern::new()
+        .set("id", pattern_id.as_str())
+        .set("x", 0_f64)
+        .set("y", 0_f64)
+        .set("width", pattern_width)
+        .set("height", pattern_height)
+        .set("patternUnits", "userSpaceOnUse")
+        .set("patternContentUnits", "userSpaceOnUse");
+
+    for i in 0..major_line_interval {
+        let width = if i == 0 { major_line_width } else { line_width };
+        let x = line_offset + f64::from(i) * column_spacing;
+        let y = line_offset + f64::from(i) * row_spacing;
+
+        pattern = pattern
+            .add(
+                element::Line::new()
+                    .set("stroke-width", width)
+                    .set("stroke", color.to_css_color_attr())
+                    .set("x1", 0_f64)
+                    .set("y1", y)
+                    .set("x2", pattern_width)
+                    .set("y2", y),
+            )
+            .add(
+                element::Line::new()
+                    .set("stroke-width", width)
+                    .set("stroke", color.to_css_color_attr())
+                    .set("x1", x)
+                    .set("y1", 0_f64)
+                    .set("x2", x)
+                    .set("y2", pattern_height),
+            );
+    }
+
+    let pattern = element::Definitions::new().add(pattern);
+
+    let mut rect = element::Rectangle::new().set("fill", format!("url(#{pattern_id})"));
+
+    rect.assign("x", format!("{}px", bounds.mins[0]));
+    rect.assign("y", format!("{}px", bounds.mins[1]));
+    rect.assign("width", format!("{}px", bounds.extents()[0]));
+    rect.assign("height", format!("{}px", bounds.extents()[1]));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+pub fn gen_hexagon_grid_pattern(
+    bounds: Aabb,
+    spacing: f64,
+    color: Color,
+    line_width: f64,
+) -> svg::node::element::Element {
+    // spacing: distance between two opposite sides of the pointy-top hexagons
+    // side: side length of the hexagons
+    // pattern_height: three times the side length, spanning two rows of hexagons
+
+    let pattern_id = rnote_compose::utils::svg_random_id_prefix() + "_bg_hexagon_grid_pattern";
+    let side = spacing / SQRT_THREE;
+    let pattern_width = spacing;
+    let pattern_height = calc_height_hexagon_pattern(spacing);
+
+    let line_offset = line_width * 0.5;
+
+    let hexagon_edges = element::path::Data::new()
+        .move_to((line_offset + pattern_width * 0.5, 0.0))
+        .line_to((line_offset + pattern_width, side * 0.5))
+        .move_to((line_offset + pattern_width * 0.5, 0.0))
+        .line_to((line_offset, side * 0.5))
+        .line_to((line_offset, side * 1.5))
+        .line_to((line_offset + pattern_width * 0.5, side * 2.0))
+        .line_to((line_offset + pattern_width, side * 1.5))
+        .move_to((line_offset + pattern_width * 0.5, side * 2.0))
+        .line_to((line_offset + pattern_width * 0.5, pattern_height));
+
+    let pattern = element::Definitions::new().add(
+        element::Pattern::new()
+            .set("id", pattern_id.as_str())
+            .set("x", 0_f64)
+            .set("y", 0_f64)
+            .set("width", pattern_width)
+            .set("height", pattern_height)
+            .set("patternUnits", "userSpaceOnUse")
+            .set("patternContentUnits", "userSpaceOnUse")
+            .add(
+                element::Path::new()
+                    .set("stroke-width", line_width)
+                    .set("stroke", color.to_css_color_attr())
+                    .set("fill", "none")
+                    .set("d", hexagon_edges),
+            ),
+    );
+
+    let mut rect = element::Rectangle::new().set("fill", format!("url(#{pattern_id})"));
+
+    rect.assign("x", format!("{}px", bounds.mins[0]));
+    rect.assign("y", format!("{}px", bounds.mins[1]));
+    rect.assign("width", format!("{}px", bounds.extents()[0]));
+    rect.assign("height", format!("{}px", bounds.extents()[1]));
+
+    let group = element::Group::new().add(pattern).add(rect);
+    group.into()
+}
+
+fn calc_height_hexagon_pattern(spacing: f64) -> f64 {
+    spacing * SQRT_THREE
+}
+
 fn calc_width_iso_pattern(spacing: f64) -> f64 {
     spacing * SQRT_THREE
 }
@@ -331,7 +517,7 @@ pub fn gen_iso_dots_pattern(
     group.into()
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(default, rename = "background")]
 pub struct Background {
     #[serde(rename = "color")]
@@ -345,6 +531,9 @@ pub struct Background {
     pub pattern_size: na::Vector2<f64>,
     #[serde(rename = "pattern_color")]
     pub pattern_color: Color,
+    /// The number of cells between two major lines of the `GridMajorLines` pattern.
+    #[serde(rename = "major_line_interval")]
+    pub major_line_interval: u32,
 }
 
 impl Default for Background {
@@ -354,18 +543,27 @@ impl Default for Background {
             pattern: PatternStyle::default(),
             pattern_size: Self::PATTERN_SIZE_DEFAULT,
             pattern_color: Self::PATTERN_COLOR_DEFAULT,
+            major_line_interval: Self::MAJOR_LINE_INTERVAL_DEFAULT,
         }
     }
 }
 
 impl Background {
     const LINE_WIDTH: f64 = 0.5;
+    const MAJOR_LINE_WIDTH: f64 = 1.2;
     const DOTS_WIDTH: f64 = 1.5;
     const HEXAGON_HEIGHT: f64 = 2.0;
+    /// The distance of the margin line from the left page edge, relative to the page width.
+    const MARGIN_WIDTH_RATIO: f64 = 0.15;
+    /// The width of the Cornell notes cue column, relative to the page width.
+    const CORNELL_CUE_COLUMN_WIDTH_RATIO: f64 = 0.3;
+    /// The height of the Cornell notes summary area, relative to the page height.
+    const CORNELL_SUMMARY_HEIGHT_RATIO: f64 = 0.2;
 
     const TILE_MAX_SIZE: f64 = 128.0;
     const COLOR_DEFAULT: Color = Color::WHITE;
     const PATTERN_SIZE_DEFAULT: na::Vector2<f64> = na::vector![32.0, 32.0];
+    const MAJOR_LINE_INTERVAL_DEFAULT: u32 = 5;
     const PATTERN_COLOR_DEFAULT: Color = Color {
         r: 0.8,
         g: 0.9,
@@ -379,7 +577,7 @@ impl Background {
             PatternStyle::None => {
                 na::vector![Self::TILE_MAX_SIZE, Self::TILE_MAX_SIZE]
             }
-            PatternStyle::Lines => {
+            PatternStyle::Lines | PatternStyle::CornellNotes | PatternStyle::LinesMargin => {
                 na::vector![Self::TILE_MAX_SIZE, self.pattern_size[1]]
             }
             PatternStyle::IsometricGrid | PatternStyle::IsometricDots => {
@@ -388,6 +586,18 @@ impl Background {
                     self.pattern_size[1]
                 ]
             }
+            PatternStyle::Staves => {
+                na::vector![Self::TILE_MAX_SIZE, self.staff_spacing()]
+            }
+            PatternStyle::GridMajorLines => {
+                self.pattern_size * f64::from(self.major_line_interval.max(1))
+            }
+            PatternStyle::HexagonGrid => {
+                na::vector![
+                    self.pattern_size[1],
+                    calc_height_hexagon_pattern(self.pattern_size[1])
+                ]
+            }
             _ => self.pattern_size,
         };
 
@@ -423,7 +633,7 @@ impl Background {
         if with_pattern {
             match self.pattern {
                 PatternStyle::None => {}
-                PatternStyle::Lines => {
+                PatternStyle::Lines | PatternStyle::CornellNotes | PatternStyle::LinesMargin => {
                     svg_group = svg_group.add(gen_hline_pattern(
                         bounds,
                         self.pattern_size[1],
@@ -465,6 +675,34 @@ impl Background {
                         Self::HEXAGON_HEIGHT,
                     ));
                 }
+                PatternStyle::Staves => {
+                    svg_group = svg_group.add(gen_staves_pattern(
+                        bounds,
+                        self.pattern_size[1] * 0.25,
+                        self.staff_spacing(),
+                        self.pattern_color,
+                        Self::LINE_WIDTH,
+                    ));
+                }
+                PatternStyle::GridMajorLines => {
+                    svg_group = svg_group.add(gen_grid_major_lines_pattern(
+                        bounds,
+                        self.pattern_size[1],
+                        self.pattern_size[0],
+                        self.major_line_interval,
+                        self.pattern_color,
+                        Self::LINE_WIDTH,
+                        Self::MAJOR_LINE_WIDTH,
+                    ));
+                }
+                PatternStyle::HexagonGrid => {
+                    svg_group = svg_group.add(gen_hexagon_grid_pattern(
+                        bounds,
+                        self.pattern_size[1],
+                        self.pattern_color,
+                        Self::LINE_WIDTH,
+                    ));
+                }
             }
         }
 
@@ -474,6 +712,80 @@ impl Background {
         Ok(render::Svg { svg_data, bounds })
     }
 
+    /// The lines of the pattern that are placed relative to the page instead of being repeated, as rectangles.
+    ///
+    /// These are not part of the tile image and the generated background svg.
+    pub fn page_decoration_rects(&self, page_bounds: Aabb) -> Vec<Aabb> {
+        let page_size = page_bounds.extents();
+        let half_width = Self::MAJOR_LINE_WIDTH * 0.5;
+        let vline = |x: f64, y_start: f64, y_end: f64| {
+            Aabb::new(
+                na::point![x - half_width, y_start],
+                na::point![x + half_width, y_end],
+            )
+        };
+        let hline = |y: f64| {
+            Aabb::new(
+                na::point![page_bounds.mins[0], y - half_width],
+                na::point![page_bounds.maxs[0], y + half_width],
+            )
+        };
+
+        match self.pattern {
+            PatternStyle::LinesMargin => vec![vline(
+                page_bounds.mins[0] + page_size[0] * Self::MARGIN_WIDTH_RATIO,
+                page_bounds.mins[1],
+                page_bounds.maxs[1],
+            )],
+            PatternStyle::CornellNotes => {
+                let summary_y =
+                    page_bounds.maxs[1] - page_size[1] * Self::CORNELL_SUMMARY_HEIGHT_RATIO;
+                vec![
+                    vline(
+                        page_bounds.mins[0] + page_size[0] * Self::CORNELL_CUE_COLUMN_WIDTH_RATIO,
+                        page_bounds.mins[1],
+                        summary_y,
+                    ),
+                    hline(summary_y),
+                ]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Generate the svg of the page decorations, clipped to the bounds. Without Xml header or Svg root.
+    pub fn gen_page_decorations_svg(
+        &self,
+        page_bounds: Aabb,
+        bounds: Aabb,
+    ) -> Result<render::Svg, anyhow::Error> {
+        let mut svg_group = element::Group::new();
+
+        for decoration_bounds in self
+            .page_decoration_rects(page_bounds)
+            .into_iter()
+            .filter_map(|rect| rect.intersection(&bounds))
+        {
+            let mut rect =
+                element::Rectangle::new().set("fill", self.pattern_color.to_css_color_attr());
+            rect.assign("x", format!("{}px", decoration_bounds.mins[0]));
+            rect.assign("y", format!("{}px", decoration_bounds.mins[1]));
+            rect.assign("width", format!("{}px", decoration_bounds.extents()[0]));
+            rect.assign("height", format!("{}px", decoration_bounds.extents()[1]));
+            svg_group = svg_group.add(rect);
+        }
+
+        let svg_data = rnote_compose::utils::svg_node_to_string(&svg_group)
+            .context("svg_node_to_string() failed for background page decorations")?;
+
+        Ok(render::Svg { svg_data, bounds })
+    }
+
+    /// The distance between two staves of the staves pattern, twice the height of a staff.
+    fn staff_spacing(&self) -> f64 {
+        self.pattern_size[1] * 2.0
+    }
+
     pub fn gen_tile_image(&self, image_scale: f64) -> Result<render::Image, anyhow::Error> {
         let tile_size = self.tile_size();
         let tile_bounds = Aabb::new(na::point![0.0, 0.0], na::point![tile_size[0], tile_size[1]]);
@@ -481,3 +793,74 @@ impl Background {
         render::Image::gen_image_from_svg(svg, tile_bounds, image_scale)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use p2d::bounding_volume::BoundingVolume;
+
+    /// Generates the svg and the tile image of the pattern and checks that both have content.
+    fn check_pattern_output(pattern: PatternStyle) {
+        let background = Background {
+            pattern,
+            ..Default::default()
+        };
+        let bounds = Aabb::new(na::point![0.0, 0.0], na::point![800.0, 1000.0]);
+
+        let svg = background.gen_svg(bounds, true).unwrap();
+        let svg_wo_pattern = background.gen_svg(bounds, false).unwrap();
+        assert!(svg.svg_data.len() > svg_wo_pattern.svg_data.len());
+
+        let tile_image = background.gen_tile_image(1.0).unwrap();
+        assert!(tile_image.pixel_width > 0 && tile_image.pixel_height > 0);
+        let tile_imgbuf = tile_image.to_imgbuf().unwrap();
+        let first_pixel = *tile_imgbuf.get_pixel(0, 0);
+        // The pattern is drawn onto the background color
+        assert!(tile_imgbuf.pixels().any(|pixel| *pixel != first_pixel));
+    }
+
+    /// Checks that the pattern has page decorations and generates their svg.
+    fn check_page_decorations(pattern: PatternStyle, n_rects: usize) {
+        let background = Background {
+            pattern,
+            ..Default::default()
+        };
+        let page_bounds = Aabb::new(na::point![0.0, 1000.0], na::point![800.0, 2000.0]);
+
+        let rects = background.page_decoration_rects(page_bounds);
+        assert_eq!(rects.len(), n_rects);
+        assert!(rects.iter().all(|rect| page_bounds.contains(rect)));
+
+        let svg = background
+            .gen_page_decorations_svg(page_bounds, page_bounds)
+            .unwrap();
+        assert!(svg.svg_data.contains("<rect"));
+    }
+
+    #[test]
+    fn staves_pattern() {
+        check_pattern_output(PatternStyle::Staves);
+    }
+
+    #[test]
+    fn cornell_notes_pattern() {
+        check_pattern_output(PatternStyle::CornellNotes);
+        check_page_decorations(PatternStyle::CornellNotes, 2);
+    }
+
+    #[test]
+    fn grid_major_lines_pattern() {
+        check_pattern_output(PatternStyle::GridMajorLines);
+    }
+
+    #[test]
+    fn hexagon_grid_pattern() {
+        check_pattern_output(PatternStyle::HexagonGrid);
+    }
+
+    #[test]
+    fn lines_margin_pattern() {
+        check_pattern_output(PatternStyle::LinesMargin);
+        check_page_decorations(PatternStyle::LinesMargin, 1);
+    }
+}
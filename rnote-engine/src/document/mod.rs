@@ -7,9 +7,10 @@ pub use background::Background;
 pub use format::Format;
 
 // Imports
-use crate::{Camera, StrokeStore, WidgetFlags};
+use crate::{render, Camera, StrokeStore, WidgetFlags};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::helpers::{AabbHelpers, SplitOrder};
+use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
 
@@ -80,7 +81,7 @@ impl std::string::ToString for Layout {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "document")]
 pub struct Document {
     #[serde(rename = "x", with = "rnote_compose::serialize::f64_dp3")]
@@ -97,6 +98,23 @@ pub struct Document {
     pub background: Background,
     #[serde(rename = "layout", alias = "expand_mode")]
     pub layout: Layout,
+    /// Background overrides for individual pages, indexed by the page index.
+    ///
+    /// Only used in fixed size layout, pages without an override use the document background.
+    /// There is no UI to set them yet, they can only be set through the engine API.
+    #[serde(rename = "page_backgrounds")]
+    pub page_backgrounds: Vec<Option<Background>>,
+}
+
+/// The state of the document pages that is changed by the page operations in fixed size layout.
+///
+/// It is recorded in the history together with the changes of the store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PagesState {
+    /// The document height, which determines the number of pages.
+    pub height: f64,
+    /// The background overrides of the pages.
+    pub page_backgrounds: Vec<Option<Background>>,
 }
 
 impl Default for Document {
@@ -109,6 +127,7 @@ impl Default for Document {
             format: Format::default(),
             background: Background::default(),
             layout: Layout::default(),
+            page_backgrounds: vec![],
         }
     }
 }
@@ -156,6 +175,155 @@ impl Document {
         }
     }
 
+    /// Whether the document has any page background overrides that are in use.
+    pub fn has_page_backgrounds(&self) -> bool {
+        self.layout == Layout::FixedSize && self.page_backgrounds.iter().any(|bg| bg.is_some())
+    }
+
+    /// The background of the page with the given index, which is the document background if it has no override.
+    pub fn page_background(&self, index: u32) -> &Background {
+        if self.layout != Layout::FixedSize {
+            return &self.background;
+        }
+        self.page_backgrounds
+            .get(index as usize)
+            .and_then(|bg| bg.as_ref())
+            .unwrap_or(&self.background)
+    }
+
+    /// Set or clear the background override of the page with the given index.
+    pub fn set_page_background(&mut self, index: u32, background: Option<Background>) {
+        let index = index as usize;
+        if index >= self.page_backgrounds.len() {
+            if background.is_none() {
+                return;
+            }
+            self.page_backgrounds.resize(index + 1, None);
+        }
+        self.page_backgrounds[index] = background;
+        self.trim_page_backgrounds();
+    }
+
+    /// Insert a page background override at the given index, shifting the overrides of the pages below it.
+    pub(crate) fn insert_page_background(&mut self, index: u32, background: Option<Background>) {
+        let index = index as usize;
+        if index > self.page_backgrounds.len() {
+            if background.is_none() {
+                return;
+            }
+            self.page_backgrounds.resize(index, None);
+        }
+        self.page_backgrounds.insert(index, background);
+        self.trim_page_backgrounds();
+    }
+
+    /// Remove the page background override at the given index, shifting the overrides of the pages below it.
+    pub(crate) fn remove_page_background(&mut self, index: u32) -> Option<Background> {
+        let index = index as usize;
+        if index >= self.page_backgrounds.len() {
+            return None;
+        }
+        let background = self.page_backgrounds.remove(index);
+        self.trim_page_backgrounds();
+        background
+    }
+
+    /// The bounds of the page with the given index in fixed size layout.
+    pub fn page_bounds_fixed_size(&self, index: u32) -> Aabb {
+        let page_y = self.y + f64::from(index) * self.format.height;
+        Aabb::new(
+            na::point![self.x, page_y],
+            na::point![self.x + self.width, page_y + self.format.height],
+        )
+    }
+
+    /// The index of the page the position is on in fixed size layout.
+    ///
+    /// Positions above or below the document are clamped to the first or last page.
+    pub fn page_index_fixed_size(&self, pos: na::Vector2<f64>) -> u32 {
+        if self.format.height <= 0.0 {
+            return 0;
+        }
+        let n_pages = (self.height / self.format.height).round().max(1.0);
+        ((pos[1] - self.y) / self.format.height)
+            .floor()
+            .clamp(0.0, n_pages - 1.0) as u32
+    }
+
+    /// The bounds of the pages that intersect the given bounds, together with their background.
+    ///
+    /// The pages are aligned to the format size, so in layouts other than fixed size they extend beyond the document.
+    pub fn pages_w_background_intersecting_bounds(&self, bounds: Aabb) -> Vec<(Aabb, &Background)> {
+        if self.format.width <= 0.0 || self.format.height <= 0.0 {
+            return vec![];
+        }
+
+        bounds
+            .split_extended_origin_aligned(
+                na::vector![self.format.width, self.format.height],
+                SplitOrder::default(),
+            )
+            .into_iter()
+            .map(|page_bounds| {
+                let background =
+                    self.page_background(self.page_index_fixed_size(page_bounds.center().coords));
+                (page_bounds, background)
+            })
+            .collect()
+    }
+
+    /// Generate the background svg for the bounds, including the page background overrides and page decorations.
+    pub fn gen_background_svg(
+        &self,
+        bounds: Aabb,
+        with_pattern: bool,
+    ) -> anyhow::Result<render::Svg> {
+        let mut background_svg = self.background.gen_svg(bounds, with_pattern)?;
+
+        if self.has_page_backgrounds() {
+            for (index, page_background) in self.page_backgrounds.iter().enumerate() {
+                let Some(page_background) = page_background else {
+                    continue;
+                };
+                let Some(page_bounds) = self
+                    .page_bounds_fixed_size(index as u32)
+                    .intersection(&bounds) else {
+                    continue;
+                };
+                background_svg.merge([page_background.gen_svg(page_bounds, with_pattern)?]);
+            }
+        }
+
+        if with_pattern {
+            for (page_bounds, background) in self.pages_w_background_intersecting_bounds(bounds) {
+                background_svg.merge([background.gen_page_decorations_svg(page_bounds, bounds)?]);
+            }
+        }
+
+        Ok(background_svg)
+    }
+
+    /// The current state of the pages.
+    pub fn pages_state(&self) -> PagesState {
+        PagesState {
+            height: self.height,
+            page_backgrounds: self.page_backgrounds.clone(),
+        }
+    }
+
+    /// Restore a recorded state of the pages.
+    pub(crate) fn restore_pages_state(&mut self, pages_state: &PagesState) {
+        self.height = pages_state.height;
+        self.page_backgrounds = pages_state.page_backgrounds.clone();
+    }
+
+    /// Remove trailing pages without background override.
+    fn trim_page_backgrounds(&mut self) {
+        while let Some(None) = self.page_backgrounds.last() {
+            self.page_backgrounds.pop();
+        }
+    }
+
     pub(crate) fn resize_to_fit_strokes(
         &mut self,
         store: &StrokeStore,
@@ -230,6 +398,39 @@ impl Document {
         widget_flags
     }
 
+    /// Expand the document by pages when in fixed size layout, until all strokes start inside it.
+    ///
+    /// Used after undo and redo, which can restore strokes on pages that were deleted.
+    /// The document is never shrunk, so that blank pages are preserved.
+    pub(crate) fn expand_fixed_size_to_fit_strokes(&mut self, store: &StrokeStore) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.layout != Layout::FixedSize || self.format.height <= 0.0 {
+            return widget_flags;
+        }
+
+        let max_stroke_start = store
+            .stroke_keys_unordered()
+            .into_iter()
+            .filter_map(|key| {
+                store
+                    .get_stroke_ref(key)
+                    .map(|stroke| stroke.bounds().mins[1])
+            })
+            .reduce(f64::max);
+        let Some(max_stroke_start) = max_stroke_start else {
+            return widget_flags;
+        };
+        let new_height =
+            (((max_stroke_start - self.y) / self.format.height).floor() + 1.0) * self.format.height;
+
+        if new_height > self.height {
+            self.height = new_height;
+            widget_flags.resize = true;
+        }
+
+        widget_flags
+    }
+
     fn resize_doc_fixed_size_layout(&mut self, store: &StrokeStore) {
         let format_height = self.format.height;
 
@@ -1,9 +1,13 @@
 // Imports
 use crate::engine::EngineView;
+#[cfg(feature = "gtk")]
 use crate::utils::GrapheneRectHelpers;
+#[cfg(feature = "gtk")]
 use gtk4::{graphene, prelude::*};
 use p2d::bounding_volume::Aabb;
+#[cfg(feature = "gtk")]
 use piet::RenderContext;
+#[cfg(feature = "gtk")]
 use rnote_compose::helpers::{AabbHelpers, Affine2Helpers};
 
 /// Trait for types that can draw themselves on a [piet::RenderContext].
@@ -35,6 +39,7 @@ pub trait DrawOnDocBehaviour {
     /// Draw itself on the snapshot.
     ///
     /// The snapshot is expected to be untransformed in surface coordinate space.
+    #[cfg(feature = "gtk")]
     fn draw_on_doc_to_gtk_snapshot(
         &self,
         snapshot: &gtk4::Snapshot,
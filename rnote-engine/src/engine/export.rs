@@ -1,17 +1,25 @@
 // Imports
+use super::import::pdf_page_transform;
 use super::{EngineConfig, EngineSnapshot, RnoteEngine};
+use crate::fileformats::pdfannotformat::PdfAnnotFile;
 use crate::fileformats::rnoteformat::RnoteFile;
-use crate::fileformats::{xoppformat, FileFormatSaver};
+use crate::fileformats::{
+    excalidrawformat, inkmlformat, xoppformat, FileFormatLoader, FileFormatSaver,
+};
+use crate::store::chrono_comp::StrokeLayer;
 use crate::store::StrokeKey;
 use crate::strokes::Stroke;
-use crate::{render, DrawBehaviour};
+use crate::{render, Document, DrawBehaviour};
 use anyhow::Context;
 use futures::channel::oneshot;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rnote_compose::helpers::{SplitOrder, Vector2Helpers};
+use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::Color;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
 /// Document export format.
 #[derive(
@@ -35,6 +43,16 @@ pub enum DocExportFormat {
     Pdf,
     #[serde(rename = "xopp")]
     Xopp,
+    #[serde(rename = "png")]
+    Png,
+    #[serde(rename = "jpeg")]
+    Jpeg,
+    #[serde(rename = "inkml")]
+    Inkml,
+    #[serde(rename = "excalidraw")]
+    Excalidraw,
+    #[serde(rename = "annotated_pdf")]
+    AnnotatedPdf,
 }
 
 impl Default for DocExportFormat {
@@ -63,6 +81,11 @@ impl DocExportFormat {
             DocExportFormat::Svg => String::from("svg"),
             DocExportFormat::Pdf => String::from("pdf"),
             DocExportFormat::Xopp => String::from("xopp"),
+            DocExportFormat::Png => String::from("png"),
+            DocExportFormat::Jpeg => String::from("jpg"),
+            DocExportFormat::Inkml => String::from("inkml"),
+            DocExportFormat::Excalidraw => String::from("excalidraw"),
+            DocExportFormat::AnnotatedPdf => String::from("pdf"),
         }
     }
 }
@@ -83,6 +106,12 @@ pub struct DocExportPrefs {
     /// The page order when documents with layouts that expand in horizontal and vertical directions are cut into pages.
     #[serde(rename = "page_order")]
     pub page_order: SplitOrder,
+    /// The bitmap scale-factor in relation to the actual size, when exporting as a bitmap image.
+    #[serde(rename = "bitmap_scalefactor")]
+    pub bitmap_scalefactor: f64,
+    /// Quality when exporting as Jpeg.
+    #[serde(rename = "jpg_quality")]
+    pub jpeg_quality: u8,
 }
 
 impl Default for DocExportPrefs {
@@ -92,6 +121,8 @@ impl Default for DocExportPrefs {
             with_pattern: true,
             export_format: DocExportFormat::default(),
             page_order: SplitOrder::default(),
+            bitmap_scalefactor: 1.8,
+            jpeg_quality: 85,
         }
     }
 }
@@ -208,6 +239,10 @@ pub enum SelectionExportFormat {
     Png,
     #[serde(rename = "jpeg")]
     Jpeg,
+    #[serde(rename = "inkml")]
+    Inkml,
+    #[serde(rename = "excalidraw")]
+    Excalidraw,
 }
 
 impl Default for SelectionExportFormat {
@@ -222,6 +257,8 @@ impl SelectionExportFormat {
             SelectionExportFormat::Svg => String::from("svg"),
             SelectionExportFormat::Png => String::from("png"),
             SelectionExportFormat::Jpeg => String::from("jpg"),
+            SelectionExportFormat::Inkml => String::from("inkml"),
+            SelectionExportFormat::Excalidraw => String::from("excalidraw"),
         }
     }
 }
@@ -293,6 +330,8 @@ pub struct ExportPrefs {
 impl RnoteEngine {
     /// The used image scale-factor for any strokes that are converted to bitmap images on export.
     pub const STROKE_EXPORT_IMAGE_SCALE: f64 = 1.8;
+    /// The maximum number of pixels of the bitmap image the doc is exported as, taking up 1 GiB.
+    pub const DOC_BITMAP_EXPORT_MAX_PIXELS: f64 = 268_435_456.0;
 
     /// Save the current document as a .rnote file.
     pub fn save_as_rnote_bytes(
@@ -318,12 +357,17 @@ impl RnoteEngine {
     /// Extract the current engine configuration.
     pub fn extract_engine_config(&self) -> EngineConfig {
         EngineConfig {
-            document: self.document,
+            // Page background overrides are specific to the current document
+            document: Document {
+                page_backgrounds: vec![],
+                ..self.document.clone()
+            },
             pens_config: self.pens_config.clone(),
             penholder: self.penholder.clone_config(),
             import_prefs: self.import_prefs,
             export_prefs: self.export_prefs,
             pen_sounds: self.pen_sounds(),
+            history_mem_budget: self.store.history_mem_budget(),
         }
     }
 
@@ -354,6 +398,14 @@ impl RnoteEngine {
             DocExportFormat::Xopp => {
                 self.export_doc_as_xopp_bytes(title, doc_export_prefs_override)
             }
+            DocExportFormat::Png | DocExportFormat::Jpeg => {
+                self.export_doc_as_bitmap_bytes(doc_export_prefs_override)
+            }
+            DocExportFormat::Inkml => self.export_doc_as_inkml_bytes(title),
+            DocExportFormat::Excalidraw => {
+                self.export_doc_as_excalidraw_bytes(title, doc_export_prefs_override)
+            }
+            DocExportFormat::AnnotatedPdf => self.export_doc_as_annotated_pdf_bytes(title),
         }
     }
 
@@ -396,6 +448,76 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Export the doc with the strokes as a single bitmap image, spanning the bounds of the content.
+    ///
+    /// Like the Svg and Pdf export, the image only contains the strokes as they are rendered,
+    /// so strokes on hidden layers are not exported.
+    ///
+    /// Returns an error if the format pref is not set to a bitmap variant,
+    /// or if the image would have more than [Self::DOC_BITMAP_EXPORT_MAX_PIXELS] pixels.
+    fn export_doc_as_bitmap_bytes(
+        &self,
+        doc_export_prefs_override: Option<DocExportPrefs>,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+
+        let doc_export_prefs =
+            doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
+        let stroke_keys = self.store.stroke_keys_as_rendered();
+        let snapshot = self.take_snapshot();
+        let content_bounds = self
+            .bounds_w_content_extended()
+            .unwrap_or_else(|| snapshot.document.bounds());
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let bitmapimage_format = match doc_export_prefs.export_format {
+                    DocExportFormat::Svg
+                    | DocExportFormat::Pdf
+                    | DocExportFormat::Xopp
+                    | DocExportFormat::Inkml
+                    | DocExportFormat::Excalidraw
+                    | DocExportFormat::AnnotatedPdf => {
+                        return Err(anyhow::anyhow!(
+                            "extracting bitmap image format from doc export prefs failed, not set to a bitmap format"
+                        ))
+                    }
+                    DocExportFormat::Png => image::ImageOutputFormat::Png,
+                    DocExportFormat::Jpeg => {
+                        image::ImageOutputFormat::Jpeg(doc_export_prefs.jpeg_quality)
+                    }
+                };
+
+                let image_size = content_bounds.extents() * doc_export_prefs.bitmap_scalefactor;
+                if image_size[0] * image_size[1] > Self::DOC_BITMAP_EXPORT_MAX_PIXELS {
+                    return Err(anyhow::anyhow!(
+                        "exporting doc as bitmap image failed, the image size {:.0}x{:.0} exceeds the maximum of {} pixels",
+                        image_size[0],
+                        image_size[1],
+                        Self::DOC_BITMAP_EXPORT_MAX_PIXELS
+                    ));
+                }
+
+                let doc_svg =
+                    gen_doc_svg(content_bounds, stroke_keys, &snapshot, doc_export_prefs)?;
+                let doc_svg_bounds = doc_svg.bounds;
+
+                render::Image::gen_image_from_svg(
+                    doc_svg,
+                    doc_svg_bounds,
+                    doc_export_prefs.bitmap_scalefactor,
+                )?
+                .into_encoded_bytes(bitmapimage_format)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_doc_as_bitmap_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Export the doc with the strokes as Pdf.
     fn export_doc_as_pdf_bytes(
         &self,
@@ -428,12 +550,10 @@ impl RnoteEngine {
                 ];
 
                 let background_svg = if doc_export_prefs.with_background {
-                    Some(
-                        snapshot
-                            .document
-                            .background
-                            .gen_svg(snapshot.document.bounds(), doc_export_prefs.with_pattern)?,
-                    )
+                    Some(snapshot.document.gen_background_svg(
+                        snapshot.document.bounds(),
+                        doc_export_prefs.with_pattern,
+                    )?)
                 } else {
                     None
                 };
@@ -478,8 +598,15 @@ impl RnoteEngine {
 
                         for stroke in page_strokes.into_iter() {
                             if let Some(stroke) = snapshot.stroke_components.get(stroke) {
-                                stroke
-                                    .draw(&mut piet_cx, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE)?;
+                                // Pdf pages are drawn with cairo directly, so that they are exported as vector pages.
+                                if let Stroke::PdfPage(pdfpage) = stroke.as_ref() {
+                                    pdfpage.draw_to_cairo(&cairo_cx)?;
+                                } else {
+                                    stroke.draw(
+                                        &mut piet_cx,
+                                        RnoteEngine::STROKE_EXPORT_IMAGE_SCALE,
+                                    )?;
+                                }
                             }
                         }
 
@@ -518,6 +645,9 @@ impl RnoteEngine {
     }
 
     /// Export the document as a Xournal++ .xopp file.
+    ///
+    /// Every user layer is exported into its own xopp layer, including the hidden ones.
+    /// Xopp files have no layer visibility, so the strokes of hidden layers are visible when the file is opened.
     fn export_doc_as_xopp_bytes(
         &self,
         title: String,
@@ -529,15 +659,27 @@ impl RnoteEngine {
             doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
         let snapshot = self.take_snapshot();
 
-        let pages_strokes: Vec<(Aabb, Vec<Stroke>)> = self
-            .pages_bounds_w_content(doc_export_prefs.page_order)
+        let pages_strokes: Vec<(Aabb, Vec<(Stroke, StrokeLayer)>)> = self
+            .pages_bounds_w_strokes(
+                &self.store.stroke_keys_unordered(),
+                doc_export_prefs.page_order,
+            )
             .into_iter()
             .map(|page_bounds| {
                 let page_keys = self
                     .store
-                    .stroke_keys_as_rendered_intersecting_bounds(page_bounds);
+                    .keys_sorted_chrono_intersecting_bounds(page_bounds);
 
-                let strokes = self.store.clone_strokes(&page_keys);
+                let strokes = page_keys
+                    .into_iter()
+                    .filter(|&key| !self.store.trashed(key).unwrap_or(true))
+                    .filter_map(|key| {
+                        Some((
+                            self.store.get_stroke_ref(key)?.clone(),
+                            self.store.stroke_layer(key)?,
+                        ))
+                    })
+                    .collect::<Vec<(Stroke, StrokeLayer)>>();
 
                 (page_bounds, strokes)
             })
@@ -545,95 +687,126 @@ impl RnoteEngine {
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<u8>> {
-                // Only one background for all pages
-                let xopp_background = xoppformat::XoppBackground {
-                    name: None,
-                    bg_type: xoppformat::XoppBackgroundType::Solid {
-                        color: crate::utils::xoppcolor_from_color(
-                            snapshot.document.background.color,
-                        ),
-                        style: xoppformat::XoppBackgroundSolidStyle::Plain,
-                    },
-                };
+                let doc_dpi = snapshot.document.format.dpi;
+                let n_user_layers = snapshot.layers.len();
+                // Xopp files can only reference a single pdf for the pdf backgrounds.
+                // Pages from other pdfs are exported as images.
+                let mut background_pdf: Option<PathBuf> = None;
 
                 // xopp spec needs at least one page in vec,
                 // but it is fine because pages_bounds_w_content() always produces at least one.
                 let pages = pages_strokes
                     .into_iter()
                     .map(|(page_bounds, strokes)| {
-                        // Translate strokes to to page mins and convert to XoppStrokStyle
-                        let xopp_strokestyles = strokes
-                            .into_iter()
-                            .filter_map(|mut stroke| {
-                                stroke.translate(-page_bounds.mins.coords);
-
-                                stroke.into_xopp(snapshot.document.format.dpi)
-                            })
-                            .collect::<Vec<xoppformat::XoppStrokeType>>();
-
-                        // Extract the strokes
-                        let xopp_strokes = xopp_strokestyles
+                        // Every user layer is exported into its own xopp layer.
+                        let mut user_layers = snapshot
+                            .layers
                             .iter()
-                            .filter_map(|stroke| {
-                                if let xoppformat::XoppStrokeType::XoppStroke(xoppstroke) = stroke {
-                                    Some(xoppstroke.clone())
-                                } else {
-                                    None
-                                }
+                            .map(|layer_props| xoppformat::XoppLayer {
+                                name: Some(layer_props.name.clone()),
+                                ..Default::default()
                             })
-                            .collect::<Vec<xoppformat::XoppStroke>>();
+                            .collect::<Vec<xoppformat::XoppLayer>>();
 
-                        // Extract the texts
-                        let xopp_texts = xopp_strokestyles
-                            .iter()
-                            .filter_map(|stroke| {
-                                if let xoppformat::XoppStrokeType::XoppText(xopptext) = stroke {
-                                    Some(xopptext.clone())
-                                } else {
-                                    None
+                        // In Rnote images are always rendered below strokes and text.
+                        // To match this behaviour accurately, images are separated into another layer.
+                        let mut image_layer = xoppformat::XoppLayer::default();
+
+                        // Pdf pages that were imported as page background and cover this page are exported as pdf background.
+                        let mut pdf_background = None;
+                        let mut page_strokes = Vec::with_capacity(strokes.len());
+                        for (stroke, layer) in strokes.into_iter() {
+                            match stroke.pdf_page_source() {
+                                Some(pdf_page_source)
+                                    if pdf_background.is_none()
+                                        && page_bounds
+                                            .contains_local_point(&stroke.bounds().center())
+                                        && background_pdf
+                                            .as_ref()
+                                            .map_or(true, |path| *path == pdf_page_source.path) =>
+                                {
+                                    // Only the first pdf background names the pdf file
+                                    let filename = if background_pdf.is_none() {
+                                        background_pdf = Some(pdf_page_source.path.clone());
+                                        Some(pdf_page_source.path.to_string_lossy().to_string())
+                                    } else {
+                                        None
+                                    };
+                                    pdf_background = Some(xoppformat::XoppBackgroundType::Pdf {
+                                        domain: filename.as_ref().map(|_| {
+                                            xoppformat::XoppBackgroundPixmapDomain::Absolute
+                                        }),
+                                        filename,
+                                        pageno: pdf_page_source.page_index + 1,
+                                    });
                                 }
-                            })
-                            .collect::<Vec<xoppformat::XoppText>>();
+                                _ => page_strokes.push((stroke, layer)),
+                            }
+                        }
 
-                        // Extract the images
-                        let xopp_images = xopp_strokestyles
-                            .iter()
-                            .filter_map(|stroke| {
-                                if let xoppformat::XoppStrokeType::XoppImage(xoppstroke) = stroke {
-                                    Some(xoppstroke.clone())
-                                } else {
-                                    None
+                        // The strokes are already in drawing order, so strokes that are not on a user layer (e.g. highlighter strokes)
+                        // are drawn below all others when they are placed into the bottom layer.
+                        for (mut stroke, layer) in page_strokes.into_iter() {
+                            // Translate strokes to to page mins and convert to XoppStrokStyle
+                            stroke.translate(-page_bounds.mins.coords);
+
+                            let Some(xopp_stroke) = stroke.into_xopp(doc_dpi) else {
+                                continue;
+                            };
+                            let layer_i = match layer {
+                                StrokeLayer::UserLayer(index) => {
+                                    index.min(n_user_layers.saturating_sub(1)) as usize
                                 }
-                            })
-                            .collect::<Vec<xoppformat::XoppImage>>();
+                                _ => 0,
+                            };
 
-                        // In Rnote images are always rendered below strokes and text.
-                        // To match this behaviour accurately, images are separated into another layer.
-                        let image_layer = xoppformat::XoppLayer {
-                            name: None,
-                            strokes: vec![],
-                            texts: vec![],
-                            images: xopp_images,
-                        };
+                            match xopp_stroke {
+                                xoppformat::XoppStrokeType::XoppStroke(xoppstroke) => {
+                                    user_layers[layer_i].strokes.push(xoppstroke);
+                                }
+                                xoppformat::XoppStrokeType::XoppText(xopptext) => {
+                                    user_layers[layer_i].texts.push(xopptext);
+                                }
+                                xoppformat::XoppStrokeType::XoppImage(xoppimage) => {
+                                    image_layer.images.push(xoppimage);
+                                }
+                            }
+                        }
 
-                        let strokes_layer = xoppformat::XoppLayer {
-                            name: None,
-                            strokes: xopp_strokes,
-                            texts: xopp_texts,
-                            images: vec![],
+                        let layers = if image_layer.images.is_empty() {
+                            user_layers
+                        } else {
+                            std::iter::once(image_layer).chain(user_layers).collect()
                         };
 
                         let page_dimensions = crate::utils::convert_coord_dpi(
                             page_bounds.extents(),
-                            snapshot.document.format.dpi,
+                            doc_dpi,
                             xoppformat::XoppFile::DPI,
                         );
 
+                        let background = snapshot.document.page_background(
+                            snapshot
+                                .document
+                                .page_index_fixed_size(page_bounds.center().coords),
+                        );
+                        let xopp_background = xoppformat::XoppBackground {
+                            name: None,
+                            bg_type: pdf_background.unwrap_or_else(|| {
+                                xoppformat::XoppBackgroundType::Solid {
+                                    color: crate::utils::xoppcolor_from_color(background.color),
+                                    style: crate::utils::xopp_bg_style_from_pattern_style(
+                                        background.pattern,
+                                    ),
+                                }
+                            }),
+                        };
+
                         xoppformat::XoppPage {
                             width: page_dimensions[0],
                             height: page_dimensions[1],
-                            background: xopp_background.clone(),
-                            layers: vec![image_layer, strokes_layer],
+                            background: xopp_background,
+                            layers,
                         }
                     })
                     .collect::<Vec<xoppformat::XoppPage>>();
@@ -643,6 +816,7 @@ impl RnoteEngine {
                 let xopp_root = xoppformat::XoppRoot {
                     title: xopp_title,
                     fileversion: String::from("4"),
+                    xournal_version: None,
                     preview: String::from(""),
                     pages,
                 };
@@ -659,6 +833,105 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Export the brush strokes of the document as InkML, in the order they were drawn.
+    fn export_doc_as_inkml_bytes(
+        &self,
+        title: String,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+
+        let doc_dpi = self.document.format.dpi;
+        let doc_origin = na::vector![self.document.x, self.document.y];
+        let strokes = self
+            .store
+            .stroke_keys_as_rendered_sorted_chrono_t()
+            .into_iter()
+            .filter_map(|key| self.store.get_stroke_ref(key).cloned())
+            .collect::<Vec<Stroke>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                gen_inkml_bytes(strokes, -doc_origin, doc_dpi, &title)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_doc_as_inkml_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export the strokes of the document as Excalidraw scene, in the order they are rendered.
+    fn export_doc_as_excalidraw_bytes(
+        &self,
+        title: String,
+        doc_export_prefs_override: Option<DocExportPrefs>,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let doc_export_prefs =
+            doc_export_prefs_override.unwrap_or(self.export_prefs.doc_export_prefs);
+
+        let doc_dpi = self.document.format.dpi;
+        let doc_origin = na::vector![self.document.x, self.document.y];
+        let background_color = doc_export_prefs
+            .with_background
+            .then_some(self.document.background.color);
+        let strokes = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| self.store.get_stroke_ref(key).cloned())
+            .collect::<Vec<Stroke>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                gen_excalidraw_bytes(strokes, -doc_origin, doc_dpi, background_color, &title)
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_doc_as_excalidraw_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export the strokes of the document as native annotations of the Pdf its pages were imported from.
+    ///
+    /// The pages of the original Pdf are kept, including their text, links and forms.
+    /// See [gen_annotated_pdf_bytes].
+    fn export_doc_as_annotated_pdf_bytes(
+        &self,
+        title: String,
+    ) -> oneshot::Receiver<Result<Vec<u8>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+
+        let strokes = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| {
+                Some((
+                    self.store.get_stroke_ref(key)?.clone(),
+                    self.store.stroke_layer(key)?,
+                ))
+            })
+            .collect::<Vec<(Stroke, StrokeLayer)>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                gen_annotated_pdf_bytes(strokes, &(title + ".pdf"))
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_doc_as_annotated_pdf_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Export the document pages.
     pub fn export_doc_pages(
         &self,
@@ -804,6 +1077,10 @@ impl RnoteEngine {
             SelectionExportFormat::Png | SelectionExportFormat::Jpeg => {
                 self.export_selection_as_bitmap_bytes(selection_export_prefs_override)
             }
+            SelectionExportFormat::Inkml => self.export_selection_as_inkml_bytes(),
+            SelectionExportFormat::Excalidraw => {
+                self.export_selection_as_excalidraw_bytes(selection_export_prefs_override)
+            }
         }
     }
 
@@ -887,7 +1164,7 @@ impl RnoteEngine {
                 let selection_svg_bounds = selection_svg.bounds;
 
                 let bitmapimage_format = match selection_export_prefs.export_format {
-                SelectionExportFormat::Svg => return Err(anyhow::anyhow!("export_selection_as_bitmap_bytes() failed, export preferences have Svg as export format")),
+                SelectionExportFormat::Svg | SelectionExportFormat::Inkml | SelectionExportFormat::Excalidraw => return Err(anyhow::anyhow!("export_selection_as_bitmap_bytes() failed, export preferences not set to a bitmap format")),
                 SelectionExportFormat::Png => image::ImageOutputFormat::Png,
                 SelectionExportFormat::Jpeg => {
                     image::ImageOutputFormat::Jpeg(selection_export_prefs.jpeg_quality)
@@ -910,6 +1187,215 @@ impl RnoteEngine {
 
         oneshot_receiver
     }
+
+    /// Export the selected brush strokes as InkML, in the order they were drawn.
+    fn export_selection_as_inkml_bytes(
+        &self,
+    ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
+
+        let doc_dpi = self.document.format.dpi;
+        let selection_keys = self
+            .store
+            .stroke_keys_as_rendered_sorted_chrono_t()
+            .into_iter()
+            .filter(|&key| self.store.selected(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
+        let selection_bounds = self.store.bounds_for_strokes(&selection_keys);
+        let strokes = selection_keys
+            .into_iter()
+            .filter_map(|key| self.store.get_stroke_ref(key).cloned())
+            .collect::<Vec<Stroke>>();
+
+        rayon::spawn(move || {
+            let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
+                let Some(selection_bounds) = selection_bounds else {
+                    return Ok(None);
+                };
+
+                Ok(Some(gen_inkml_bytes(
+                    strokes,
+                    -selection_bounds.mins.coords,
+                    doc_dpi,
+                    "selection",
+                )?))
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_selection_as_inkml_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export the selected strokes as Excalidraw scene, in the order they are rendered.
+    fn export_selection_as_excalidraw_bytes(
+        &self,
+        selection_export_prefs_override: Option<SelectionExportPrefs>,
+    ) -> oneshot::Receiver<Result<Option<Vec<u8>>, anyhow::Error>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Option<Vec<u8>>>>();
+        let selection_export_prefs =
+            selection_export_prefs_override.unwrap_or(self.export_prefs.selection_export_prefs);
+
+        let doc_dpi = self.document.format.dpi;
+        let background_color = selection_export_prefs
+            .with_background
+            .then_some(self.document.background.color);
+        let selection_keys = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter(|&key| self.store.selected(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
+        let selection_bounds = self.store.bounds_for_strokes(&selection_keys);
+        let strokes = selection_keys
+            .into_iter()
+            .filter_map(|key| self.store.get_stroke_ref(key).cloned())
+            .collect::<Vec<Stroke>>();
+
+        rayon::spawn(move || {
+            let result = || -> Result<Option<Vec<u8>>, anyhow::Error> {
+                let Some(selection_bounds) = selection_bounds else {
+                    return Ok(None);
+                };
+
+                Ok(Some(gen_excalidraw_bytes(
+                    strokes,
+                    -selection_bounds.mins.coords,
+                    doc_dpi,
+                    background_color,
+                    "selection",
+                )?))
+            };
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_selection_as_excalidraw_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+}
+
+/// Generates the InkML file bytes for the brush strokes, the traces are in the order of the strokes.
+///
+/// Strokes that can't be represented as InkML traces are skipped.
+fn gen_inkml_bytes(
+    strokes: Vec<Stroke>,
+    offset: na::Vector2<f64>,
+    dpi: f64,
+    file_name: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut ink = inkmlformat::InkmlInk {
+        channels: inkmlformat::InkmlFile::export_channels(),
+        ..Default::default()
+    };
+    let mut time = 0.0;
+
+    for mut stroke in strokes.into_iter() {
+        stroke.translate(offset);
+
+        let Some((trace, brush)) = stroke.into_inkml(dpi, time) else {
+            continue;
+        };
+        if let Some(last_time) = trace.points.last().and_then(|point| point.last()) {
+            time = last_time + inkmlformat::InkmlFile::EXPORT_TRACE_INTERVAL;
+        }
+        ink.push_trace(trace, brush);
+    }
+
+    inkmlformat::InkmlFile { ink }.save_as_bytes(file_name)
+}
+
+/// Generates the Excalidraw scene bytes for the strokes, the elements are in the order of the strokes.
+///
+/// Strokes that can't be represented as Excalidraw elements are skipped.
+fn gen_excalidraw_bytes(
+    strokes: Vec<Stroke>,
+    offset: na::Vector2<f64>,
+    dpi: f64,
+    background_color: Option<Color>,
+    file_name: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let mut excalidraw_file = excalidrawformat::ExcalidrawFile::default();
+    if let Some(background_color) = background_color {
+        excalidraw_file.app_state.view_background_color =
+            crate::utils::excalidrawcolor_from_color(background_color).to_css_color();
+    }
+
+    excalidraw_file.elements = strokes
+        .into_iter()
+        .filter_map(|mut stroke| {
+            stroke.translate(offset);
+            stroke.into_excalidraw(dpi)
+        })
+        .collect();
+
+    excalidraw_file.save_as_bytes(file_name)
+}
+
+/// Generate the bytes of the embedded Pdf of the Pdf pages, with the strokes added as native annotations.
+///
+/// The strokes are added to the page whose bounds contain the center of their bounds,
+/// strokes outside of the pages are skipped. Only pages of the embedded Pdf of the first Pdf page are annotated.
+fn gen_annotated_pdf_bytes(
+    strokes: Vec<(Stroke, StrokeLayer)>,
+    file_name: &str,
+) -> anyhow::Result<Vec<u8>> {
+    let (pdf_id, pdf_bytes) = strokes
+        .iter()
+        .find_map(|(stroke, _)| match stroke {
+            Stroke::PdfPage(pdfpage) => Some((pdfpage.pdf_id, pdfpage.pdf_bytes()?.clone())),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "document has no embedded pdf pages, only pdfs imported as embedded pages can be annotated"
+            )
+        })?;
+    let mut pdf_annot_file = PdfAnnotFile::load_from_bytes(&pdf_bytes)?;
+
+    // The transforms from the document to the displayed coordinates of the pdf pages
+    let pages = strokes
+        .iter()
+        .filter_map(|(stroke, _)| {
+            let Stroke::PdfPage(pdfpage) = stroke else {
+                return None;
+            };
+            if pdfpage.pdf_id != pdf_id {
+                return None;
+            }
+            let page_size = pdf_annot_file.page_size(pdfpage.page_index).ok()?;
+            let transform = pdf_page_transform(stroke, page_size)?.inverse();
+
+            Some((pdfpage.page_index, stroke.bounds(), transform))
+        })
+        .collect::<Vec<(u32, Aabb, na::Affine2<f64>)>>();
+
+    for (stroke, layer) in strokes {
+        if matches!(
+            stroke,
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) | Stroke::PdfPage(_)
+        ) {
+            continue;
+        }
+        let center = stroke.bounds().center();
+        let Some((page_index, _, transform)) = pages
+            .iter()
+            .find(|(_, page_bounds, _)| page_bounds.contains_local_point(&center))
+        else {
+            continue;
+        };
+        let highlight = matches!(layer, StrokeLayer::Highlighter);
+        let Some(annotation) = stroke.into_pdf_annotation(transform, highlight) else {
+            continue;
+        };
+
+        pdf_annot_file.add_annotation(*page_index, &annotation)?;
+    }
+
+    pdf_annot_file.save_as_bytes(file_name)
 }
 
 /// Generates the doc Svg.
@@ -924,8 +1410,7 @@ fn gen_doc_svg(
     let mut doc_svg = if doc_export_prefs.with_background {
         snapshot
             .document
-            .background
-            .gen_svg(doc_w_content_bounds, doc_export_prefs.with_pattern)?
+            .gen_background_svg(doc_w_content_bounds, doc_export_prefs.with_pattern)?
     } else {
         render::Svg {
             svg_data: String::new(),
@@ -968,8 +1453,7 @@ fn gen_doc_pages_svgs(
         let mut page_svg = if doc_pages_export_prefs.with_background {
             snapshot
                 .document
-                .background
-                .gen_svg(page_bounds, doc_pages_export_prefs.with_pattern)?
+                .gen_background_svg(page_bounds, doc_pages_export_prefs.with_pattern)?
         } else {
             render::Svg {
                 svg_data: String::new(),
@@ -1016,8 +1500,7 @@ fn gen_selection_svg(
     let mut selection_svg = if selection_export_prefs.with_background {
         snapshot
             .document
-            .background
-            .gen_svg(selection_bounds, selection_export_prefs.with_pattern)?
+            .gen_background_svg(selection_bounds, selection_export_prefs.with_pattern)?
     } else {
         render::Svg {
             svg_data: String::new(),
@@ -1,16 +1,24 @@
 // Imports
 use super::{EngineConfig, EngineViewMut, StrokeContent};
+use crate::document::Format;
+use crate::fileformats::excalidrawformat::ExcalidrawFile;
+use crate::fileformats::inkmlformat::InkmlFile;
+use crate::fileformats::pdfannotformat::PdfAnnotFile;
+use crate::fileformats::xoppformat::XoppBackgroundPixmapDomain;
+use crate::fileformats::FileFormatLoader;
 use crate::pens::Pen;
 use crate::pens::PenStyle;
 use crate::store::chrono_comp::StrokeLayer;
-use crate::store::StrokeKey;
-use crate::strokes::{BitmapImage, Stroke, VectorImage};
+use crate::store::{HistoryLabel, StrokeKey};
+use crate::strokes::{BitmapImage, PdfPage, Stroke, VectorImage};
 use crate::{RnoteEngine, WidgetFlags};
 use futures::channel::oneshot;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::transform::TransformBehaviour;
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::path::{Path, PathBuf};
 
 #[derive(
     Debug, Clone, Copy, Serialize, Deserialize, num_derive::FromPrimitive, num_derive::ToPrimitive,
@@ -21,11 +29,14 @@ pub enum PdfImportPagesType {
     Bitmap = 0,
     #[serde(rename = "vector")]
     Vector,
+    /// The Pdf is embedded into the document once, its pages are rendered on demand.
+    #[serde(rename = "embedded")]
+    Embedded,
 }
 
 impl Default for PdfImportPagesType {
     fn default() -> Self {
-        Self::Vector
+        Self::Embedded
     }
 }
 
@@ -88,6 +99,11 @@ pub struct PdfImportPrefs {
     /// The scalefactor when importing as bitmap image
     #[serde(rename = "bitmap_scalefactor")]
     pub bitmap_scalefactor: f64,
+    /// Whether the annotations of the pages are imported as editable strokes instead of being rendered into the pages.
+    ///
+    /// Annotations of types that can't be converted are dropped and logged on import.
+    #[serde(rename = "annotations_as_strokes")]
+    pub annotations_as_strokes: bool,
 }
 
 impl Default for PdfImportPrefs {
@@ -97,10 +113,36 @@ impl Default for PdfImportPrefs {
             page_width_perc: 50.0,
             page_spacing: PdfImportPageSpacing::default(),
             bitmap_scalefactor: 1.8,
+            annotations_as_strokes: false,
         }
     }
 }
 
+impl PdfImportPrefs {
+    /// The flags for rendering the pages, which exclude all annotations when they are imported as strokes.
+    pub(crate) fn poppler_print_flags(&self) -> poppler::PrintFlags {
+        if self.annotations_as_strokes {
+            poppler::PrintFlags::DOCUMENT
+        } else {
+            poppler::PrintFlags::ALL
+        }
+    }
+}
+
+/// The Pdf page a stroke was generated from.
+///
+/// Kept for Pdf pages that were imported as page backgrounds, so that they can be exported as such again.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "pdf_page_source")]
+pub struct PdfPageSource {
+    /// The path of the Pdf file.
+    #[serde(rename = "path")]
+    pub path: PathBuf,
+    /// The index of the page, starting from zero.
+    #[serde(rename = "page_index")]
+    pub page_index: u32,
+}
+
 /// Xournal++ `.xopp` file import preferences.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename = "xopp_import_prefs")]
@@ -116,6 +158,15 @@ impl Default for XoppImportPrefs {
     }
 }
 
+/// Svg import preferences.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "svg_import_prefs")]
+pub struct SvgImportPrefs {
+    /// Whether the svg is imported as editable strokes instead of a single vector image.
+    #[serde(rename = "import_as_strokes")]
+    pub import_as_strokes: bool,
+}
+
 /// Import preferences.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 #[serde(default, rename = "import_prefs")]
@@ -126,6 +177,9 @@ pub struct ImportPrefs {
     /// Xournal++ `.xopp` file import preferences
     #[serde(rename = "xopp_import_prefs")]
     pub xopp_import_prefs: XoppImportPrefs,
+    /// Svg import preferences
+    #[serde(rename = "svg_import_prefs")]
+    pub svg_import_prefs: SvgImportPrefs,
 }
 
 impl RnoteEngine {
@@ -143,6 +197,8 @@ impl RnoteEngine {
         self.import_prefs = engine_config.import_prefs;
         self.export_prefs = engine_config.export_prefs;
         self.pen_sounds = engine_config.pen_sounds;
+        self.store
+            .set_history_mem_budget(engine_config.history_mem_budget);
 
         // Set the pen sounds to update the audioplayer
         self.set_pen_sounds(self.pen_sounds, data_dir);
@@ -203,6 +259,37 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Generate editable strokes from the paths and texts of svg bytes.
+    ///
+    /// The upper left corner of the svg is placed at the given position.
+    /// Elements that can't be converted are kept in a vector image.
+    #[allow(clippy::type_complexity)]
+    pub fn generate_svg_strokes_from_bytes(
+        &self,
+        pos: na::Vector2<f64>,
+        bytes: Vec<u8>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+                let svg_str = String::from_utf8(bytes)?;
+
+                Ok(VectorImage::import_from_svg_data_as_strokes(&svg_str, pos)?
+                    .into_iter()
+                    .map(|stroke| (stroke, None))
+                    .collect())
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_svg_strokes_from_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Generate a bitmapimage for the bytes.
     ///
     /// The bytes are expected to be from a valid bitmap image (Png/Jpeg).
@@ -226,15 +313,125 @@ impl RnoteEngine {
         oneshot_receiver
     }
 
+    /// Generate brush strokes from the traces of InkML bytes.
+    ///
+    /// The strokes are in the order the traces were drawn and are moved so that their bounds start at the given position.
+    #[allow(clippy::type_complexity)]
+    pub fn generate_inkml_strokes_from_bytes(
+        &self,
+        pos: na::Vector2<f64>,
+        bytes: Vec<u8>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
+        let dpi = self.document.format.dpi;
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+                let ink = InkmlFile::load_from_bytes(&bytes)?.ink;
+
+                let mut traces = ink.traces.iter().collect::<Vec<_>>();
+                if let Some(time_i) = ink.channel_index("T") {
+                    // The sort is stable, so traces without time stay in document order
+                    traces.sort_by(|first, second| {
+                        let first_time = first.points.first().and_then(|p| p.get(time_i));
+                        let second_time = second.points.first().and_then(|p| p.get(time_i));
+                        match (first_time, second_time) {
+                            (Some(first_time), Some(second_time)) => {
+                                first_time.total_cmp(second_time)
+                            }
+                            _ => std::cmp::Ordering::Equal,
+                        }
+                    });
+                }
+
+                let mut strokes = traces
+                    .into_iter()
+                    .filter_map(|trace| match Stroke::from_inkml_trace(trace, &ink, dpi) {
+                        Ok(stroke) => Some(stroke),
+                        Err(e) => {
+                            log::error!("from_inkml_trace() failed in generate_inkml_strokes_from_bytes() with Err: {e:?}");
+                            None
+                        }
+                    })
+                    .collect::<Vec<Stroke>>();
+
+                if strokes.is_empty() {
+                    return Ok(vec![]);
+                }
+                let bounds = strokes.iter().fold(Aabb::new_invalid(), |acc, stroke| {
+                    acc.merged(&stroke.bounds())
+                });
+                for stroke in strokes.iter_mut() {
+                    stroke.translate(pos - bounds.mins.coords);
+                }
+
+                Ok(strokes.into_iter().map(|stroke| (stroke, None)).collect())
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_inkml_strokes_from_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Generate strokes from the elements of Excalidraw scene bytes.
+    ///
+    /// The strokes are in the order the elements are drawn and are moved so that their bounds start at the given position.
+    #[allow(clippy::type_complexity)]
+    pub fn generate_excalidraw_strokes_from_bytes(
+        &self,
+        pos: na::Vector2<f64>,
+        bytes: Vec<u8>,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
+        let (oneshot_sender, oneshot_receiver) =
+            oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
+        let dpi = self.document.format.dpi;
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+                let excalidraw_file = ExcalidrawFile::load_from_bytes(&bytes)?;
+
+                let mut strokes = excalidraw_file
+                    .elements
+                    .iter()
+                    .flat_map(|element| Stroke::from_excalidraw_element(element, dpi))
+                    .collect::<Vec<Stroke>>();
+
+                if strokes.is_empty() {
+                    return Ok(vec![]);
+                }
+                let bounds = strokes.iter().fold(Aabb::new_invalid(), |acc, stroke| {
+                    acc.merged(&stroke.bounds())
+                });
+                for stroke in strokes.iter_mut() {
+                    stroke.translate(pos - bounds.mins.coords);
+                }
+
+                Ok(strokes.into_iter().map(|stroke| (stroke, None)).collect())
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in generate_excalidraw_strokes_from_bytes() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
     /// Generate image strokes for each page for the bytes.
     ///
     /// The bytes are expected to be from a valid Pdf.
+    /// When the path of the Pdf file is given, the pages remember it as their source.
     #[allow(clippy::type_complexity)]
     pub fn generate_pdf_pages_from_bytes(
         &self,
         bytes: Vec<u8>,
         insert_pos: na::Vector2<f64>,
         page_range: Option<Range<u32>>,
+        source_path: Option<PathBuf>,
     ) -> oneshot::Receiver<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>> {
         let (oneshot_sender, oneshot_receiver) =
             oneshot::channel::<anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>>>();
@@ -243,34 +440,25 @@ impl RnoteEngine {
 
         rayon::spawn(move || {
             let result = || -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
-                match pdf_import_prefs.pages_type {
-                    PdfImportPagesType::Bitmap => {
-                        let bitmapimages = BitmapImage::import_from_pdf_bytes(
-                            &bytes,
-                            pdf_import_prefs,
-                            insert_pos,
-                            page_range,
-                            &format,
-                        )?
-                        .into_iter()
-                        .map(|s| (Stroke::BitmapImage(s), Some(StrokeLayer::Document)))
-                        .collect::<Vec<(Stroke, Option<StrokeLayer>)>>();
-                        Ok(bitmapimages)
-                    }
-                    PdfImportPagesType::Vector => {
-                        let vectorimages = VectorImage::import_from_pdf_bytes(
-                            &bytes,
-                            pdf_import_prefs,
-                            insert_pos,
-                            page_range,
-                            &format,
-                        )?
-                        .into_iter()
-                        .map(|s| (Stroke::VectorImage(s), Some(StrokeLayer::Document)))
-                        .collect::<Vec<(Stroke, Option<StrokeLayer>)>>();
-                        Ok(vectorimages)
+                let page_start = page_range.as_ref().map_or(0, |range| range.start);
+                let mut pdf_pages = pdf_pages_from_bytes(
+                    &glib::Bytes::from_owned(bytes),
+                    pdf_import_prefs,
+                    insert_pos,
+                    page_range,
+                    &format,
+                )?;
+
+                if let Some(source_path) = source_path {
+                    for (i, (pdf_page, _)) in pdf_pages.iter_mut().enumerate() {
+                        pdf_page.set_pdf_page_source(Some(PdfPageSource {
+                            path: source_path.clone(),
+                            page_index: page_start + i as u32,
+                        }));
                     }
                 }
+
+                Ok(pdf_pages)
             };
 
             if let Err(_data) = oneshot_sender.send(result()) {
@@ -306,7 +494,7 @@ impl RnoteEngine {
         widget_flags.merge(self.current_pen_update_state());
         self.update_rendering_current_viewport();
 
-        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.merge(self.store.record(HistoryLabel::Import));
         widget_flags.redraw = true;
         widget_flags.resize = true;
         widget_flags.store_modified = true;
@@ -344,7 +532,7 @@ impl RnoteEngine {
             ));
         }
 
-        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.merge(self.store.record(HistoryLabel::InsertText));
         widget_flags.redraw = true;
 
         Ok(widget_flags)
@@ -383,9 +571,162 @@ impl RnoteEngine {
             audioplayer: &mut self.audioplayer,
         }));
 
-        widget_flags.merge(self.store.record(Instant::now()));
+        widget_flags.merge(self.store.record(HistoryLabel::Paste));
         widget_flags.redraw = true;
 
         widget_flags
     }
 }
+
+/// Generate image strokes for each page of the Pdf bytes, depending on the pages type of the import preferences.
+///
+/// The bytes are shared with the pages when they are embedded.
+pub(crate) fn pdf_pages_from_bytes(
+    bytes: &glib::Bytes,
+    pdf_import_prefs: PdfImportPrefs,
+    insert_pos: na::Vector2<f64>,
+    page_range: Option<Range<u32>>,
+    format: &Format,
+) -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+    let page_start = page_range.as_ref().map_or(0, |range| range.start);
+    let pdf_pages = match pdf_import_prefs.pages_type {
+        PdfImportPagesType::Bitmap => BitmapImage::import_from_pdf_bytes(
+            bytes,
+            pdf_import_prefs,
+            insert_pos,
+            page_range,
+            format,
+        )?
+        .into_iter()
+        .map(|s| (Stroke::BitmapImage(s), Some(StrokeLayer::Document)))
+        .collect::<Vec<(Stroke, Option<StrokeLayer>)>>(),
+        PdfImportPagesType::Vector => VectorImage::import_from_pdf_bytes(
+            bytes,
+            pdf_import_prefs,
+            insert_pos,
+            page_range,
+            format,
+        )?
+        .into_iter()
+        .map(|s| (Stroke::VectorImage(s), Some(StrokeLayer::Document)))
+        .collect::<Vec<(Stroke, Option<StrokeLayer>)>>(),
+        PdfImportPagesType::Embedded => {
+            PdfPage::import_from_pdf_bytes(bytes, pdf_import_prefs, insert_pos, page_range, format)?
+                .into_iter()
+                .map(|s| (Stroke::PdfPage(s), Some(StrokeLayer::Document)))
+                .collect::<Vec<(Stroke, Option<StrokeLayer>)>>()
+        }
+    };
+
+    if !pdf_import_prefs.annotations_as_strokes {
+        return Ok(pdf_pages);
+    }
+    // The pages are still usable when the annotations can't be read
+    let annotations = match pdf_annotations_as_strokes(bytes, page_start, &pdf_pages) {
+        Ok(annotations) => annotations,
+        Err(e) => {
+            log::error!("importing pdf annotations as strokes failed in pdf_pages_from_bytes() with Err: {e:?}");
+            vec![]
+        }
+    };
+
+    Ok(pdf_pages.into_iter().chain(annotations).collect())
+}
+
+/// Convert the annotations of the Pdf into strokes, positioned on the generated page strokes.
+///
+/// The pages are expected to be in order, starting at the given page index.
+fn pdf_annotations_as_strokes(
+    bytes: &[u8],
+    page_start: u32,
+    pdf_pages: &[(Stroke, Option<StrokeLayer>)],
+) -> anyhow::Result<Vec<(Stroke, Option<StrokeLayer>)>> {
+    let pdf_annot_file = PdfAnnotFile::load_from_bytes(bytes)?;
+    let mut strokes = vec![];
+    let mut unsupported = vec![];
+
+    for (i, (pdf_page, _)) in pdf_pages.iter().enumerate() {
+        let page_index = page_start + i as u32;
+        let Some(transform) = pdf_page_transform(pdf_page, pdf_annot_file.page_size(page_index)?)
+        else {
+            continue;
+        };
+        unsupported.extend(
+            pdf_annot_file
+                .unsupported_annotation_subtypes(page_index)?
+                .into_iter()
+                .map(|subtype| (page_index, subtype)),
+        );
+
+        strokes.extend(
+            pdf_annot_file
+                .annotations(page_index)?
+                .iter()
+                .flat_map(|annotation| Stroke::from_pdf_annotation(annotation, &transform)),
+        );
+    }
+
+    // The pages are rendered without any annotations, so the ones that can't be converted are dropped
+    if !unsupported.is_empty() {
+        log::warn!(
+            "{} pdf annotations can't be converted into strokes and are dropped in pdf_annotations_as_strokes(), (page index, type): {:?}",
+            unsupported.len(),
+            unsupported
+        );
+    }
+
+    Ok(strokes)
+}
+
+/// The transform from the displayed coordinates of a Pdf page with the given size to the coordinates of its
+/// page image stroke. Returns None if the stroke is not an image.
+pub(crate) fn pdf_page_transform(
+    pdf_page: &Stroke,
+    page_size: na::Vector2<f64>,
+) -> Option<na::Affine2<f64>> {
+    let rectangle = match pdf_page {
+        Stroke::VectorImage(vectorimage) => &vectorimage.rectangle,
+        Stroke::BitmapImage(bitmapimage) => &bitmapimage.rectangle,
+        Stroke::PdfPage(pdfpage) => &pdfpage.rectangle,
+        _ => return None,
+    };
+    let half_extents = rectangle.cuboid.half_extents;
+
+    Some(
+        rectangle.transform.affine
+            * na::Affine2::from_matrix_unchecked(
+                na::Translation2::from(-half_extents).to_homogeneous()
+                    * na::Matrix3::new_nonuniform_scaling(&na::vector![
+                        2.0 * half_extents[0] / page_size[0],
+                        2.0 * half_extents[1] / page_size[1]
+                    ]),
+            ),
+    )
+}
+
+/// Resolve the path of the pdf that is referenced by the pdf background of a Xournal++ file.
+///
+/// Pdfs in the `attach` domain are stored next to the file, as `<file name>.<filename>`.
+/// When an absolute path can't be found, the pdf is looked up next to the file by its file name.
+pub(crate) fn resolve_xopp_pdf_background_path(
+    domain: Option<&XoppBackgroundPixmapDomain>,
+    filename: &str,
+    xopp_file_path: Option<&Path>,
+) -> Option<PathBuf> {
+    let xopp_dir = xopp_file_path.and_then(|p| p.parent());
+    let candidates = match domain {
+        Some(XoppBackgroundPixmapDomain::Attach) => vec![xopp_file_path.map(|p| {
+            let mut attached = p.as_os_str().to_owned();
+            attached.push(".");
+            attached.push(filename);
+            PathBuf::from(attached)
+        })],
+        _ => vec![
+            Some(PathBuf::from(filename)).filter(|p| p.is_absolute()),
+            xopp_dir.map(|dir| dir.join(filename)),
+            xopp_dir.and_then(|dir| Some(dir.join(Path::new(filename).file_name()?))),
+        ],
+    };
+
+    candidates.into_iter().flatten().find(|p| p.is_file())
+}
@@ -1,28 +1,42 @@
 // Modules
 pub mod export;
 pub mod import;
+pub mod pages;
+pub mod penrecording;
 pub mod rendering;
+pub mod replay;
+pub mod search;
+#[cfg(feature = "gtk")]
 pub mod visual_debug;
 
 // Re-exports
 pub use self::export::ExportPrefs;
 pub use self::import::ImportPrefs;
+pub use self::penrecording::{PenRecorder, PenRecording};
+pub use self::replay::{ReplayExportPrefs, ReplayState};
+pub use self::search::{SearchMatch, SearchOptions};
 
 // Imports
 use self::import::XoppImportPrefs;
+use self::penrecording::PenRecordingEvent;
+use self::search::SearchState;
 use crate::document::{background, Layout};
 use crate::fileformats::{rnoteformat, xoppformat, FileFormatLoader};
+use crate::pens::shortcuts::ShortcutAction;
 use crate::pens::{Pen, PenStyle};
 use crate::pens::{PenMode, PensConfig};
 use crate::render::Svg;
+use crate::store::chrono_comp::StrokeLayer;
 use crate::store::render_comp::{self, RenderCompState};
-use crate::store::{ChronoComponent, StrokeKey};
+use crate::store::{ChronoComponent, HistoryLabel, StrokeKey, UserLayers};
+use crate::strokes::pdfpage::EmbeddedPdf;
 use crate::strokes::strokebehaviour::GeneratedStrokeImages;
-use crate::strokes::Stroke;
+use crate::strokes::{PdfPage, Stroke};
 use crate::{render, AudioPlayer, DrawBehaviour, WidgetFlags};
 use crate::{Camera, Document, PenHolder, StrokeStore};
 use anyhow::Context;
 use futures::channel::{mpsc, oneshot};
+#[cfg(feature = "gtk")]
 use gtk4::gsk;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use rnote_compose::helpers::{AabbHelpers, SplitOrder};
@@ -99,6 +113,8 @@ pub enum EngineTask {
     },
     /// Requests that the typewriter cursor should be blinked/toggled
     BlinkTypewriterCursor,
+    /// Indicates that the pen was held still while drawing with the brush, which triggers the shape recognition.
+    BrushHoldStill,
     /// Change the permanent zoom to the given value
     Zoom(f64),
     /// Indicates that the application is quitting. Sent to quit the handler which receives the tasks.
@@ -106,7 +122,7 @@ pub enum EngineTask {
 }
 
 /// The engine configuration. Used when loading/saving the current configuration from/into persistent application settings.
-#[derive(Debug, Default, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(default, rename = "engine_config")]
 pub struct EngineConfig {
     #[serde(rename = "document")]
@@ -121,6 +137,23 @@ pub struct EngineConfig {
     export_prefs: ExportPrefs,
     #[serde(rename = "pen_sounds")]
     pen_sounds: bool,
+    /// The memory budget of the history in bytes.
+    #[serde(rename = "history_mem_budget")]
+    history_mem_budget: usize,
+}
+
+impl Default for EngineConfig {
+    fn default() -> Self {
+        Self {
+            document: Document::default(),
+            pens_config: PensConfig::default(),
+            penholder: PenHolder::default(),
+            import_prefs: ImportPrefs::default(),
+            export_prefs: ExportPrefs::default(),
+            pen_sounds: false,
+            history_mem_budget: StrokeStore::HISTORY_MEM_BUDGET_DEFAULT,
+        }
+    }
 }
 
 // An engine snapshot, used when loading/saving the current document from/into a file.
@@ -135,6 +168,11 @@ pub struct EngineSnapshot {
     pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
     #[serde(rename = "chrono_counter")]
     pub chrono_counter: u32,
+    #[serde(rename = "layers")]
+    pub layers: Arc<UserLayers>,
+    /// The Pdfs that are embedded once into the document, and referenced by its Pdf pages.
+    #[serde(rename = "embedded_pdfs")]
+    pub embedded_pdfs: Vec<EmbeddedPdf>,
 }
 
 impl Default for EngineSnapshot {
@@ -144,6 +182,8 @@ impl Default for EngineSnapshot {
             stroke_components: Arc::new(HopSlotMap::with_key()),
             chrono_components: Arc::new(SecondaryMap::new()),
             chrono_counter: 0,
+            layers: Arc::new(UserLayers::default()),
+            embedded_pdfs: vec![],
         }
     }
 }
@@ -159,7 +199,9 @@ impl EngineSnapshot {
             let result = || -> anyhow::Result<Self> {
                 let rnote_file = rnoteformat::RnoteFile::load_from_bytes(&bytes)
                     .context("loading RnoteFile from bytes failed.")?;
-                Ok(ijson::from_value(&rnote_file.engine_snapshot)?)
+                let mut snapshot: Self = ijson::from_value(&rnote_file.engine_snapshot)?;
+                snapshot.resolve_embedded_pdfs();
+                Ok(snapshot)
             };
 
             if let Err(_data) = snapshot_sender.send(result()) {
@@ -169,12 +211,16 @@ impl EngineSnapshot {
 
         snapshot_receiver.await?
     }
-    /// Loads from the bytes of a Xournal++ .xopp file.
+    /// Loads from the bytes of a Xournal++ .xopp file or a legacy Xournal .xoj file.
+    ///
+    /// The path of the file is needed to resolve the pdf of pdf page backgrounds,
+    /// which are imported as images on the document layer.
     ///
     /// To import this snapshot into the current engine, use `import_snapshot()`.
     pub async fn load_from_xopp_bytes(
         bytes: Vec<u8>,
         xopp_import_prefs: XoppImportPrefs,
+        xopp_file_path: Option<PathBuf>,
     ) -> anyhow::Result<Self> {
         let (snapshot_sender, snapshot_receiver) = oneshot::channel::<anyhow::Result<Self>>();
 
@@ -234,11 +280,109 @@ impl EngineSnapshot {
                     }
                 }
 
+                // The xopp layers of every page are mapped onto the user layers by their position,
+                // skipping the image layers exported by Rnote.
+                // The first name that is found for a layer is used.
+                let user_layers_masks = xopp_file.xopp_root.user_layers_masks();
+                let mut layer_names: Vec<Option<String>> = vec![];
+                for (page, user_layers_mask) in xopp_file
+                    .xopp_root
+                    .pages
+                    .iter()
+                    .zip(user_layers_masks.iter())
+                {
+                    for (layer_i, layer) in page
+                        .layers
+                        .iter()
+                        .zip(user_layers_mask.iter().copied())
+                        .filter_map(|(layer, is_user_layer)| is_user_layer.then_some(layer))
+                        .enumerate()
+                    {
+                        if layer_i >= layer_names.len() {
+                            layer_names.push(None);
+                        }
+                        if layer_names[layer_i].is_none() {
+                            layer_names[layer_i] = layer.name.clone();
+                        }
+                    }
+                }
+                for (layer_i, name) in layer_names.into_iter().enumerate() {
+                    if layer_i == 0 {
+                        if let Some(name) = name {
+                            engine.store.rename_layer(0, name);
+                        }
+                    } else {
+                        engine.store.add_layer(name);
+                    }
+                }
+
                 // Offsetting as rnote has one global coordinate space
                 let mut offset = na::Vector2::<f64>::zeros();
+                // The pdfs referenced by the pdf backgrounds, every one read once.
+                // Only the first pdf background of a pdf names the file, the following ones reference the same pdf.
+                let mut background_pdfs: Vec<(PathBuf, EmbeddedPdf)> = vec![];
+                let mut current_pdf: Option<usize> = None;
+                // The index of the pdf, the page index, the position and the width of each pdf background page
+                let mut pdf_background_pages: Vec<(usize, u32, na::Vector2<f64>, f64)> = vec![];
+
+                for (page, user_layers_mask) in
+                    xopp_file.xopp_root.pages.into_iter().zip(user_layers_masks)
+                {
+                    let mut user_layer = 0;
+
+                    // collect the pdf background, the pages are imported after all pages are read
+                    if let xoppformat::XoppBackgroundType::Pdf {
+                        domain,
+                        filename,
+                        pageno,
+                    } = &page.background.bg_type
+                    {
+                        if let Some(filename) = filename {
+                            current_pdf = import::resolve_xopp_pdf_background_path(
+                                domain.as_ref(),
+                                filename,
+                                xopp_file_path.as_deref(),
+                            )
+                            .and_then(|path| {
+                                if let Some(pdf_i) =
+                                    background_pdfs.iter().position(|(p, _)| *p == path)
+                                {
+                                    return Some(pdf_i);
+                                }
+                                match std::fs::read(&path) {
+                                    Ok(bytes) => {
+                                        background_pdfs.push((
+                                            path,
+                                            EmbeddedPdf::new(glib::Bytes::from_owned(bytes)),
+                                        ));
+                                        Some(background_pdfs.len() - 1)
+                                    }
+                                    Err(e) => {
+                                        log::error!("reading pdf background `{}` failed in open_from_xopp_bytes() with Err: {e:?}", path.display());
+                                        None
+                                    }
+                                }
+                            });
+                            if current_pdf.is_none() {
+                                log::warn!("pdf background `{filename}` could not be found in open_from_xopp_bytes()");
+                            }
+                        }
+
+                        if let Some(pdf_i) = current_pdf {
+                            pdf_background_pages.push((
+                                pdf_i,
+                                pageno.saturating_sub(1),
+                                offset,
+                                crate::utils::convert_value_dpi(
+                                    page.width,
+                                    xoppformat::XoppFile::DPI,
+                                    xopp_import_prefs.dpi,
+                                ),
+                            ));
+                        }
+                    }
 
-                for (_page_i, page) in xopp_file.xopp_root.pages.into_iter().enumerate() {
-                    for layers in page.layers.into_iter() {
+                    for (layers, is_user_layer) in page.layers.into_iter().zip(user_layers_mask) {
                         // import strokes
                         for new_xoppstroke in layers.strokes.into_iter() {
                             match Stroke::from_xoppstroke(
@@ -247,6 +391,12 @@ impl EngineSnapshot {
                                 xopp_import_prefs.dpi,
                             ) {
                                 Ok((new_stroke, layer)) => {
+                                    let layer = match layer {
+                                        StrokeLayer::UserLayer(_) => {
+                                            StrokeLayer::UserLayer(user_layer)
+                                        }
+                                        layer => layer,
+                                    };
                                     engine.store.insert_stroke(new_stroke, Some(layer));
                                 }
                                 Err(e) => {
@@ -276,6 +426,10 @@ impl EngineSnapshot {
                                 }
                             }
                         }
+
+                        if is_user_layer {
+                            user_layer += 1;
+                        }
                     }
 
                     // Only add to y offset, results in vertical pages
@@ -286,6 +440,36 @@ impl EngineSnapshot {
                     );
                 }
 
+                // import the pdf background pages, parsing every pdf only once
+                for (pdf_i, (pdf_path, embedded_pdf)) in background_pdfs.iter().enumerate() {
+                    let pages = pdf_background_pages
+                        .iter()
+                        .filter(|(i, ..)| *i == pdf_i)
+                        .map(|&(_, page_index, pos, width)| (page_index, pos, width))
+                        .collect::<Vec<(u32, na::Vector2<f64>, f64)>>();
+
+                    match PdfPage::from_embedded_pdf_pages(embedded_pdf, &pages, true) {
+                        Ok(pdf_pages) => {
+                            for mut pdf_page in pdf_pages {
+                                pdf_page.pdf_page_source = Some(import::PdfPageSource {
+                                    path: pdf_path.clone(),
+                                    page_index: pdf_page.page_index,
+                                });
+                                engine.store.insert_stroke(
+                                    Stroke::PdfPage(pdf_page),
+                                    Some(StrokeLayer::Document),
+                                );
+                            }
+                        }
+                        Err(e) => {
+                            log::error!(
+                                "importing pdf background pages failed in open_from_xopp_bytes() with Err {:?}",
+                                e
+                            );
+                        }
+                    }
+                }
+
                 Ok(engine.take_snapshot())
             };
 
@@ -296,6 +480,18 @@ impl EngineSnapshot {
 
         snapshot_receiver.await?
     }
+
+    /// Resolve the embedded Pdfs of the Pdf pages, which are not (de)serialized with the pages.
+    fn resolve_embedded_pdfs(&mut self) {
+        if self.embedded_pdfs.is_empty() {
+            return;
+        }
+        for stroke in Arc::make_mut(&mut self.stroke_components).values_mut() {
+            if let Stroke::PdfPage(pdfpage) = Arc::make_mut(stroke) {
+                pdfpage.resolve_pdf(&self.embedded_pdfs);
+            }
+        }
+    }
 }
 
 /// Stroke content. Used when copying/cutting/pasting a selection into/from the clipboard
@@ -304,11 +500,27 @@ impl EngineSnapshot {
 pub struct StrokeContent {
     #[serde(rename = "strokes")]
     pub strokes: Vec<Arc<Stroke>>,
+    /// The Pdfs embedded by the Pdf pages of the strokes.
+    ///
+    /// Only deserialized, to not encode whole Pdfs on every copy.
+    /// Pasted Pdf pages are also resolved from the Pdfs kept by `EmbeddedPdf::set_clipboard_pdfs()`.
+    #[serde(rename = "embedded_pdfs", skip_serializing)]
+    pub embedded_pdfs: Vec<EmbeddedPdf>,
 }
 
 impl StrokeContent {
     pub const MIME_TYPE: &str = "application/rnote-stroke-content";
 
+    pub fn new(strokes: Vec<Arc<Stroke>>) -> Self {
+        let embedded_pdfs =
+            EmbeddedPdf::collect_from_strokes(strokes.iter().map(|stroke| stroke.as_ref()));
+
+        Self {
+            strokes,
+            embedded_pdfs,
+        }
+    }
+
     pub fn bounds(&self) -> Option<Aabb> {
         if self.strokes.is_empty() {
             return None;
@@ -379,6 +591,14 @@ pub struct RnoteEngine {
     pub audioplayer: Option<AudioPlayer>,
     #[serde(skip)]
     pub visual_debug: bool,
+    #[serde(skip)]
+    pub search: SearchState,
+    /// The replay of the document in chronological order, when it is active.
+    #[serde(skip)]
+    pub replay: Option<ReplayState>,
+    /// Records the input events, when it is active.
+    #[serde(skip)]
+    pub pen_recorder: Option<PenRecorder>,
     // the task sender. Must not be modified, only cloned.
     #[serde(skip)]
     pub tasks_tx: EngineTaskSender,
@@ -387,6 +607,10 @@ pub struct RnoteEngine {
     // Background rendering
     #[serde(skip)]
     pub background_tile_image: Option<render::Image>,
+    /// The tile images of the page background overrides, indexed by the page index.
+    #[serde(skip)]
+    pub page_background_tile_images: Vec<Option<render::Image>>,
+    #[cfg(feature = "gtk")]
     #[serde(skip)]
     background_rendernodes: Vec<gsk::RenderNode>,
 }
@@ -408,9 +632,14 @@ impl Default for RnoteEngine {
 
             audioplayer: None,
             visual_debug: false,
+            search: SearchState::default(),
+            replay: None,
+            pen_recorder: None,
             tasks_tx,
             tasks_rx: Some(tasks_rx),
             background_tile_image: None,
+            page_background_tile_images: Vec::default(),
+            #[cfg(feature = "gtk")]
             background_rendernodes: Vec::default(),
         }
     }
@@ -487,11 +716,20 @@ impl RnoteEngine {
             Arc::make_mut(&mut store_history_entry.stroke_components).remove(key);
         }
 
+        let embedded_pdfs = EmbeddedPdf::collect_from_strokes(
+            store_history_entry
+                .stroke_components
+                .values()
+                .map(|stroke| stroke.as_ref()),
+        );
+
         EngineSnapshot {
-            document: self.document,
+            document: self.document.clone(),
             stroke_components: Arc::clone(&store_history_entry.stroke_components),
             chrono_components: Arc::clone(&store_history_entry.chrono_components),
             chrono_counter: store_history_entry.chrono_counter,
+            layers: Arc::clone(&store_history_entry.layers),
+            embedded_pdfs,
         }
     }
 
@@ -501,30 +739,39 @@ impl RnoteEngine {
     pub fn load_snapshot(&mut self, snapshot: EngineSnapshot) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
-        self.document = snapshot.document;
+        self.document = snapshot.document.clone();
+        widget_flags.merge(self.replay_stop());
         widget_flags.merge(self.store.import_from_snapshot(&snapshot));
         widget_flags.merge(self.current_pen_update_state());
 
         widget_flags
     }
 
-    /// Records the current store state and saves it as a history entry.
-    pub fn record(&mut self, now: Instant) -> WidgetFlags {
-        self.store.record(now)
+    /// Records the changes of the store since the last record and saves them as a history entry, labeled with the operation.
+    pub fn record(&mut self, label: HistoryLabel) -> WidgetFlags {
+        self.store.record(label)
     }
 
-    /// Update the state of the latest history entry with the current document state.
-    pub fn update_latest_history_entry(&mut self, now: Instant) -> WidgetFlags {
-        self.store.update_latest_history_entry(now)
+    /// Update the latest history entry with the changes of the store since the last record.
+    ///
+    /// A new entry is recorded instead when the latest entry has a different label.
+    pub fn update_latest_history_entry(&mut self, label: HistoryLabel) -> WidgetFlags {
+        self.store.update_latest_history_entry(label)
     }
 
     /// Undo the latest changes.
-    pub fn undo(&mut self, now: Instant) -> WidgetFlags {
+    pub fn undo(&mut self) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
-        widget_flags.merge(self.store.undo(now));
+        let (store_widget_flags, pages_state) = self.store.undo();
+        widget_flags.merge(store_widget_flags);
+        if let Some(pages_state) = pages_state {
+            widget_flags.merge(self.doc_restore_pages_state(&pages_state));
+        }
         widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.document.expand_fixed_size_to_fit_strokes(&self.store));
         widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.search_refresh());
         self.update_rendering_current_viewport();
         widget_flags.redraw = true;
 
@@ -532,12 +779,18 @@ impl RnoteEngine {
     }
 
     /// Redo the latest changes.
-    pub fn redo(&mut self, now: Instant) -> WidgetFlags {
+    pub fn redo(&mut self) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
-        widget_flags.merge(self.store.redo(now));
+        let (store_widget_flags, pages_state) = self.store.redo();
+        widget_flags.merge(store_widget_flags);
+        if let Some(pages_state) = pages_state {
+            widget_flags.merge(self.doc_restore_pages_state(&pages_state));
+        }
         widget_flags.merge(self.doc_resize_autoexpand());
+        widget_flags.merge(self.document.expand_fixed_size_to_fit_strokes(&self.store));
         widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.search_refresh());
         self.update_rendering_current_viewport();
         widget_flags.redraw = true;
 
@@ -552,6 +805,16 @@ impl RnoteEngine {
         self.store.can_redo()
     }
 
+    /// The label of the operation that is reverted by the next undo.
+    pub fn undo_label(&self) -> Option<HistoryLabel> {
+        self.store.undo_label()
+    }
+
+    /// The label of the operation that is reapplied by the next redo.
+    pub fn redo_label(&self) -> Option<HistoryLabel> {
+        self.store.redo_label()
+    }
+
     // Clears the entire store.
     pub fn clear(&mut self) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
@@ -636,6 +899,22 @@ impl RnoteEngine {
                     widget_flags.redraw = true;
                 }
             }
+            EngineTask::BrushHoldStill => {
+                let tasks_tx = self.tasks_tx();
+                if let Pen::Brush(brush) = self.penholder.current_pen_mut() {
+                    widget_flags.merge(brush.handle_hold_still_timeout(
+                        Instant::now(),
+                        &mut EngineViewMut {
+                            tasks_tx,
+                            pens_config: &mut self.pens_config,
+                            doc: &mut self.document,
+                            store: &mut self.store,
+                            camera: &mut self.camera,
+                            audioplayer: &mut self.audioplayer,
+                        },
+                    ));
+                }
+            }
             EngineTask::Zoom(zoom) => {
                 widget_flags.merge(self.camera.zoom_temporarily_to(1.0));
                 widget_flags.merge(self.camera.zoom_to(zoom));
@@ -663,6 +942,16 @@ impl RnoteEngine {
         pen_mode: Option<PenMode>,
         now: Instant,
     ) -> WidgetFlags {
+        if let Some(pen_recorder) = self.pen_recorder.as_mut() {
+            pen_recorder.push(
+                PenRecordingEvent::PenEvent {
+                    event: event.clone(),
+                    pen_mode,
+                },
+                Some(now),
+            );
+        }
+
         self.penholder.handle_pen_event(
             event,
             pen_mode,
@@ -684,22 +973,49 @@ impl RnoteEngine {
         shortcut_key: ShortcutKey,
         now: Instant,
     ) -> WidgetFlags {
-        self.penholder.handle_pressed_shortcut_key(
-            shortcut_key,
-            now,
-            &mut EngineViewMut {
-                tasks_tx: self.tasks_tx(),
-                pens_config: &mut self.pens_config,
-                doc: &mut self.document,
-                store: &mut self.store,
-                camera: &mut self.camera,
-                audioplayer: &mut self.audioplayer,
-            },
-        )
+        if let Some(pen_recorder) = self.pen_recorder.as_mut() {
+            pen_recorder.push(
+                PenRecordingEvent::PressedShortcutKey { shortcut_key },
+                Some(now),
+            );
+        }
+
+        match self.penholder.get_shortcut_action(shortcut_key) {
+            Some(ShortcutAction::Undo) => self.undo(),
+            Some(ShortcutAction::Redo) => self.redo(),
+            Some(ShortcutAction::ZoomIn) => self.zoom_w_timeout_keep_viewport_center(
+                self.camera.total_zoom() * (1.0 + Camera::ZOOM_STEP),
+            ),
+            Some(ShortcutAction::ZoomOut) => self.zoom_w_timeout_keep_viewport_center(
+                self.camera.total_zoom() * (1.0 - Camera::ZOOM_STEP),
+            ),
+            Some(ShortcutAction::ZoomFitWidth) => self.zoom_w_timeout_keep_viewport_center(
+                self.camera.size()[0]
+                    / (self.document.format.width + 2.0 * Camera::ZOOM_FIT_WIDTH_MARGIN),
+            ),
+            Some(ShortcutAction::NextPage) => self.camera_goto_adjacent_page(true),
+            Some(ShortcutAction::PreviousPage) => self.camera_goto_adjacent_page(false),
+            _ => self.penholder.handle_pressed_shortcut_key(
+                shortcut_key,
+                now,
+                &mut EngineViewMut {
+                    tasks_tx: self.tasks_tx(),
+                    pens_config: &mut self.pens_config,
+                    doc: &mut self.document,
+                    store: &mut self.store,
+                    camera: &mut self.camera,
+                    audioplayer: &mut self.audioplayer,
+                },
+            ),
+        }
     }
 
     /// Change the pen style.
     pub fn change_pen_style(&mut self, new_style: PenStyle) -> WidgetFlags {
+        if let Some(pen_recorder) = self.pen_recorder.as_mut() {
+            pen_recorder.push(PenRecordingEvent::ChangePenStyle { style: new_style }, None);
+        }
+
         self.penholder.change_style(
             new_style,
             &mut EngineViewMut {
@@ -775,10 +1091,13 @@ impl RnoteEngine {
 
     /// Generate bounds for each page on the document which contains content.
     pub fn pages_bounds_w_content(&self, split_order: SplitOrder) -> Vec<Aabb> {
-        let doc_bounds = self.document.bounds();
-        let keys = self.store.stroke_keys_as_rendered();
+        self.pages_bounds_w_strokes(&self.store.stroke_keys_as_rendered(), split_order)
+    }
 
-        let strokes_bounds = self.store.strokes_bounds(&keys);
+    /// Generate bounds for each page on the document which contains any of the given strokes.
+    fn pages_bounds_w_strokes(&self, keys: &[StrokeKey], split_order: SplitOrder) -> Vec<Aabb> {
+        let doc_bounds = self.document.bounds();
+        let strokes_bounds = self.store.strokes_bounds(keys);
 
         let pages_bounds = doc_bounds
             .split_extended_origin_aligned(
@@ -827,6 +1146,14 @@ impl RnoteEngine {
         self.camera.zoom_w_timeout(zoom, self.tasks_tx.clone())
     }
 
+    /// Zoom with a timeout like [Self::zoom_w_timeout()], while keeping the viewport center in place.
+    pub fn zoom_w_timeout_keep_viewport_center(&mut self, zoom: f64) -> WidgetFlags {
+        let viewport_center = self.camera.viewport_center();
+        let mut widget_flags = self.zoom_w_timeout(zoom);
+        widget_flags.merge(self.camera.set_viewport_center(viewport_center));
+        widget_flags
+    }
+
     /// Resizes the doc to the format and to fit all strokes.
     ///
     /// Background rendering then needs to be updated.
@@ -891,6 +1218,151 @@ impl RnoteEngine {
         true
     }
 
+    /// Set the active user layer, which new strokes are inserted into.
+    pub fn layer_set_active(&mut self, index: u32) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.store.set_active_layer(index) {
+            widget_flags.refresh_ui = true;
+        }
+        widget_flags
+    }
+
+    /// Add a new user layer on top of all others and make it the active layer.
+    ///
+    /// If no name is given, a name is generated.
+    pub fn layer_add(&mut self, name: Option<String>) -> WidgetFlags {
+        self.store.add_layer(name);
+        self.layers_changed(HistoryLabel::AddLayer)
+    }
+
+    /// Permanently remove the user layer and all strokes on it.
+    ///
+    /// The last remaining layer can't be removed.
+    pub fn layer_remove(&mut self, index: u32) -> WidgetFlags {
+        if !self.store.remove_layer(index) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::RemoveLayer)
+    }
+
+    /// Rename the user layer.
+    pub fn layer_rename(&mut self, index: u32, name: String) -> WidgetFlags {
+        if !self.store.rename_layer(index, name) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::RenameLayer)
+    }
+
+    /// Set the user layer hidden or visible.
+    pub fn layer_set_hidden(&mut self, index: u32, hidden: bool) -> WidgetFlags {
+        if !self.store.set_layer_hidden(index, hidden) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::ChangeLayerVisibility)
+    }
+
+    /// Set the user layer locked or unlocked.
+    pub fn layer_set_locked(&mut self, index: u32, locked: bool) -> WidgetFlags {
+        if !self.store.set_layer_locked(index, locked) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::ChangeLayerLock)
+    }
+
+    /// Move the user layer from index `from` to index `to`, changing the drawing order.
+    pub fn layer_move(&mut self, from: u32, to: u32) -> WidgetFlags {
+        if from == to || !self.store.move_layer(from, to) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::MoveLayer)
+    }
+
+    /// Merge the user layer into the layer below it.
+    pub fn layer_merge_down(&mut self, index: u32) -> WidgetFlags {
+        if !self.store.merge_layer_down(index) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::MergeLayerDown)
+    }
+
+    /// Move the currently selected strokes to the given user layer.
+    pub fn move_selection_to_layer(&mut self, index: u32) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_unordered();
+        if selection_keys.is_empty() || !self.store.move_strokes_to_layer(&selection_keys, index) {
+            return WidgetFlags::default();
+        }
+        self.layers_changed(HistoryLabel::MoveToLayer)
+    }
+
+    /// Smooth the currently selected brush strokes and record the new state in the history.
+    ///
+    /// See [StrokeStore::smooth_brushstrokes].
+    pub fn smooth_selection(&mut self, tolerance: f64) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_unordered();
+        let mut widget_flags = self.store.smooth_brushstrokes(&selection_keys, tolerance);
+        if !widget_flags.store_modified {
+            return widget_flags;
+        }
+
+        widget_flags.merge(self.current_pen_update_state());
+        self.update_rendering_current_viewport();
+        widget_flags.merge(self.store.record(HistoryLabel::SmoothStrokes));
+
+        widget_flags
+    }
+
+    /// Recognize shapes in the currently selected brush strokes, replace them with shape strokes
+    /// and record the new state in the history.
+    ///
+    /// See [StrokeStore::recognize_shapes].
+    pub fn recognize_shapes_in_selection(&mut self, tolerance: f64) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_unordered();
+        let mut widget_flags = self.store.recognize_shapes(&selection_keys, tolerance);
+        if !widget_flags.store_modified {
+            return widget_flags;
+        }
+
+        widget_flags.merge(self.current_pen_update_state());
+        self.update_rendering_current_viewport();
+        widget_flags.merge(self.store.record(HistoryLabel::RecognizeShapes));
+
+        widget_flags
+    }
+
+    /// Ungroup the currently selected vector images into editable strokes
+    /// and record the new state in the history.
+    ///
+    /// See [StrokeStore::ungroup_vectorimages].
+    pub fn ungroup_vectorimages_in_selection(&mut self) -> WidgetFlags {
+        let selection_keys = self.store.selection_keys_unordered();
+        let mut widget_flags = self.store.ungroup_vectorimages(&selection_keys);
+        if !widget_flags.store_modified {
+            return widget_flags;
+        }
+
+        widget_flags.merge(self.current_pen_update_state());
+        self.update_rendering_current_viewport();
+        widget_flags.merge(self.store.record(HistoryLabel::UngroupVectorImages));
+
+        widget_flags
+    }
+
+    /// Update the engine after the user layers were modified and record the new state in the history.
+    fn layers_changed(&mut self, label: HistoryLabel) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        widget_flags.merge(self.current_pen_update_state());
+        widget_flags.merge(self.doc_resize_autoexpand());
+        self.update_rendering_current_viewport();
+        widget_flags.merge(self.store.record(label));
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+        widget_flags.refresh_ui = true;
+
+        widget_flags
+    }
+
     /// Update the viewport offset of the camera, clamped to mins and maxs values depending on the document layout.
     ///
     /// Background and strokes rendering then need to be updated.
@@ -898,6 +1370,15 @@ impl RnoteEngine {
         self.camera.set_offset(offset, &self.document)
     }
 
+    /// Move the camera to the top of the next or previous page.
+    pub fn camera_goto_adjacent_page(&mut self, forward: bool) -> WidgetFlags {
+        let offset = self.camera.adjacent_page_offset(&self.document, forward);
+        let mut widget_flags = self.camera_set_offset(offset);
+        widget_flags.merge(self.doc_expand_autoexpand());
+        self.update_rendering_current_viewport();
+        widget_flags
+    }
+
     /// Update the viewport size of the camera.
     ///
     /// Background and strokes rendering then need to be updated.
@@ -957,3 +1438,442 @@ impl RnoteEngine {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::export::{DocExportFormat, DocExportPrefs};
+    use super::import::XoppImportPrefs;
+    use super::{EngineSnapshot, RnoteEngine};
+    use crate::fileformats::{xoppformat, FileFormatSaver};
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::store::{HistoryLabel, StrokeKey};
+    use crate::strokes::{BrushStroke, ShapeStroke, Stroke};
+    use approx::assert_relative_eq;
+    use p2d::bounding_volume::Aabb;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::shapes::{Rectangle, Shape, ShapeBehaviour};
+    use rnote_compose::{PenPath, Style};
+
+    fn xopp_pen_stroke(coords: &[(f64, f64)]) -> xoppformat::XoppStroke {
+        xoppformat::XoppStroke {
+            tool: xoppformat::XoppTool::Pen,
+            color: xoppformat::XoppColor {
+                red: 0,
+                green: 0,
+                blue: 0,
+                alpha: 0xff,
+            },
+            width: vec![2.0],
+            coords: coords.iter().map(|&(x, y)| na::vector![x, y]).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// The layer names, and the bounds of the strokes on every user layer in drawing order.
+    fn user_layers_content(snapshot: &EngineSnapshot) -> Vec<(String, Vec<Aabb>)> {
+        snapshot
+            .layers
+            .iter()
+            .enumerate()
+            .map(|(layer_i, layer_props)| {
+                let mut strokes = snapshot
+                    .chrono_components
+                    .iter()
+                    .filter(|(_, chrono_comp)| {
+                        chrono_comp.layer == StrokeLayer::UserLayer(layer_i as u32)
+                    })
+                    .map(|(key, chrono_comp)| (chrono_comp.t(), key))
+                    .collect::<Vec<_>>();
+                strokes.sort_unstable_by_key(|(t, _)| *t);
+
+                let bounds = strokes
+                    .into_iter()
+                    .filter_map(|(_, key)| Some(snapshot.stroke_components.get(key)?.bounds()))
+                    .collect();
+
+                (layer_props.name.clone(), bounds)
+            })
+            .collect()
+    }
+
+    fn xopp_import(pages: Vec<xoppformat::XoppPage>) -> EngineSnapshot {
+        let xopp_file = xoppformat::XoppFile {
+            xopp_root: xoppformat::XoppRoot {
+                fileversion: String::from("4"),
+                xournal_version: None,
+                title: String::from("multi-layer document"),
+                preview: String::new(),
+                pages,
+            },
+        };
+
+        futures::executor::block_on(EngineSnapshot::load_from_xopp_bytes(
+            xopp_file.save_as_bytes("multi-layer").unwrap(),
+            XoppImportPrefs { dpi: 96.0 },
+            None,
+        ))
+        .unwrap()
+    }
+
+    /// Exports the document of the engine as xopp and imports it again.
+    fn xopp_export_reimport(engine: &RnoteEngine) -> EngineSnapshot {
+        let doc_export_prefs = DocExportPrefs {
+            export_format: DocExportFormat::Xopp,
+            ..Default::default()
+        };
+        let exported_bytes = futures::executor::block_on(
+            engine.export_doc(String::from("multi-layer"), Some(doc_export_prefs)),
+        )
+        .unwrap()
+        .unwrap();
+
+        futures::executor::block_on(EngineSnapshot::load_from_xopp_bytes(
+            exported_bytes,
+            XoppImportPrefs { dpi: 96.0 },
+            None,
+        ))
+        .unwrap()
+    }
+
+    /// Imports the xopp pages, exports the document as xopp and imports it again.
+    fn xopp_roundtrip(pages: Vec<xoppformat::XoppPage>) -> (EngineSnapshot, EngineSnapshot) {
+        let imported = xopp_import(pages);
+
+        let mut engine = RnoteEngine::default();
+        let _ = engine.load_snapshot(imported.clone());
+        let reimported = xopp_export_reimport(&engine);
+
+        (imported, reimported)
+    }
+
+    fn assert_user_layers_content_eq(
+        content: &[(String, Vec<Aabb>)],
+        other_content: &[(String, Vec<Aabb>)],
+    ) {
+        assert_eq!(content.len(), other_content.len());
+
+        for ((name, bounds), (other_name, other_bounds)) in content.iter().zip(other_content.iter())
+        {
+            assert_eq!(name, other_name);
+            assert_eq!(bounds.len(), other_bounds.len());
+
+            for (b, other_b) in bounds.iter().zip(other_bounds.iter()) {
+                assert_relative_eq!(b.mins, other_b.mins, epsilon = 1e-2);
+                assert_relative_eq!(b.maxs, other_b.maxs, epsilon = 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn xopp_layers_roundtrip() {
+        let layers = vec![
+            xoppformat::XoppLayer {
+                name: Some(String::from("Sketch")),
+                strokes: vec![
+                    xopp_pen_stroke(&[(10.0, 10.0), (50.0, 20.0), (90.0, 60.0)]),
+                    xopp_pen_stroke(&[(100.0, 100.0), (120.0, 140.0)]),
+                ],
+                ..Default::default()
+            },
+            xoppformat::XoppLayer {
+                name: Some(String::from("Ink")),
+                strokes: vec![xopp_pen_stroke(&[(200.0, 300.0), (250.0, 310.0)])],
+                ..Default::default()
+            },
+            xoppformat::XoppLayer {
+                name: Some(String::from("Annotations")),
+                strokes: vec![
+                    xopp_pen_stroke(&[(300.0, 400.0), (320.0, 420.0), (340.0, 400.0)]),
+                    xopp_pen_stroke(&[(20.0, 700.0), (500.0, 700.0)]),
+                ],
+                ..Default::default()
+            },
+        ];
+        let (imported, reimported) = xopp_roundtrip(vec![xoppformat::XoppPage {
+            width: 612.0,
+            height: 792.0,
+            background: xoppformat::XoppBackground::default(),
+            layers,
+        }]);
+
+        let imported_content = user_layers_content(&imported);
+        assert_eq!(
+            imported_content
+                .iter()
+                .map(|(name, bounds)| (name.as_str(), bounds.len()))
+                .collect::<Vec<_>>(),
+            vec![("Sketch", 2), ("Ink", 1), ("Annotations", 2)]
+        );
+        assert_user_layers_content_eq(&imported_content, &user_layers_content(&reimported));
+    }
+
+    #[test]
+    fn xopp_multi_page_layers_roundtrip() {
+        let layer = |name: &str, strokes| xoppformat::XoppLayer {
+            name: Some(String::from(name)),
+            strokes,
+            ..Default::default()
+        };
+        let page = |layers| xoppformat::XoppPage {
+            width: 612.0,
+            height: 792.0,
+            background: xoppformat::XoppBackground::default(),
+            layers,
+        };
+        let pages = vec![
+            page(vec![
+                layer(
+                    "Sketch",
+                    vec![xopp_pen_stroke(&[(10.0, 10.0), (50.0, 20.0)])],
+                ),
+                layer(
+                    "Ink",
+                    vec![xopp_pen_stroke(&[(200.0, 300.0), (250.0, 310.0)])],
+                ),
+                layer("Annotations", vec![]),
+            ]),
+            // The lowest layer is empty on the second page
+            page(vec![
+                layer("Sketch", vec![]),
+                layer(
+                    "Ink",
+                    vec![xopp_pen_stroke(&[(100.0, 100.0), (120.0, 140.0)])],
+                ),
+                layer(
+                    "Annotations",
+                    vec![xopp_pen_stroke(&[(300.0, 400.0), (340.0, 400.0)])],
+                ),
+            ]),
+        ];
+        let (imported, reimported) = xopp_roundtrip(pages);
+
+        let imported_content = user_layers_content(&imported);
+        assert_eq!(
+            imported_content
+                .iter()
+                .map(|(name, bounds)| (name.as_str(), bounds.len()))
+                .collect::<Vec<_>>(),
+            vec![("Sketch", 1), ("Ink", 2), ("Annotations", 1)]
+        );
+        // The strokes of the second page are below the first page
+        assert!(imported_content[1].1[1].mins[1] > 792.0);
+        assert_user_layers_content_eq(&imported_content, &user_layers_content(&reimported));
+    }
+
+    #[test]
+    fn xopp_export_keeps_hidden_layers() {
+        let layer = |name: &str, strokes| xoppformat::XoppLayer {
+            name: Some(String::from(name)),
+            strokes,
+            ..Default::default()
+        };
+        let page = |layers| xoppformat::XoppPage {
+            width: 612.0,
+            height: 792.0,
+            background: xoppformat::XoppBackground::default(),
+            layers,
+        };
+        // The hidden layer has the only content of the second page
+        let imported = xopp_import(vec![
+            page(vec![
+                layer("Ink", vec![xopp_pen_stroke(&[(10.0, 10.0), (50.0, 20.0)])]),
+                layer("Hidden", vec![]),
+            ]),
+            page(vec![
+                layer("Ink", vec![]),
+                layer(
+                    "Hidden",
+                    vec![xopp_pen_stroke(&[(100.0, 100.0), (120.0, 140.0)])],
+                ),
+            ]),
+        ]);
+
+        let mut engine = RnoteEngine::default();
+        let _ = engine.load_snapshot(imported.clone());
+        assert!(engine.store.set_layer_hidden(1, true));
+        let reimported = xopp_export_reimport(&engine);
+
+        assert_user_layers_content_eq(
+            &user_layers_content(&imported),
+            &user_layers_content(&reimported),
+        );
+    }
+
+    #[test]
+    fn doc_bitmap_export_size() {
+        let mut engine = RnoteEngine::default();
+        engine.store.insert_stroke(
+            Stroke::BrushStroke(BrushStroke::from_penpath(
+                PenPath::new_w_segments(
+                    Element::new(na::vector![100.0, 100.0], 0.5),
+                    [Segment::LineTo {
+                        end: Element::new(na::vector![300.0, 200.0], 0.5),
+                    }],
+                ),
+                Style::default(),
+            )),
+            None,
+        );
+        let content_bounds = engine.bounds_w_content_extended().unwrap();
+
+        for (export_format, image_format) in [
+            (DocExportFormat::Png, image::ImageFormat::Png),
+            (DocExportFormat::Jpeg, image::ImageFormat::Jpeg),
+        ] {
+            let doc_export_prefs = DocExportPrefs {
+                export_format,
+                bitmap_scalefactor: 0.5,
+                ..Default::default()
+            };
+            let bytes = futures::executor::block_on(
+                engine.export_doc(String::from("bitmap"), Some(doc_export_prefs)),
+            )
+            .unwrap()
+            .unwrap();
+            let image = image::load_from_memory_with_format(&bytes, image_format).unwrap();
+
+            // The rendered bounds are rounded up to whole numbers and extended by one on every side
+            let expected_size = content_bounds.extents() * doc_export_prefs.bitmap_scalefactor;
+            let tolerance = 4.0 * doc_export_prefs.bitmap_scalefactor + 1.0;
+            assert!((f64::from(image.width()) - expected_size[0]).abs() <= tolerance);
+            assert!((f64::from(image.height()) - expected_size[1]).abs() <= tolerance);
+        }
+
+        // Too large images are rejected before rendering
+        let doc_export_prefs = DocExportPrefs {
+            export_format: DocExportFormat::Png,
+            bitmap_scalefactor: 100.0,
+            ..Default::default()
+        };
+        assert!(futures::executor::block_on(
+            engine.export_doc(String::from("bitmap"), Some(doc_export_prefs))
+        )
+        .unwrap()
+        .is_err());
+    }
+
+    /// A brush stroke along a sine wave, with jitter on every input element.
+    fn jittered_brushstroke(y: f64) -> Stroke {
+        let element = |i: u32| {
+            let x = 10.0 + f64::from(i) * 4.0;
+            let jitter = if i % 2 == 0 { 0.5 } else { -0.5 };
+            Element::new(na::vector![x, y + (x * 0.05).sin() * 20.0 + jitter], 0.5)
+        };
+        Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new_w_segments(
+                element(0),
+                (1..100).map(|i| Segment::LineTo { end: element(i) }),
+            ),
+            Style::default(),
+        ))
+    }
+
+    fn strokes_as_json(engine: &RnoteEngine, keys: &[StrokeKey]) -> Vec<serde_json::Value> {
+        keys.iter()
+            .map(|&key| serde_json::to_value(engine.store.get_stroke_ref(key).unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn smooth_selection_and_undo() {
+        let mut engine = RnoteEngine::default();
+        let selected_brushstroke = engine
+            .store
+            .insert_stroke(jittered_brushstroke(100.0), None);
+        let shapestroke = engine.store.insert_stroke(
+            Stroke::ShapeStroke(ShapeStroke::new(
+                Shape::Rectangle(Rectangle::from_corners(
+                    na::vector![10.0, 200.0],
+                    na::vector![200.0, 300.0],
+                )),
+                Style::default(),
+            )),
+            None,
+        );
+        let unselected_brushstroke = engine
+            .store
+            .insert_stroke(jittered_brushstroke(400.0), None);
+        let keys = [selected_brushstroke, shapestroke, unselected_brushstroke];
+        engine
+            .store
+            .set_selected_keys(&[selected_brushstroke, shapestroke], true);
+        let _ = engine.record(HistoryLabel::DrawStroke);
+        let original = strokes_as_json(&engine, &keys);
+
+        let widget_flags = engine.smooth_selection(1.0);
+        assert!(widget_flags.store_modified);
+        let smoothed = strokes_as_json(&engine, &keys);
+        assert_ne!(smoothed[0], original[0]);
+        assert_eq!(smoothed[1..], original[1..]);
+        let Some(Stroke::BrushStroke(brushstroke)) =
+            engine.store.get_stroke_ref(selected_brushstroke)
+        else {
+            panic!("smoothed stroke is not a brush stroke");
+        };
+        assert!(brushstroke.path.segments.len() < 99);
+
+        let _ = engine.undo();
+        assert_eq!(strokes_as_json(&engine, &keys), original);
+
+        let _ = engine.redo();
+        assert_eq!(strokes_as_json(&engine, &keys), smoothed);
+    }
+
+    #[test]
+    fn legacy_xoj_import() {
+        let xoj_bytes = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../misc/file-tests/xournal-0-4-8-test.xoj"
+        ));
+        let xopp_import_prefs = XoppImportPrefs { dpi: 96.0 };
+
+        let imported = futures::executor::block_on(EngineSnapshot::load_from_xopp_bytes(
+            xoj_bytes.to_vec(),
+            xopp_import_prefs,
+            None,
+        ))
+        .unwrap();
+
+        let layer_strokes = |layer: StrokeLayer| {
+            imported
+                .chrono_components
+                .iter()
+                .filter(|(_, chrono_comp)| chrono_comp.layer == layer)
+                .count()
+        };
+        assert_relative_eq!(imported.document.width, 816.0);
+        assert_relative_eq!(imported.document.height, 3.0 * 1056.0);
+        assert_eq!(layer_strokes(StrokeLayer::UserLayer(0)), 3);
+        assert_eq!(layer_strokes(StrokeLayer::Highlighter), 1);
+        assert_eq!(layer_strokes(StrokeLayer::Image), 1);
+    }
+
+    #[test]
+    fn older_rnote_xopp_export_import() {
+        // Older Rnote versions export an empty image layer as the first layer of every page
+        let xopp_bytes = include_bytes!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../misc/file-tests/rnote-0-7-export-test.xopp"
+        ));
+        let xopp_import_prefs = XoppImportPrefs { dpi: 96.0 };
+
+        let imported = futures::executor::block_on(EngineSnapshot::load_from_xopp_bytes(
+            xopp_bytes.to_vec(),
+            xopp_import_prefs,
+            None,
+        ))
+        .unwrap();
+
+        let layer_strokes = |layer: StrokeLayer| {
+            imported
+                .chrono_components
+                .iter()
+                .filter(|(_, chrono_comp)| chrono_comp.layer == layer)
+                .count()
+        };
+        assert_eq!(imported.layers.len(), 1);
+        assert_eq!(layer_strokes(StrokeLayer::UserLayer(0)), 2);
+        assert_eq!(layer_strokes(StrokeLayer::UserLayer(1)), 0);
+        assert_eq!(layer_strokes(StrokeLayer::Image), 1);
+    }
+}
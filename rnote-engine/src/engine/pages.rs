@@ -0,0 +1,389 @@
+// Imports
+use super::RnoteEngine;
+use crate::document::{Background, Layout, PagesState};
+use crate::store::{HistoryLabel, StrokeKey};
+use crate::WidgetFlags;
+use rnote_compose::transform::TransformBehaviour;
+use std::collections::HashSet;
+
+/// Page operations for documents in fixed size layout.
+///
+/// The pages are numbered starting from zero. A stroke belongs to the page its upper bound is on,
+/// strokes above the document belong to the first page and strokes below it to the last page.
+impl RnoteEngine {
+    /// The number of pages of the document in fixed size layout.
+    pub fn doc_n_pages_fixed_size(&self) -> u32 {
+        if self.document.format.height > 0.0 {
+            (self.document.height / self.document.format.height).round() as u32
+        } else {
+            0
+        }
+    }
+
+    /// Insert a blank page at the given index, moving the strokes on this page and the pages below it one page down.
+    ///
+    /// The index may be equal to the number of pages, then the page is appended.
+    pub fn doc_insert_page_fixed_size(&mut self, index: u32) -> anyhow::Result<WidgetFlags> {
+        self.ensure_fixed_size_layout()?;
+        let n_pages = self.doc_n_pages_fixed_size();
+        if index > n_pages {
+            return Err(anyhow::anyhow!(
+                "Inserting a page at index {index} failed, the document has {n_pages} pages"
+            ));
+        }
+
+        let old_pages_state = self.document.pages_state();
+
+        self.insert_blank_page_fixed_size(index);
+
+        Ok(self.pages_changed(HistoryLabel::InsertPage, old_pages_state))
+    }
+
+    /// Delete the page with the given index, trashing its strokes and moving the pages below it one page up.
+    ///
+    /// The last remaining page can't be deleted.
+    pub fn doc_delete_page_fixed_size(&mut self, index: u32) -> anyhow::Result<WidgetFlags> {
+        self.ensure_fixed_size_layout()?;
+        let n_pages = self.doc_n_pages_fixed_size();
+        if n_pages <= 1 {
+            return Err(anyhow::anyhow!(
+                "Deleting a page failed, the last remaining page can't be deleted"
+            ));
+        }
+        self.ensure_page_index(index, n_pages)?;
+
+        let old_pages_state = self.document.pages_state();
+        let format_height = self.document.format.height;
+        let page_keys = self.keys_on_page_fixed_size(index);
+        let below_keys = self.keys_from_page_fixed_size(index + 1);
+
+        self.store.set_trashed_keys(&page_keys, true);
+        self.store
+            .translate_strokes(&below_keys, na::vector![0.0, -format_height]);
+        self.store
+            .translate_strokes_images(&below_keys, na::vector![0.0, -format_height]);
+        self.document.height -= format_height;
+        self.document.remove_page_background(index);
+
+        Ok(self.pages_changed(HistoryLabel::DeletePage, old_pages_state))
+    }
+
+    /// Duplicate the page with the given index, inserting the copy with its strokes below it.
+    pub fn doc_duplicate_page_fixed_size(&mut self, index: u32) -> anyhow::Result<WidgetFlags> {
+        self.ensure_fixed_size_layout()?;
+        self.ensure_page_index(index, self.doc_n_pages_fixed_size())?;
+
+        let old_pages_state = self.document.pages_state();
+        let format_height = self.document.format.height;
+        let page_keys = self
+            .keys_on_page_fixed_size(index)
+            .into_iter()
+            .filter(|&key| !self.store.trashed(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
+
+        self.insert_blank_page_fixed_size(index + 1);
+        self.document
+            .set_page_background(index + 1, self.doc_page_background_override(index));
+
+        for key in page_keys {
+            let layer = self.store.stroke_layer(key);
+            let Some(mut stroke) = self.store.get_stroke_ref(key).cloned() else {
+                continue;
+            };
+            stroke.translate(na::vector![0.0, format_height]);
+            self.store.insert_stroke(stroke, layer);
+        }
+
+        Ok(self.pages_changed(HistoryLabel::DuplicatePage, old_pages_state))
+    }
+
+    /// Move the page with the given index to the new index, shifting the pages in between by one page.
+    pub fn doc_move_page_fixed_size(&mut self, index: u32, to: u32) -> anyhow::Result<WidgetFlags> {
+        self.ensure_fixed_size_layout()?;
+        let n_pages = self.doc_n_pages_fixed_size();
+        self.ensure_page_index(index, n_pages)?;
+        self.ensure_page_index(to, n_pages)?;
+        if index == to {
+            return Ok(WidgetFlags::default());
+        }
+
+        let old_pages_state = self.document.pages_state();
+        let format_height = self.document.format.height;
+        // Collect the keys of all affected pages before moving any strokes
+        let pages_keys = (index.min(to)..=index.max(to))
+            .map(|i| (i, self.keys_on_page_fixed_size(i)))
+            .collect::<Vec<(u32, Vec<StrokeKey>)>>();
+
+        for (i, keys) in pages_keys {
+            let offset_pages = if i == index {
+                f64::from(to) - f64::from(index)
+            } else if index < to {
+                -1.0
+            } else {
+                1.0
+            };
+            let offset = na::vector![0.0, offset_pages * format_height];
+
+            self.store.translate_strokes(&keys, offset);
+            self.store.translate_strokes_images(&keys, offset);
+        }
+        let page_background = self.document.remove_page_background(index);
+        self.document.insert_page_background(to, page_background);
+
+        Ok(self.pages_changed(HistoryLabel::MovePage, old_pages_state))
+    }
+
+    /// Set the background override of the page with the given index, or clear it to use the document background.
+    ///
+    /// There is no UI for it yet, the overrides can only be set through the engine API.
+    pub fn doc_set_page_background_fixed_size(
+        &mut self,
+        index: u32,
+        background: Option<Background>,
+    ) -> anyhow::Result<WidgetFlags> {
+        self.ensure_fixed_size_layout()?;
+        self.ensure_page_index(index, self.doc_n_pages_fixed_size())?;
+        let mut widget_flags = WidgetFlags::default();
+
+        let old_pages_state = self.document.pages_state();
+        self.document.set_page_background(index, background);
+        self.background_regenerate_pattern();
+        widget_flags.merge(self.store.record_w_pages(
+            HistoryLabel::ChangePageBackground,
+            Some((old_pages_state, self.document.pages_state())),
+        ));
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        Ok(widget_flags)
+    }
+
+    /// Restore a recorded state of the pages on undo or redo.
+    pub(crate) fn doc_restore_pages_state(&mut self, pages_state: &PagesState) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        self.document.restore_pages_state(pages_state);
+        self.background_regenerate_pattern();
+        widget_flags.resize = true;
+
+        widget_flags
+    }
+
+    /// The background override of the page with the given index.
+    pub fn doc_page_background_override(&self, index: u32) -> Option<Background> {
+        self.document
+            .page_backgrounds
+            .get(index as usize)
+            .copied()
+            .flatten()
+    }
+
+    /// Moves the strokes from the page with the given index one page down and enlarges the document by one page.
+    fn insert_blank_page_fixed_size(&mut self, index: u32) {
+        let format_height = self.document.format.height;
+        let below_keys = self.keys_from_page_fixed_size(index);
+
+        self.store
+            .translate_strokes(&below_keys, na::vector![0.0, format_height]);
+        self.store
+            .translate_strokes_images(&below_keys, na::vector![0.0, format_height]);
+        self.document.height += format_height;
+        self.document.insert_page_background(index, None);
+    }
+
+    /// The keys of all strokes on the page with the given index and the pages below it.
+    fn keys_from_page_fixed_size(&self, index: u32) -> Vec<StrokeKey> {
+        if index == 0 {
+            self.store.keys_unordered()
+        } else if index >= self.doc_n_pages_fixed_size() {
+            vec![]
+        } else {
+            self.store
+                .keys_below_y(self.document.y + f64::from(index) * self.document.format.height)
+        }
+    }
+
+    /// The keys of all strokes on the page with the given index.
+    fn keys_on_page_fixed_size(&self, index: u32) -> Vec<StrokeKey> {
+        let below_keys = self
+            .keys_from_page_fixed_size(index + 1)
+            .into_iter()
+            .collect::<HashSet<StrokeKey>>();
+
+        self.keys_from_page_fixed_size(index)
+            .into_iter()
+            .filter(|key| !below_keys.contains(key))
+            .collect()
+    }
+
+    fn ensure_fixed_size_layout(&self) -> anyhow::Result<()> {
+        if self.document.layout != Layout::FixedSize {
+            return Err(anyhow::anyhow!(
+                "Page operations are only available in fixed size layout"
+            ));
+        }
+        Ok(())
+    }
+
+    fn ensure_page_index(&self, index: u32, n_pages: u32) -> anyhow::Result<()> {
+        if index >= n_pages {
+            return Err(anyhow::anyhow!(
+                "Page index {index} is out of range, the document has {n_pages} pages"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Update the engine after the pages were modified and record the new state in the history.
+    fn pages_changed(&mut self, label: HistoryLabel, old_pages_state: PagesState) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        widget_flags.merge(self.current_pen_update_state());
+        // The page background overrides might have moved to other pages
+        self.background_regenerate_pattern();
+        self.update_content_rendering_current_viewport();
+        widget_flags.merge(
+            self.store
+                .record_w_pages(label, Some((old_pages_state, self.document.pages_state()))),
+        );
+        widget_flags.redraw = true;
+        widget_flags.resize = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::background::PatternStyle;
+    use crate::strokes::{BrushStroke, Stroke};
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::ShapeBehaviour;
+    use rnote_compose::{PenPath, Style};
+
+    /// An engine with a fixed size document of three pages and a stroke in the middle of every page.
+    fn engine_w_three_pages() -> (RnoteEngine, Vec<StrokeKey>) {
+        let mut engine = RnoteEngine::default();
+        let format_height = engine.document.format.height;
+        engine.document.layout = Layout::FixedSize;
+        engine.document.height = format_height * 3.0;
+
+        let keys = (0..3)
+            .map(|i| {
+                let pos = na::vector![10.0, (f64::from(i) + 0.5) * format_height];
+                engine.store.insert_stroke(
+                    Stroke::BrushStroke(BrushStroke::from_penpath(
+                        PenPath::new(Element::new(pos, 0.5)),
+                        Style::default(),
+                    )),
+                    None,
+                )
+            })
+            .collect();
+        let _ = engine.record(HistoryLabel::DrawStroke);
+
+        (engine, keys)
+    }
+
+    /// The pages the strokes are on, `None` when the stroke is trashed.
+    fn strokes_pages(engine: &RnoteEngine, keys: &[StrokeKey]) -> Vec<Option<u32>> {
+        keys.iter()
+            .map(|&key| {
+                if engine.store.trashed(key).unwrap_or(true) {
+                    return None;
+                }
+                let bounds = engine.store.get_stroke_ref(key)?.bounds();
+                Some(
+                    engine
+                        .document
+                        .page_index_fixed_size(bounds.center().coords),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn insert_page_and_undo() {
+        let (mut engine, keys) = engine_w_three_pages();
+
+        let _ = engine.doc_insert_page_fixed_size(1).unwrap();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 4);
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(0), Some(2), Some(3)]
+        );
+
+        let _ = engine.undo();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 3);
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(0), Some(1), Some(2)]
+        );
+
+        let _ = engine.redo();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 4);
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(0), Some(2), Some(3)]
+        );
+    }
+
+    #[test]
+    fn delete_page_and_undo() {
+        let (mut engine, keys) = engine_w_three_pages();
+
+        let _ = engine.doc_delete_page_fixed_size(1).unwrap();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 2);
+        assert_eq!(strokes_pages(&engine, &keys), vec![Some(0), None, Some(1)]);
+
+        let _ = engine.undo();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 3);
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn move_page_and_undo() {
+        let (mut engine, keys) = engine_w_three_pages();
+
+        let _ = engine.doc_move_page_fixed_size(0, 2).unwrap();
+        assert_eq!(engine.doc_n_pages_fixed_size(), 3);
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(2), Some(0), Some(1)]
+        );
+
+        let _ = engine.undo();
+        assert_eq!(
+            strokes_pages(&engine, &keys),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn page_backgrounds_move_with_pages_and_undo() {
+        let (mut engine, _) = engine_w_three_pages();
+        let grid = Background {
+            pattern: PatternStyle::Grid,
+            ..Default::default()
+        };
+
+        let _ = engine
+            .doc_set_page_background_fixed_size(0, Some(grid))
+            .unwrap();
+        let _ = engine.doc_move_page_fixed_size(0, 2).unwrap();
+        assert_eq!(engine.doc_page_background_override(0), None);
+        assert_eq!(engine.doc_page_background_override(2), Some(grid));
+
+        let _ = engine.undo();
+        assert_eq!(engine.doc_page_background_override(0), Some(grid));
+        assert_eq!(engine.doc_page_background_override(2), None);
+
+        let _ = engine.undo();
+        assert_eq!(engine.doc_page_background_override(0), None);
+    }
+}
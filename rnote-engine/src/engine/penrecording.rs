@@ -0,0 +1,247 @@
+// Imports
+use super::RnoteEngine;
+use crate::pens::{PenMode, PenStyle, PensConfig};
+use crate::WidgetFlags;
+use rnote_compose::penevents::{PenEvent, ShortcutKey};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// A recorded input event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "pen_recording_event")]
+pub enum PenRecordingEvent {
+    /// Passed to [RnoteEngine::handle_pen_event].
+    #[serde(rename = "pen_event")]
+    PenEvent {
+        #[serde(rename = "event")]
+        event: PenEvent,
+        #[serde(rename = "pen_mode", default)]
+        pen_mode: Option<PenMode>,
+    },
+    /// Passed to [RnoteEngine::handle_pressed_shortcut_key].
+    #[serde(rename = "pressed_shortcut_key")]
+    PressedShortcutKey {
+        #[serde(rename = "shortcut_key")]
+        shortcut_key: ShortcutKey,
+    },
+    /// Passed to [RnoteEngine::change_pen_style].
+    #[serde(rename = "change_pen_style")]
+    ChangePenStyle {
+        #[serde(rename = "style")]
+        style: PenStyle,
+    },
+}
+
+/// A recorded input event with its timestamp.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "pen_recording_entry")]
+pub struct PenRecordingEntry {
+    /// The time since the start of the recording in microseconds.
+    #[serde(rename = "time_micros", default)]
+    pub time_micros: u64,
+    #[serde(rename = "event")]
+    pub event: PenRecordingEvent,
+}
+
+/// A recording of the input events of the engine.
+///
+/// Replaying it into a fresh engine reproduces the same document, as long as only the smooth brush and shaper styles
+/// are used. Textured brush strokes and rough shapes get a random seed for every new stroke.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, rename = "pen_recording")]
+pub struct PenRecording {
+    /// The pens config when the recording was started.
+    #[serde(rename = "pens_config")]
+    pub pens_config: PensConfig,
+    #[serde(rename = "entries")]
+    pub entries: Vec<PenRecordingEntry>,
+}
+
+impl PenRecording {
+    /// Loads a recording from Json bytes.
+    pub fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Saves the recording as Json bytes.
+    pub fn save_as_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+
+    /// Replays the recording into the engine, which is expected to be fresh.
+    ///
+    /// The timestamps of the events are offset from `start`, so replaying with the same start always produces the same result.
+    pub fn replay(&self, engine: &mut RnoteEngine, start: Instant) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        engine.pens_config = self.pens_config.clone();
+
+        for entry in self.entries.iter() {
+            let now = start + Duration::from_micros(entry.time_micros);
+
+            widget_flags.merge(match entry.event.clone() {
+                PenRecordingEvent::PenEvent { event, pen_mode } => {
+                    engine.handle_pen_event(event, pen_mode, now)
+                }
+                PenRecordingEvent::PressedShortcutKey { shortcut_key } => {
+                    engine.handle_pressed_shortcut_key(shortcut_key, now)
+                }
+                PenRecordingEvent::ChangePenStyle { style } => engine.change_pen_style(style),
+            });
+        }
+
+        widget_flags
+    }
+}
+
+/// Records the input events of the engine while it is active.
+#[derive(Debug, Clone)]
+pub struct PenRecorder {
+    start: Instant,
+    recording: PenRecording,
+}
+
+impl PenRecorder {
+    /// Appends the event. Without a timestamp the time of the previous event is used.
+    pub(crate) fn push(&mut self, event: PenRecordingEvent, now: Option<Instant>) {
+        let time_micros = match now {
+            Some(now) => now.saturating_duration_since(self.start).as_micros() as u64,
+            None => self
+                .recording
+                .entries
+                .last()
+                .map(|entry| entry.time_micros)
+                .unwrap_or(0),
+        };
+
+        self.recording
+            .entries
+            .push(PenRecordingEntry { time_micros, event });
+    }
+}
+
+impl RnoteEngine {
+    /// Start recording the input events.
+    ///
+    /// `now` is the start of the recording, the timestamps of the recorded events are relative to it.
+    pub fn pen_recording_start(&mut self, now: Instant) {
+        self.pen_recorder = Some(PenRecorder {
+            start: now,
+            recording: PenRecording {
+                pens_config: self.pens_config.clone(),
+                entries: vec![],
+            },
+        });
+    }
+
+    /// Stop recording the input events and return the recording, if it was active.
+    pub fn pen_recording_stop(&mut self) -> Option<PenRecording> {
+        self.pen_recorder.take().map(|recorder| recorder.recording)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineSnapshot;
+    use crate::utils::goldens::{check_golden, UPDATE_GOLDENS_ENV};
+    use rnote_compose::penpath::Element;
+    use std::path::Path;
+
+    const PEN_RECORDINGS_DIR: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../misc/file-tests/pen-recordings"
+    );
+
+    fn snapshot_value(engine: &RnoteEngine) -> serde_json::Value {
+        serde_json::to_value::<EngineSnapshot>(engine.take_snapshot()).unwrap()
+    }
+
+    fn replay_into_fresh_engine(recording: &PenRecording, start: Instant) -> RnoteEngine {
+        let mut engine = RnoteEngine::default();
+        let _ = recording.replay(&mut engine, start);
+        engine
+    }
+
+    /// Replays the recording and compares the resulting snapshot against the golden file next to it.
+    ///
+    /// New recordings need their golden generated once by running the test with `RNOTE_UPDATE_GOLDENS` set.
+    fn check_pen_recording_golden(recording_file: &Path) {
+        let recording =
+            PenRecording::load_from_bytes(&std::fs::read(recording_file).unwrap()).unwrap();
+        let snapshot = snapshot_value(&replay_into_fresh_engine(&recording, Instant::now()));
+        let golden_file = recording_file.with_extension("golden.json");
+
+        check_golden(
+            &golden_file,
+            |golden_file| {
+                std::fs::write(golden_file, serde_json::to_vec_pretty(&snapshot)?)?;
+                Ok(())
+            },
+            |golden_file| {
+                let golden = serde_json::from_slice::<serde_json::Value>(
+                    &std::fs::read(golden_file).unwrap(),
+                )
+                .unwrap();
+                assert!(
+                    snapshot == golden,
+                    "replaying {} does not match {}, set {UPDATE_GOLDENS_ENV} to regenerate it",
+                    recording_file.display(),
+                    golden_file.display()
+                );
+            },
+        );
+    }
+
+    #[test]
+    fn pen_recordings_match_goldens() {
+        let mut recording_files = std::fs::read_dir(PEN_RECORDINGS_DIR)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension().map_or(false, |ext| ext == "json")
+                    && !path.to_string_lossy().ends_with(".golden.json")
+            })
+            .collect::<Vec<_>>();
+        recording_files.sort();
+        assert!(!recording_files.is_empty());
+
+        for recording_file in recording_files {
+            check_pen_recording_golden(&recording_file);
+        }
+    }
+
+    #[test]
+    fn record_replay_roundtrip() {
+        let start = Instant::now();
+        let at = |millis: u64| start + Duration::from_millis(millis);
+        let down = |x: f64, y: f64| PenEvent::Down {
+            element: Element::new(na::vector![x, y], 0.5),
+            modifier_keys: vec![],
+        };
+        let up = |x: f64, y: f64| PenEvent::Up {
+            element: Element::new(na::vector![x, y], 0.5),
+            modifier_keys: vec![],
+        };
+
+        let mut engine = RnoteEngine::default();
+        engine.pen_recording_start(start);
+        let _ = engine.change_pen_style(PenStyle::Brush);
+        for i in 0..10 {
+            let _ = engine.handle_pen_event(down(100.0 + 10.0 * i as f64, 100.0), None, at(i * 10));
+        }
+        let _ = engine.handle_pen_event(up(200.0, 100.0), None, at(100));
+        let _ = engine.change_pen_style(PenStyle::Eraser);
+        let _ = engine.handle_pen_event(down(150.0, 80.0), None, at(200));
+        let _ = engine.handle_pen_event(down(150.0, 120.0), None, at(210));
+        let _ = engine.handle_pen_event(up(150.0, 120.0), None, at(220));
+        let recording = engine.pen_recording_stop().unwrap();
+
+        assert_eq!(recording.entries.len(), 16);
+        assert_eq!(recording.entries[11].time_micros, 100_000);
+
+        let recording = PenRecording::load_from_bytes(&recording.save_as_bytes().unwrap()).unwrap();
+        let replayed = replay_into_fresh_engine(&recording, start);
+
+        assert_eq!(snapshot_value(&engine), snapshot_value(&replayed));
+    }
+}
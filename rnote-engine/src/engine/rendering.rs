@@ -1,49 +1,28 @@
 // Imports
+#[cfg(feature = "gtk")]
 use super::{visual_debug, EngineView};
+#[cfg(feature = "gtk")]
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
-use crate::{Document, DrawOnDocBehaviour, RnoteEngine};
+use crate::RnoteEngine;
+#[cfg(feature = "gtk")]
+use crate::{Document, DrawOnDocBehaviour};
+#[cfg(feature = "gtk")]
 use gtk4::{gdk, graphene, gsk, prelude::*, Snapshot};
+#[cfg(feature = "gtk")]
 use p2d::bounding_volume::{Aabb, BoundingVolume};
+#[cfg(feature = "gtk")]
 use piet::RenderContext;
+#[cfg(feature = "gtk")]
 use rnote_compose::color;
+#[cfg(feature = "gtk")]
 use rnote_compose::helpers::{AabbHelpers, Affine2Helpers, SplitOrder};
 
 impl RnoteEngine {
     /// Update the background rendering for the current viewport.
     ///
-    /// If the background pattern or zoom has changed, the background pattern needs to be regenerated first.
-    pub fn update_background_rendering_current_viewport(&mut self) {
-        let viewport = self.camera.viewport();
-        let mut rendernodes: Vec<gsk::RenderNode> = vec![];
-
-        if let Some(image) = &self.background_tile_image {
-            // Only create the texture once, it is expensive
-            let new_texture = match image.to_memtexture() {
-                Ok(t) => t,
-                Err(e) => {
-                    log::error!(
-                        "failed to generate memory-texture of background tile image, {e:?}"
-                    );
-                    return;
-                }
-            };
-
-            for split_bounds in viewport.split_extended_origin_aligned(
-                self.document.background.tile_size(),
-                SplitOrder::default(),
-            ) {
-                rendernodes.push(
-                    gsk::TextureNode::new(
-                        &new_texture,
-                        &graphene::Rect::from_p2d_aabb(split_bounds),
-                    )
-                    .upcast(),
-                );
-            }
-        }
-
-        self.background_rendernodes = rendernodes;
-    }
+    /// Without GTK the background is only drawn when exporting, so there is no rendering to update.
+    #[cfg(not(feature = "gtk"))]
+    pub fn update_background_rendering_current_viewport(&mut self) {}
 
     /// Update the content rendering for the current viewport.
     pub fn update_content_rendering_current_viewport(&mut self) {
@@ -70,19 +49,146 @@ impl RnoteEngine {
     pub fn clear_rendering(&mut self) {
         self.store.clear_rendering();
         self.background_tile_image.take();
+        self.page_background_tile_images.clear();
+        #[cfg(feature = "gtk")]
         self.background_rendernodes.clear();
     }
 
-    /// Regenerate the background tile image and updates the background rendering.
+    /// Regenerate the background tile images and updates the background rendering.
     pub fn background_regenerate_pattern(&mut self) {
         let image_scale = self.camera.image_scale();
         match self.document.background.gen_tile_image(image_scale) {
             Ok(image) => {
                 self.background_tile_image = Some(image);
-                self.update_background_rendering_current_viewport();
             }
             Err(e) => log::error!("regenerating background tile image failed, Err: {e:?}"),
         }
+        self.page_background_tile_images = if self.document.has_page_backgrounds() {
+            self.document
+                .page_backgrounds
+                .iter()
+                .map(|page_background| {
+                    page_background.as_ref().and_then(|page_background| {
+                        page_background
+                            .gen_tile_image(image_scale)
+                            .map_err(|e| {
+                                log::error!(
+                                    "regenerating page background tile image failed, Err: {e:?}"
+                                )
+                            })
+                            .ok()
+                    })
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+        self.update_background_rendering_current_viewport();
+    }
+}
+
+#[cfg(feature = "gtk")]
+impl RnoteEngine {
+    /// Update the background rendering for the current viewport.
+    ///
+    /// If the background pattern or zoom has changed, the background pattern needs to be regenerated first.
+    pub fn update_background_rendering_current_viewport(&mut self) {
+        let viewport = self.camera.viewport();
+        let mut rendernodes: Vec<gsk::RenderNode> = vec![];
+
+        if let Some(image) = &self.background_tile_image {
+            // Only create the texture once, it is expensive
+            let new_texture = match image.to_memtexture() {
+                Ok(t) => t,
+                Err(e) => {
+                    log::error!(
+                        "failed to generate memory-texture of background tile image, {e:?}"
+                    );
+                    return;
+                }
+            };
+
+            for split_bounds in viewport.split_extended_origin_aligned(
+                self.document.background.tile_size(),
+                SplitOrder::default(),
+            ) {
+                rendernodes.push(
+                    gsk::TextureNode::new(
+                        &new_texture,
+                        &graphene::Rect::from_p2d_aabb(split_bounds),
+                    )
+                    .upcast(),
+                );
+            }
+        }
+
+        // The page background overrides are drawn on top, clipped to their page
+        if self.document.has_page_backgrounds() {
+            for (index, image) in self.page_background_tile_images.iter().enumerate() {
+                let Some(image) = image else {
+                    continue;
+                };
+                let Some(page_viewport) = self
+                    .document
+                    .page_bounds_fixed_size(index as u32)
+                    .intersection(&viewport) else {
+                    continue;
+                };
+                let page_background = self.document.page_background(index as u32);
+                let texture = match image.to_memtexture() {
+                    Ok(t) => t,
+                    Err(e) => {
+                        log::error!(
+                            "failed to generate memory-texture of page background tile image, {e:?}"
+                        );
+                        continue;
+                    }
+                };
+
+                let mut page_nodes: Vec<gsk::RenderNode> = vec![gsk::ColorNode::new(
+                    &gdk::RGBA::from_compose_color(page_background.color),
+                    &graphene::Rect::from_p2d_aabb(page_viewport),
+                )
+                .upcast()];
+                for split_bounds in page_viewport.split_extended_origin_aligned(
+                    page_background.tile_size(),
+                    SplitOrder::default(),
+                ) {
+                    page_nodes.push(
+                        gsk::TextureNode::new(
+                            &texture,
+                            &graphene::Rect::from_p2d_aabb(split_bounds),
+                        )
+                        .upcast(),
+                    );
+                }
+
+                rendernodes.push(
+                    gsk::ClipNode::new(
+                        &gsk::ContainerNode::new(&page_nodes),
+                        &graphene::Rect::from_p2d_aabb(page_viewport),
+                    )
+                    .upcast(),
+                );
+            }
+        }
+
+        for (page_bounds, background) in self
+            .document
+            .pages_w_background_intersecting_bounds(viewport)
+        {
+            for decoration_bounds in background.page_decoration_rects(page_bounds) {
+                rendernodes.push(
+                    gsk::ColorNode::new(
+                        &gdk::RGBA::from_compose_color(background.pattern_color),
+                        &graphene::Rect::from_p2d_aabb(decoration_bounds),
+                    )
+                    .upcast(),
+                );
+            }
+        }
+
+        self.background_rendernodes = rendernodes;
     }
 
     /// Draws the entire engine (doc, pens, strokes, selection, ..) to a GTK snapshot.
@@ -104,8 +210,16 @@ impl RnoteEngine {
         self.draw_origin_indicator_to_gtk_snapshot(snapshot)?;
         snapshot.save();
         snapshot.transform(Some(&camera_transform));
-        self.store
-            .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport);
+        if self.replay.is_some() {
+            self.store.draw_keys_to_gtk_snapshot(
+                snapshot,
+                &self.replay_revealed_keys_intersecting_bounds(viewport),
+                doc_bounds,
+            );
+        } else {
+            self.store
+                .draw_strokes_to_gtk_snapshot(snapshot, doc_bounds, viewport);
+        }
         snapshot.restore();
         /*
                let cairo_cx = snapshot.append_cairo(&graphene::Rect::from_p2d_aabb(surface_bounds));
@@ -118,17 +232,21 @@ impl RnoteEngine {
                    self.camera.image_scale(),
                );
         */
-        self.penholder.draw_on_doc_to_gtk_snapshot(
-            snapshot,
-            &EngineView {
-                tasks_tx: self.tasks_tx(),
-                pens_config: &self.pens_config,
-                doc: &self.document,
-                store: &self.store,
-                camera: &self.camera,
-                audioplayer: &self.audioplayer,
-            },
-        )?;
+        let engine_view = EngineView {
+            tasks_tx: self.tasks_tx(),
+            pens_config: &self.pens_config,
+            doc: &self.document,
+            store: &self.store,
+            camera: &self.camera,
+            audioplayer: &self.audioplayer,
+        };
+        if let Some(replay) = &self.replay {
+            replay.draw_on_doc_to_gtk_snapshot(snapshot, &engine_view)?;
+        }
+        self.search
+            .draw_on_doc_to_gtk_snapshot(snapshot, &engine_view)?;
+        self.penholder
+            .draw_on_doc_to_gtk_snapshot(snapshot, &engine_view)?;
 
         if self.visual_debug {
             snapshot.save();
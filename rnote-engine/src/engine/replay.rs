@@ -0,0 +1,597 @@
+// Imports
+use super::{EngineView, RnoteEngine};
+use crate::store::StrokeKey;
+use crate::strokes::{Stroke, StrokeBehaviour};
+use crate::{render, DrawBehaviour, DrawOnDocBehaviour, StrokeStore, WidgetFlags};
+use futures::channel::oneshot;
+use p2d::bounding_volume::Aabb;
+use piet::RenderContext;
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::PenPath;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Replay export preferences.
+#[derive(Debug, Clone, Copy)]
+pub struct ReplayExportPrefs {
+    /// The replay speed in steps per second.
+    pub speed: f64,
+    /// The frames per second, when exporting as frame sequence.
+    pub fps: f64,
+    /// Whether the background should be exported.
+    pub with_background: bool,
+    /// Whether the background pattern should be exported.
+    pub with_pattern: bool,
+    /// The bitmap scale-factor in relation to the actual size, when exporting as frame sequence.
+    pub bitmap_scalefactor: f64,
+}
+
+impl Default for ReplayExportPrefs {
+    fn default() -> Self {
+        Self {
+            speed: ReplayState::SPEED_DEFAULT,
+            fps: 30.0,
+            with_background: true,
+            with_pattern: true,
+            bitmap_scalefactor: 1.0,
+        }
+    }
+}
+
+/// The state of a replay of the document in chronological order.
+///
+/// Every stroke is revealed in one step, except brush strokes, which are revealed segment by segment.
+#[derive(Debug, Clone)]
+pub struct ReplayState {
+    /// The replayed strokes in chronological order, with the number of steps it takes to reveal them.
+    strokes: Vec<(StrokeKey, usize)>,
+    n_steps: usize,
+    /// The progress in steps.
+    progress: f64,
+    /// The speed in steps per second.
+    speed: f64,
+    playing: bool,
+}
+
+impl ReplayState {
+    /// The default speed in steps per second.
+    pub const SPEED_DEFAULT: f64 = 60.0;
+    /// The minimum speed in steps per second.
+    pub const SPEED_MIN: f64 = 0.1;
+
+    fn new(store: &StrokeStore, speed: f64) -> Self {
+        let strokes = replay_strokes(store);
+        let n_steps = strokes.iter().map(|(_, n)| n).sum();
+
+        Self {
+            strokes,
+            n_steps,
+            progress: 0.0,
+            speed: speed.max(Self::SPEED_MIN),
+            playing: true,
+        }
+    }
+
+    /// The total number of steps of the replay.
+    pub fn n_steps(&self) -> usize {
+        self.n_steps
+    }
+
+    /// The current progress in steps.
+    pub fn progress(&self) -> f64 {
+        self.progress
+    }
+
+    /// The speed in steps per second.
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Whether the replay is currently playing.
+    pub fn playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Whether all strokes are revealed.
+    pub fn finished(&self) -> bool {
+        self.progress >= self.n_steps as f64
+    }
+
+    fn revealed_steps(&self) -> usize {
+        self.progress.floor() as usize
+    }
+}
+
+impl DrawOnDocBehaviour for ReplayState {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let (_, partial) = revealed_strokes(&self.strokes, self.revealed_steps());
+        let (key, _) = partial?;
+
+        engine_view
+            .store
+            .get_stroke_ref(key)
+            .map(|stroke| stroke.bounds())
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        let (_, partial) = revealed_strokes(&self.strokes, self.revealed_steps());
+        let Some(partial_stroke) = partial.and_then(|(key, n_revealed)| {
+            partially_revealed_stroke(engine_view.store.get_stroke_ref(key)?, n_revealed)
+        }) else {
+            return Ok(());
+        };
+
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        partial_stroke.draw(cx, engine_view.camera.image_scale())?;
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+/// The rendered strokes in chronological order, with the number of steps it takes to reveal them.
+fn replay_strokes(store: &StrokeStore) -> Vec<(StrokeKey, usize)> {
+    store
+        .stroke_keys_as_rendered_sorted_chrono_t()
+        .into_iter()
+        .filter_map(|key| {
+            let n_steps = match store.get_stroke_ref(key)? {
+                Stroke::BrushStroke(brushstroke) => brushstroke.path.segments.len().max(1),
+                _ => 1,
+            };
+            Some((key, n_steps))
+        })
+        .collect()
+}
+
+/// Splits the strokes into the ones that are completely revealed after the given number of steps,
+/// and the one that is partially revealed together with its number of revealed steps.
+fn revealed_strokes(
+    strokes: &[(StrokeKey, usize)],
+    steps: usize,
+) -> (HashSet<StrokeKey>, Option<(StrokeKey, usize)>) {
+    let mut revealed = HashSet::new();
+    let mut steps_left = steps;
+
+    for &(key, n_steps) in strokes {
+        if steps_left >= n_steps {
+            revealed.insert(key);
+            steps_left -= n_steps;
+        } else {
+            return (revealed, (steps_left > 0).then_some((key, steps_left)));
+        }
+    }
+
+    (revealed, None)
+}
+
+/// The step at which each stroke starts to be revealed.
+fn replay_first_steps(strokes: &[(StrokeKey, usize)]) -> HashMap<StrokeKey, usize> {
+    let mut step = 0;
+
+    strokes
+        .iter()
+        .map(|&(key, n_steps)| {
+            let first_step = step;
+            step += n_steps;
+            (key, first_step)
+        })
+        .collect()
+}
+
+/// The brush stroke with only the given number of segments, None for other strokes or when nothing is revealed yet.
+fn partially_revealed_stroke(stroke: &Stroke, n_revealed: usize) -> Option<Stroke> {
+    let Stroke::BrushStroke(brushstroke) = stroke else {
+        return None;
+    };
+    if n_revealed == 0 || n_revealed >= brushstroke.path.segments.len() {
+        return None;
+    }
+
+    let mut partial = brushstroke.clone();
+    partial.replace_path(PenPath::new_w_segments(
+        brushstroke.path.start,
+        brushstroke.path.segments[..n_revealed].iter().copied(),
+    ));
+    Some(Stroke::BrushStroke(partial))
+}
+
+/// The brush stroke split into one stroke for every segment, other strokes are returned unchanged.
+fn split_into_steps(stroke: &Stroke) -> Vec<Stroke> {
+    let Stroke::BrushStroke(brushstroke) = stroke else {
+        return vec![stroke.clone()];
+    };
+    if brushstroke.path.segments.is_empty() {
+        return vec![stroke.clone()];
+    }
+
+    brushstroke
+        .path
+        .segments
+        .iter()
+        .enumerate()
+        .map(|(i, &segment)| {
+            let start = if i == 0 {
+                brushstroke.path.start
+            } else {
+                brushstroke.path.segments[i - 1].end()
+            };
+            let mut segment_stroke = brushstroke.clone();
+            segment_stroke.replace_path(PenPath::new_w_segments(start, [segment]));
+            Stroke::BrushStroke(segment_stroke)
+        })
+        .collect()
+}
+
+impl RnoteEngine {
+    /// Start the replay of the document in chronological order.
+    ///
+    /// While replaying, only the revealed strokes are drawn.
+    pub fn replay_start(&mut self, speed: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.replay = Some(ReplayState::new(&self.store, speed));
+        widget_flags.redraw = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Stop the replay and show all strokes again.
+    pub fn replay_stop(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.replay.take().is_some() {
+            widget_flags.redraw = true;
+            widget_flags.refresh_ui = true;
+        }
+        widget_flags
+    }
+
+    /// Pause or resume the replay. Resuming a finished replay restarts it from the beginning.
+    pub fn replay_set_playing(&mut self, playing: bool) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(replay) = self.replay.as_mut() else {
+            return widget_flags;
+        };
+        if playing && replay.finished() {
+            replay.progress = 0.0;
+        }
+        replay.playing = playing;
+        widget_flags.redraw = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Set the replay speed in steps per second.
+    pub fn replay_set_speed(&mut self, speed: f64) {
+        if let Some(replay) = self.replay.as_mut() {
+            replay.speed = speed.max(ReplayState::SPEED_MIN);
+        }
+    }
+
+    /// Jump to the given progress of the replay, as fraction between 0.0 and 1.0.
+    pub fn replay_seek(&mut self, fraction: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(replay) = self.replay.as_mut() else {
+            return widget_flags;
+        };
+        replay.progress = fraction.clamp(0.0, 1.0) * replay.n_steps as f64;
+        widget_flags.redraw = true;
+        widget_flags
+    }
+
+    /// Advance the replay by the elapsed time, when it is playing. Expected to be called periodically.
+    ///
+    /// The replay is paused when it reaches the end.
+    pub fn replay_advance(&mut self, elapsed: Duration) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(replay) = self.replay.as_mut() else {
+            return widget_flags;
+        };
+        if !replay.playing {
+            return widget_flags;
+        }
+
+        let prev_steps = replay.revealed_steps();
+        replay.progress =
+            (replay.progress + elapsed.as_secs_f64() * replay.speed).min(replay.n_steps as f64);
+        if replay.finished() {
+            replay.playing = false;
+            widget_flags.refresh_ui = true;
+        }
+        if replay.revealed_steps() != prev_steps {
+            widget_flags.redraw = true;
+        }
+        widget_flags
+    }
+
+    /// The keys of the strokes that are completely revealed by the replay, in rendering order.
+    pub(crate) fn replay_revealed_keys_intersecting_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
+        let Some(replay) = self.replay.as_ref() else {
+            return self
+                .store
+                .stroke_keys_as_rendered_intersecting_bounds(bounds);
+        };
+        let (revealed, _) = revealed_strokes(&replay.strokes, replay.revealed_steps());
+
+        self.store
+            .stroke_keys_as_rendered_intersecting_bounds(bounds)
+            .into_iter()
+            .filter(|key| revealed.contains(key))
+            .collect()
+    }
+
+    /// Export the replay as animated Svg, revealing the strokes in chronological order.
+    pub fn export_replay_as_animated_svg(
+        &self,
+        replay_export_prefs: ReplayExportPrefs,
+    ) -> oneshot::Receiver<anyhow::Result<Vec<u8>>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<Vec<u8>>>();
+        let snapshot = self.take_snapshot();
+        let first_steps = replay_first_steps(&replay_strokes(&self.store));
+        let rendered_keys = self.store.stroke_keys_as_rendered();
+        let content_bounds = self
+            .bounds_w_content_extended()
+            .unwrap_or_else(|| snapshot.document.bounds());
+        let speed = replay_export_prefs.speed.max(ReplayState::SPEED_MIN);
+
+        rayon::spawn(move || {
+            let result = || -> anyhow::Result<Vec<u8>> {
+                let mut svg_data = if replay_export_prefs.with_background {
+                    snapshot
+                        .document
+                        .gen_background_svg(content_bounds, replay_export_prefs.with_pattern)?
+                        .svg_data
+                } else {
+                    String::new()
+                };
+
+                // The strokes are revealed in chronological order, but are emitted in rendering order
+                // so that strokes on upper layers are drawn above the ones on lower layers.
+                for key in rendered_keys {
+                    let (Some(&first_step), Some(stroke)) =
+                        (first_steps.get(&key), snapshot.stroke_components.get(key)) else {
+                        continue;
+                    };
+
+                    for (i, step_stroke) in split_into_steps(stroke).into_iter().enumerate() {
+                        let mut step_svg = step_stroke.gen_svg()?;
+                        let step_bounds = step_svg.bounds;
+                        // Simplifying gives every step unique ids
+                        step_svg.simplify()?;
+
+                        svg_data += &format!(
+                            "\n<g visibility=\"hidden\" transform=\"translate({:.3} {:.3})\"><set attributeName=\"visibility\" to=\"visible\" begin=\"{:.3}s\" fill=\"freeze\"/>{}</g>",
+                            step_bounds.mins[0],
+                            step_bounds.mins[1],
+                            (first_step + i) as f64 / speed,
+                            step_svg.svg_data
+                        );
+                    }
+                }
+
+                Ok(rnote_compose::utils::add_xml_header(
+                    rnote_compose::utils::wrap_svg_root(
+                        svg_data.as_str(),
+                        Some(content_bounds),
+                        Some(content_bounds),
+                        false,
+                    )
+                    .as_str(),
+                )
+                .into_bytes())
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_replay_as_animated_svg() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+
+    /// Export the replay as a sequence of Png frames, revealing the strokes in chronological order.
+    ///
+    /// Every frame is passed to the sink together with its index as soon as it is encoded, so that the frames
+    /// don't need to be held in memory. The last frame shows the complete document.
+    pub fn export_replay_as_png_frames(
+        &self,
+        replay_export_prefs: ReplayExportPrefs,
+        mut frame_sink: impl FnMut(usize, Vec<u8>) -> anyhow::Result<()> + Send + 'static,
+    ) -> oneshot::Receiver<anyhow::Result<()>> {
+        let (oneshot_sender, oneshot_receiver) = oneshot::channel::<anyhow::Result<()>>();
+        let snapshot = self.take_snapshot();
+        let replay_strokes = replay_strokes(&self.store);
+        let rendered_keys = self.store.stroke_keys_as_rendered();
+        let content_bounds = self
+            .bounds_w_content_extended()
+            .unwrap_or_else(|| snapshot.document.bounds());
+        let speed = replay_export_prefs.speed.max(ReplayState::SPEED_MIN);
+        let fps = replay_export_prefs.fps.max(1.0);
+
+        rayon::spawn(move || {
+            let mut result = || -> anyhow::Result<()> {
+                let background_svg = if replay_export_prefs.with_background {
+                    Some(
+                        snapshot
+                            .document
+                            .gen_background_svg(content_bounds, replay_export_prefs.with_pattern)?,
+                    )
+                } else {
+                    None
+                };
+                let n_steps = replay_strokes.iter().map(|(_, n)| n).sum::<usize>();
+                let n_frames = (n_steps as f64 / speed * fps).ceil() as usize + 1;
+                let stroke_components = Arc::clone(&snapshot.stroke_components);
+
+                for frame in 0..n_frames {
+                    let steps = ((frame as f64 * speed / fps).round() as usize).min(n_steps);
+                    let (revealed, partial) = revealed_strokes(&replay_strokes, steps);
+                    let partial_stroke = partial.and_then(|(key, n_revealed)| {
+                        partially_revealed_stroke(stroke_components.get(key)?, n_revealed)
+                    });
+
+                    let mut frame_svg = background_svg.clone().unwrap_or(render::Svg {
+                        svg_data: String::new(),
+                        bounds: content_bounds,
+                    });
+                    frame_svg.merge([render::Svg::gen_with_piet_cairo_backend(
+                        |piet_cx| {
+                            for key in rendered_keys.iter().filter(|key| revealed.contains(key)) {
+                                if let Some(stroke) = stroke_components.get(*key) {
+                                    stroke.draw(piet_cx, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE)?;
+                                }
+                            }
+                            if let Some(partial_stroke) = &partial_stroke {
+                                partial_stroke
+                                    .draw(piet_cx, RnoteEngine::STROKE_EXPORT_IMAGE_SCALE)?;
+                            }
+                            Ok(())
+                        },
+                        content_bounds,
+                    )?]);
+
+                    let frame_bytes = render::Image::gen_image_from_svg(
+                        frame_svg,
+                        content_bounds,
+                        replay_export_prefs.bitmap_scalefactor,
+                    )?
+                    .into_encoded_bytes(image::ImageOutputFormat::Png)?;
+                    frame_sink(frame, frame_bytes)?;
+                }
+
+                Ok(())
+            };
+
+            if let Err(_data) = oneshot_sender.send(result()) {
+                log::error!("sending result to receiver in export_replay_as_png_frames() failed. Receiver already dropped");
+            }
+        });
+
+        oneshot_receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::chrono_comp::StrokeLayer;
+    use crate::strokes::BrushStroke;
+    use rnote_compose::penpath::{Element, Segment};
+    use rnote_compose::Style;
+    use std::sync::Mutex;
+
+    /// A brush stroke with a line segment for every position after the first.
+    fn brushstroke(positions: &[(f64, f64)]) -> Stroke {
+        let mut elements = positions
+            .iter()
+            .map(|&(x, y)| Element::new(na::vector![x, y], 0.5));
+        let start = elements.next().unwrap();
+
+        Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new_w_segments(start, elements.map(|end| Segment::LineTo { end })),
+            Style::default(),
+        ))
+    }
+
+    /// An engine with a stroke on an upper layer that is drawn before a stroke on the lower layer.
+    fn engine_w_layered_strokes() -> (RnoteEngine, StrokeKey, StrokeKey) {
+        let mut engine = RnoteEngine::default();
+        let upper_layer = engine.store.add_layer(None);
+
+        let upper = engine.store.insert_stroke(
+            brushstroke(&[(10.0, 10.0), (50.0, 10.0), (50.0, 50.0)]),
+            Some(StrokeLayer::UserLayer(upper_layer)),
+        );
+        let lower = engine.store.insert_stroke(
+            brushstroke(&[(20.0, 20.0), (60.0, 60.0)]),
+            Some(StrokeLayer::UserLayer(0)),
+        );
+
+        (engine, upper, lower)
+    }
+
+    #[test]
+    fn revealed_strokes_by_steps() {
+        let (engine, upper, lower) = engine_w_layered_strokes();
+        let strokes = replay_strokes(&engine.store);
+        assert_eq!(strokes, vec![(upper, 2), (lower, 1)]);
+
+        let (revealed, partial) = revealed_strokes(&strokes, 1);
+        assert!(revealed.is_empty());
+        assert_eq!(partial, Some((upper, 1)));
+
+        let (revealed, partial) = revealed_strokes(&strokes, 2);
+        assert_eq!(revealed, HashSet::from([upper]));
+        assert_eq!(partial, None);
+
+        let (revealed, partial) = revealed_strokes(&strokes, 3);
+        assert_eq!(revealed, HashSet::from([upper, lower]));
+        assert_eq!(partial, None);
+
+        assert_eq!(
+            replay_first_steps(&strokes),
+            HashMap::from([(upper, 0), (lower, 2)])
+        );
+    }
+
+    #[test]
+    fn animated_svg_steps_in_rendering_order() {
+        let (engine, _, _) = engine_w_layered_strokes();
+        let prefs = ReplayExportPrefs {
+            speed: 10.0,
+            with_background: false,
+            ..Default::default()
+        };
+
+        let svg_bytes = futures::executor::block_on(engine.export_replay_as_animated_svg(prefs))
+            .unwrap()
+            .unwrap();
+        let svg_data = String::from_utf8(svg_bytes).unwrap();
+        let begins = svg_data
+            .split("begin=\"")
+            .skip(1)
+            .filter_map(|s| s.split('"').next())
+            .collect::<Vec<&str>>();
+
+        // The stroke on the lower layer is drawn last, but must be emitted first
+        assert_eq!(begins, vec!["0.200s", "0.000s", "0.100s"]);
+    }
+
+    #[test]
+    fn png_frames_streamed_to_sink() {
+        let (engine, _, _) = engine_w_layered_strokes();
+        let prefs = ReplayExportPrefs {
+            speed: 10.0,
+            fps: 10.0,
+            ..Default::default()
+        };
+        let frames = Arc::new(Mutex::new(Vec::new()));
+
+        let sink_frames = Arc::clone(&frames);
+        futures::executor::block_on(engine.export_replay_as_png_frames(
+            prefs,
+            move |i, frame_bytes| {
+                sink_frames.lock().unwrap().push((i, frame_bytes));
+                Ok(())
+            },
+        ))
+        .unwrap()
+        .unwrap();
+
+        let frames = frames.lock().unwrap();
+        // One frame for every step and the initial empty frame
+        assert_eq!(
+            frames.iter().map(|(i, _)| *i).collect::<Vec<usize>>(),
+            vec![0, 1, 2, 3]
+        );
+        for (_, frame_bytes) in frames.iter() {
+            assert_eq!(
+                image::guess_format(frame_bytes).unwrap(),
+                image::ImageFormat::Png
+            );
+        }
+    }
+}
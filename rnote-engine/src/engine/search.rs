@@ -0,0 +1,447 @@
+// Imports
+use super::{EngineView, RnoteEngine};
+use crate::store::StrokeKey;
+use crate::strokes::{Stroke, StrokeBehaviour};
+use crate::{DrawOnDocBehaviour, WidgetFlags};
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use piet::RenderContext;
+use rnote_compose::color;
+use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::shapes::ShapeBehaviour;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+/// Search options.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, rename = "search_options")]
+pub struct SearchOptions {
+    /// Whether the search is case-sensitive.
+    #[serde(rename = "case_sensitive")]
+    pub case_sensitive: bool,
+    /// Whether the query is interpreted as a regular expression.
+    #[serde(rename = "regex")]
+    pub regex: bool,
+}
+
+/// A stroke whose text content matches the search query.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename = "search_match")]
+pub struct SearchMatch {
+    /// The key of the matching stroke.
+    #[serde(rename = "key")]
+    pub key: StrokeKey,
+    /// The byte ranges of the matches in the text content of the stroke.
+    #[serde(rename = "ranges")]
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// The state of the current search.
+#[derive(Debug, Clone, Default)]
+pub struct SearchState {
+    query: String,
+    options: SearchOptions,
+    matches: Vec<SearchMatch>,
+    current: Option<usize>,
+}
+
+impl SearchState {
+    /// The outline width of the current match.
+    const CURRENT_MATCH_OUTLINE_WIDTH: f64 = 2.0;
+    /// The margin of the outline around the current match.
+    const CURRENT_MATCH_OUTLINE_MARGIN: f64 = 6.0;
+
+    /// The query of the current search.
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// The options of the current search.
+    pub fn options(&self) -> SearchOptions {
+        self.options
+    }
+
+    /// The matches of the current search, in reading order.
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    /// The index of the current match.
+    pub fn current(&self) -> Option<usize> {
+        self.current
+    }
+
+    /// The current match.
+    pub fn current_match(&self) -> Option<&SearchMatch> {
+        self.matches.get(self.current?)
+    }
+}
+
+impl DrawOnDocBehaviour for SearchState {
+    fn bounds_on_doc(&self, engine_view: &EngineView) -> Option<Aabb> {
+        let keys = self
+            .matches
+            .iter()
+            .map(|m| m.key)
+            .collect::<Vec<StrokeKey>>();
+        let margin = (Self::CURRENT_MATCH_OUTLINE_MARGIN + Self::CURRENT_MATCH_OUTLINE_WIDTH)
+            / engine_view.camera.total_zoom();
+
+        engine_view
+            .store
+            .bounds_for_strokes(&keys)
+            .map(|b| b.loosened(margin))
+    }
+
+    fn draw_on_doc(
+        &self,
+        cx: &mut piet_cairo::CairoRenderContext,
+        engine_view: &EngineView,
+    ) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        let total_zoom = engine_view.camera.total_zoom();
+        let viewport = engine_view.camera.viewport();
+
+        for search_match in self.matches.iter() {
+            let Some(stroke) = engine_view.store.get_stroke_ref(search_match.key) else {
+                continue;
+            };
+            if !stroke.bounds().intersects(&viewport) {
+                continue;
+            }
+            stroke.draw_highlight(cx, total_zoom)?;
+        }
+
+        if let Some(stroke) = self
+            .current_match()
+            .and_then(|m| engine_view.store.get_stroke_ref(m.key))
+        {
+            let outline_bounds = stroke
+                .bounds()
+                .loosened(Self::CURRENT_MATCH_OUTLINE_MARGIN / total_zoom);
+            cx.stroke(
+                outline_bounds.to_kurbo_rect(),
+                &color::GNOME_ORANGES[2],
+                Self::CURRENT_MATCH_OUTLINE_WIDTH / total_zoom,
+            );
+        }
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+/// Builds the regex for the query and the search options.
+fn build_search_regex(query: &str, options: SearchOptions) -> anyhow::Result<regex::Regex> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+
+    Ok(regex::RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .build()?)
+}
+
+/// Finds the byte ranges of all non-empty matches of the regex in the text content of the stroke.
+fn stroke_match_ranges(stroke: &Stroke, regex: &regex::Regex) -> Vec<Range<usize>> {
+    stroke
+        .text_content()
+        .map(|text| {
+            regex
+                .find_iter(text)
+                .filter(|m| !m.range().is_empty())
+                .map(|m| m.range())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+impl RnoteEngine {
+    /// Find all strokes with text content that match the query.
+    ///
+    /// Trashed strokes and strokes on hidden layers are not searched.
+    /// The matches are sorted in reading order, top to bottom and then left to right.
+    /// Returns an error if the query is not a valid regular expression when `options.regex` is set.
+    pub fn find_matches(
+        &self,
+        query: &str,
+        options: SearchOptions,
+    ) -> anyhow::Result<Vec<SearchMatch>> {
+        if query.is_empty() {
+            return Ok(vec![]);
+        }
+        let regex = build_search_regex(query, options)?;
+
+        let mut matches = self
+            .store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .filter_map(|key| {
+                let stroke = self.store.get_stroke_ref(key)?;
+                let ranges = stroke_match_ranges(stroke, &regex);
+                if ranges.is_empty() {
+                    return None;
+                }
+                Some((stroke.bounds(), SearchMatch { key, ranges }))
+            })
+            .collect::<Vec<(Aabb, SearchMatch)>>();
+
+        matches.sort_by(|(first, _), (second, _)| {
+            first.mins[1]
+                .total_cmp(&second.mins[1])
+                .then(first.mins[0].total_cmp(&second.mins[0]))
+        });
+
+        Ok(matches.into_iter().map(|(_, m)| m).collect())
+    }
+
+    /// Start a new search with the query and jump to the first match.
+    ///
+    /// The matches are highlighted until the search is cleared.
+    pub fn search(&mut self, query: String, options: SearchOptions) -> anyhow::Result<WidgetFlags> {
+        let matches = self.find_matches(&query, options)?;
+        let current = if matches.is_empty() { None } else { Some(0) };
+
+        self.search = SearchState {
+            query,
+            options,
+            matches,
+            current,
+        };
+
+        let mut widget_flags = self.search_jump_to_current_match();
+        widget_flags.redraw = true;
+        widget_flags.refresh_ui = true;
+        Ok(widget_flags)
+    }
+
+    /// Jump to the next match of the current search, wrapping around at the end.
+    pub fn search_next_match(&mut self) -> WidgetFlags {
+        let n_matches = self.search.matches.len();
+        if n_matches == 0 {
+            return WidgetFlags::default();
+        }
+        self.search.current = Some(
+            self.search
+                .current
+                .map(|current| (current + 1) % n_matches)
+                .unwrap_or(0),
+        );
+
+        self.search_jump_to_current_match()
+    }
+
+    /// Jump to the previous match of the current search, wrapping around at the start.
+    pub fn search_previous_match(&mut self) -> WidgetFlags {
+        let n_matches = self.search.matches.len();
+        if n_matches == 0 {
+            return WidgetFlags::default();
+        }
+        self.search.current = Some(
+            self.search
+                .current
+                .map(|current| (current + n_matches - 1) % n_matches)
+                .unwrap_or(n_matches - 1),
+        );
+
+        self.search_jump_to_current_match()
+    }
+
+    /// Refresh the matches of the current search after the strokes have changed.
+    ///
+    /// Matches of strokes that got trashed, removed or edited are dropped and new matches are added.
+    /// The current match is kept when its stroke still matches.
+    pub fn search_refresh(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        if self.search.query.is_empty() {
+            return widget_flags;
+        }
+        let matches = match self.find_matches(&self.search.query, self.search.options) {
+            Ok(matches) => matches,
+            Err(e) => {
+                log::error!("finding matches failed in search_refresh(), Err: {e:?}");
+                vec![]
+            }
+        };
+        if matches == self.search.matches {
+            return widget_flags;
+        }
+
+        let current_key = self.search.current_match().map(|m| m.key);
+        self.search.current = current_key
+            .and_then(|key| matches.iter().position(|m| m.key == key))
+            .or_else(|| {
+                let last = matches.len().checked_sub(1)?;
+                Some(self.search.current.unwrap_or(0).min(last))
+            });
+        self.search.matches = matches;
+
+        widget_flags.redraw = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Clear the current search and its highlights.
+    pub fn search_clear(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        self.search = SearchState::default();
+        widget_flags.redraw = true;
+        widget_flags.refresh_ui = true;
+        widget_flags
+    }
+
+    /// Center the viewport on the current match.
+    fn search_jump_to_current_match(&mut self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+        let Some(bounds) = self
+            .search
+            .current_match()
+            .and_then(|m| self.store.get_stroke_ref(m.key))
+            .map(|stroke| stroke.bounds()) else {
+            return widget_flags;
+        };
+
+        widget_flags.merge(self.camera.set_viewport_center(bounds.center().coords));
+        widget_flags.redraw = true;
+        widget_flags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::textstroke::TextStyle;
+    use crate::strokes::TextStroke;
+
+    fn insert_text(engine: &mut RnoteEngine, text: &str, pos: na::Vector2<f64>) -> StrokeKey {
+        engine.store.insert_stroke(
+            Stroke::TextStroke(TextStroke::new(text.to_string(), pos, TextStyle::default())),
+            None,
+        )
+    }
+
+    #[test]
+    fn find_matches_in_reading_order() {
+        let mut engine = RnoteEngine::default();
+        let bottom = insert_text(&mut engine, "Second lecture", na::vector![0.0, 500.0]);
+        let top_right = insert_text(&mut engine, "lecture notes", na::vector![300.0, 0.0]);
+        let top_left = insert_text(&mut engine, "Lecture 1, lecture 2", na::vector![0.0, 0.0]);
+        let _ = insert_text(&mut engine, "Exercises", na::vector![0.0, 1000.0]);
+
+        let matches = engine
+            .find_matches("lecture", SearchOptions::default())
+            .unwrap();
+        assert_eq!(
+            matches,
+            vec![
+                SearchMatch {
+                    key: top_left,
+                    ranges: vec![0..7, 11..18],
+                },
+                SearchMatch {
+                    key: top_right,
+                    ranges: vec![0..7],
+                },
+                SearchMatch {
+                    key: bottom,
+                    ranges: vec![7..14],
+                },
+            ]
+        );
+
+        let case_sensitive = SearchOptions {
+            case_sensitive: true,
+            ..Default::default()
+        };
+        let matches = engine.find_matches("Lecture", case_sensitive).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].ranges, vec![0..7]);
+
+        assert!(engine
+            .find_matches("", SearchOptions::default())
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn find_matches_with_regex() {
+        let mut engine = RnoteEngine::default();
+        let key = insert_text(&mut engine, "Lecture 1, lecture 2", na::vector![0.0, 0.0]);
+        let regex = SearchOptions {
+            regex: true,
+            ..Default::default()
+        };
+
+        let matches = engine.find_matches(r"lecture \d", regex).unwrap();
+        assert_eq!(
+            matches,
+            vec![SearchMatch {
+                key,
+                ranges: vec![0..9, 11..20],
+            }]
+        );
+        // Without the regex option the query is matched literally
+        assert!(engine
+            .find_matches(r"lecture \d", SearchOptions::default())
+            .unwrap()
+            .is_empty());
+        // Empty matches are skipped
+        assert!(engine.find_matches("x*", regex).unwrap().is_empty());
+        assert!(engine.find_matches("(unclosed", regex).is_err());
+    }
+
+    #[test]
+    fn search_navigation_wraps_around() {
+        let mut engine = RnoteEngine::default();
+        let first = insert_text(&mut engine, "note", na::vector![0.0, 0.0]);
+        let second = insert_text(&mut engine, "note", na::vector![0.0, 500.0]);
+
+        let _ = engine
+            .search(String::from("note"), SearchOptions::default())
+            .unwrap();
+        assert_eq!(engine.search.current_match().map(|m| m.key), Some(first));
+        let _ = engine.search_next_match();
+        assert_eq!(engine.search.current_match().map(|m| m.key), Some(second));
+        let _ = engine.search_next_match();
+        assert_eq!(engine.search.current(), Some(0));
+        let _ = engine.search_previous_match();
+        assert_eq!(engine.search.current(), Some(1));
+
+        let _ = engine.search_clear();
+        assert!(engine.search.matches().is_empty());
+        assert_eq!(engine.search.current(), None);
+    }
+
+    #[test]
+    fn search_refresh_drops_trashed_strokes() {
+        let mut engine = RnoteEngine::default();
+        let first = insert_text(&mut engine, "note", na::vector![0.0, 0.0]);
+        let second = insert_text(&mut engine, "note", na::vector![0.0, 500.0]);
+
+        let _ = engine
+            .search(String::from("note"), SearchOptions::default())
+            .unwrap();
+        let _ = engine.search_next_match();
+        assert_eq!(engine.search.current_match().map(|m| m.key), Some(second));
+
+        engine.store.set_trashed_keys(&[first], true);
+        let widget_flags = engine.search_refresh();
+        assert!(widget_flags.redraw);
+        assert_eq!(engine.search.matches().len(), 1);
+        // The current match is kept
+        assert_eq!(engine.search.current_match().map(|m| m.key), Some(second));
+
+        engine.store.set_trashed_keys(&[second], true);
+        let _ = engine.search_refresh();
+        assert!(engine.search.matches().is_empty());
+        assert_eq!(engine.search.current(), None);
+
+        // Untrashed strokes are found again
+        engine.store.set_trashed_keys(&[first, second], false);
+        let _ = engine.search_refresh();
+        assert_eq!(engine.search.matches().len(), 2);
+        assert_eq!(engine.search.current(), Some(0));
+        assert!(!engine.search_refresh().redraw);
+    }
+}
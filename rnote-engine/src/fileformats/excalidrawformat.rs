@@ -0,0 +1,670 @@
+// Imports
+use super::{FileFormatLoader, FileFormatSaver};
+use rand::Rng;
+use serde::de::Error;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Serialize};
+
+/// Represents an Excalidraw `.excalidraw` scene file.
+///
+/// Only the elements that have an equivalent in rnote are modeled: rectangles, ellipses, lines, arrows,
+/// freedraw paths and texts. Elements of other types are loaded as `ExcalidrawElementKind::Unsupported`,
+/// and are saved back unchanged.
+///
+/// The format is described here: <https://docs.excalidraw.com/docs/codebase/json-schema>
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawFile {
+    /// The file type, `excalidraw` for scene files.
+    #[serde(rename = "type")]
+    pub file_type: String,
+    /// The version of the scene format.
+    #[serde(rename = "version")]
+    pub version: u32,
+    /// The application that created the file.
+    #[serde(rename = "source")]
+    pub source: String,
+    /// The elements, in the order they are drawn.
+    #[serde(
+        rename = "elements",
+        serialize_with = "serialize_elements",
+        deserialize_with = "deserialize_elements"
+    )]
+    pub elements: Vec<ExcalidrawElement>,
+    /// The app state.
+    #[serde(rename = "appState")]
+    pub app_state: ExcalidrawAppState,
+    /// The binary files of image elements. Not used, but preserved.
+    #[serde(rename = "files")]
+    pub files: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Default for ExcalidrawFile {
+    fn default() -> Self {
+        Self {
+            file_type: String::from(Self::FILE_TYPE),
+            version: Self::VERSION,
+            source: String::from("https://rnote.flxzt.net"),
+            elements: vec![],
+            app_state: ExcalidrawAppState::default(),
+            files: serde_json::Map::new(),
+        }
+    }
+}
+
+impl FileFormatLoader for ExcalidrawFile {
+    fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let file = serde_json::from_slice::<Self>(bytes)?;
+        // scenes that are copied to the clipboard have the same structure
+        if file.file_type != Self::FILE_TYPE && file.file_type != Self::CLIPBOARD_FILE_TYPE {
+            return Err(anyhow::anyhow!(
+                "failed to load Excalidraw file, file type is `{}` and not `{}`",
+                file.file_type,
+                Self::FILE_TYPE
+            ));
+        }
+        Ok(file)
+    }
+}
+
+impl FileFormatSaver for ExcalidrawFile {
+    fn save_as_bytes(&self, _file_name: &str) -> anyhow::Result<Vec<u8>> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
+
+impl ExcalidrawFile {
+    /// The file type of scene files.
+    pub const FILE_TYPE: &'static str = "excalidraw";
+    /// The file type of scenes copied to the clipboard.
+    pub const CLIPBOARD_FILE_TYPE: &'static str = "excalidraw/clipboard";
+    /// The scene format version that is written.
+    pub const VERSION: u32 = 2;
+    /// The coordinates of Excalidraw scenes are in css pixels, which is 96 values per inch.
+    pub const DPI: f64 = 96.0;
+}
+
+/// Serializes the elements, unsupported elements are written with their raw Json.
+fn serialize_elements<S>(elements: &[ExcalidrawElement], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    let mut seq = serializer.serialize_seq(Some(elements.len()))?;
+    for element in elements {
+        match &element.unsupported_raw {
+            Some(raw) => seq.serialize_element(raw)?,
+            None => seq.serialize_element(element)?,
+        }
+    }
+    seq.end()
+}
+
+/// Deserializes the elements, keeping the raw Json of unsupported elements.
+fn deserialize_elements<'de, D>(deserializer: D) -> Result<Vec<ExcalidrawElement>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Vec::<serde_json::Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|raw| {
+            let mut element = serde_json::from_value::<ExcalidrawElement>(raw.clone())
+                .map_err(D::Error::custom)?;
+            if matches!(element.kind, ExcalidrawElementKind::Unsupported) {
+                element.unsupported_raw = Some(raw);
+            }
+            Ok(element)
+        })
+        .collect()
+}
+
+/// The Excalidraw app state. Only the canvas related values are modeled.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawAppState {
+    /// The background color of the canvas.
+    #[serde(rename = "viewBackgroundColor")]
+    pub view_background_color: String,
+    /// The grid size, None when the grid is disabled.
+    #[serde(rename = "gridSize")]
+    pub grid_size: Option<f64>,
+}
+
+impl Default for ExcalidrawAppState {
+    fn default() -> Self {
+        Self {
+            view_background_color: String::from("#ffffff"),
+            grid_size: None,
+        }
+    }
+}
+
+/// An Excalidraw element.
+///
+/// The position is the upper left corner of the unrotated element, the angle rotates it around its center.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawElement {
+    /// The unique id.
+    #[serde(rename = "id")]
+    pub id: String,
+    /// The element type and the data that is specific to it.
+    #[serde(flatten)]
+    pub kind: ExcalidrawElementKind,
+    /// The x coordinate.
+    #[serde(rename = "x")]
+    pub x: f64,
+    /// The y coordinate.
+    #[serde(rename = "y")]
+    pub y: f64,
+    /// The width.
+    #[serde(rename = "width")]
+    pub width: f64,
+    /// The height.
+    #[serde(rename = "height")]
+    pub height: f64,
+    /// The rotation angle around the center of the element (in radians).
+    #[serde(rename = "angle")]
+    pub angle: f64,
+    /// The stroke color as css color, `transparent` when the stroke is not drawn.
+    #[serde(rename = "strokeColor")]
+    pub stroke_color: String,
+    /// The fill color as css color, `transparent` when the element is not filled.
+    #[serde(rename = "backgroundColor")]
+    pub background_color: String,
+    /// The fill style.
+    #[serde(rename = "fillStyle")]
+    pub fill_style: ExcalidrawFillStyle,
+    /// The stroke width.
+    #[serde(rename = "strokeWidth")]
+    pub stroke_width: f64,
+    /// The stroke style, `solid`, `dashed` or `dotted`.
+    #[serde(rename = "strokeStyle")]
+    pub stroke_style: String,
+    /// The roughness. `0` is drawn without any roughness.
+    #[serde(rename = "roughness")]
+    pub roughness: f64,
+    /// The opacity, ranging from 0 to 100.
+    #[serde(rename = "opacity")]
+    pub opacity: f64,
+    /// The seed for the rough shape generation.
+    #[serde(rename = "seed")]
+    pub seed: u64,
+    /// The version of the element, incremented on every change.
+    #[serde(rename = "version")]
+    pub version: u32,
+    /// A random nonce of the version.
+    #[serde(rename = "versionNonce")]
+    pub version_nonce: u64,
+    /// Whether the element is deleted.
+    #[serde(rename = "isDeleted")]
+    pub is_deleted: bool,
+    /// The ids of the groups the element is part of.
+    #[serde(rename = "groupIds")]
+    pub group_ids: Vec<String>,
+    /// The raw Json of unsupported elements, which is written back unchanged.
+    #[serde(skip)]
+    pub unsupported_raw: Option<serde_json::Value>,
+}
+
+impl Default for ExcalidrawElement {
+    fn default() -> Self {
+        Self {
+            id: String::default(),
+            kind: ExcalidrawElementKind::Unsupported,
+            x: 0.0,
+            y: 0.0,
+            width: 0.0,
+            height: 0.0,
+            angle: 0.0,
+            stroke_color: String::from("#1e1e1e"),
+            background_color: String::from(ExcalidrawColor::TRANSPARENT),
+            fill_style: ExcalidrawFillStyle::default(),
+            stroke_width: 2.0,
+            stroke_style: String::from("solid"),
+            roughness: 1.0,
+            opacity: 100.0,
+            seed: 1,
+            version: 1,
+            version_nonce: 0,
+            is_deleted: false,
+            group_ids: vec![],
+            unsupported_raw: None,
+        }
+    }
+}
+
+impl ExcalidrawElement {
+    /// Excalidraw seeds are below this value.
+    pub const SEED_MAX: u64 = 1 << 31;
+    /// The length of generated ids.
+    const ID_LEN: usize = 20;
+
+    /// Generates a new random id.
+    pub fn new_id() -> String {
+        rand::thread_rng()
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(Self::ID_LEN)
+            .map(char::from)
+            .collect()
+    }
+
+    /// Generates a new random seed.
+    pub fn new_seed() -> u64 {
+        rand::thread_rng().gen_range(1..Self::SEED_MAX)
+    }
+
+    /// The rotation center of the element, in the coordinates of the scene.
+    ///
+    /// For linear and freedraw elements this is the center of the bounds of their points.
+    pub fn center(&self) -> na::Vector2<f64> {
+        match &self.kind {
+            ExcalidrawElementKind::Line(linear) | ExcalidrawElementKind::Arrow(linear) => {
+                points_center(&linear.points)
+                    .map(|center| na::vector![self.x, self.y] + center)
+                    .unwrap_or_else(|| self.bounds_center())
+            }
+            ExcalidrawElementKind::Freedraw(freedraw) => points_center(&freedraw.points)
+                .map(|center| na::vector![self.x, self.y] + center)
+                .unwrap_or_else(|| self.bounds_center()),
+            _ => self.bounds_center(),
+        }
+    }
+
+    /// The points of linear and freedraw elements, rotated and in the coordinates of the scene.
+    pub fn absolute_points(&self, points: &[[f64; 2]]) -> Vec<na::Vector2<f64>> {
+        let center = self.center();
+        let rotation = na::Rotation2::new(self.angle);
+        points
+            .iter()
+            .map(|point| {
+                let pos = na::vector![self.x + point[0], self.y + point[1]];
+                rotation * (pos - center) + center
+            })
+            .collect()
+    }
+
+    /// The opacity as factor ranging from 0.0 to 1.0.
+    pub fn opacity_factor(&self) -> f64 {
+        (self.opacity / 100.0).clamp(0.0, 1.0)
+    }
+
+    fn bounds_center(&self) -> na::Vector2<f64> {
+        na::vector![self.x + self.width * 0.5, self.y + self.height * 0.5]
+    }
+}
+
+/// The center of the bounds of the points.
+fn points_center(points: &[[f64; 2]]) -> Option<na::Vector2<f64>> {
+    let first = points.first()?;
+    let (mins, maxs) = points.iter().fold(
+        (
+            na::vector![first[0], first[1]],
+            na::vector![first[0], first[1]],
+        ),
+        |(mins, maxs), point| {
+            let point = na::vector![point[0], point[1]];
+            (mins.inf(&point), maxs.sup(&point))
+        },
+    );
+    Some((mins + maxs) * 0.5)
+}
+
+/// The element types and the data that is specific to them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ExcalidrawElementKind {
+    /// A rectangle.
+    #[serde(rename = "rectangle")]
+    Rectangle,
+    /// An ellipse.
+    #[serde(rename = "ellipse")]
+    Ellipse,
+    /// A line through its points.
+    #[serde(rename = "line")]
+    Line(ExcalidrawLinear),
+    /// A line through its points with optional arrowheads.
+    #[serde(rename = "arrow")]
+    Arrow(ExcalidrawLinear),
+    /// A freehand drawn path.
+    #[serde(rename = "freedraw")]
+    Freedraw(ExcalidrawFreedraw),
+    /// A text.
+    #[serde(rename = "text")]
+    Text(ExcalidrawText),
+    /// Element types that are not supported, e.g. diamonds, images or frames.
+    #[serde(other)]
+    Unsupported,
+}
+
+/// The data of line and arrow elements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawLinear {
+    /// The points, relative to the position of the element.
+    #[serde(rename = "points")]
+    pub points: Vec<[f64; 2]>,
+    /// The arrowhead at the first point, e.g. `arrow`, `bar`, `dot` or `triangle`.
+    #[serde(rename = "startArrowhead")]
+    pub start_arrowhead: Option<String>,
+    /// The arrowhead at the last point.
+    #[serde(rename = "endArrowhead")]
+    pub end_arrowhead: Option<String>,
+}
+
+/// The data of freedraw elements.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawFreedraw {
+    /// The points, relative to the position of the element.
+    #[serde(rename = "points")]
+    pub points: Vec<[f64; 2]>,
+    /// The pressures of the points, ranging from 0.0 to 1.0. Empty when the pressure is simulated.
+    #[serde(rename = "pressures")]
+    pub pressures: Vec<f64>,
+    /// Whether the pressure is simulated from the velocity when drawing the path.
+    #[serde(rename = "simulatePressure")]
+    pub simulate_pressure: bool,
+}
+
+impl ExcalidrawFreedraw {
+    /// Excalidraw draws freedraw paths with a size of the stroke width multiplied by this factor.
+    pub const STROKE_WIDTH_SIZE_FACTOR: f64 = 4.25;
+}
+
+/// The data of text elements.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExcalidrawText {
+    /// The text, with line breaks where it is wrapped.
+    #[serde(rename = "text")]
+    pub text: String,
+    /// The text as it was entered.
+    #[serde(rename = "originalText")]
+    pub original_text: String,
+    /// The font size.
+    #[serde(rename = "fontSize")]
+    pub font_size: f64,
+    /// The font family id.
+    #[serde(rename = "fontFamily")]
+    pub font_family: u32,
+    /// The horizontal alignment, `left`, `center` or `right`.
+    #[serde(rename = "textAlign")]
+    pub text_align: String,
+    /// The vertical alignment, `top`, `middle` or `bottom`.
+    #[serde(rename = "verticalAlign")]
+    pub vertical_align: String,
+    /// The line height, as factor of the font size.
+    #[serde(rename = "lineHeight")]
+    pub line_height: f64,
+    /// The id of the shape that contains the text.
+    #[serde(rename = "containerId")]
+    pub container_id: Option<String>,
+}
+
+impl Default for ExcalidrawText {
+    fn default() -> Self {
+        Self {
+            text: String::default(),
+            original_text: String::default(),
+            font_size: 20.0,
+            font_family: 1,
+            text_align: String::from("left"),
+            vertical_align: String::from("top"),
+            line_height: 1.25,
+            container_id: None,
+        }
+    }
+}
+
+impl ExcalidrawText {
+    /// The font families with their ids.
+    pub const FONT_FAMILIES: [(u32, &'static str); 7] = [
+        (1, "Virgil"),
+        (2, "Helvetica"),
+        (3, "Cascadia"),
+        (5, "Excalifont"),
+        (6, "Nunito"),
+        (7, "Lilita One"),
+        (8, "Comic Shanns"),
+    ];
+    /// The font family id that is used for fonts that are unknown to Excalidraw.
+    pub const FONT_FAMILY_FALLBACK: u32 = 2;
+
+    /// The name of the font family with the given id.
+    pub fn font_family_name(id: u32) -> Option<&'static str> {
+        Self::FONT_FAMILIES
+            .iter()
+            .find(|(family_id, _)| *family_id == id)
+            .map(|(_, name)| *name)
+    }
+
+    /// The id of the font family with the given name, the fallback font family if it is unknown.
+    pub fn font_family_id(name: &str) -> u32 {
+        Self::FONT_FAMILIES
+            .iter()
+            .find(|(_, family_name)| family_name.eq_ignore_ascii_case(name))
+            .map_or(Self::FONT_FAMILY_FALLBACK, |(id, _)| *id)
+    }
+}
+
+/// The fill styles of Excalidraw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExcalidrawFillStyle {
+    /// Cross hatch.
+    #[serde(rename = "cross-hatch")]
+    CrossHatch,
+    /// Solid.
+    #[serde(rename = "solid")]
+    Solid,
+    /// Zig zag.
+    #[serde(rename = "zigzag")]
+    ZigZag,
+    /// Hachure. Also used for unknown fill styles, so it needs to be the last variant.
+    #[serde(rename = "hachure", other)]
+    Hachure,
+}
+
+impl Default for ExcalidrawFillStyle {
+    fn default() -> Self {
+        Self::Hachure
+    }
+}
+
+/// A color parsed from the css colors of Excalidraw.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExcalidrawColor {
+    /// Red, ranging from 0 to 255.
+    pub red: u8,
+    /// Green, ranging from 0 to 255.
+    pub green: u8,
+    /// Blue, ranging from 0 to 255.
+    pub blue: u8,
+    /// Alpha, ranging from 0 to 255.
+    pub alpha: u8,
+}
+
+impl ExcalidrawColor {
+    /// The css color of colors that are not drawn.
+    pub const TRANSPARENT: &'static str = "transparent";
+
+    /// Parses a css color in the hex notations `#rgb`, `#rrggbb` and `#rrggbbaa`.
+    ///
+    /// Returns None for `transparent` and for colors that can't be parsed.
+    pub fn from_css_color(css_color: &str) -> Option<Self> {
+        let hex = css_color.trim().strip_prefix('#')?;
+        if !hex.is_ascii() {
+            return None;
+        }
+        let channel = |i: usize, len: usize| -> Option<u8> {
+            let value = u8::from_str_radix(hex.get(i * len..(i + 1) * len)?, 16).ok()?;
+            // expand the short notation, `f` is `ff`
+            Some(if len == 1 { value * 17 } else { value })
+        };
+
+        match hex.len() {
+            3 => Some(Self {
+                red: channel(0, 1)?,
+                green: channel(1, 1)?,
+                blue: channel(2, 1)?,
+                alpha: 0xff,
+            }),
+            6 | 8 => Some(Self {
+                red: channel(0, 2)?,
+                green: channel(1, 2)?,
+                blue: channel(2, 2)?,
+                alpha: if hex.len() == 8 { channel(3, 2)? } else { 0xff },
+            }),
+            _ => None,
+        }
+    }
+
+    /// The css color, in the `#rrggbb` notation when it is opaque and in `#rrggbbaa` otherwise.
+    pub fn to_css_color(self) -> String {
+        if self.alpha == 0xff {
+            format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+        } else {
+            format!(
+                "#{:02x}{:02x}{:02x}{:02x}",
+                self.red, self.green, self.blue, self.alpha
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn css_colors() {
+        assert_eq!(
+            ExcalidrawColor::from_css_color("#1e1e1e"),
+            Some(ExcalidrawColor {
+                red: 0x1e,
+                green: 0x1e,
+                blue: 0x1e,
+                alpha: 0xff
+            })
+        );
+        assert_eq!(
+            ExcalidrawColor::from_css_color("#f0a"),
+            ExcalidrawColor::from_css_color("#ff00aa")
+        );
+        let translucent = ExcalidrawColor::from_css_color("#a5d8ff80").unwrap();
+        assert_eq!(translucent.alpha, 0x80);
+        assert_eq!(translucent.to_css_color(), "#a5d8ff80");
+        assert_eq!(
+            ExcalidrawColor::from_css_color(ExcalidrawColor::TRANSPARENT),
+            None
+        );
+    }
+
+    #[test]
+    fn load_excalidraw_scene() {
+        let scene = r##"{
+            "type": "excalidraw",
+            "version": 2,
+            "source": "https://excalidraw.com",
+            "elements": [
+                {
+                    "id": "rect",
+                    "type": "rectangle",
+                    "x": 10, "y": 20, "width": 100, "height": 50, "angle": 0,
+                    "strokeColor": "#1e1e1e", "backgroundColor": "#a5d8ff",
+                    "fillStyle": "cross-hatch", "strokeWidth": 2, "strokeStyle": "solid",
+                    "roughness": 1, "opacity": 100, "seed": 1968410350,
+                    "groupIds": [], "roundness": { "type": 3 }, "boundElements": null,
+                    "isDeleted": false, "locked": false
+                },
+                {
+                    "id": "arrow",
+                    "type": "arrow",
+                    "x": 0, "y": 0, "width": 40, "height": 30, "angle": 0,
+                    "points": [[0, 0], [40, 30]],
+                    "startArrowhead": null, "endArrowhead": "arrow"
+                },
+                {
+                    "id": "draw",
+                    "type": "freedraw",
+                    "x": 5, "y": 5, "width": 2, "height": 2, "angle": 0,
+                    "points": [[0, 0], [1, 1], [2, 2]],
+                    "pressures": [], "simulatePressure": true
+                },
+                {
+                    "id": "text",
+                    "type": "text",
+                    "x": 0, "y": 100, "width": 50, "height": 25, "angle": 0,
+                    "text": "Hello", "fontSize": 20, "fontFamily": 1,
+                    "textAlign": "left", "verticalAlign": "top"
+                },
+                {
+                    "id": "diamond",
+                    "type": "diamond",
+                    "x": 0, "y": 0, "width": 10, "height": 10, "angle": 0,
+                    "fillStyle": "dots"
+                }
+            ],
+            "appState": { "gridSize": null, "viewBackgroundColor": "#ffffff" },
+            "files": {}
+        }"##;
+
+        let file = ExcalidrawFile::load_from_bytes(scene.as_bytes()).unwrap();
+        assert_eq!(file.elements.len(), 5);
+
+        let rect = &file.elements[0];
+        assert!(matches!(rect.kind, ExcalidrawElementKind::Rectangle));
+        assert_eq!(rect.fill_style, ExcalidrawFillStyle::CrossHatch);
+        assert_eq!(rect.seed, 1968410350);
+        assert_eq!(rect.center(), na::vector![60.0, 45.0]);
+
+        match &file.elements[1].kind {
+            ExcalidrawElementKind::Arrow(linear) => {
+                assert_eq!(linear.points, vec![[0.0, 0.0], [40.0, 30.0]]);
+                assert_eq!(linear.start_arrowhead, None);
+                assert_eq!(linear.end_arrowhead.as_deref(), Some("arrow"));
+            }
+            kind => panic!("expected an arrow, got {kind:?}"),
+        }
+        match &file.elements[2].kind {
+            ExcalidrawElementKind::Freedraw(freedraw) => {
+                assert_eq!(freedraw.points.len(), 3);
+                assert!(freedraw.simulate_pressure);
+            }
+            kind => panic!("expected a freedraw path, got {kind:?}"),
+        }
+        match &file.elements[3].kind {
+            ExcalidrawElementKind::Text(text) => {
+                assert_eq!(text.text, "Hello");
+                assert_eq!(
+                    ExcalidrawText::font_family_name(text.font_family),
+                    Some("Virgil")
+                );
+            }
+            kind => panic!("expected a text, got {kind:?}"),
+        }
+        assert!(matches!(
+            file.elements[4].kind,
+            ExcalidrawElementKind::Unsupported
+        ));
+        assert_eq!(file.elements[4].fill_style, ExcalidrawFillStyle::Hachure);
+
+        // saved files are loaded back with the same elements
+        let bytes = file.save_as_bytes("").unwrap();
+        let reloaded = ExcalidrawFile::load_from_bytes(&bytes).unwrap();
+        assert_eq!(reloaded.elements.len(), 5);
+        assert!(matches!(
+            reloaded.elements[1].kind,
+            ExcalidrawElementKind::Arrow(_)
+        ));
+
+        // unsupported elements are saved back unchanged
+        let saved = serde_json::from_slice::<serde_json::Value>(&bytes).unwrap();
+        assert_eq!(saved["elements"][4]["type"], "diamond");
+        assert_eq!(saved["elements"][4]["fillStyle"], "dots");
+        assert_eq!(
+            reloaded.elements[4].unsupported_raw,
+            file.elements[4].unsupported_raw
+        );
+        assert!(reloaded.elements[0].unsupported_raw.is_none());
+    }
+}
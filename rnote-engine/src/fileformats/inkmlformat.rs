@@ -0,0 +1,648 @@
+// Imports
+use super::{AsXmlAttributeValue, FileFormatLoader, FileFormatSaver, XmlLoadable, XmlWritable};
+use roxmltree::{Node, NodeType};
+use serde::{Deserialize, Serialize};
+
+/// The decimal places when serializing values.
+pub const VALS_DEC_PLACES: usize = 3;
+
+/// The InkML namespace.
+const INKML_NAMESPACE: &str = "http://www.w3.org/2003/InkML";
+/// The Xml namespace, used by the `xml:id` attributes.
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Represents a W3C InkML `.inkml` file.
+///
+/// Only the subset that is needed to represent pen input is supported: a single trace format,
+/// brushes with a width and color and the traces. Traces inside trace groups are flattened.
+///
+/// The spec can be found here: <https://www.w3.org/TR/InkML/>
+#[derive(Debug)]
+pub struct InkmlFile {
+    /// The InkML `ink` root element.
+    pub ink: InkmlInk,
+}
+
+impl FileFormatLoader for InkmlFile {
+    fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let xml = std::str::from_utf8(bytes)?;
+        let parsed_doc =
+            roxmltree::Document::parse_with_options(xml, roxmltree::ParsingOptions::default())?;
+        let root_node = parsed_doc.root_element();
+        if root_node.tag_name().name() != "ink" {
+            return Err(anyhow::anyhow!(
+                "failed to load InkML file, root element is `{}` and not `ink`",
+                root_node.tag_name().name()
+            ));
+        }
+        let mut ink = InkmlInk::default();
+        ink.load_from_xml(root_node)?;
+        Ok(Self { ink })
+    }
+}
+
+impl FileFormatSaver for InkmlFile {
+    fn save_as_bytes(&self, _file_name: &str) -> anyhow::Result<Vec<u8>> {
+        let mut xml_writer = xmlwriter::XmlWriter::new(xmlwriter::Options::default());
+        xml_writer.write_declaration();
+        self.ink.write_to_xml(&mut xml_writer);
+        Ok(xml_writer.end_document().into_bytes())
+    }
+}
+
+impl InkmlFile {
+    /// The exported positions and widths are in millimeters, which is 25.4 values per inch.
+    pub const EXPORT_DPI: f64 = 25.4;
+    /// The time between the exported points of a trace in milliseconds.
+    ///
+    /// Rnote does not record the time of the pen input, so the time channel is generated with fixed intervals.
+    pub const EXPORT_POINT_INTERVAL: f64 = 10.0;
+    /// The time between the end of an exported trace and the start of the next trace in milliseconds.
+    pub const EXPORT_TRACE_INTERVAL: f64 = 200.0;
+    /// The maximum distance between the exported points of the curved segments of a trace in millimeters.
+    pub const EXPORT_SAMPLE_DIST: f64 = 0.5;
+
+    /// The channels of exported traces.
+    ///
+    /// The positions are in millimeters, the force is the pen pressure and the time is in milliseconds.
+    pub fn export_channels() -> Vec<InkmlChannel> {
+        vec![
+            InkmlChannel::new("X", Some("mm"), None),
+            InkmlChannel::new("Y", Some("mm"), None),
+            InkmlChannel::new("F", None, Some(1.0)),
+            InkmlChannel::new("T", Some("ms"), None),
+        ]
+    }
+}
+
+/// The InkML `ink` element.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InkmlInk {
+    /// The channels of the trace format, in the order of the values of the trace points.
+    pub channels: Vec<InkmlChannel>,
+    /// The brushes.
+    pub brushes: Vec<InkmlBrush>,
+    /// The traces.
+    pub traces: Vec<InkmlTrace>,
+}
+
+impl Default for InkmlInk {
+    fn default() -> Self {
+        // The default trace format of InkML
+        Self {
+            channels: vec![
+                InkmlChannel::new("X", None, None),
+                InkmlChannel::new("Y", None, None),
+            ],
+            brushes: vec![],
+            traces: vec![],
+        }
+    }
+}
+
+impl XmlLoadable for InkmlInk {
+    fn load_from_xml(&mut self, node: Node) -> anyhow::Result<()> {
+        // The first trace format is used for all traces
+        if let Some(trace_format) = node
+            .descendants()
+            .find(|n| n.is_element() && n.tag_name().name() == "traceFormat")
+        {
+            let channels = trace_format
+                .children()
+                .filter(|n| n.is_element() && n.tag_name().name() == "channel")
+                .map(|n| {
+                    let mut channel = InkmlChannel::default();
+                    channel.load_from_xml(n)?;
+                    Ok(channel)
+                })
+                .collect::<anyhow::Result<Vec<InkmlChannel>>>()?;
+            if !channels.is_empty() {
+                self.channels = channels;
+            }
+        }
+
+        for child in node.descendants() {
+            match child.node_type() {
+                NodeType::Element => match child.tag_name().name() {
+                    "brush" => {
+                        let mut new_brush = InkmlBrush::default();
+                        new_brush.load_from_xml(child)?;
+                        self.brushes.push(new_brush);
+                    }
+                    "trace" => {
+                        let mut new_trace = InkmlTrace::default();
+                        new_trace.load_from_xml(child)?;
+                        self.traces.push(new_trace);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl XmlWritable for InkmlInk {
+    fn write_to_xml(&self, w: &mut xmlwriter::XmlWriter) {
+        w.start_element("ink");
+        w.write_attribute("xmlns", INKML_NAMESPACE);
+
+        w.start_element("definitions");
+        w.start_element("context");
+        w.write_attribute("xml:id", "ctx0");
+        w.start_element("traceFormat");
+        for channel in self.channels.iter() {
+            channel.write_to_xml(w);
+        }
+        w.end_element();
+        w.end_element();
+        for brush in self.brushes.iter() {
+            brush.write_to_xml(w);
+        }
+        w.end_element();
+
+        for trace in self.traces.iter() {
+            trace.write_to_xml(w);
+        }
+        w.end_element();
+    }
+}
+
+impl InkmlInk {
+    /// The index of the channel with the given name.
+    pub fn channel_index(&self, name: &str) -> Option<usize> {
+        self.channels
+            .iter()
+            .position(|channel| channel.name == name)
+    }
+
+    /// The brush with the given id.
+    pub fn brush(&self, id: &str) -> Option<&InkmlBrush> {
+        self.brushes.iter().find(|brush| brush.id == id)
+    }
+
+    /// Add the trace that is drawn with the given brush.
+    ///
+    /// Equal brushes are only added once, the brush id is assigned and referenced by the trace.
+    pub fn push_trace(&mut self, mut trace: InkmlTrace, mut brush: InkmlBrush) {
+        let id =
+            match self.brushes.iter().find(|b| {
+                b.color == brush.color && b.width == brush.width && b.units == brush.units
+            }) {
+                Some(existing) => existing.id.clone(),
+                None => {
+                    let id = format!("br{}", self.brushes.len());
+                    brush.id = id.clone();
+                    self.brushes.push(brush);
+                    id
+                }
+            };
+        trace.brush_ref = Some(id);
+        self.traces.push(trace);
+    }
+}
+
+/// An InkML trace format channel.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InkmlChannel {
+    /// The channel name, e.g. `X`, `Y`, `F` (force) or `T` (time).
+    pub name: String,
+    /// The units of the channel values.
+    pub units: Option<String>,
+    /// The maximum value of the channel.
+    pub max: Option<f64>,
+}
+
+impl XmlLoadable for InkmlChannel {
+    fn load_from_xml(&mut self, node: Node) -> anyhow::Result<()> {
+        self.name = node
+            .attribute("name")
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "failed to parse `name` attribute in InkmlChannel with node id {:?}, could not find attribute",
+                    node.id()
+                )
+            })?
+            .to_string();
+        self.units = node.attribute("units").map(|units| units.to_string());
+        self.max = node
+            .attribute("max")
+            .map(|max| max.trim().parse::<f64>())
+            .transpose()?;
+
+        Ok(())
+    }
+}
+
+impl XmlWritable for InkmlChannel {
+    fn write_to_xml(&self, w: &mut xmlwriter::XmlWriter) {
+        w.start_element("channel");
+        w.write_attribute("name", &self.name);
+        // All values of the traces are written with decimal places
+        w.write_attribute("type", "decimal");
+        if let Some(units) = self.units.as_ref() {
+            w.write_attribute("units", units);
+        }
+        if let Some(max) = self.max {
+            w.write_attribute("max", &format!("{max:.VALS_DEC_PLACES$}"));
+        }
+        w.end_element();
+    }
+}
+
+impl InkmlChannel {
+    /// A new channel.
+    pub fn new(name: &str, units: Option<&str>, max: Option<f64>) -> Self {
+        Self {
+            name: name.to_string(),
+            units: units.map(|units| units.to_string()),
+            max,
+        }
+    }
+
+    /// The number of values per inch for length units, so the units can be treated as DPI.
+    ///
+    /// Returns None for unknown units.
+    pub fn units_per_inch(units: &str) -> Option<f64> {
+        match units {
+            "in" => Some(1.0),
+            "cm" => Some(2.54),
+            "mm" => Some(25.4),
+            "pt" => Some(72.0),
+            "pc" => Some(6.0),
+            "px" => Some(96.0),
+            _ => None,
+        }
+    }
+}
+
+/// An InkML color.
+///
+/// Represented in Xml as hexadecimal values in format `#RRGGBB`, the alpha is the inverse of the brush `transparency` property.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InkmlColor {
+    /// Red ranging [0 - 255].
+    pub red: u8,
+    /// Green ranging [0 - 255].
+    pub green: u8,
+    /// Blue ranging [0 - 255].
+    pub blue: u8,
+    /// Alpha ranging [0 - 255].
+    pub alpha: u8,
+}
+
+impl Default for InkmlColor {
+    fn default() -> Self {
+        Self {
+            red: 0,
+            green: 0,
+            blue: 0,
+            alpha: 0xff,
+        }
+    }
+}
+
+impl AsXmlAttributeValue for InkmlColor {
+    fn as_xml_attr_value(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+impl InkmlColor {
+    /// Parse the color from a attribute value that is in format `#RRGGBB`.
+    fn from_hexcolor_attr_value(s: &str) -> Result<Self, anyhow::Error> {
+        let s = s.trim().trim_start_matches('#');
+        if s.len() != 6 {
+            return Err(anyhow::anyhow!(
+                "failed to parse InkML color `{s}`, not in format `#RRGGBB`"
+            ));
+        }
+        let value = u32::from_str_radix(s, 16)?;
+
+        Ok(Self {
+            red: ((value >> 16) & 0xff) as u8,
+            green: ((value >> 8) & 0xff) as u8,
+            blue: (value & 0xff) as u8,
+            alpha: 0xff,
+        })
+    }
+}
+
+/// An InkML brush.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InkmlBrush {
+    /// The brush id.
+    pub id: String,
+    /// The brush color.
+    pub color: InkmlColor,
+    /// The brush width.
+    pub width: Option<f64>,
+    /// The units of the width. If not set, the units of the `X` channel apply.
+    pub units: Option<String>,
+}
+
+impl XmlLoadable for InkmlBrush {
+    fn load_from_xml(&mut self, node: Node) -> anyhow::Result<()> {
+        self.id = node
+            .attribute((XML_NAMESPACE, "id"))
+            .or_else(|| node.attribute("id"))
+            .unwrap_or_default()
+            .to_string();
+
+        for child in node.children() {
+            if !child.is_element() || child.tag_name().name() != "brushProperty" {
+                continue;
+            }
+            let Some(value) = child.attribute("value") else {
+                continue;
+            };
+
+            match child.attribute("name") {
+                Some("width") => {
+                    self.width = Some(value.trim().parse::<f64>()?);
+                    self.units = child.attribute("units").map(|units| units.to_string());
+                }
+                Some("color") => match InkmlColor::from_hexcolor_attr_value(value) {
+                    Ok(color) => {
+                        self.color = InkmlColor {
+                            alpha: self.color.alpha,
+                            ..color
+                        };
+                    }
+                    Err(e) => {
+                        log::warn!("failed to parse brush color in InkmlBrush, {e:?}");
+                    }
+                },
+                Some("transparency") => {
+                    let transparency = value.trim().parse::<f64>()?.clamp(0.0, 255.0);
+                    self.color.alpha = 255 - transparency.round() as u8;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl XmlWritable for InkmlBrush {
+    fn write_to_xml(&self, w: &mut xmlwriter::XmlWriter) {
+        w.start_element("brush");
+        w.write_attribute("xml:id", &self.id);
+
+        if let Some(width) = self.width {
+            w.start_element("brushProperty");
+            w.write_attribute("name", "width");
+            w.write_attribute("value", &format!("{width:.VALS_DEC_PLACES$}"));
+            if let Some(units) = self.units.as_ref() {
+                w.write_attribute("units", units);
+            }
+            w.end_element();
+        }
+
+        w.start_element("brushProperty");
+        w.write_attribute("name", "color");
+        w.write_attribute("value", &self.color.as_xml_attr_value());
+        w.end_element();
+
+        if self.color.alpha != 0xff {
+            w.start_element("brushProperty");
+            w.write_attribute("name", "transparency");
+            w.write_attribute("value", &(255 - self.color.alpha).to_string());
+            w.end_element();
+        }
+
+        w.end_element();
+    }
+}
+
+/// An InkML trace.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct InkmlTrace {
+    /// The id of the brush the trace is drawn with.
+    pub brush_ref: Option<String>,
+    /// The trace points, holding the values in the order of the trace format channels.
+    pub points: Vec<Vec<f64>>,
+}
+
+impl XmlLoadable for InkmlTrace {
+    fn load_from_xml(&mut self, node: Node) -> anyhow::Result<()> {
+        // Traces inherit the brush of the trace group they are in
+        self.brush_ref = node
+            .ancestors()
+            .find_map(|n| n.attribute("brushRef"))
+            .map(|brush_ref| brush_ref.trim_start_matches('#').to_string());
+
+        if let Some(text) = node.text() {
+            self.points = parse_trace_points(text)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl XmlWritable for InkmlTrace {
+    fn write_to_xml(&self, w: &mut xmlwriter::XmlWriter) {
+        w.start_element("trace");
+        w.write_attribute("contextRef", "#ctx0");
+        if let Some(brush_ref) = self.brush_ref.as_ref() {
+            w.write_attribute("brushRef", &format!("#{brush_ref}"));
+        }
+        w.write_text(
+            &self
+                .points
+                .iter()
+                .map(|point| {
+                    point
+                        .iter()
+                        .map(|value| format!("{value:.VALS_DEC_PLACES$}"))
+                        .collect::<Vec<String>>()
+                        .join(" ")
+                })
+                .collect::<Vec<String>>()
+                .join(", "),
+        );
+        w.end_element();
+    }
+}
+
+/// The encoding of a trace value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TraceValueEncoding {
+    /// The value itself, prefixed with `!` or nothing.
+    Explicit,
+    /// The difference to the previous value, prefixed with `'`.
+    FirstDifference,
+    /// The difference to the previous first difference, prefixed with `"`.
+    SecondDifference,
+}
+
+/// Parse and decode the points of a trace.
+///
+/// The points are separated by commas. A prefix changes the encoding of the values of the channel,
+/// which is kept for the following points until it is changed again.
+fn parse_trace_points(text: &str) -> anyhow::Result<Vec<Vec<f64>>> {
+    let mut points = vec![];
+    let mut encodings: Vec<TraceValueEncoding> = vec![];
+    let mut prev_values: Vec<f64> = vec![];
+    let mut prev_differences: Vec<f64> = vec![];
+
+    for point_str in text.split(',') {
+        let tokens = tokenize_trace_point(point_str);
+        if tokens.is_empty() {
+            continue;
+        }
+        let mut point = Vec::with_capacity(tokens.len());
+
+        for (channel_i, (encoding, value_str)) in tokens.into_iter().enumerate() {
+            if channel_i >= encodings.len() {
+                encodings.push(TraceValueEncoding::Explicit);
+                prev_values.push(0.0);
+                prev_differences.push(0.0);
+            }
+            if let Some(encoding) = encoding {
+                encodings[channel_i] = encoding;
+            }
+
+            let parsed = value_str.parse::<f64>().map_err(|e| {
+                anyhow::anyhow!("failed to parse InkML trace value `{value_str}`, Err: {e:?}")
+            })?;
+            let value = match encodings[channel_i] {
+                TraceValueEncoding::Explicit => parsed,
+                TraceValueEncoding::FirstDifference => prev_values[channel_i] + parsed,
+                TraceValueEncoding::SecondDifference => {
+                    prev_values[channel_i] + prev_differences[channel_i] + parsed
+                }
+            };
+            prev_differences[channel_i] = value - prev_values[channel_i];
+            prev_values[channel_i] = value;
+            point.push(value);
+        }
+
+        points.push(point);
+    }
+
+    Ok(points)
+}
+
+/// Split a trace point into its values, together with the encoding prefix if there is one.
+///
+/// Values may be written without whitespace in between when they are separated by a prefix or a sign.
+fn tokenize_trace_point(point_str: &str) -> Vec<(Option<TraceValueEncoding>, String)> {
+    let mut tokens: Vec<(Option<TraceValueEncoding>, String)> = vec![];
+    let mut current: Option<(Option<TraceValueEncoding>, String)> = None;
+
+    for c in point_str.chars() {
+        let prefix = match c {
+            '!' => Some(TraceValueEncoding::Explicit),
+            '\'' => Some(TraceValueEncoding::FirstDifference),
+            '"' => Some(TraceValueEncoding::SecondDifference),
+            _ => None,
+        };
+
+        if let Some(prefix) = prefix {
+            tokens.extend(current.take().filter(|(_, s)| !s.is_empty()));
+            current = Some((Some(prefix), String::new()));
+        } else if c.is_whitespace() {
+            tokens.extend(current.take().filter(|(_, s)| !s.is_empty()));
+        } else {
+            // A sign that does not belong to an exponent starts a new value
+            let starts_new_value = (c == '-' || c == '+')
+                && current
+                    .as_ref()
+                    .map_or(false, |(_, s)| !s.is_empty() && !s.ends_with(['e', 'E']));
+            if starts_new_value {
+                tokens.extend(current.take());
+            }
+            current
+                .get_or_insert_with(|| (None, String::new()))
+                .1
+                .push(c);
+        }
+    }
+    tokens.extend(current.take().filter(|(_, s)| !s.is_empty()));
+
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trace_points_difference_encoding() {
+        let points = parse_trace_points("10 0, 9 14, 8 28, 7 42").unwrap();
+        assert_eq!(
+            points,
+            vec![
+                vec![10.0, 0.0],
+                vec![9.0, 14.0],
+                vec![8.0, 28.0],
+                vec![7.0, 42.0]
+            ]
+        );
+
+        let first_differences = parse_trace_points("10 0, '-1 '14, -1 14, -1 14").unwrap();
+        assert_eq!(first_differences, points);
+
+        let second_differences = parse_trace_points("10 0, '-1'14, \"0\"0, 0 0").unwrap();
+        assert_eq!(second_differences, points);
+    }
+
+    #[test]
+    fn inkml_roundtrip() {
+        let mut ink = InkmlInk {
+            channels: InkmlFile::export_channels(),
+            ..Default::default()
+        };
+        let brush = InkmlBrush {
+            color: InkmlColor {
+                red: 0x33,
+                green: 0x66,
+                blue: 0x99,
+                alpha: 0x80,
+            },
+            width: Some(0.5),
+            units: Some(String::from("mm")),
+            ..Default::default()
+        };
+        ink.push_trace(
+            InkmlTrace {
+                points: vec![vec![1.0, 2.0, 0.25, 0.0], vec![3.0, 4.0, 0.75, 10.0]],
+                ..Default::default()
+            },
+            brush.clone(),
+        );
+        ink.push_trace(
+            InkmlTrace {
+                points: vec![vec![5.0, 6.0, 1.0, 300.0], vec![7.0, 8.0, 0.5, 310.0]],
+                ..Default::default()
+            },
+            brush,
+        );
+        assert_eq!(ink.brushes.len(), 1);
+
+        let bytes = InkmlFile { ink: ink.clone() }.save_as_bytes("").unwrap();
+        let loaded = InkmlFile::load_from_bytes(&bytes).unwrap().ink;
+
+        assert_eq!(
+            loaded
+                .channels
+                .iter()
+                .map(|channel| channel.name.as_str())
+                .collect::<Vec<&str>>(),
+            vec!["X", "Y", "F", "T"]
+        );
+        assert_eq!(loaded.channel_index("F"), Some(2));
+        assert_eq!(loaded.brushes.len(), 1);
+        assert_eq!(loaded.brushes[0].color, ink.brushes[0].color);
+        assert_eq!(loaded.brushes[0].width, Some(0.5));
+        assert_eq!(loaded.traces.len(), 2);
+        for (trace, loaded_trace) in ink.traces.iter().zip(loaded.traces.iter()) {
+            assert_eq!(trace.brush_ref, loaded_trace.brush_ref);
+            assert_eq!(trace.points, loaded_trace.points);
+        }
+    }
+}
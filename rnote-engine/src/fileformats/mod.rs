@@ -1,4 +1,7 @@
 // Modules
+pub mod excalidrawformat;
+pub mod inkmlformat;
+pub mod pdfannotformat;
 pub mod rnoteformat;
 pub mod xoppformat;
 
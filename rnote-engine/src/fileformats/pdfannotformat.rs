@@ -0,0 +1,961 @@
+// Imports
+use super::{FileFormatLoader, FileFormatSaver};
+use anyhow::Context;
+use lopdf::{dictionary, Dictionary, Object, ObjectId, Stream, StringFormat};
+
+/// A Pdf document, loaded to read and write native annotations of its pages.
+///
+/// Annotation coordinates are in points in the coordinate space of the displayed page:
+/// the origin is in the upper left corner of the page as it is displayed, the y-axis is pointing downwards.
+/// They are converted from and to the user space of the page, depending on its crop box and rotation.
+#[derive(Debug, Clone)]
+pub struct PdfAnnotFile {
+    document: lopdf::Document,
+}
+
+impl FileFormatLoader for PdfAnnotFile {
+    fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let document = lopdf::Document::load_mem(bytes).context("loading pdf document failed")?;
+        if document.is_encrypted() {
+            return Err(anyhow::anyhow!(
+                "loading pdf document failed, encrypted documents are not supported"
+            ));
+        }
+
+        Ok(Self { document })
+    }
+}
+
+impl FileFormatSaver for PdfAnnotFile {
+    fn save_as_bytes(&self, _file_name: &str) -> anyhow::Result<Vec<u8>> {
+        let mut document = self.document.clone();
+        let mut bytes = vec![];
+        document
+            .save_to(&mut bytes)
+            .context("saving pdf document failed")?;
+
+        Ok(bytes)
+    }
+}
+
+impl PdfAnnotFile {
+    /// The opacity of the ink of highlight annotations.
+    pub const HIGHLIGHT_OPACITY: f64 = 0.5;
+    /// The maximum depth of the page tree that is searched for inherited page attributes.
+    const PAGE_TREE_DEPTH_MAX: usize = 32;
+
+    /// The number of pages.
+    pub fn n_pages(&self) -> u32 {
+        self.document.get_pages().len() as u32
+    }
+
+    /// The size of the page with the given index as it is displayed, in points.
+    pub fn page_size(&self, page_index: u32) -> anyhow::Result<na::Vector2<f64>> {
+        Ok(self.page_geometry(self.page_id(page_index)?)?.size())
+    }
+
+    /// Add an annotation to the page with the given index.
+    pub fn add_annotation(
+        &mut self,
+        page_index: u32,
+        annotation: &PdfAnnotation,
+    ) -> anyhow::Result<()> {
+        let page_id = self.page_id(page_index)?;
+        let geometry = self.page_geometry(page_id)?;
+
+        let annot_dict = match annotation {
+            PdfAnnotation::Ink(ink) => {
+                let appearance = ink.gen_appearance_stream(&geometry);
+                let appearance_id = self.document.add_object(appearance);
+                let mut annot_dict = ink.to_dict(&geometry);
+                annot_dict.set(
+                    "AP",
+                    dictionary! { "N" => Object::Reference(appearance_id) },
+                );
+                annot_dict
+            }
+            PdfAnnotation::Markup(markup) => markup.to_dict(&geometry),
+            PdfAnnotation::FreeText(free_text) => free_text.to_dict(&geometry),
+        };
+        let annot_id = self.document.add_object(annot_dict);
+
+        // The annotations array of the page can be a direct object or a reference
+        let annots = self
+            .document
+            .get_dictionary(page_id)?
+            .get(b"Annots")
+            .ok()
+            .cloned();
+        match annots {
+            Some(Object::Reference(annots_id)) => {
+                self.document
+                    .get_object_mut(annots_id)?
+                    .as_array_mut()?
+                    .push(Object::Reference(annot_id));
+            }
+            Some(Object::Array(mut annots)) => {
+                annots.push(Object::Reference(annot_id));
+                self.document
+                    .get_object_mut(page_id)?
+                    .as_dict_mut()?
+                    .set("Annots", annots);
+            }
+            _ => {
+                self.document
+                    .get_object_mut(page_id)?
+                    .as_dict_mut()?
+                    .set("Annots", vec![Object::Reference(annot_id)]);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The annotations of the page with the given index, in the order they are drawn.
+    ///
+    /// Hidden annotations and annotations of types that can't be represented are skipped.
+    pub fn annotations(&self, page_index: u32) -> anyhow::Result<Vec<PdfAnnotation>> {
+        let page_id = self.page_id(page_index)?;
+        let geometry = self.page_geometry(page_id)?;
+        let Ok(annots) = self.document.get_dictionary(page_id)?.get(b"Annots") else {
+            return Ok(vec![]);
+        };
+
+        Ok(self
+            .resolve(annots)?
+            .as_array()?
+            .iter()
+            .filter_map(|annot| {
+                let annot_dict = self.resolve(annot).ok()?.as_dict().ok()?;
+                match self.annotation_from_dict(annot_dict, &geometry) {
+                    Ok(annotation) => annotation,
+                    Err(e) => {
+                        log::error!("reading pdf annotation failed in annotations(), Err: {e:?}");
+                        None
+                    }
+                }
+            })
+            .collect())
+    }
+
+    /// The subtypes of the visible annotations of the page with the given index that can't be represented.
+    ///
+    /// Links and popups are not included, as they are not drawn.
+    pub fn unsupported_annotation_subtypes(&self, page_index: u32) -> anyhow::Result<Vec<String>> {
+        let page_id = self.page_id(page_index)?;
+        let geometry = self.page_geometry(page_id)?;
+        let Ok(annots) = self.document.get_dictionary(page_id)?.get(b"Annots") else {
+            return Ok(vec![]);
+        };
+
+        Ok(self
+            .resolve(annots)?
+            .as_array()?
+            .iter()
+            .filter_map(|annot| {
+                let annot_dict = self.resolve(annot).ok()?.as_dict().ok()?;
+                // Hidden flag
+                if self.dict_number(annot_dict, b"F").unwrap_or(0.0) as i64 & 2 != 0 {
+                    return None;
+                }
+                let subtype = annot_dict
+                    .get(b"Subtype")
+                    .and_then(|subtype| subtype.as_name_str())
+                    .unwrap_or("Unknown");
+                if matches!(subtype, "Link" | "Popup") {
+                    return None;
+                }
+                match self.annotation_from_dict(annot_dict, &geometry) {
+                    Ok(Some(_)) => None,
+                    Ok(None) | Err(_) => Some(subtype.to_string()),
+                }
+            })
+            .collect())
+    }
+
+    fn annotation_from_dict(
+        &self,
+        annot_dict: &Dictionary,
+        geometry: &PdfPageGeometry,
+    ) -> anyhow::Result<Option<PdfAnnotation>> {
+        // Hidden flag
+        if self.dict_number(annot_dict, b"F").unwrap_or(0.0) as i64 & 2 != 0 {
+            return Ok(None);
+        }
+        let opacity = self.dict_number(annot_dict, b"CA").unwrap_or(1.0);
+        let color = annot_dict
+            .get(b"C")
+            .ok()
+            .and_then(|color| self.numbers(color).ok())
+            .and_then(|components| PdfColor::from_components(&components, opacity));
+
+        let annotation = match annot_dict.get(b"Subtype")?.as_name_str()? {
+            "Ink" => {
+                let Some(color) = color else {
+                    return Ok(None);
+                };
+                let width = annot_dict
+                    .get(b"BS")
+                    .ok()
+                    .and_then(|border_style| self.resolve(border_style).ok()?.as_dict().ok())
+                    .and_then(|border_style| self.dict_number(border_style, b"W"))
+                    .or_else(|| {
+                        self.numbers(annot_dict.get(b"Border").ok()?)
+                            .ok()?
+                            .get(2)
+                            .copied()
+                    })
+                    .unwrap_or(1.0);
+                let ink_list = self
+                    .resolve(annot_dict.get(b"InkList")?)?
+                    .as_array()?
+                    .iter()
+                    .map(|path| {
+                        Ok(self
+                            .numbers(path)?
+                            .chunks_exact(2)
+                            .map(|pos| geometry.user_space_to_page(na::vector![pos[0], pos[1]]))
+                            .collect())
+                    })
+                    .collect::<anyhow::Result<Vec<Vec<na::Vector2<f64>>>>>()?;
+
+                PdfAnnotation::Ink(PdfInkAnnotation {
+                    ink_list,
+                    color,
+                    width,
+                    highlight: false,
+                })
+            }
+            subtype @ ("Highlight" | "Underline" | "Squiggly" | "StrikeOut") => {
+                let Some(color) = color else {
+                    return Ok(None);
+                };
+                let kind = match subtype {
+                    "Highlight" => PdfMarkupKind::Highlight,
+                    "StrikeOut" => PdfMarkupKind::StrikeOut,
+                    _ => PdfMarkupKind::Underline,
+                };
+                let quads = self
+                    .numbers(annot_dict.get(b"QuadPoints")?)?
+                    .chunks_exact(8)
+                    .map(|quad| {
+                        [0, 1, 2, 3].map(|i| {
+                            geometry.user_space_to_page(na::vector![quad[2 * i], quad[2 * i + 1]])
+                        })
+                    })
+                    .collect();
+
+                PdfAnnotation::Markup(PdfMarkupAnnotation { kind, quads, color })
+            }
+            "FreeText" => {
+                let rect = self.numbers(annot_dict.get(b"Rect")?)?;
+                if rect.len() != 4 {
+                    return Err(anyhow::anyhow!("free text annotation has an invalid rect"));
+                }
+                let corners = [
+                    geometry.user_space_to_page(na::vector![rect[0], rect[1]]),
+                    geometry.user_space_to_page(na::vector![rect[2], rect[3]]),
+                ];
+                let upper_left = corners[0].inf(&corners[1]);
+                let contents = annot_dict
+                    .get(b"Contents")
+                    .ok()
+                    .and_then(|contents| self.resolve(contents).ok()?.as_str().ok())
+                    .map(text_from_pdf_string)
+                    .unwrap_or_default();
+                let default_appearance = annot_dict
+                    .get(b"DA")
+                    .ok()
+                    .and_then(|da| self.resolve(da).ok()?.as_str().ok())
+                    .map(|da| String::from_utf8_lossy(da).to_string())
+                    .unwrap_or_default();
+                let (font_size, color) = parse_default_appearance(&default_appearance, opacity);
+
+                PdfAnnotation::FreeText(PdfFreeTextAnnotation {
+                    upper_left,
+                    size: corners[0].sup(&corners[1]) - upper_left,
+                    contents,
+                    font_size,
+                    color,
+                })
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(annotation))
+    }
+
+    /// The numbers of an array.
+    fn numbers(&self, array: &Object) -> anyhow::Result<Vec<f64>> {
+        self.resolve(array)?
+            .as_array()?
+            .iter()
+            .map(|value| Ok(self.resolve(value)?.as_float()? as f64))
+            .collect()
+    }
+
+    fn dict_number(&self, dict: &Dictionary, key: &[u8]) -> Option<f64> {
+        Some(self.resolve(dict.get(key).ok()?).ok()?.as_float().ok()? as f64)
+    }
+
+    fn page_id(&self, page_index: u32) -> anyhow::Result<ObjectId> {
+        // Page numbers start at one
+        self.document
+            .get_pages()
+            .get(&(page_index + 1))
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("pdf document has no page with index {page_index}"))
+    }
+
+    /// Resolve a reference to the object it points to.
+    fn resolve<'a>(&'a self, object: &'a Object) -> anyhow::Result<&'a Object> {
+        match object {
+            Object::Reference(id) => Ok(self.document.get_object(*id)?),
+            object => Ok(object),
+        }
+    }
+
+    /// Get an attribute of the page, which may be inherited from its ancestors in the page tree.
+    fn page_attribute(&self, page_id: ObjectId, key: &[u8]) -> Option<&Object> {
+        let mut dict = self.document.get_dictionary(page_id).ok()?;
+
+        for _ in 0..Self::PAGE_TREE_DEPTH_MAX {
+            if let Ok(object) = dict.get(key) {
+                return self.resolve(object).ok();
+            }
+            let parent_id = dict.get(b"Parent").ok()?.as_reference().ok()?;
+            dict = self.document.get_dictionary(parent_id).ok()?;
+        }
+
+        None
+    }
+
+    fn page_geometry(&self, page_id: ObjectId) -> anyhow::Result<PdfPageGeometry> {
+        // Pages are displayed with their crop box, which defaults to the media box
+        let page_box = self.numbers(
+            self.page_attribute(page_id, b"CropBox")
+                .or_else(|| self.page_attribute(page_id, b"MediaBox"))
+                .ok_or_else(|| anyhow::anyhow!("pdf page has no media box"))?,
+        )?;
+        if page_box.len() != 4 {
+            return Err(anyhow::anyhow!("pdf page has an invalid media box"));
+        }
+        let rotate = match self.page_attribute(page_id, b"Rotate") {
+            Some(rotate) => rotate.as_i64()?,
+            None => 0,
+        };
+
+        Ok(PdfPageGeometry {
+            mins: na::vector![page_box[0].min(page_box[2]), page_box[1].min(page_box[3])],
+            maxs: na::vector![page_box[0].max(page_box[2]), page_box[1].max(page_box[3])],
+            rotate: rotate.rem_euclid(360),
+        })
+    }
+}
+
+/// The box and rotation of a page, to convert between displayed page coordinates and the user space of the page.
+#[derive(Debug, Clone, Copy)]
+struct PdfPageGeometry {
+    mins: na::Vector2<f64>,
+    maxs: na::Vector2<f64>,
+    /// Clockwise rotation in degrees, a multiple of 90.
+    rotate: i64,
+}
+
+impl PdfPageGeometry {
+    /// The displayed size.
+    fn size(&self) -> na::Vector2<f64> {
+        let extents = self.maxs - self.mins;
+        match self.rotate {
+            90 | 270 => na::vector![extents[1], extents[0]],
+            _ => extents,
+        }
+    }
+
+    /// Convert displayed page coordinates into the user space of the page.
+    fn page_to_user_space(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let (mins, maxs) = (self.mins, self.maxs);
+        match self.rotate {
+            90 => na::vector![mins[0] + pos[1], mins[1] + pos[0]],
+            180 => na::vector![maxs[0] - pos[0], mins[1] + pos[1]],
+            270 => na::vector![maxs[0] - pos[1], maxs[1] - pos[0]],
+            _ => na::vector![mins[0] + pos[0], maxs[1] - pos[1]],
+        }
+    }
+
+    /// Convert the user space of the page into displayed page coordinates.
+    fn user_space_to_page(&self, pos: na::Vector2<f64>) -> na::Vector2<f64> {
+        let (mins, maxs) = (self.mins, self.maxs);
+        match self.rotate {
+            90 => na::vector![pos[1] - mins[1], pos[0] - mins[0]],
+            180 => na::vector![maxs[0] - pos[0], pos[1] - mins[1]],
+            270 => na::vector![maxs[1] - pos[1], maxs[0] - pos[0]],
+            _ => na::vector![pos[0] - mins[0], maxs[1] - pos[1]],
+        }
+    }
+
+    /// The rect in user space that contains the given displayed page coordinates, loosened by the margin.
+    fn user_space_rect(
+        &self,
+        positions: impl IntoIterator<Item = na::Vector2<f64>>,
+        margin: f64,
+    ) -> [f64; 4] {
+        let (mins, maxs) = positions
+            .into_iter()
+            .map(|pos| self.page_to_user_space(pos))
+            .fold(
+                (
+                    na::Vector2::repeat(f64::INFINITY),
+                    na::Vector2::repeat(f64::NEG_INFINITY),
+                ),
+                |(mins, maxs), pos| (mins.inf(&pos), maxs.sup(&pos)),
+            );
+
+        [
+            mins[0] - margin,
+            mins[1] - margin,
+            maxs[0] + margin,
+            maxs[1] + margin,
+        ]
+    }
+}
+
+/// A native Pdf annotation.
+#[derive(Debug, Clone)]
+pub enum PdfAnnotation {
+    /// A freehand `/Ink` annotation.
+    Ink(PdfInkAnnotation),
+    /// A text markup annotation, like `/Highlight` or `/Underline`.
+    Markup(PdfMarkupAnnotation),
+    /// A `/FreeText` annotation.
+    FreeText(PdfFreeTextAnnotation),
+}
+
+/// A freehand `/Ink` annotation.
+#[derive(Debug, Clone)]
+pub struct PdfInkAnnotation {
+    /// The paths of the ink, in displayed page coordinates.
+    pub ink_list: Vec<Vec<na::Vector2<f64>>>,
+    /// The color. The alpha is the opacity of the annotation.
+    pub color: PdfColor,
+    /// The line width in points.
+    pub width: f64,
+    /// Whether the ink highlights the page content below by multiplying its colors.
+    pub highlight: bool,
+}
+
+impl PdfInkAnnotation {
+    fn to_dict(&self, geometry: &PdfPageGeometry) -> Dictionary {
+        let ink_list = self
+            .ink_list
+            .iter()
+            .map(|path| {
+                Object::Array(
+                    path.iter()
+                        .flat_map(|&pos| {
+                            let pos = geometry.page_to_user_space(pos);
+                            [pdf_number(pos[0]), pdf_number(pos[1])]
+                        })
+                        .collect(),
+                )
+            })
+            .collect::<Vec<Object>>();
+
+        dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Ink",
+            "Rect" => pdf_rect(self.rect(geometry)),
+            "InkList" => ink_list,
+            "C" => self.color.to_rgb_array(),
+            "CA" => pdf_number(self.color.alpha),
+            "BS" => dictionary! { "W" => pdf_number(self.width) },
+            // Print flag
+            "F" => 4,
+        }
+    }
+
+    fn rect(&self, geometry: &PdfPageGeometry) -> [f64; 4] {
+        geometry.user_space_rect(self.ink_list.iter().flatten().copied(), self.width * 0.5)
+    }
+
+    /// The appearance stream draws the ink as round polylines.
+    fn gen_appearance_stream(&self, geometry: &PdfPageGeometry) -> Stream {
+        let mut ext_gstate = dictionary! {
+            "CA" => pdf_number(self.color.alpha),
+        };
+        if self.highlight {
+            ext_gstate.set("BM", "Multiply");
+        }
+
+        let mut content = format!(
+            "/GS0 gs\n{:.3} {:.3} {:.3} RG\n{:.3} w\n1 J\n1 j\n",
+            self.color.red, self.color.green, self.color.blue, self.width
+        );
+        for path in self.ink_list.iter() {
+            for (i, &pos) in path.iter().enumerate() {
+                let pos = geometry.page_to_user_space(pos);
+                let op = if i == 0 { "m" } else { "l" };
+                content.push_str(&format!("{:.3} {:.3} {op}\n", pos[0], pos[1]));
+            }
+            // Single points are drawn as dots by the round line caps
+            if path.len() == 1 {
+                let pos = geometry.page_to_user_space(path[0]);
+                content.push_str(&format!("{:.3} {:.3} l\n", pos[0], pos[1]));
+            }
+            content.push_str("S\n");
+        }
+
+        Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Form",
+                "BBox" => pdf_rect(self.rect(geometry)),
+                "Resources" => dictionary! {
+                    "ExtGState" => dictionary! { "GS0" => ext_gstate },
+                },
+            },
+            content.into_bytes(),
+        )
+    }
+}
+
+/// The kind of a text markup annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PdfMarkupKind {
+    /// `/Highlight`.
+    Highlight,
+    /// `/Underline`. `/Squiggly` annotations are read as underline.
+    Underline,
+    /// `/StrikeOut`.
+    StrikeOut,
+}
+
+/// A text markup annotation.
+#[derive(Debug, Clone)]
+pub struct PdfMarkupAnnotation {
+    /// The kind of markup.
+    pub kind: PdfMarkupKind,
+    /// The quadrilaterals of the marked up text, in displayed page coordinates.
+    ///
+    /// Their corners are in the order upper left, upper right, lower left, lower right, relative to the text.
+    pub quads: Vec<[na::Vector2<f64>; 4]>,
+    /// The color. The alpha is the opacity of the annotation.
+    pub color: PdfColor,
+}
+
+impl PdfMarkupAnnotation {
+    fn to_dict(&self, geometry: &PdfPageGeometry) -> Dictionary {
+        let subtype = match self.kind {
+            PdfMarkupKind::Highlight => "Highlight",
+            PdfMarkupKind::Underline => "Underline",
+            PdfMarkupKind::StrikeOut => "StrikeOut",
+        };
+        let quad_points = self
+            .quads
+            .iter()
+            .flatten()
+            .flat_map(|&pos| {
+                let pos = geometry.page_to_user_space(pos);
+                [pdf_number(pos[0]), pdf_number(pos[1])]
+            })
+            .collect::<Vec<Object>>();
+        let rect = geometry.user_space_rect(self.quads.iter().flatten().copied(), 0.0);
+
+        dictionary! {
+            "Type" => "Annot",
+            "Subtype" => subtype,
+            "Rect" => pdf_rect(rect),
+            "QuadPoints" => quad_points,
+            "C" => self.color.to_rgb_array(),
+            "CA" => pdf_number(self.color.alpha),
+            // Print flag
+            "F" => 4,
+        }
+    }
+}
+
+/// A `/FreeText` annotation.
+///
+/// Only the default appearance is written, the appearance is generated by the viewers.
+#[derive(Debug, Clone)]
+pub struct PdfFreeTextAnnotation {
+    /// The upper left corner, in displayed page coordinates.
+    pub upper_left: na::Vector2<f64>,
+    /// The size, in displayed page coordinates.
+    pub size: na::Vector2<f64>,
+    /// The text.
+    pub contents: String,
+    /// The font size in points.
+    pub font_size: f64,
+    /// The text color.
+    pub color: PdfColor,
+}
+
+impl PdfFreeTextAnnotation {
+    fn to_dict(&self, geometry: &PdfPageGeometry) -> Dictionary {
+        let rect = geometry.user_space_rect([self.upper_left, self.upper_left + self.size], 0.0);
+
+        dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "FreeText",
+            "Rect" => pdf_rect(rect),
+            "Contents" => pdf_text_string(&self.contents),
+            "DA" => Object::string_literal(format!(
+                "/Helv {:.1} Tf {:.3} {:.3} {:.3} rg",
+                self.font_size, self.color.red, self.color.green, self.color.blue
+            )),
+            "CA" => pdf_number(self.color.alpha),
+            // Print flag
+            "F" => 4,
+        }
+    }
+}
+
+/// A Pdf color with an opacity. Components are in the range [0.0, 1.0].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PdfColor {
+    /// Red, ranging [0.0, 1.0].
+    pub red: f64,
+    /// Green, ranging [0.0, 1.0].
+    pub green: f64,
+    /// Blue, ranging [0.0, 1.0].
+    pub blue: f64,
+    /// Alpha, ranging [0.0, 1.0].
+    pub alpha: f64,
+}
+
+impl PdfColor {
+    /// The color from the components of a gray, RGB or CMYK color.
+    ///
+    /// Returns None for no components, which means the color is transparent.
+    fn from_components(components: &[f64], alpha: f64) -> Option<Self> {
+        let (red, green, blue) = match *components {
+            [gray] => (gray, gray, gray),
+            [red, green, blue] => (red, green, blue),
+            [cyan, magenta, yellow, black] => (
+                (1.0 - cyan) * (1.0 - black),
+                (1.0 - magenta) * (1.0 - black),
+                (1.0 - yellow) * (1.0 - black),
+            ),
+            _ => return None,
+        };
+
+        Some(Self {
+            red,
+            green,
+            blue,
+            alpha,
+        })
+    }
+
+    fn to_rgb_array(self) -> Vec<Object> {
+        vec![
+            pdf_number(self.red),
+            pdf_number(self.green),
+            pdf_number(self.blue),
+        ]
+    }
+}
+
+fn pdf_number(value: f64) -> Object {
+    Object::Real(value as f32)
+}
+
+fn pdf_rect(rect: [f64; 4]) -> Vec<Object> {
+    rect.into_iter().map(pdf_number).collect()
+}
+
+/// Text strings are encoded as UTF-16BE with a byte order mark, unless they are Ascii.
+fn pdf_text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::string_literal(text);
+    }
+
+    Object::String(
+        [0xFE, 0xFF]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()))
+            .collect(),
+        StringFormat::Hexadecimal,
+    )
+}
+
+/// Decode a text string, which is either UTF-16BE or UTF-8 with a byte order mark, or PDFDocEncoding.
+///
+/// PDFDocEncoding is decoded as Latin-1, which it matches for most characters.
+fn text_from_pdf_string(bytes: &[u8]) -> String {
+    match bytes {
+        [0xFE, 0xFF, utf16 @ ..] => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|unit| u16::from_be_bytes([unit[0], unit[1]]))
+                .collect::<Vec<u16>>(),
+        ),
+        [0xEF, 0xBB, 0xBF, utf8 @ ..] => String::from_utf8_lossy(utf8).to_string(),
+        bytes => bytes.iter().map(|&byte| char::from(byte)).collect(),
+    }
+}
+
+/// Parse the font size and the text color from the default appearance string of a free text annotation.
+fn parse_default_appearance(default_appearance: &str, alpha: f64) -> (f64, PdfColor) {
+    const FONT_SIZE_DEFAULT: f64 = 12.0;
+    let mut font_size = FONT_SIZE_DEFAULT;
+    let mut color = PdfColor {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+        alpha,
+    };
+    let mut operands: Vec<f64> = vec![];
+
+    for token in default_appearance.split_whitespace() {
+        if let Ok(operand) = token.parse::<f64>() {
+            operands.push(operand);
+            continue;
+        }
+        match token {
+            "Tf" => {
+                // A font size of zero means auto sizing
+                if let Some(&size) = operands.last().filter(|&&size| size > 0.0) {
+                    font_size = size;
+                }
+            }
+            "g" | "rg" | "k" => {
+                let n_components = match token {
+                    "g" => 1,
+                    "rg" => 3,
+                    _ => 4,
+                };
+                if let Some(components) = operands
+                    .len()
+                    .checked_sub(n_components)
+                    .map(|start| &operands[start..])
+                {
+                    color = PdfColor::from_components(components, alpha).unwrap_or(color);
+                }
+            }
+            _ => {}
+        }
+        operands.clear();
+    }
+
+    (font_size, color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_pdf_bytes(media_box: [i64; 4], rotate: i64) -> Vec<u8> {
+        let mut document = lopdf::Document::with_version("1.5");
+        let pages_id = document.new_object_id();
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Rotate" => rotate,
+        });
+        document.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "MediaBox" => media_box.into_iter().map(Object::Integer).collect::<Vec<Object>>(),
+            }),
+        );
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let mut bytes = vec![];
+        document.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn page_geometry() {
+        let file = PdfAnnotFile::load_from_bytes(&gen_pdf_bytes([0, 0, 600, 800], 90)).unwrap();
+        assert_eq!(file.n_pages(), 1);
+        assert_eq!(file.page_size(0).unwrap(), na::vector![800.0, 600.0]);
+
+        let geometry = file.page_geometry(file.page_id(0).unwrap()).unwrap();
+        assert_eq!(
+            geometry.page_to_user_space(na::vector![0.0, 0.0]),
+            na::vector![0.0, 0.0]
+        );
+        assert_eq!(
+            geometry.page_to_user_space(na::vector![800.0, 600.0]),
+            na::vector![600.0, 800.0]
+        );
+        for rotate in [0, 90, 180, 270] {
+            let geometry = PdfPageGeometry { rotate, ..geometry };
+            let pos = na::vector![120.0, 30.0];
+            assert_eq!(
+                geometry.user_space_to_page(geometry.page_to_user_space(pos)),
+                pos
+            );
+        }
+    }
+
+    #[test]
+    fn default_appearance() {
+        let (font_size, color) = parse_default_appearance("/Helv 14 Tf 0 0 1 rg", 0.5);
+        assert_eq!(font_size, 14.0);
+        assert_eq!(
+            color,
+            PdfColor {
+                red: 0.0,
+                green: 0.0,
+                blue: 1.0,
+                alpha: 0.5
+            }
+        );
+        let (font_size, color) = parse_default_appearance("0.5 g /Helv 0 Tf", 1.0);
+        assert_eq!(font_size, 12.0);
+        assert_eq!(color.red, 0.5);
+    }
+
+    #[test]
+    fn add_annotations() {
+        let mut file = PdfAnnotFile::load_from_bytes(&gen_pdf_bytes([0, 0, 600, 800], 0)).unwrap();
+        let color = PdfColor {
+            red: 1.0,
+            green: 0.0,
+            blue: 0.0,
+            alpha: 1.0,
+        };
+        file.add_annotation(
+            0,
+            &PdfAnnotation::Ink(PdfInkAnnotation {
+                ink_list: vec![vec![na::vector![10.0, 10.0], na::vector![20.0, 30.0]]],
+                color,
+                width: 2.0,
+                highlight: false,
+            }),
+        )
+        .unwrap();
+        file.add_annotation(
+            0,
+            &PdfAnnotation::FreeText(PdfFreeTextAnnotation {
+                upper_left: na::vector![100.0, 100.0],
+                size: na::vector![50.0, 20.0],
+                contents: String::from("Annotation"),
+                font_size: 12.0,
+                color,
+            }),
+        )
+        .unwrap();
+        assert!(file
+            .add_annotation(
+                1,
+                &PdfAnnotation::Ink(PdfInkAnnotation {
+                    ink_list: vec![],
+                    color,
+                    width: 1.0,
+                    highlight: false,
+                })
+            )
+            .is_err());
+
+        let file =
+            PdfAnnotFile::load_from_bytes(&file.save_as_bytes("annotated.pdf").unwrap()).unwrap();
+        let page_id = file.page_id(0).unwrap();
+        let annots = file
+            .document
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Annots")
+            .unwrap()
+            .as_array()
+            .unwrap();
+        assert_eq!(annots.len(), 2);
+
+        let ink = file
+            .document
+            .get_dictionary(annots[0].as_reference().unwrap())
+            .unwrap();
+        assert_eq!(ink.get(b"Subtype").unwrap().as_name_str().unwrap(), "Ink");
+        let rect = ink
+            .get(b"Rect")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_float().unwrap())
+            .collect::<Vec<f32>>();
+        assert_eq!(rect, vec![9.0, 769.0, 21.0, 791.0]);
+
+        let annotations = file.annotations(0).unwrap();
+        assert_eq!(annotations.len(), 2);
+        let PdfAnnotation::Ink(ink) = &annotations[0] else {
+            panic!("first annotation is not an ink annotation");
+        };
+        assert_eq!(
+            ink.ink_list,
+            vec![vec![na::vector![10.0, 10.0], na::vector![20.0, 30.0]]]
+        );
+        assert_eq!(ink.color, color);
+        let PdfAnnotation::FreeText(free_text) = &annotations[1] else {
+            panic!("second annotation is not a free text annotation");
+        };
+        assert_eq!(free_text.contents, "Annotation");
+        assert_eq!(free_text.upper_left, na::vector![100.0, 100.0]);
+    }
+
+    #[test]
+    fn unsupported_annotation_subtypes() {
+        let mut file = PdfAnnotFile::load_from_bytes(&gen_pdf_bytes([0, 0, 600, 800], 0)).unwrap();
+        file.add_annotation(
+            0,
+            &PdfAnnotation::Ink(PdfInkAnnotation {
+                ink_list: vec![vec![na::vector![10.0, 10.0], na::vector![20.0, 30.0]]],
+                color: PdfColor {
+                    red: 1.0,
+                    green: 0.0,
+                    blue: 0.0,
+                    alpha: 1.0,
+                },
+                width: 2.0,
+                highlight: false,
+            }),
+        )
+        .unwrap();
+        let page_id = file.page_id(0).unwrap();
+        let mut annots = file
+            .document
+            .get_dictionary(page_id)
+            .unwrap()
+            .get(b"Annots")
+            .unwrap()
+            .as_array()
+            .unwrap()
+            .clone();
+        for subtype in ["Square", "Link", "Stamp"] {
+            annots.push(Object::Reference(file.document.add_object(dictionary! {
+                "Type" => "Annot",
+                "Subtype" => subtype,
+                "Rect" => vec![0.into(), 0.into(), 10.into(), 10.into()],
+            })));
+        }
+        // Hidden annotations are not drawn
+        annots.push(Object::Reference(file.document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => "Circle",
+            "F" => 2,
+        })));
+        file.document
+            .get_object_mut(page_id)
+            .unwrap()
+            .as_dict_mut()
+            .unwrap()
+            .set("Annots", annots);
+
+        assert_eq!(file.annotations(0).unwrap().len(), 1);
+        assert_eq!(
+            file.unsupported_annotation_subtypes(0).unwrap(),
+            vec![String::from("Square"), String::from("Stamp")]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+// Imports
+use super::maj0min6::RnoteFileMaj0Min6;
+use crate::render;
+use anyhow::Context;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RnoteFileMaj0Min8 {
+    /// A snapshot of the engine.
+    #[serde(rename = "engine_snapshot")]
+    pub engine_snapshot: ijson::IValue,
+}
+
+impl TryFrom<RnoteFileMaj0Min6> for RnoteFileMaj0Min8 {
+    type Error = anyhow::Error;
+
+    fn try_from(mut value: RnoteFileMaj0Min6) -> Result<Self, Self::Error> {
+        // Bitmap images were stored as raw pixel data, they are now stored in their encoded form.
+        // The raw pixel data is losslessly encoded as Png.
+        let stroke_components = value
+            .engine_snapshot
+            .as_object_mut()
+            .ok_or_else(|| anyhow::anyhow!("engine snapshot is not a JSON object."))?
+            .get_mut("stroke_components")
+            .and_then(|v| v.as_array_mut())
+            .ok_or_else(|| anyhow::anyhow!("engine snapshot has no array `stroke_components`."))?;
+
+        for slot in stroke_components.iter_mut() {
+            let Some(bitmapimage) = slot
+                .as_object_mut()
+                .and_then(|slot| slot.get_mut("value"))
+                .and_then(|stroke| stroke.as_object_mut())
+                .and_then(|stroke| stroke.get_mut("bitmapimage"))
+                .and_then(|bitmapimage| bitmapimage.as_object_mut())
+            else {
+                continue;
+            };
+            let Some(image) = bitmapimage.remove("image") else {
+                continue;
+            };
+
+            let png_bytes = ijson::from_value::<render::Image>(&image)
+                .context("deserializing bitmapimage image failed.")?
+                .into_encoded_bytes(image::ImageOutputFormat::Png)
+                .context("encoding bitmapimage image as Png failed.")?;
+            bitmapimage.insert(
+                "encoded_image",
+                base64::engine::general_purpose::STANDARD.encode(png_bytes),
+            );
+        }
+
+        Ok(Self {
+            engine_snapshot: value.engine_snapshot,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{BitmapImage, Stroke};
+    use rnote_compose::shapes::Rectangle;
+    use std::io::Cursor;
+
+    /// A photo-like test image with smooth gradients and some noise.
+    fn test_photo(width: u32, height: u32) -> image::DynamicImage {
+        let mut seed: u32 = 0x2545_f491;
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            let noise = (seed % 9) as u8;
+            image::Rgba([
+                ((x * 255) / width) as u8 / 2 + noise,
+                ((y * 255) / height) as u8 / 2 + noise,
+                (((x + y) * 255) / (width + height)) as u8 / 2 + noise,
+                255,
+            ])
+        }))
+    }
+
+    fn engine_snapshot_w_stroke(stroke: ijson::IValue) -> ijson::IValue {
+        let mut slot = ijson::IObject::new();
+        slot.insert("value", stroke);
+        slot.insert("version", 1);
+        let mut stroke_components = ijson::IArray::new();
+        stroke_components.push(slot);
+        let mut engine_snapshot = ijson::IObject::new();
+        engine_snapshot.insert("stroke_components", stroke_components);
+        engine_snapshot.into()
+    }
+
+    #[test]
+    fn bitmapimage_raw_pixels_converted_to_encoded() {
+        let photo = test_photo(320, 240);
+        let raw_image = render::Image::from(photo.clone());
+
+        let mut old_bitmapimage = ijson::IObject::new();
+        old_bitmapimage.insert("image", ijson::to_value(&raw_image).unwrap());
+        old_bitmapimage.insert("rectangle", ijson::to_value(Rectangle::default()).unwrap());
+        let mut old_stroke = ijson::IObject::new();
+        old_stroke.insert("bitmapimage", old_bitmapimage);
+
+        let converted = RnoteFileMaj0Min8::try_from(RnoteFileMaj0Min6 {
+            engine_snapshot: engine_snapshot_w_stroke(old_stroke.into()),
+        })
+        .unwrap();
+        let converted_stroke = converted
+            .engine_snapshot
+            .as_object()
+            .and_then(|engine_snapshot| engine_snapshot.get("stroke_components"))
+            .and_then(|stroke_components| stroke_components.as_array())
+            .and_then(|stroke_components| stroke_components.first())
+            .and_then(|slot| slot.as_object())
+            .and_then(|slot| slot.get("value"))
+            .unwrap();
+        let stroke = ijson::from_value::<Stroke>(converted_stroke).unwrap();
+        let Stroke::BitmapImage(bitmapimage) = stroke else {
+            panic!("converted stroke is not a bitmap image");
+        };
+
+        let decoded = bitmapimage.image().unwrap();
+        assert_eq!(decoded.pixel_width, raw_image.pixel_width);
+        assert_eq!(decoded.pixel_height, raw_image.pixel_height);
+        assert_eq!(&decoded.data[..], &raw_image.data[..]);
+    }
+
+    #[test]
+    fn bitmapimage_encoded_smaller_than_raw_pixels() {
+        let photo = test_photo(640, 480);
+        let mut jpeg_bytes = Cursor::new(Vec::new());
+        photo
+            .write_to(&mut jpeg_bytes, image::ImageOutputFormat::Jpeg(90))
+            .unwrap();
+        let jpeg_bytes = jpeg_bytes.into_inner();
+
+        let bitmapimage = BitmapImage::new(&jpeg_bytes, Rectangle::default()).unwrap();
+        assert_eq!(&bitmapimage.encoded_image()[..], &jpeg_bytes[..]);
+
+        let raw_image = render::Image::from(photo);
+        let mut old_bitmapimage = ijson::IObject::new();
+        old_bitmapimage.insert("image", ijson::to_value(&raw_image).unwrap());
+        old_bitmapimage.insert("rectangle", ijson::to_value(Rectangle::default()).unwrap());
+        let mut old_stroke = ijson::IObject::new();
+        old_stroke.insert("bitmapimage", old_bitmapimage);
+
+        let old_size = serde_json::to_vec(&old_stroke).unwrap().len();
+        let new_size = serde_json::to_vec(&Stroke::BitmapImage(bitmapimage))
+            .unwrap()
+            .len();
+        assert!(
+            new_size * 4 < old_size,
+            "encoded size {new_size} is not considerably smaller than the raw size {old_size}"
+        );
+    }
+}
@@ -24,8 +24,13 @@ fn decompress_from_gzip(compressed: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
     Ok(bytes)
 }
 
+/// The magic bytes at the start of gzip compressed data.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
 /// Represents a Xournal++ `.xopp` file.
 ///
+/// The legacy Xournal `.xoj` files share the format and are loaded through it as well.
+///
 /// The coordinates units saved to a .xopp are in 72dpi, meaning a vector of (1,0) has a length of 1 / 72 inch.
 ///
 /// The original Xournal spec can be found here: <http://xournal.sourceforge.net/manual.html#file-format>
@@ -37,7 +42,12 @@ pub struct XoppFile {
 
 impl FileFormatLoader for XoppFile {
     fn load_from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
-        let decompressed = String::from_utf8(decompress_from_gzip(bytes)?)?;
+        // Xournal also reads uncompressed .xoj files, so they can be found in the wild
+        let decompressed = if bytes.starts_with(&GZIP_MAGIC) {
+            String::from_utf8(decompress_from_gzip(bytes)?)?
+        } else {
+            String::from_utf8(bytes.to_vec())?
+        };
         let parsed_doc = roxmltree::Document::parse_with_options(
             decompressed.as_str(),
             roxmltree::ParsingOptions::default(),
@@ -68,6 +78,10 @@ impl XoppFile {
 pub struct XoppRoot {
     /// The file version.
     pub fileversion: String,
+    /// The version of Xournal that created a legacy `.xoj` file.
+    ///
+    /// Xournal++ files have a `fileversion` instead.
+    pub xournal_version: Option<String>,
     /// The file title.
     pub title: String,
     /// A preview image, encoded as base64.
@@ -81,6 +95,9 @@ impl XmlLoadable for XoppRoot {
         if let Some(fileversion) = root_node.attribute("fileversion") {
             self.fileversion = fileversion.to_string();
         }
+        self.xournal_version = root_node
+            .attribute("version")
+            .map(|version| version.to_string());
 
         for child in root_node.children() {
             match child.node_type() {
@@ -108,10 +125,79 @@ impl XmlLoadable for XoppRoot {
                 _ => {}
             }
         }
+
+        self.resolve_cloned_backgrounds();
         Ok(())
     }
 }
 
+impl XoppRoot {
+    /// Whether the file is a legacy Xournal `.xoj` file.
+    pub fn is_legacy_xoj(&self) -> bool {
+        self.xournal_version.is_some() && self.fileversion.is_empty()
+    }
+
+    /// Replace the pixmap backgrounds with the `clone` domain by the background of the page they refer to.
+    ///
+    /// For these backgrounds the filename is the index of an earlier page, which Xournal uses to not store the same image multiple times.
+    fn resolve_cloned_backgrounds(&mut self) {
+        for page_i in 0..self.pages.len() {
+            let XoppBackgroundType::Pixmap {
+                domain: XoppBackgroundPixmapDomain::Clone,
+                filename,
+            } = &self.pages[page_i].background.bg_type else {
+                continue;
+            };
+
+            let Some(cloned_i) = filename
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .filter(|&cloned_i| cloned_i < page_i) else {
+                log::warn!("the cloned background `{filename}` of page {page_i} does not refer to an earlier page");
+                continue;
+            };
+            self.pages[page_i].background.bg_type =
+                self.pages[cloned_i].background.bg_type.clone();
+        }
+    }
+
+    /// Whether the layers of every page are mapped onto user layers on import, by their position.
+    ///
+    /// Rnote draws images below all other content, so it exports them into an unnamed first layer of the page
+    /// that is not mapped. Newer Rnote versions only add it when the page has images and name all user layers,
+    /// older versions export every page with an (often empty) image layer followed by a single unnamed layer.
+    pub fn user_layers_masks(&self) -> Vec<Vec<bool>> {
+        let is_rnote_image_layer = |layer: &XoppLayer| {
+            layer.name.is_none() && (layer.is_image_layer() || layer.is_empty())
+        };
+        let legacy_rnote_export = !self.pages.is_empty()
+            && self.pages.iter().all(|page| {
+                matches!(&page.layers[..], [image_layer, strokes_layer]
+                    if is_rnote_image_layer(image_layer) && strokes_layer.name.is_none())
+            });
+
+        self.pages
+            .iter()
+            .map(|page| {
+                let skip_first = match page.layers.split_first() {
+                    Some((first, _)) if legacy_rnote_export => is_rnote_image_layer(first),
+                    Some((first, others)) => {
+                        first.name.is_none()
+                            && first.is_image_layer()
+                            && !others.is_empty()
+                            && others.iter().all(|layer| layer.name.is_some())
+                    }
+                    None => false,
+                };
+                (0..page.layers.len())
+                    .map(|layer_i| !(skip_first && layer_i == 0))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
 impl XmlWritable for XoppRoot {
     fn write_to_xml(&self, w: &mut xmlwriter::XmlWriter) {
         w.set_preserve_whitespaces(true);
@@ -216,8 +302,18 @@ pub enum XoppBackgroundType {
         /// The filename that is to the image for the pixmap.
         filename: String,
     },
-    /// A background with a pdf. Currently **UNIMPLEMENTED**.
-    Pdf,
+    /// A background with a page of a pdf.
+    ///
+    /// The domain and filename are only present on the first page with a pdf background,
+    /// the following pages refer to the same pdf.
+    Pdf {
+        /// The domain for the pdf.
+        domain: Option<XoppBackgroundPixmapDomain>,
+        /// The filename of the pdf.
+        filename: Option<String>,
+        /// The page number of the pdf, starting from 1.
+        pageno: u32,
+    },
 }
 
 impl XmlWritable for XoppBackgroundType {
@@ -233,8 +329,19 @@ impl XmlWritable for XoppBackgroundType {
                 w.write_attribute("domain", &domain.as_xml_attr_value());
                 w.write_attribute("filename", filename);
             }
-            Self::Pdf => {
+            Self::Pdf {
+                domain,
+                filename,
+                pageno,
+            } => {
                 w.write_attribute("type", "pdf");
+                if let Some(domain) = domain {
+                    w.write_attribute("domain", &domain.as_xml_attr_value());
+                }
+                if let Some(filename) = filename {
+                    w.write_attribute("filename", filename);
+                }
+                w.write_attribute("pageno", &pageno.to_string());
             }
         }
     }
@@ -339,6 +446,23 @@ impl AsXmlAttributeValue for XoppBackgroundPixmapDomain {
     }
 }
 
+impl FromXmlAttributeValue for XoppBackgroundPixmapDomain {
+    fn from_xml_attr_value(s: &str) -> Result<Self, anyhow::Error>
+    where
+        Self: Sized,
+    {
+        match s {
+            "absolute" => Ok(Self::Absolute),
+            "attach" => Ok(Self::Attach),
+            "clone" => Ok(Self::Clone),
+            o => Err(anyhow::anyhow!(
+                "Err while parsing `domain` attribute of XoppBackground, {:?} is not a valid value",
+                o
+            )),
+        }
+    }
+}
+
 impl Default for XoppBackgroundPixmapDomain {
     fn default() -> Self {
         Self::Absolute
@@ -386,16 +510,9 @@ impl XmlLoadable for XoppBackground {
                 self.bg_type = XoppBackgroundType::Solid { color, style };
             }
             "pixmap" => {
-                let domain = match node.attribute("domain").ok_or_else(|| {
+                let domain = XoppBackgroundPixmapDomain::from_xml_attr_value(node.attribute("domain").ok_or_else(|| {
                     anyhow::anyhow!("failed to parse `domain` attribute in XoppBackground with node id {:?}, could not find attribute", node.id())
-                })? {
-                    "absolute" => XoppBackgroundPixmapDomain::Absolute,
-                    "attach" => XoppBackgroundPixmapDomain::Attach,
-                    "clone" => XoppBackgroundPixmapDomain::Clone,
-                    _ => {
-                        return Err(anyhow::anyhow!("Err while parsing `style` attribute of XoppBackground with node id {:?}, is not a valid value", node.id()));
-                    }
-                };
+                })?)?;
                 let filename = node
                     .attribute("filename")
                     .ok_or_else(|| {
@@ -405,7 +522,24 @@ impl XmlLoadable for XoppBackground {
                 self.bg_type = XoppBackgroundType::Pixmap { domain, filename };
             }
             "pdf" => {
-                self.bg_type = XoppBackgroundType::Pdf;
+                let domain = node
+                    .attribute("domain")
+                    .map(XoppBackgroundPixmapDomain::from_xml_attr_value)
+                    .transpose()?;
+                let filename = node.attribute("filename").map(|filename| filename.to_string());
+                // Xournal++ writes the page number with a trailing `ll`
+                let pageno = node
+                    .attribute("pageno")
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("failed to parse `pageno` attribute in XoppBackground with node id {:?}, could not find attribute", node.id())
+                    })?
+                    .trim_end_matches(|c: char| !c.is_ascii_digit())
+                    .parse::<u32>()?;
+                self.bg_type = XoppBackgroundType::Pdf {
+                    domain,
+                    filename,
+                    pageno,
+                };
             }
             _ => {
                 return Err(anyhow::anyhow!("Err while parsing `type` attribute of XoppBackground with node id {:?}, is not a valid value", node.id()));
@@ -440,6 +574,20 @@ pub struct XoppLayer {
     pub images: Vec<XoppImage>,
 }
 
+impl XoppLayer {
+    /// Whether the layer only holds images.
+    ///
+    /// Rnote draws images below all other content, so they are exported into a separate layer below the user layers.
+    pub fn is_image_layer(&self) -> bool {
+        !self.images.is_empty() && self.strokes.is_empty() && self.texts.is_empty()
+    }
+
+    /// Whether the layer holds no content at all.
+    pub fn is_empty(&self) -> bool {
+        self.images.is_empty() && self.strokes.is_empty() && self.texts.is_empty()
+    }
+}
+
 impl XmlLoadable for XoppLayer {
     fn load_from_xml(&mut self, node: Node) -> anyhow::Result<()> {
         self.name = node.attribute("name").map(|name| name.to_string());
@@ -1009,3 +1157,203 @@ impl XmlWritable for XoppImage {
         w.set_preserve_whitespaces(false);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XOJ_FIXTURE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../misc/file-tests/xournal-0-4-8-test.xoj"
+    ));
+    const XOJ_UNCOMPRESSED_FIXTURE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../misc/file-tests/xournal-0-4-8-uncompressed-test.xoj"
+    ));
+    const RNOTE_EXPORT_FIXTURE: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../misc/file-tests/rnote-0-7-export-test.xopp"
+    ));
+
+    #[test]
+    fn load_legacy_xoj() {
+        let xopp_root = XoppFile::load_from_bytes(XOJ_FIXTURE).unwrap().xopp_root;
+
+        assert!(xopp_root.is_legacy_xoj());
+        assert_eq!(xopp_root.xournal_version.as_deref(), Some("0.4.8"));
+        assert_eq!(xopp_root.pages.len(), 3);
+
+        let first_page = &xopp_root.pages[0];
+        assert_eq!(first_page.width, 612.0);
+        assert_eq!(first_page.height, 792.0);
+        assert!(matches!(
+            first_page.background.bg_type,
+            XoppBackgroundType::Solid {
+                style: XoppBackgroundSolidStyle::Lined,
+                ..
+            }
+        ));
+        assert_eq!(first_page.layers.len(), 1);
+
+        let layer = &first_page.layers[0];
+        assert_eq!(layer.strokes.len(), 3);
+        assert_eq!(layer.strokes[0].width, vec![1.41]);
+        assert_eq!(layer.strokes[0].coords.len(), 3);
+        assert_eq!(layer.strokes[0].audio_filename, None);
+        // Xournal stores the pressure widths per segment
+        assert_eq!(layer.strokes[1].width, vec![1.41, 1.2, 1.6, 1.8]);
+        assert_eq!(layer.strokes[1].coords.len(), 4);
+        assert!(matches!(layer.strokes[2].tool, XoppTool::Highlighter));
+        assert_eq!(layer.texts.len(), 1);
+        assert_eq!(layer.texts[0].text, "Lecture 1");
+        assert_eq!(layer.images.len(), 1);
+        assert!(!layer.images[0].data.is_empty());
+    }
+
+    #[test]
+    fn load_legacy_xoj_uncompressed() {
+        let compressed = XoppFile::load_from_bytes(XOJ_FIXTURE).unwrap().xopp_root;
+        let uncompressed = XoppFile::load_from_bytes(XOJ_UNCOMPRESSED_FIXTURE)
+            .unwrap()
+            .xopp_root;
+
+        assert_eq!(
+            serde_json::to_value(compressed).unwrap(),
+            serde_json::to_value(uncompressed).unwrap()
+        );
+    }
+
+    #[test]
+    fn load_legacy_xoj_cloned_background() {
+        let xopp_root = XoppFile::load_from_bytes(XOJ_FIXTURE).unwrap().xopp_root;
+
+        let XoppBackgroundType::Pixmap { domain, filename } = &xopp_root.pages[2].background.bg_type else {
+            panic!("the cloned background of the third page is not a pixmap background");
+        };
+        assert!(matches!(domain, XoppBackgroundPixmapDomain::Absolute));
+        assert_eq!(filename, "/tmp/lecture-background.png");
+    }
+
+    #[test]
+    fn user_layers_masks_skip_rnote_image_layers() {
+        let xopp_root = XoppFile::load_from_bytes(RNOTE_EXPORT_FIXTURE)
+            .unwrap()
+            .xopp_root;
+
+        assert_eq!(xopp_root.pages.len(), 2);
+        // The image layers exported by older Rnote versions, empty on the first page
+        assert!(xopp_root.pages[0].layers[0].is_empty());
+        assert!(xopp_root.pages[1].layers[0].is_image_layer());
+        assert_eq!(
+            xopp_root.user_layers_masks(),
+            vec![vec![false, true], vec![false, true]]
+        );
+
+        let image_layer = xopp_root.pages[1].layers[0].clone();
+        let strokes_layer = xopp_root.pages[1].layers[1].clone();
+        let named = |layer: &XoppLayer, name: &str| XoppLayer {
+            name: Some(name.to_string()),
+            ..layer.clone()
+        };
+        let page = |layers| XoppPage {
+            layers,
+            ..xopp_root.pages[0].clone()
+        };
+
+        // Newer Rnote versions only export an image layer for pages with images
+        let rnote_export = XoppRoot {
+            pages: vec![
+                page(vec![
+                    image_layer.clone(),
+                    named(&XoppLayer::default(), "Layer 1"),
+                    named(&strokes_layer, "Layer 2"),
+                ]),
+                page(vec![
+                    named(&XoppLayer::default(), "Layer 1"),
+                    named(&strokes_layer, "Layer 2"),
+                ]),
+            ],
+            ..xopp_root.clone()
+        };
+        assert_eq!(
+            rnote_export.user_layers_masks(),
+            vec![vec![false, true, true], vec![true, true]]
+        );
+
+        // Empty unnamed layers are kept when not all pages look like an older Rnote export
+        let unnamed_layers = XoppRoot {
+            pages: vec![
+                page(vec![strokes_layer.clone(), strokes_layer.clone()]),
+                page(vec![XoppLayer::default(), strokes_layer.clone()]),
+                page(vec![image_layer, XoppLayer::default(), strokes_layer]),
+            ],
+            ..xopp_root
+        };
+        assert_eq!(
+            unnamed_layers.user_layers_masks(),
+            vec![vec![true, true], vec![true, true], vec![true, true, true]]
+        );
+    }
+
+    #[test]
+    fn save_load_pdf_backgrounds() {
+        let pdf_page = |domain, filename: Option<&str>, pageno| XoppPage {
+            width: 612.0,
+            height: 792.0,
+            background: XoppBackground {
+                name: None,
+                bg_type: XoppBackgroundType::Pdf {
+                    domain,
+                    filename: filename.map(String::from),
+                    pageno,
+                },
+            },
+            layers: vec![XoppLayer::default()],
+        };
+        let xopp_file = XoppFile {
+            xopp_root: XoppRoot {
+                fileversion: String::from("4"),
+                title: String::from(
+                    "Xournal++ document - see https://github.com/xournalpp/xournalpp",
+                ),
+                pages: vec![
+                    pdf_page(
+                        Some(XoppBackgroundPixmapDomain::Absolute),
+                        Some("/tmp/lecture.pdf"),
+                        1,
+                    ),
+                    // only the first pdf background names the file
+                    pdf_page(None, None, 2),
+                ],
+                ..Default::default()
+            },
+        };
+
+        let loaded = XoppFile::load_from_bytes(&xopp_file.save_as_bytes("").unwrap())
+            .unwrap()
+            .xopp_root;
+
+        assert_eq!(loaded.pages.len(), 2);
+        let XoppBackgroundType::Pdf {
+            domain,
+            filename,
+            pageno,
+        } = &loaded.pages[0].background.bg_type else {
+            panic!("the background of the first page is not a pdf background");
+        };
+        assert!(matches!(domain, Some(XoppBackgroundPixmapDomain::Absolute)));
+        assert_eq!(filename.as_deref(), Some("/tmp/lecture.pdf"));
+        assert_eq!(*pageno, 1);
+
+        let XoppBackgroundType::Pdf {
+            domain,
+            filename,
+            pageno,
+        } = &loaded.pages[1].background.bg_type else {
+            panic!("the background of the second page is not a pdf background");
+        };
+        assert!(domain.is_none());
+        assert!(filename.is_none());
+        assert_eq!(*pageno, 2);
+    }
+}
@@ -7,6 +7,9 @@
 //! The rnote-engine crate is the core of Rnote. It holds the strokes store, the pens, has methods for importing / exporting, rendering, etc.. .
 //!
 //! The main entry point is the [RnoteEngine] struct.
+//!
+//! The default `gtk` feature enables drawing the engine to GTK snapshots.
+//! Without it the engine is headless and renders through piet-cairo only, which is sufficient for loading, editing and exporting documents.
 
 // Modules
 pub mod audioplayer;
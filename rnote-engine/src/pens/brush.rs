@@ -1,11 +1,12 @@
 // Imports
 use super::penbehaviour::{PenBehaviour, PenProgress};
-use super::pensconfig::brushconfig::BrushStyle;
+use super::pensconfig::brushconfig::{BrushConfig, BrushStyle};
 use super::PenStyle;
-use crate::engine::{EngineView, EngineViewMut};
-use crate::store::StrokeKey;
+use crate::engine::{EngineTask, EngineTaskSender, EngineView, EngineViewMut};
+use crate::store::{HistoryLabel, StrokeKey};
 use crate::strokes::BrushStroke;
 use crate::strokes::Stroke;
+use crate::tasks::{OneOffTaskError, OneOffTaskHandle};
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
@@ -17,7 +18,7 @@ use rnote_compose::builders::{PenPathCurvedBuilder, PenPathSimpleBuilder};
 use rnote_compose::penevents::PenEvent;
 use rnote_compose::penpath::Element;
 use rnote_compose::Constraints;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 enum BrushState {
@@ -25,7 +26,17 @@ enum BrushState {
     Drawing {
         path_builder: Box<dyn PenPathBuilderBehaviour>,
         current_stroke_key: StrokeKey,
+        /// The position where the pen started to hold still and the time when it did.
+        hold_still_start: (na::Vector2<f64>, Instant),
+        /// Triggers the shape recognition when the pen is held still, without needing further pen events.
+        ///
+        /// None when shape recognition is disabled.
+        hold_still_task_handle: Option<OneOffTaskHandle>,
     },
+    /// The shape of the stroke was recognized while the pen was held still.
+    ///
+    /// The stroke is finished, further pen events are ignored until the pen is lifted.
+    ShapeRecognized,
 }
 
 #[derive(Debug)]
@@ -86,15 +97,12 @@ impl PenBehaviour for Brush {
                             .brush_config
                             .style_for_current_options(),
                     ));
-                    let current_stroke_key = engine_view.store.insert_stroke(
-                        brushstroke,
-                        Some(
-                            engine_view
-                                .pens_config
-                                .brush_config
-                                .layer_for_current_options(),
-                        ),
-                    );
+                    let layer = engine_view
+                        .pens_config
+                        .brush_config
+                        .layer_for_current_options(engine_view.store.active_layer());
+                    let current_stroke_key =
+                        engine_view.store.insert_stroke(brushstroke, Some(layer));
 
                     engine_view.store.regenerate_rendering_for_stroke(
                         current_stroke_key,
@@ -102,6 +110,12 @@ impl PenBehaviour for Brush {
                         engine_view.camera.image_scale(),
                     );
 
+                    let hold_still_task_handle = engine_view
+                        .pens_config
+                        .brush_config
+                        .shape_recognition
+                        .then(|| new_hold_still_task(engine_view.tasks_tx.clone()));
+
                     self.state = BrushState::Drawing {
                         path_builder: new_builder(
                             engine_view.pens_config.brush_config.builder_type,
@@ -109,6 +123,8 @@ impl PenBehaviour for Brush {
                             now,
                         ),
                         current_stroke_key,
+                        hold_still_start: (element.pos, now),
+                        hold_still_task_handle,
                     };
 
                     PenProgress::InProgress
@@ -117,42 +133,54 @@ impl PenBehaviour for Brush {
                 }
             }
             (BrushState::Idle, _) => PenProgress::Idle,
+            (BrushState::ShapeRecognized, PenEvent::Up { .. } | PenEvent::Cancel) => {
+                self.state = BrushState::Idle;
+
+                PenProgress::Finished
+            }
+            (BrushState::ShapeRecognized, _) => PenProgress::InProgress,
             (
                 BrushState::Drawing {
                     current_stroke_key, ..
                 },
                 PenEvent::Cancel,
             ) => {
-                // Finish up the last stroke
-                engine_view
-                    .store
-                    .update_geometry_for_stroke(*current_stroke_key);
-                engine_view.store.regenerate_rendering_for_stroke_threaded(
-                    engine_view.tasks_tx.clone(),
-                    *current_stroke_key,
-                    engine_view.camera.viewport(),
-                    engine_view.camera.image_scale(),
-                );
-                widget_flags.merge(
-                    engine_view
-                        .doc
-                        .resize_autoexpand(engine_view.store, engine_view.camera),
-                );
+                widget_flags.merge(finish_stroke(*current_stroke_key, engine_view));
 
                 self.state = BrushState::Idle;
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
-                widget_flags.store_modified = true;
-
                 PenProgress::Finished
             }
             (
                 BrushState::Drawing {
                     path_builder,
                     current_stroke_key,
+                    hold_still_start,
+                    hold_still_task_handle,
                 },
                 pen_event,
             ) => {
+                if let PenEvent::Down { element, .. } | PenEvent::Up { element, .. } = &pen_event {
+                    if (element.pos - hold_still_start.0).magnitude()
+                        > Self::HOLD_STILL_RADIUS / engine_view.camera.total_zoom()
+                    {
+                        *hold_still_start = (element.pos, now);
+
+                        if let Some(handle) = hold_still_task_handle {
+                            match handle.reset_timeout() {
+                                Ok(()) => {}
+                                Err(OneOffTaskError::TimeoutReached) => {
+                                    *handle = new_hold_still_task(engine_view.tasks_tx.clone());
+                                }
+                                Err(e) => {
+                                    log::error!("Could not reset the timeout of the hold still task, Err: {e:?}");
+                                    *handle = new_hold_still_task(engine_view.tasks_tx.clone());
+                                }
+                            }
+                        }
+                    }
+                }
+
                 match path_builder.handle_event(pen_event, now, Constraints::default()) {
                     PenPathBuilderProgress::InProgress => {
                         if engine_view.pens_config.brush_config.style != BrushStyle::Marker {
@@ -207,26 +235,18 @@ impl PenBehaviour for Brush {
                             );
                         }
 
-                        // Finish up the last stroke
-                        engine_view
-                            .store
-                            .update_geometry_for_stroke(*current_stroke_key);
-                        engine_view.store.regenerate_rendering_for_stroke_threaded(
-                            engine_view.tasks_tx.clone(),
-                            *current_stroke_key,
-                            engine_view.camera.viewport(),
-                            engine_view.camera.image_scale(),
-                        );
-                        widget_flags.merge(
-                            engine_view
-                                .doc
-                                .resize_autoexpand(engine_view.store, engine_view.camera),
-                        );
+                        if engine_view.pens_config.brush_config.shape_recognition
+                            && now.duration_since(hold_still_start.1) >= Self::HOLD_STILL_DURATION
+                        {
+                            widget_flags.merge(engine_view.store.recognize_shapes(
+                                &[*current_stroke_key],
+                                BrushConfig::SHAPE_RECOGNITION_TOLERANCE,
+                            ));
+                        }
 
-                        self.state = BrushState::Idle;
+                        widget_flags.merge(finish_stroke(*current_stroke_key, engine_view));
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
-                        widget_flags.store_modified = true;
+                        self.state = BrushState::Idle;
 
                         PenProgress::Finished
                     }
@@ -246,7 +266,7 @@ impl DrawOnDocBehaviour for Brush {
             .style_for_current_options();
 
         match &self.state {
-            BrushState::Idle => None,
+            BrushState::Idle | BrushState::ShapeRecognized => None,
             BrushState::Drawing { path_builder, .. } => {
                 path_builder.bounds(&style, engine_view.camera.zoom())
             }
@@ -261,7 +281,7 @@ impl DrawOnDocBehaviour for Brush {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
         match &self.state {
-            BrushState::Idle => {}
+            BrushState::Idle | BrushState::ShapeRecognized => {}
             BrushState::Drawing { path_builder, .. } => {
                 match engine_view.pens_config.brush_config.style {
                     BrushStyle::Marker => {
@@ -285,6 +305,81 @@ impl DrawOnDocBehaviour for Brush {
 
 impl Brush {
     const INPUT_OVERSHOOT: f64 = 30.0;
+    /// The radius (in surface coordinates) the pen may move within while holding still.
+    const HOLD_STILL_RADIUS: f64 = 4.0;
+    /// The duration the pen needs to hold still at the end of the stroke to trigger shape recognition.
+    const HOLD_STILL_DURATION: Duration = Duration::from_millis(600);
+
+    /// Handle the timeout of the hold still task.
+    ///
+    /// Recognizes the shape of the current stroke while the pen is still down, when it was held still long enough.
+    pub fn handle_hold_still_timeout(
+        &mut self,
+        now: Instant,
+        engine_view: &mut EngineViewMut,
+    ) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let BrushState::Drawing {
+            current_stroke_key,
+            hold_still_start,
+            ..
+        } = &self.state else {
+            return widget_flags;
+        };
+        // The pen might have moved since the task was triggered
+        if !engine_view.pens_config.brush_config.shape_recognition
+            || now.saturating_duration_since(hold_still_start.1) < Self::HOLD_STILL_DURATION
+        {
+            return widget_flags;
+        }
+        let current_stroke_key = *current_stroke_key;
+
+        widget_flags.merge(engine_view.store.recognize_shapes(
+            &[current_stroke_key],
+            BrushConfig::SHAPE_RECOGNITION_TOLERANCE,
+        ));
+        widget_flags.merge(finish_stroke(current_stroke_key, engine_view));
+        widget_flags.redraw = true;
+
+        self.state = BrushState::ShapeRecognized;
+
+        widget_flags
+    }
+}
+
+/// Finish up the stroke when drawing has ended, and record it.
+fn finish_stroke(key: StrokeKey, engine_view: &mut EngineViewMut) -> WidgetFlags {
+    let mut widget_flags = WidgetFlags::default();
+
+    engine_view.store.update_geometry_for_stroke(key);
+    engine_view.store.regenerate_rendering_for_stroke_threaded(
+        engine_view.tasks_tx.clone(),
+        key,
+        engine_view.camera.viewport(),
+        engine_view.camera.image_scale(),
+    );
+    widget_flags.merge(
+        engine_view
+            .doc
+            .resize_autoexpand(engine_view.store, engine_view.camera),
+    );
+    widget_flags.merge(engine_view.store.record(HistoryLabel::DrawStroke));
+    widget_flags.store_modified = true;
+
+    widget_flags
+}
+
+/// Spawns the task that is triggered when the pen was held still for [Brush::HOLD_STILL_DURATION].
+fn new_hold_still_task(tasks_tx: EngineTaskSender) -> OneOffTaskHandle {
+    let hold_still_task = move || {
+        if let Err(e) = tasks_tx.unbounded_send(EngineTask::BrushHoldStill) {
+            log::error!(
+                "Failed to send `EngineTask::BrushHoldStill` from hold still task, Err: {e:?}"
+            );
+        }
+    };
+    OneOffTaskHandle::new(hold_still_task, Brush::HOLD_STILL_DURATION)
 }
 
 fn play_marker_sound(engine_view: &mut EngineViewMut) {
@@ -3,6 +3,7 @@ use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::eraserconfig::EraserStyle;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::store::HistoryLabel;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 use once_cell::sync::Lazy;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
@@ -84,7 +85,7 @@ impl PenBehaviour for Eraser {
             }
             (EraserState::Down { .. }, PenEvent::Up { element, .. }) => {
                 widget_flags.merge(erase(element, engine_view));
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::Erase));
 
                 self.state = EraserState::Up;
 
@@ -104,7 +105,7 @@ impl PenBehaviour for Eraser {
             (EraserState::Proximity { .. } | EraserState::Down { .. }, PenEvent::Cancel) => {
                 self.state = EraserState::Up;
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::Erase));
 
                 PenProgress::Finished
             }
@@ -1,6 +1,7 @@
 // Imports
 use super::penbehaviour::PenProgress;
 use super::penmode::PenModeState;
+use super::pensconfig::eraserconfig::EraserStyle;
 use super::shortcuts::ShortcutMode;
 use super::{
     Brush, Eraser, Pen, PenBehaviour, PenMode, PenStyle, Selector, Shaper, Shortcuts, Tools,
@@ -331,6 +332,37 @@ impl PenHolder {
                         }
                     }
                 },
+                ShortcutAction::CycleBrushColor => {
+                    engine_view.pens_config.brush_config.cycle_stroke_color();
+                    widget_flags.refresh_ui = true;
+                }
+                ShortcutAction::IncreaseStrokeWidth => {
+                    engine_view.pens_config.brush_config.step_stroke_width(true);
+                    widget_flags.refresh_ui = true;
+                }
+                ShortcutAction::DecreaseStrokeWidth => {
+                    engine_view
+                        .pens_config
+                        .brush_config
+                        .step_stroke_width(false);
+                    widget_flags.refresh_ui = true;
+                }
+                ShortcutAction::ToggleEraserStyle => {
+                    let eraser_config = &mut engine_view.pens_config.eraser_config;
+                    eraser_config.style = match eraser_config.style {
+                        EraserStyle::TrashCollidingStrokes => EraserStyle::SplitCollidingStrokes,
+                        EraserStyle::SplitCollidingStrokes => EraserStyle::TrashCollidingStrokes,
+                    };
+                    widget_flags.refresh_ui = true;
+                }
+                // These act on the whole engine and are handled there.
+                ShortcutAction::Undo
+                | ShortcutAction::Redo
+                | ShortcutAction::ZoomIn
+                | ShortcutAction::ZoomOut
+                | ShortcutAction::ZoomFitWidth
+                | ShortcutAction::NextPage
+                | ShortcutAction::PreviousPage => {}
             }
         }
 
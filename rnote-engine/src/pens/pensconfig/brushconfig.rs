@@ -2,10 +2,11 @@
 use crate::store::chrono_comp::StrokeLayer;
 use rand::{Rng, SeedableRng};
 use rnote_compose::builders::PenPathBuilderType;
+use rnote_compose::color;
 use rnote_compose::style::smooth::SmoothOptions;
 use rnote_compose::style::textured::TexturedOptions;
 use rnote_compose::style::PressureCurve;
-use rnote_compose::Style;
+use rnote_compose::{Color, Style};
 use serde::{Deserialize, Serialize};
 
 #[derive(
@@ -97,7 +98,7 @@ impl std::ops::DerefMut for SolidOptions {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default, rename = "brush_config")]
 pub struct BrushConfig {
     #[serde(rename = "builder_type")]
@@ -110,16 +111,105 @@ pub struct BrushConfig {
     pub solid_options: SolidOptions,
     #[serde(rename = "textured_options")]
     pub textured_options: TexturedOptions,
+    /// Whether the stroke is converted into a recognized shape when the pen is held still at the end of the stroke.
+    #[serde(rename = "shape_recognition")]
+    pub shape_recognition: bool,
+    /// The colors the brush cycles through with the `CycleBrushColor` shortcut action.
+    #[serde(rename = "color_palette")]
+    pub color_palette: Vec<Color>,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        Self {
+            builder_type: PenPathBuilderType::default(),
+            style: BrushStyle::default(),
+            marker_options: MarkerOptions::default(),
+            solid_options: SolidOptions::default(),
+            textured_options: TexturedOptions::default(),
+            shape_recognition: false,
+            color_palette: vec![
+                Color::BLACK,
+                Color::from(color::GNOME_BLUES[3]),
+                Color::from(color::GNOME_REDS[3]),
+                Color::from(color::GNOME_GREENS[4]),
+                Color::from(color::GNOME_ORANGES[3]),
+            ],
+        }
+    }
 }
 
 impl BrushConfig {
     pub const STROKE_WIDTH_MIN: f64 = 0.1;
     pub const STROKE_WIDTH_MAX: f64 = 500.0;
+    /// The maximum deviation of a stroke from a recognized shape, relative to the size of the stroke.
+    pub const SHAPE_RECOGNITION_TOLERANCE: f64 = 0.1;
+    /// The factor the stroke width is changed by when stepping it up or down.
+    pub const STROKE_WIDTH_STEP: f64 = 0.25;
 
-    pub(crate) fn layer_for_current_options(&self) -> StrokeLayer {
+    /// The layer new strokes are inserted into. Non-marker strokes go to the given active user layer.
+    pub(crate) fn layer_for_current_options(&self, active_layer: u32) -> StrokeLayer {
         match &self.style {
             BrushStyle::Marker => StrokeLayer::Highlighter,
-            BrushStyle::Solid | BrushStyle::Textured => StrokeLayer::UserLayer(0),
+            BrushStyle::Solid | BrushStyle::Textured => StrokeLayer::UserLayer(active_layer),
+        }
+    }
+
+    /// The stroke width of the current brush style.
+    pub fn stroke_width(&self) -> f64 {
+        match &self.style {
+            BrushStyle::Marker => self.marker_options.stroke_width,
+            BrushStyle::Solid => self.solid_options.stroke_width,
+            BrushStyle::Textured => self.textured_options.stroke_width,
+        }
+    }
+
+    /// Set the stroke width of the current brush style, clamped to the allowed range.
+    pub fn set_stroke_width(&mut self, stroke_width: f64) {
+        let stroke_width = stroke_width.clamp(Self::STROKE_WIDTH_MIN, Self::STROKE_WIDTH_MAX);
+        match &self.style {
+            BrushStyle::Marker => self.marker_options.stroke_width = stroke_width,
+            BrushStyle::Solid => self.solid_options.stroke_width = stroke_width,
+            BrushStyle::Textured => self.textured_options.stroke_width = stroke_width,
+        }
+    }
+
+    /// Step the stroke width of the current brush style up or down by [Self::STROKE_WIDTH_STEP].
+    pub(crate) fn step_stroke_width(&mut self, increase: bool) {
+        let factor = if increase {
+            1.0 + Self::STROKE_WIDTH_STEP
+        } else {
+            1.0 / (1.0 + Self::STROKE_WIDTH_STEP)
+        };
+        self.set_stroke_width(self.stroke_width() * factor);
+    }
+
+    /// Switch the stroke color of all brush styles to the palette color following the current one.
+    ///
+    /// Starts at the beginning of the palette when the current color is not part of it.
+    pub(crate) fn cycle_stroke_color(&mut self) {
+        let current_color = match &self.style {
+            BrushStyle::Marker => self.marker_options.stroke_color,
+            BrushStyle::Solid => self.solid_options.stroke_color,
+            BrushStyle::Textured => self.textured_options.stroke_color,
+        };
+        let next_index = current_color
+            .and_then(|current| {
+                self.color_palette
+                    .iter()
+                    .position(|c| *c == current)
+                    .map(|i| i + 1)
+            })
+            .unwrap_or(0);
+        if let Some(next_color) = self
+            .color_palette
+            .get(next_index)
+            .or_else(|| self.color_palette.first())
+            .copied()
+        {
+            self.marker_options.stroke_color = Some(next_color);
+            self.solid_options.stroke_color = Some(next_color);
+            self.textured_options.stroke_color = Some(next_color);
         }
     }
 
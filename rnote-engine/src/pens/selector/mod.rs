@@ -7,7 +7,8 @@ use super::pensconfig::selectorconfig::SelectorStyle;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut, StrokeContent};
 use crate::render::{self, Svg};
-use crate::store::StrokeKey;
+use crate::store::{HistoryLabel, StrokeKey};
+use crate::strokes::pdfpage::EmbeddedPdf;
 use crate::strokes::StrokeBehaviour;
 use crate::{Camera, DrawOnDocBehaviour, RnoteEngine, WidgetFlags};
 use futures::channel::oneshot;
@@ -171,6 +172,8 @@ impl PenBehaviour for Selector {
                 if let Some(stroke_content) = stroke_content {
                     let stroke_content_svg = stroke_content.generate_svg()?;
 
+                    // The embedded Pdfs are kept for pasting instead of being serialized
+                    EmbeddedPdf::set_clipboard_pdfs(stroke_content.embedded_pdfs.clone());
                     // Add StrokeContent
                     clipboard_content.push((
                         serde_json::to_string(&stroke_content)?.into_bytes(),
@@ -217,7 +220,7 @@ impl PenBehaviour for Selector {
         let stroke_content = if let SelectorState::ModifySelection { selection, .. } = &self.state {
             let c = Some(engine_view.store.cut_stroke_content(selection));
             self.state = SelectorState::Idle;
-            widget_flags.merge(engine_view.store.record(Instant::now()));
+            widget_flags.merge(engine_view.store.record(HistoryLabel::CutSelection));
             widget_flags.store_modified = true;
             widget_flags.redraw = true;
             c
@@ -230,6 +233,8 @@ impl PenBehaviour for Selector {
                 if let Some(stroke_content) = stroke_content {
                     let stroke_content_svg = stroke_content.generate_svg()?;
 
+                    // The embedded Pdfs are kept for pasting instead of being serialized
+                    EmbeddedPdf::set_clipboard_pdfs(stroke_content.embedded_pdfs.clone());
                     // Add StrokeContent
                     clipboard_content.push((
                         serde_json::to_string(&stroke_content)?.into_bytes(),
@@ -766,7 +771,12 @@ impl Selector {
     ) -> PenProgress {
         if modifier_keys.contains(&ModifierKey::KeyboardCtrl) {
             // Select all keys
-            let all_strokes = engine_view.store.stroke_keys_as_rendered();
+            let all_strokes = engine_view
+                .store
+                .stroke_keys_as_rendered()
+                .into_iter()
+                .filter(|&key| !engine_view.store.layer_locked(key))
+                .collect::<Vec<StrokeKey>>();
 
             if let Some(new_bounds) = engine_view.store.bounds_for_strokes(&all_strokes) {
                 engine_view.store.set_selected_keys(&all_strokes, true);
@@ -801,7 +811,7 @@ fn cancel_selection(selection: &[StrokeKey], engine_view: &mut EngineViewMut) ->
         engine_view.camera.image_scale(),
     );
 
-    widget_flags.merge(engine_view.store.record(Instant::now()));
+    widget_flags.merge(engine_view.store.record(HistoryLabel::Deselect));
     widget_flags.store_modified = true;
     widget_flags.resize = true;
     widget_flags
@@ -3,6 +3,7 @@ use super::{ModifyState, ResizeCorner, Selector, SelectorState};
 use crate::engine::EngineViewMut;
 use crate::pens::penbehaviour::PenProgress;
 use crate::pens::pensconfig::selectorconfig::SelectorStyle;
+use crate::store::HistoryLabel;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
 use p2d::bounding_volume::Aabb;
 use p2d::query::PointQuery;
@@ -393,7 +394,12 @@ impl Selector {
                             *selection_bounds = new_bounds;
                         }
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        let label = match modify_state {
+                            ModifyState::Rotate { .. } => HistoryLabel::RotateSelection,
+                            ModifyState::Resize { .. } => HistoryLabel::ResizeSelection,
+                            _ => HistoryLabel::MoveSelection,
+                        };
+                        widget_flags.merge(engine_view.store.record(label));
                         widget_flags.store_modified = true;
                     }
                     _ => {}
@@ -483,7 +489,8 @@ impl Selector {
                                 engine_view.camera.image_scale(),
                             );
 
-                            widget_flags.merge(engine_view.store.record(Instant::now()));
+                            widget_flags
+                                .merge(engine_view.store.record(HistoryLabel::DuplicateSelection));
                             widget_flags.resize = true;
                             widget_flags.store_modified = true;
                         }
@@ -2,6 +2,7 @@
 use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
+use crate::store::HistoryLabel;
 use crate::strokes::ShapeStroke;
 use crate::strokes::Stroke;
 use crate::{DrawOnDocBehaviour, WidgetFlags};
@@ -126,7 +127,7 @@ impl PenBehaviour for Shaper {
                         }
 
                         if shapes_emitted {
-                            widget_flags.merge(engine_view.store.record(Instant::now()));
+                            widget_flags.merge(engine_view.store.record(HistoryLabel::DrawShape));
                             widget_flags.store_modified = true;
                         }
                         PenProgress::InProgress
@@ -160,7 +161,7 @@ impl PenBehaviour for Shaper {
                                     .resize_autoexpand(engine_view.store, engine_view.camera),
                             );
 
-                            widget_flags.merge(engine_view.store.record(Instant::now()));
+                            widget_flags.merge(engine_view.store.record(HistoryLabel::DrawShape));
                             widget_flags.store_modified = true;
                         }
                         PenProgress::Finished
@@ -47,6 +47,30 @@ pub enum ShortcutAction {
         #[serde(rename = "mode")]
         mode: ShortcutMode,
     },
+    #[serde(rename = "undo")]
+    Undo,
+    #[serde(rename = "redo")]
+    Redo,
+    /// Switch the brush to the next color of the brush color palette.
+    #[serde(rename = "cycle_brush_color")]
+    CycleBrushColor,
+    #[serde(rename = "increase_stroke_width")]
+    IncreaseStrokeWidth,
+    #[serde(rename = "decrease_stroke_width")]
+    DecreaseStrokeWidth,
+    /// Toggle between trashing and splitting colliding strokes with the eraser.
+    #[serde(rename = "toggle_eraser_style")]
+    ToggleEraserStyle,
+    #[serde(rename = "zoom_in")]
+    ZoomIn,
+    #[serde(rename = "zoom_out")]
+    ZoomOut,
+    #[serde(rename = "zoom_fit_width")]
+    ZoomFitWidth,
+    #[serde(rename = "next_page")]
+    NextPage,
+    #[serde(rename = "previous_page")]
+    PreviousPage,
 }
 
 /// The registered shortcut actions for the given shortcut keys.
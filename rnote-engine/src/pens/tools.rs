@@ -3,7 +3,7 @@ use super::penbehaviour::{PenBehaviour, PenProgress};
 use super::pensconfig::toolsconfig::ToolStyle;
 use super::PenStyle;
 use crate::engine::{EngineView, EngineViewMut};
-use crate::store::StrokeKey;
+use crate::store::{HistoryLabel, StrokeKey};
 use crate::{Camera, DrawOnDocBehaviour, WidgetFlags};
 use once_cell::sync::Lazy;
 use p2d::bounding_volume::Aabb;
@@ -420,7 +420,7 @@ impl PenBehaviour for Tools {
                             .store
                             .update_geometry_for_strokes(&self.verticalspace_tool.strokes_below);
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::InsertSpace));
                         widget_flags.store_modified = true;
                     }
                     ToolStyle::OffsetCamera | ToolStyle::Zoom => {}
@@ -6,7 +6,7 @@ use super::penbehaviour::PenProgress;
 use super::PenBehaviour;
 use super::PenStyle;
 use crate::engine::{EngineTask, EngineView, EngineViewMut};
-use crate::store::StrokeKey;
+use crate::store::{HistoryLabel, StrokeKey};
 use crate::strokes::textstroke::{RangedTextAttribute, TextAttribute, TextStyle};
 use crate::strokes::{Stroke, TextStroke};
 use crate::{AudioPlayer, Camera, DrawOnDocBehaviour, WidgetFlags};
@@ -487,7 +487,7 @@ impl PenBehaviour for Typewriter {
                                 pen_down: false,
                             };
 
-                            widget_flags.merge(engine_view.store.record(Instant::now()));
+                            widget_flags.merge(engine_view.store.record(HistoryLabel::CutText));
                             widget_flags.store_modified = true;
                             widget_flags.redraw = true;
 
@@ -662,7 +662,7 @@ impl Typewriter {
                     pen_down: false,
                 };
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::InsertText));
                 widget_flags.store_modified = true;
                 widget_flags.resize = true;
             }
@@ -691,7 +691,7 @@ impl Typewriter {
                     pen_down: false,
                 };
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::InsertText));
                 widget_flags.store_modified = true;
                 widget_flags.resize = true;
             }
@@ -731,7 +731,7 @@ impl Typewriter {
                             pen_down: false,
                         };
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::EditText));
                         widget_flags.store_modified = true;
                     }
                 }
@@ -752,7 +752,7 @@ impl Typewriter {
                                 .resize_autoexpand(engine_view.store, engine_view.camera),
                         );
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::EditText));
                         widget_flags.store_modified = true;
                     }
                 }
@@ -788,7 +788,7 @@ impl Typewriter {
                     engine_view.camera.image_scale(),
                 );
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::ChangeTextStyle));
                 widget_flags.redraw = true;
                 widget_flags.store_modified = true;
             }
@@ -815,7 +815,7 @@ impl Typewriter {
                     engine_view.camera.image_scale(),
                 );
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::ChangeTextStyle));
                 widget_flags.redraw = true;
                 widget_flags.store_modified = true;
             }
@@ -849,7 +849,7 @@ impl Typewriter {
                     engine_view.camera.image_scale(),
                 );
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::ChangeTextStyle));
                 widget_flags.redraw = true;
                 widget_flags.store_modified = true;
             }
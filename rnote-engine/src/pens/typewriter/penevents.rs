@@ -3,6 +3,7 @@ use super::{ModifyState, Typewriter, TypewriterState};
 use crate::engine::EngineViewMut;
 use crate::pens::penbehaviour::PenProgress;
 use crate::pens::PenBehaviour;
+use crate::store::HistoryLabel;
 use crate::strokes::{Stroke, TextStroke};
 use crate::{DrawOnDocBehaviour, StrokeStore, WidgetFlags};
 use rnote_compose::penevents::{KeyboardKey, ModifierKey};
@@ -320,7 +321,7 @@ impl Typewriter {
                             pen_down: false,
                         };
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::MoveText));
                         widget_flags.store_modified = true;
                     }
                     ModifyState::AdjustTextWidth { .. } => {
@@ -345,7 +346,7 @@ impl Typewriter {
                             pen_down: false,
                         };
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::AdjustTextWidth));
                         widget_flags.store_modified = true;
                     }
                 }
@@ -441,7 +442,7 @@ impl Typewriter {
                             pen_down: false,
                         };
 
-                        widget_flags.merge(engine_view.store.record(Instant::now()));
+                        widget_flags.merge(engine_view.store.record(HistoryLabel::EditText));
                         widget_flags.store_modified = true;
                     }
                     _ => {}
@@ -476,10 +477,12 @@ impl Typewriter {
                                             .resize_autoexpand(store, engine_view.camera),
                                     );
                                     if keychar_is_whitespace {
-                                        widget_flags.merge(store.record(Instant::now()));
+                                        widget_flags.merge(store.record(HistoryLabel::EditText));
                                     } else {
                                         widget_flags.merge(
-                                            store.update_latest_history_entry(Instant::now()),
+                                            store.update_latest_history_entry(
+                                                HistoryLabel::EditText,
+                                            ),
                                         );
                                     }
 
@@ -676,7 +679,7 @@ impl Typewriter {
                                     engine_view.doc.resize_autoexpand(store, engine_view.camera),
                                 );
 
-                                widget_flags.merge(store.record(Instant::now()));
+                                widget_flags.merge(store.record(HistoryLabel::EditText));
                                 widget_flags.store_modified = true;
                             };
 
@@ -852,7 +855,7 @@ impl Typewriter {
                     pen_down: false,
                 };
 
-                widget_flags.merge(engine_view.store.record(Instant::now()));
+                widget_flags.merge(engine_view.store.record(HistoryLabel::EditText));
                 widget_flags.resize = true;
                 widget_flags.store_modified = true;
 
@@ -889,12 +892,13 @@ impl Typewriter {
                             // only record new history entry if the text contains ascii-whitespace,
                             // else only update history
                             if text.contains(char::is_whitespace) {
-                                widget_flags.merge(engine_view.store.record(Instant::now()));
+                                widget_flags
+                                    .merge(engine_view.store.record(HistoryLabel::EditText));
                             } else {
                                 widget_flags.merge(
                                     engine_view
                                         .store
-                                        .update_latest_history_entry(Instant::now()),
+                                        .update_latest_history_entry(HistoryLabel::EditText),
                                 );
                             }
 
@@ -934,12 +938,13 @@ impl Typewriter {
                             // only record new history entry if the text contains ascii-whitespace,
                             // else only update history
                             if text.contains(char::is_whitespace) {
-                                widget_flags.merge(engine_view.store.record(Instant::now()));
+                                widget_flags
+                                    .merge(engine_view.store.record(HistoryLabel::EditText));
                             } else {
                                 widget_flags.merge(
                                     engine_view
                                         .store
-                                        .update_latest_history_entry(Instant::now()),
+                                        .update_latest_history_entry(HistoryLabel::EditText),
                                 );
                             }
                             widget_flags.store_modified = true;
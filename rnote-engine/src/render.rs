@@ -1,13 +1,15 @@
 // Imports
+#[cfg(feature = "gtk")]
 use crate::utils::GrapheneRectHelpers;
 use crate::DrawBehaviour;
 use anyhow::Context;
-use gtk4::{gdk, gio, graphene, gsk, prelude::*};
+#[cfg(feature = "gtk")]
+use gtk4::{gdk, graphene, gsk, prelude::*};
 use image::io::Reader;
 use once_cell::sync::Lazy;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
-use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
+use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
 use rnote_compose::transform::TransformBehaviour;
 use serde::{Deserialize, Serialize};
@@ -45,6 +47,7 @@ impl Default for ImageMemoryFormat {
     }
 }
 
+#[cfg(feature = "gtk")]
 impl TryFrom<gdk::MemoryFormat> for ImageMemoryFormat {
     type Error = anyhow::Error;
     fn try_from(value: gdk::MemoryFormat) -> Result<Self, Self::Error> {
@@ -58,6 +61,7 @@ impl TryFrom<gdk::MemoryFormat> for ImageMemoryFormat {
     }
 }
 
+#[cfg(feature = "gtk")]
 impl From<ImageMemoryFormat> for gdk::MemoryFormat {
     fn from(value: ImageMemoryFormat) -> Self {
         match value {
@@ -231,6 +235,7 @@ impl Image {
         Ok(bytes_buf.into_inner())
     }
 
+    #[cfg(feature = "gtk")]
     pub fn to_memtexture(&self) -> Result<gdk::MemoryTexture, anyhow::Error> {
         self.assert_valid()?;
 
@@ -243,6 +248,7 @@ impl Image {
         ))
     }
 
+    #[cfg(feature = "gtk")]
     pub fn to_rendernode(&self) -> Result<gsk::RenderNode, anyhow::Error> {
         self.assert_valid()?;
 
@@ -261,6 +267,7 @@ impl Image {
         Ok(transform_node)
     }
 
+    #[cfg(feature = "gtk")]
     pub fn images_to_rendernodes<'a>(
         images: impl IntoIterator<Item = &'a Self>,
     ) -> Result<Vec<gsk::RenderNode>, anyhow::Error> {
@@ -366,13 +373,36 @@ impl Image {
     }
 
     /// Generates an image with a provided closure that draws onto a piet CairoRenderContext.
-    pub fn gen_with_piet<F>(
+    pub fn gen_with_piet<F>(draw_func: F, bounds: Aabb, image_scale: f64) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
+    {
+        Self::gen_with_cairo(
+            |cairo_cx| {
+                let mut piet_cx = piet_cairo::CairoRenderContext::new(cairo_cx);
+
+                // Apply the draw function
+                draw_func(&mut piet_cx)?;
+
+                piet_cx.finish().map_err(|e| {
+                    anyhow::anyhow!(
+                        "piet_cx.finish() failed in image.gen_with_piet() with Err: {e:?}"
+                    )
+                })
+            },
+            bounds,
+            image_scale,
+        )
+    }
+
+    /// Generates an image with a provided closure that draws onto a cairo context.
+    pub fn gen_with_cairo<F>(
         draw_func: F,
         mut bounds: Aabb,
         image_scale: f64,
     ) -> anyhow::Result<Self>
     where
-        F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
     {
         bounds.ensure_positive();
         bounds = bounds.ceil().loosened(1.0);
@@ -388,7 +418,7 @@ impl Image {
         )
         .map_err(|e| {
             anyhow::anyhow!(
-                "create ImageSurface with dimensions ({}, {}) failed in Image gen_with_piet(), {}",
+                "create ImageSurface with dimensions ({}, {}) failed in Image gen_with_cairo(), {}",
                 width_scaled,
                 height_scaled,
                 e
@@ -397,17 +427,12 @@ impl Image {
 
         {
             let cairo_cx = cairo::Context::new(&image_surface)?;
-            let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
 
-            piet_cx.transform(kurbo::Affine::scale(image_scale));
-            piet_cx.transform(kurbo::Affine::translate(-bounds.mins.coords.to_kurbo_vec()));
+            cairo_cx.scale(image_scale, image_scale);
+            cairo_cx.translate(-bounds.mins[0], -bounds.mins[1]);
 
             // Apply the draw function
-            draw_func(&mut piet_cx)?;
-
-            piet_cx.finish().map_err(|e| {
-                anyhow::anyhow!("piet_cx.finish() failed in image.gen_with_piet() with Err: {e:?}")
-            })?;
+            draw_func(&cairo_cx)?;
         }
         // Surface needs to be flushed before accessing its data
         image_surface.flush();
@@ -416,7 +441,7 @@ impl Image {
                 .data()
                 .map_err(|e| {
                     anyhow::Error::msg(format!(
-                "accessing imagesurface data failed in strokebehaviour image.gen_with_piet() with Err: {e:?}"
+                "accessing imagesurface data failed in strokebehaviour image.gen_with_cairo() with Err: {e:?}"
             ))
                 })?
                 .to_vec();
@@ -478,9 +503,31 @@ impl Svg {
     /// Generate an Svg with piet, using the `piet_cairo` backend and cairo's SvgSurface.
     ///
     /// This might be preferable to the `piet_svg` backend, because especially text alignment and sizes can be different with it.
-    pub fn gen_with_piet_cairo_backend<F>(draw_func: F, mut bounds: Aabb) -> anyhow::Result<Self>
+    pub fn gen_with_piet_cairo_backend<F>(draw_func: F, bounds: Aabb) -> anyhow::Result<Self>
     where
         F: FnOnce(&mut piet_cairo::CairoRenderContext) -> anyhow::Result<()>,
+    {
+        Self::gen_with_cairo(
+            |cairo_cx| {
+                let mut piet_cx = piet_cairo::CairoRenderContext::new(cairo_cx);
+
+                // Apply the draw function
+                draw_func(&mut piet_cx)?;
+
+                piet_cx.finish().map_err(|e| {
+                    anyhow::anyhow!(
+                        "piet_cx.finish() failed in Svg gen_with_piet_cairo_backend() with Err: {e:?}"
+                    )
+                })
+            },
+            bounds,
+        )
+    }
+
+    /// Generate an Svg with a provided closure that draws onto a cairo context of a SvgSurface.
+    pub fn gen_with_cairo<F>(draw_func: F, mut bounds: Aabb) -> anyhow::Result<Self>
+    where
+        F: FnOnce(&cairo::Context) -> anyhow::Result<()>,
     {
         bounds.ensure_positive();
         bounds.assert_valid()?;
@@ -490,30 +537,26 @@ impl Svg {
 
         let svg_stream: Vec<u8> = vec![];
 
-        let mut svg_surface = cairo::SvgSurface::for_stream(
-            width, height, svg_stream
-        )
-        .map_err(|e| {
-            anyhow::anyhow!("create SvgSurface with dimensions ({}, {}) failed in Svg gen_with_piet_cairo_backend(), {}", width, height, e)
-        })?;
+        let mut svg_surface =
+            cairo::SvgSurface::for_stream(width, height, svg_stream).map_err(|e| {
+                anyhow::anyhow!(
+                    "create SvgSurface with dimensions ({}, {}) failed in Svg gen_with_cairo(), {}",
+                    width,
+                    height,
+                    e
+                )
+            })?;
 
         svg_surface.set_document_unit(cairo::SvgUnit::Px);
 
         {
             let cairo_cx = cairo::Context::new(&svg_surface)?;
-            let mut piet_cx = piet_cairo::CairoRenderContext::new(&cairo_cx);
 
             // Cairo only draws elements with positive coordinates, so we need to transform them here
-            piet_cx.transform(kurbo::Affine::translate(-bounds.mins.coords.to_kurbo_vec()));
+            cairo_cx.translate(-bounds.mins[0], -bounds.mins[1]);
 
             // Apply the draw function
-            draw_func(&mut piet_cx)?;
-
-            piet_cx.finish().map_err(|e| {
-                anyhow::anyhow!(
-                    "piet_cx.finish() failed in Svg gen_with_piet_cairo_backend() with Err: {e:?}"
-                )
-            })?;
+            draw_func(&cairo_cx)?;
         }
 
         let file_content = svg_surface
@@ -522,9 +565,7 @@ impl Svg {
 
         let svg_data = rnote_compose::utils::remove_xml_header(
             String::from_utf8(*file_content.downcast::<Vec<u8>>().map_err(|_e| {
-                anyhow::anyhow!(
-                    "failed to downcast svg surface content in Svg gen_with_piet_cairo_backend()"
-                )
+                anyhow::anyhow!("failed to downcast svg surface content in Svg gen_with_cairo()")
             })?)?
             .as_str(),
         );
@@ -608,6 +649,7 @@ impl Svg {
     }
 
     #[allow(unused)]
+    #[cfg(feature = "gtk")]
     fn render_to_caironode(&self) -> Result<gsk::CairoNode, anyhow::Error> {
         if self.bounds.extents()[0] < 0.0 || self.bounds.extents()[1] < 0.0 {
             return Err(anyhow::anyhow!(
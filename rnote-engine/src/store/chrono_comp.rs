@@ -83,6 +83,11 @@ impl ChronoComponent {
     pub fn new(t: u32, layer: StrokeLayer) -> Self {
         Self { t, layer }
     }
+
+    /// The chronological ordering counter.
+    pub fn t(&self) -> u32 {
+        self.t
+    }
 }
 
 /// Systems that are related to their chronological ordering.
@@ -99,6 +104,13 @@ impl StrokeStore {
         }
     }
 
+    /// The layer the stroke is on.
+    pub fn stroke_layer(&self, key: StrokeKey) -> Option<StrokeLayer> {
+        self.chrono_components
+            .get(key)
+            .map(|chrono_comp| chrono_comp.layer)
+    }
+
     /// Returns the keys in chronological order, as in first: gets drawn first, last: gets drawn last.
     pub fn keys_sorted_chrono(&self) -> Vec<StrokeKey> {
         let chrono_components = &self.chrono_components;
@@ -148,6 +160,23 @@ impl StrokeStore {
         keys
     }
 
+    /// Returns the keys of the strokes that are rendered in the order they were last modified,
+    /// regardless of the layer they are on.
+    pub fn stroke_keys_as_rendered_sorted_chrono_t(&self) -> Vec<StrokeKey> {
+        let chrono_components = &self.chrono_components;
+
+        let mut keys = self.stroke_keys_as_rendered();
+
+        keys.par_sort_by_key(|&key| {
+            chrono_components
+                .get(key)
+                .map(|chrono_comp| chrono_comp.t)
+                .unwrap_or(0)
+        });
+
+        keys
+    }
+
     pub fn keys_sorted_chrono_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         let chrono_components = &self.chrono_components;
 
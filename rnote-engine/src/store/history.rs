@@ -0,0 +1,881 @@
+// Imports
+use super::{ChronoComponent, StrokeKey, StrokeStore, TrashComponent, UserLayers};
+use crate::document::{Background, PagesState};
+use crate::strokes::Stroke;
+use crate::WidgetFlags;
+use serde::{Deserialize, Serialize};
+use slotmap::{HopSlotMap, SecondaryMap};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Describes the operation that is recorded in a history entry.
+///
+/// Used by the UI to display what will be undone or redone, e.g. "Undo Move Selection".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename = "history_label")]
+pub enum HistoryLabel {
+    #[serde(rename = "draw_stroke")]
+    DrawStroke,
+    #[serde(rename = "draw_shape")]
+    DrawShape,
+    #[serde(rename = "erase")]
+    Erase,
+    #[serde(rename = "insert_space")]
+    InsertSpace,
+    #[serde(rename = "select")]
+    Select,
+    #[serde(rename = "deselect")]
+    Deselect,
+    #[serde(rename = "move_selection")]
+    MoveSelection,
+    #[serde(rename = "rotate_selection")]
+    RotateSelection,
+    #[serde(rename = "resize_selection")]
+    ResizeSelection,
+    #[serde(rename = "duplicate_selection")]
+    DuplicateSelection,
+    #[serde(rename = "trash_selection")]
+    TrashSelection,
+    #[serde(rename = "cut_selection")]
+    CutSelection,
+    #[serde(rename = "change_stroke_color")]
+    ChangeStrokeColor,
+    #[serde(rename = "change_fill_color")]
+    ChangeFillColor,
+    #[serde(rename = "smooth_strokes")]
+    SmoothStrokes,
+    #[serde(rename = "recognize_shapes")]
+    RecognizeShapes,
+    #[serde(rename = "ungroup_vectorimages")]
+    UngroupVectorImages,
+    #[serde(rename = "insert_text")]
+    InsertText,
+    #[serde(rename = "edit_text")]
+    EditText,
+    #[serde(rename = "cut_text")]
+    CutText,
+    #[serde(rename = "move_text")]
+    MoveText,
+    #[serde(rename = "adjust_text_width")]
+    AdjustTextWidth,
+    #[serde(rename = "change_text_style")]
+    ChangeTextStyle,
+    #[serde(rename = "import")]
+    Import,
+    #[serde(rename = "paste")]
+    Paste,
+    #[serde(rename = "add_layer")]
+    AddLayer,
+    #[serde(rename = "remove_layer")]
+    RemoveLayer,
+    #[serde(rename = "rename_layer")]
+    RenameLayer,
+    #[serde(rename = "change_layer_visibility")]
+    ChangeLayerVisibility,
+    #[serde(rename = "change_layer_lock")]
+    ChangeLayerLock,
+    #[serde(rename = "move_layer")]
+    MoveLayer,
+    #[serde(rename = "merge_layer_down")]
+    MergeLayerDown,
+    #[serde(rename = "move_to_layer")]
+    MoveToLayer,
+    #[serde(rename = "insert_page")]
+    InsertPage,
+    #[serde(rename = "delete_page")]
+    DeletePage,
+    #[serde(rename = "duplicate_page")]
+    DuplicatePage,
+    #[serde(rename = "move_page")]
+    MovePage,
+    #[serde(rename = "change_page_background")]
+    ChangePageBackground,
+}
+
+/// The state of the store at the time it was recorded.
+///
+/// The history entries are computed as the changes against it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "history_entry")]
+pub struct HistoryEntry {
+    #[serde(rename = "stroke_components")]
+    pub stroke_components: Arc<HopSlotMap<StrokeKey, Arc<Stroke>>>,
+    #[serde(rename = "trash_components")]
+    pub trash_components: Arc<SecondaryMap<StrokeKey, Arc<TrashComponent>>>,
+    #[serde(rename = "chrono_components")]
+    pub chrono_components: Arc<SecondaryMap<StrokeKey, Arc<ChronoComponent>>>,
+    #[serde(rename = "chrono_counter")]
+    pub chrono_counter: u32,
+    #[serde(rename = "layers")]
+    pub layers: Arc<UserLayers>,
+}
+
+impl Default for HistoryEntry {
+    fn default() -> Self {
+        Self {
+            stroke_components: Arc::new(HopSlotMap::with_key()),
+            trash_components: Arc::new(SecondaryMap::new()),
+            chrono_components: Arc::new(SecondaryMap::new()),
+
+            chrono_counter: 0,
+            layers: Arc::new(UserLayers::default()),
+        }
+    }
+}
+
+/// The change of a single component between two recorded states.
+#[derive(Debug, Clone)]
+struct ComponentChange<T> {
+    key: StrokeKey,
+    /// Is None when the component was added.
+    old: Option<Arc<T>>,
+    /// Is None when the component was removed.
+    new: Option<Arc<T>>,
+}
+
+impl<T> ComponentChange<T> {
+    /// The component that should be present after undoing (`revert`) or redoing the change.
+    fn target(&self, revert: bool) -> Option<&Arc<T>> {
+        if revert {
+            self.old.as_ref()
+        } else {
+            self.new.as_ref()
+        }
+    }
+
+    fn is_noop(&self) -> bool {
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => Arc::ptr_eq(old, new),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Replaces the key when the stroke was restored with a new key.
+    fn replace_key(&mut self, restored_keys: &HashMap<StrokeKey, StrokeKey>) {
+        if let Some(&new_key) = restored_keys.get(&self.key) {
+            self.key = new_key;
+        }
+    }
+}
+
+/// The maps holding components, which can be compared for changes.
+trait ComponentMap<T> {
+    fn component(&self, key: StrokeKey) -> Option<&Arc<T>>;
+    fn components(&self) -> Box<dyn Iterator<Item = (StrokeKey, &Arc<T>)> + '_>;
+}
+
+impl<T> ComponentMap<T> for HopSlotMap<StrokeKey, Arc<T>> {
+    fn component(&self, key: StrokeKey) -> Option<&Arc<T>> {
+        self.get(key)
+    }
+
+    fn components(&self) -> Box<dyn Iterator<Item = (StrokeKey, &Arc<T>)> + '_> {
+        Box::new(self.iter())
+    }
+}
+
+impl<T> ComponentMap<T> for SecondaryMap<StrokeKey, Arc<T>> {
+    fn component(&self, key: StrokeKey) -> Option<&Arc<T>> {
+        self.get(key)
+    }
+
+    fn components(&self) -> Box<dyn Iterator<Item = (StrokeKey, &Arc<T>)> + '_> {
+        Box::new(self.iter())
+    }
+}
+
+/// Collects the changed components, doing pointer compares because they are wrapped inside Arc's.
+fn diff_components<T, M: ComponentMap<T>>(old: &Arc<M>, new: &Arc<M>) -> Vec<ComponentChange<T>> {
+    if Arc::ptr_eq(old, new) {
+        return vec![];
+    }
+
+    let mut changes = new
+        .components()
+        .filter_map(|(key, new_comp)| match old.component(key) {
+            Some(old_comp) if Arc::ptr_eq(old_comp, new_comp) => None,
+            old_comp => Some(ComponentChange {
+                key,
+                old: old_comp.cloned(),
+                new: Some(Arc::clone(new_comp)),
+            }),
+        })
+        .collect::<Vec<ComponentChange<T>>>();
+    changes.extend(
+        old.components()
+            .filter(|(key, _)| new.component(*key).is_none())
+            .map(|(key, old_comp)| ComponentChange {
+                key,
+                old: Some(Arc::clone(old_comp)),
+                new: None,
+            }),
+    );
+
+    changes
+}
+
+/// Merges the following changes, keeping the old components of the earlier changes.
+fn merge_component_changes<T>(
+    changes: &mut Vec<ComponentChange<T>>,
+    following: Vec<ComponentChange<T>>,
+) {
+    let indices = changes
+        .iter()
+        .enumerate()
+        .map(|(i, change)| (change.key, i))
+        .collect::<HashMap<StrokeKey, usize>>();
+
+    for change in following {
+        match indices.get(&change.key) {
+            Some(&i) => changes[i].new = change.new,
+            None => changes.push(change),
+        }
+    }
+    changes.retain(|change| !change.is_noop());
+}
+
+/// Applies the changes to the components, with the new keys of the restored strokes.
+fn apply_component_changes<T>(
+    components: &mut SecondaryMap<StrokeKey, Arc<T>>,
+    changes: &[ComponentChange<T>],
+    revert: bool,
+    restored_keys: &HashMap<StrokeKey, StrokeKey>,
+) {
+    for change in changes {
+        let key = restored_keys
+            .get(&change.key)
+            .copied()
+            .unwrap_or(change.key);
+
+        match change.target(revert) {
+            Some(comp) => {
+                components.insert(key, Arc::clone(comp));
+            }
+            None => {
+                components.remove(key);
+            }
+        }
+    }
+}
+
+fn user_layers_mem_size(layers: &UserLayers) -> usize {
+    std::mem::size_of::<UserLayers>()
+        + layers
+            .iter()
+            .map(|props| std::mem::size_of_val(props) + props.name.len())
+            .sum::<usize>()
+}
+
+/// A history entry, holding the changes per stroke key between two recorded states of the store.
+///
+/// Removed strokes are held together with their components, so that they can be restored.
+/// The slotmap can't insert with a given key, so restored strokes get a new key which then replaces the old key
+/// in all history entries.
+#[derive(Debug, Clone)]
+pub(crate) struct HistoryDelta {
+    label: HistoryLabel,
+    stroke_changes: Vec<ComponentChange<Stroke>>,
+    trash_changes: Vec<ComponentChange<TrashComponent>>,
+    chrono_changes: Vec<ComponentChange<ChronoComponent>>,
+    /// The chrono counter before and after the changes.
+    chrono_counter: (u32, u32),
+    /// The user layers before and after the changes, when they have changed.
+    layers: Option<(Arc<UserLayers>, Arc<UserLayers>)>,
+    /// The document pages before and after the changes, when they were changed by a page operation.
+    pages: Option<(PagesState, PagesState)>,
+    /// The estimated memory size in bytes.
+    mem_size: usize,
+}
+
+impl HistoryDelta {
+    /// Collects the changes from the old to the new state.
+    fn new(label: HistoryLabel, old: &HistoryEntry, new: &HistoryEntry) -> Self {
+        let mut delta = Self {
+            label,
+            stroke_changes: diff_components(&old.stroke_components, &new.stroke_components),
+            trash_changes: diff_components(&old.trash_components, &new.trash_components),
+            chrono_changes: diff_components(&old.chrono_components, &new.chrono_components),
+            chrono_counter: (old.chrono_counter, new.chrono_counter),
+            layers: (!Arc::ptr_eq(&old.layers, &new.layers))
+                .then(|| (Arc::clone(&old.layers), Arc::clone(&new.layers))),
+            pages: None,
+            mem_size: 0,
+        };
+        delta.mem_size = delta.approx_mem_size();
+        delta
+    }
+
+    fn is_empty(&self) -> bool {
+        self.stroke_changes.is_empty()
+            && self.trash_changes.is_empty()
+            && self.chrono_changes.is_empty()
+            && self.chrono_counter.0 == self.chrono_counter.1
+            && self.layers.is_none()
+            && self.pages.is_none()
+    }
+
+    /// Merges the following delta into this one, so that it spans the changes of both.
+    fn merge(&mut self, following: Self) {
+        merge_component_changes(&mut self.stroke_changes, following.stroke_changes);
+        merge_component_changes(&mut self.trash_changes, following.trash_changes);
+        merge_component_changes(&mut self.chrono_changes, following.chrono_changes);
+        self.chrono_counter.1 = following.chrono_counter.1;
+        self.layers = match (self.layers.take(), following.layers) {
+            (Some((old, _)), Some((_, new))) => (!Arc::ptr_eq(&old, &new)).then_some((old, new)),
+            (layers, None) | (None, layers) => layers,
+        };
+        self.pages = match (self.pages.take(), following.pages) {
+            (Some((old, _)), Some((_, new))) => (old != new).then_some((old, new)),
+            (pages, None) | (None, pages) => pages,
+        };
+        self.mem_size = self.approx_mem_size();
+    }
+
+    /// Replaces the keys of the strokes that were restored with new keys.
+    fn replace_keys(&mut self, restored_keys: &HashMap<StrokeKey, StrokeKey>) {
+        self.stroke_changes
+            .iter_mut()
+            .for_each(|change| change.replace_key(restored_keys));
+        self.trash_changes
+            .iter_mut()
+            .for_each(|change| change.replace_key(restored_keys));
+        self.chrono_changes
+            .iter_mut()
+            .for_each(|change| change.replace_key(restored_keys));
+    }
+
+    /// An estimate of the memory that is held by the changes, in bytes.
+    ///
+    /// Strokes that are shared with the current state or other entries are counted as well.
+    fn approx_mem_size(&self) -> usize {
+        let strokes_size = self
+            .stroke_changes
+            .iter()
+            .flat_map(|change| change.old.iter().chain(change.new.iter()))
+            .map(|stroke| stroke.approx_mem_size())
+            .sum::<usize>();
+        let layers_size = self
+            .layers
+            .as_ref()
+            .map(|(old, new)| user_layers_mem_size(old) + user_layers_mem_size(new))
+            .unwrap_or(0);
+        let pages_size = self
+            .pages
+            .as_ref()
+            .map(|(old, new)| {
+                (old.page_backgrounds.len() + new.page_backgrounds.len())
+                    * std::mem::size_of::<Option<Background>>()
+            })
+            .unwrap_or(0);
+
+        std::mem::size_of::<Self>()
+            + self.stroke_changes.len() * std::mem::size_of::<ComponentChange<Stroke>>()
+            + strokes_size
+            + self.trash_changes.len()
+                * (std::mem::size_of::<ComponentChange<TrashComponent>>()
+                    + std::mem::size_of::<TrashComponent>())
+            + self.chrono_changes.len()
+                * (std::mem::size_of::<ComponentChange<ChronoComponent>>()
+                    + std::mem::size_of::<ChronoComponent>())
+            + layers_size
+            + pages_size
+    }
+}
+
+/// Systems that are related to the history.
+impl StrokeStore {
+    /// The default memory budget of the history in bytes.
+    pub const HISTORY_MEM_BUDGET_DEFAULT: usize = 64 * 1024 * 1024;
+
+    /// Create a history entry from the current state.
+    pub(crate) fn create_history_entry(&self) -> HistoryEntry {
+        HistoryEntry {
+            stroke_components: Arc::clone(&self.stroke_components),
+            trash_components: Arc::clone(&self.trash_components),
+            chrono_components: Arc::clone(&self.chrono_components),
+            chrono_counter: self.chrono_counter,
+            layers: Arc::clone(&self.layers),
+        }
+    }
+
+    /// Record the changes since the last record and save them in the history, labeled with the operation.
+    pub(crate) fn record(&mut self, label: HistoryLabel) -> WidgetFlags {
+        self.record_w_pages(label, None)
+    }
+
+    /// Record the changes since the last record together with the document pages before and after a page operation.
+    pub(crate) fn record_w_pages(
+        &mut self,
+        label: HistoryLabel,
+        pages: Option<(PagesState, PagesState)>,
+    ) -> WidgetFlags {
+        let current = self.create_history_entry();
+        let mut delta = HistoryDelta::new(label, &self.history_checkpoint, &current);
+        delta.pages = pages.filter(|(old, new)| old != new);
+        self.history_checkpoint = current;
+
+        if delta.is_empty() {
+            log::debug!("state has not changed, no need to record");
+        } else {
+            self.push_history_delta(delta);
+        }
+
+        self.history_widget_flags()
+    }
+
+    /// Update the latest history entry with the changes since the last record.
+    ///
+    /// A new entry is recorded instead when the latest entry has a different label.
+    pub(crate) fn update_latest_history_entry(&mut self, label: HistoryLabel) -> WidgetFlags {
+        let current = self.create_history_entry();
+        let delta = HistoryDelta::new(label, &self.history_checkpoint, &current);
+        self.history_checkpoint = current;
+
+        if delta.is_empty() {
+            log::debug!("state has not changed, no need to update current state to history");
+        } else if self.undo_label() == Some(label) {
+            // as soon as the current state is recorded, remove the future
+            self.history.truncate(self.live_index);
+
+            let latest = &mut self.history[self.live_index - 1];
+            latest.merge(delta);
+            if latest.is_empty() {
+                self.history.pop_back();
+                self.live_index -= 1;
+            }
+            self.shrink_history_to_mem_budget();
+        } else {
+            self.push_history_delta(delta);
+        }
+
+        self.history_widget_flags()
+    }
+
+    /// Undo the latest changes.
+    ///
+    /// Returns the document pages that need to be restored, when the changes were recorded by a page operation.
+    /// Should only be called from inside the engine undo wrapper function.
+    pub(crate) fn undo(&mut self) -> (WidgetFlags, Option<PagesState>) {
+        let mut pages = None;
+        if self.can_undo() {
+            self.revert_unrecorded_changes();
+            pages = self.apply_history_delta(self.live_index - 1, true);
+            self.live_index -= 1;
+        }
+
+        (self.history_widget_flags(), pages)
+    }
+
+    /// Redo the latest changes.
+    ///
+    /// Returns the document pages that need to be restored, when the changes were recorded by a page operation.
+    /// Should only be called from inside the engine redo wrapper function.
+    pub(crate) fn redo(&mut self) -> (WidgetFlags, Option<PagesState>) {
+        let mut pages = None;
+        if self.can_redo() {
+            self.revert_unrecorded_changes();
+            pages = self.apply_history_delta(self.live_index, false);
+            self.live_index += 1;
+        }
+
+        (self.history_widget_flags(), pages)
+    }
+
+    pub(crate) fn can_undo(&self) -> bool {
+        self.live_index > 0
+    }
+
+    pub(crate) fn can_redo(&self) -> bool {
+        self.live_index < self.history.len()
+    }
+
+    /// The label of the operation that is reverted by the next undo.
+    pub fn undo_label(&self) -> Option<HistoryLabel> {
+        self.live_index
+            .checked_sub(1)
+            .and_then(|i| self.history.get(i))
+            .map(|delta| delta.label)
+    }
+
+    /// The label of the operation that is reapplied by the next redo.
+    pub fn redo_label(&self) -> Option<HistoryLabel> {
+        self.history.get(self.live_index).map(|delta| delta.label)
+    }
+
+    /// The memory budget of the history in bytes.
+    pub fn history_mem_budget(&self) -> usize {
+        self.history_mem_budget
+    }
+
+    /// Set the memory budget of the history in bytes. Entries are dropped when it is exceeded.
+    ///
+    /// The entry that is undone next is always kept, so that the last operation can be undone regardless of its size.
+    pub fn set_history_mem_budget(&mut self, history_mem_budget: usize) {
+        self.history_mem_budget = history_mem_budget;
+        self.shrink_history_to_mem_budget();
+    }
+
+    /// The estimated memory that is held by the history, in bytes.
+    pub fn history_mem_size(&self) -> usize {
+        self.history.iter().map(|delta| delta.mem_size).sum()
+    }
+
+    /// Clear the history. The current state becomes the state the next record is compared against.
+    pub(crate) fn clear_history(&mut self) -> WidgetFlags {
+        self.history_checkpoint = self.create_history_entry();
+        self.history.clear();
+        self.live_index = 0;
+
+        self.history_widget_flags()
+    }
+
+    fn history_widget_flags(&self) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        widget_flags.hide_undo = Some(!self.can_undo());
+        widget_flags.hide_redo = Some(!self.can_redo());
+        widget_flags.undo_label = Some(self.undo_label());
+        widget_flags.redo_label = Some(self.redo_label());
+
+        widget_flags
+    }
+
+    fn push_history_delta(&mut self, delta: HistoryDelta) {
+        // as soon as the current state is recorded, remove the future
+        self.history.truncate(self.live_index);
+        self.history.push_back(delta);
+        self.live_index += 1;
+
+        self.shrink_history_to_mem_budget();
+    }
+
+    /// Drops history entries until the history fits into the memory budget.
+    ///
+    /// The redo entries that are the farthest from the current state are dropped first, then the oldest entries.
+    fn shrink_history_to_mem_budget(&mut self) {
+        let mut mem_size = self.history_mem_size();
+
+        while mem_size > self.history_mem_budget && self.history.len() > self.live_index {
+            let Some(newest) = self.history.pop_back() else {
+                break;
+            };
+            mem_size -= newest.mem_size;
+        }
+        while mem_size > self.history_mem_budget && self.live_index > 1 {
+            let Some(oldest) = self.history.pop_front() else {
+                break;
+            };
+            mem_size -= oldest.mem_size;
+            self.live_index -= 1;
+        }
+    }
+
+    /// Reverts the changes since the last record, so that the history entries are applied to the recorded state.
+    fn revert_unrecorded_changes(&mut self) {
+        let checkpoint = &self.history_checkpoint;
+
+        self.stroke_components = Arc::clone(&checkpoint.stroke_components);
+        self.trash_components = Arc::clone(&checkpoint.trash_components);
+        self.chrono_components = Arc::clone(&checkpoint.chrono_components);
+        self.chrono_counter = checkpoint.chrono_counter;
+        if !Arc::ptr_eq(&self.layers, &checkpoint.layers) {
+            let active_layer = self.layers.active();
+            self.layers = Arc::clone(&checkpoint.layers);
+            self.set_active_layer(active_layer);
+        }
+    }
+
+    /// Applies the history entry at the index to the current state, reverting its changes when `revert` is true.
+    ///
+    /// The current state must be the recorded state the entry was computed against.
+    /// Returns the document pages that need to be restored.
+    fn apply_history_delta(&mut self, index: usize, revert: bool) -> Option<PagesState> {
+        let delta = self.history[index].clone();
+
+        // The old keys of the restored strokes mapped to their new keys
+        let mut restored_keys = HashMap::new();
+        if !delta.stroke_changes.is_empty() {
+            let stroke_components = Arc::make_mut(&mut self.stroke_components);
+
+            for change in delta.stroke_changes.iter() {
+                match change.target(revert) {
+                    Some(stroke) => match stroke_components.get_mut(change.key) {
+                        Some(current) => *current = Arc::clone(stroke),
+                        None => {
+                            let new_key = stroke_components.insert(Arc::clone(stroke));
+                            restored_keys.insert(change.key, new_key);
+                        }
+                    },
+                    None => {
+                        stroke_components.remove(change.key);
+                    }
+                }
+            }
+        }
+        apply_component_changes(
+            Arc::make_mut(&mut self.trash_components),
+            &delta.trash_changes,
+            revert,
+            &restored_keys,
+        );
+        apply_component_changes(
+            Arc::make_mut(&mut self.chrono_components),
+            &delta.chrono_changes,
+            revert,
+            &restored_keys,
+        );
+        if !restored_keys.is_empty() {
+            // All entries must refer to the strokes with their current keys
+            self.history
+                .iter_mut()
+                .for_each(|delta| delta.replace_keys(&restored_keys));
+        }
+        self.chrono_counter = if revert {
+            delta.chrono_counter.0
+        } else {
+            delta.chrono_counter.1
+        };
+        let layers = delta
+            .layers
+            .as_ref()
+            .map(|(old, new)| Arc::clone(if revert { old } else { new }));
+        let pages = delta
+            .pages
+            .as_ref()
+            .map(|(old, new)| if revert { old.clone() } else { new.clone() });
+
+        if let Some(layers) = layers {
+            // The active layer is not part of the undo/redo, so it is retained if it is still valid.
+            let active_layer = self.layers.active();
+            self.layers = layers;
+            self.set_active_layer(active_layer);
+        }
+
+        // Since we don't store the rtree in the history, we need to rebuild it.
+        self.rebuild_rtree();
+        self.rebuild_selection_components_slotmap();
+        // Rebuild but retain the render components for the strokes that are still present.
+        // This ensures that we are able to continue displaying the strokes after undo/redo while they are rerendered.
+        self.rebuild_retain_valid_keys_render_components();
+
+        let all_strokes = self.stroke_keys_unordered();
+        self.set_rendering_dirty_for_strokes(&all_strokes);
+
+        self.history_checkpoint = self.create_history_entry();
+
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::BrushStroke;
+    use rnote_compose::penpath::Element;
+    use rnote_compose::shapes::ShapeBehaviour;
+    use rnote_compose::{PenPath, Style};
+
+    fn brushstroke(x: f64) -> Stroke {
+        Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new(Element::new(na::vector![x, 0.0], 0.5)),
+            Style::default(),
+        ))
+    }
+
+    /// The x coordinates of the strokes in the order they are rendered.
+    fn rendered_strokes_x(store: &StrokeStore) -> Vec<f64> {
+        store
+            .stroke_keys_as_rendered()
+            .into_iter()
+            .map(|key| store.get_stroke_ref(key).unwrap().bounds().center()[0])
+            .collect()
+    }
+
+    #[test]
+    fn undo_redo_restores_removed_strokes() {
+        let mut store = StrokeStore::default();
+
+        let first = store.insert_stroke(brushstroke(0.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        let _ = store.insert_stroke(brushstroke(10.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        store.set_trashed_keys(&[first], true);
+        let _ = store.record(HistoryLabel::Erase);
+        let chrono_counter = store.chrono_counter;
+
+        assert_eq!(store.undo_label(), Some(HistoryLabel::Erase));
+        assert_eq!(store.redo_label(), None);
+
+        for _ in 0..3 {
+            let _ = store.undo();
+        }
+        assert!(!store.can_undo());
+        assert_eq!(store.stroke_keys_unordered().len(), 0);
+        assert_eq!(store.redo_label(), Some(HistoryLabel::DrawStroke));
+
+        // The following entries are applied to the restored strokes
+        for _ in 0..3 {
+            let _ = store.redo();
+        }
+        assert!(!store.can_redo());
+        assert_eq!(store.keys_unordered().len(), 2);
+        assert_eq!(rendered_strokes_x(&store), vec![10.0]);
+        assert_eq!(store.chrono_counter, chrono_counter);
+
+        let _ = store.undo();
+        assert_eq!(rendered_strokes_x(&store), vec![0.0, 10.0]);
+    }
+
+    #[test]
+    fn undo_redo_restores_permanently_removed_strokes() {
+        let mut store = StrokeStore::default();
+
+        let first = store.insert_stroke(brushstroke(0.0), None);
+        let _ = store.insert_stroke(brushstroke(10.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        let _ = store.remove_stroke(first);
+        let _ = store.record(HistoryLabel::Erase);
+
+        // The restored stroke keeps its components
+        let _ = store.undo();
+        assert_eq!(rendered_strokes_x(&store), vec![0.0, 10.0]);
+
+        let _ = store.redo();
+        assert_eq!(store.stroke_keys_unordered().len(), 1);
+        assert_eq!(rendered_strokes_x(&store), vec![10.0]);
+
+        for _ in 0..2 {
+            let _ = store.undo();
+        }
+        assert_eq!(store.stroke_keys_unordered().len(), 0);
+        for _ in 0..2 {
+            let _ = store.redo();
+        }
+        assert_eq!(rendered_strokes_x(&store), vec![10.0]);
+    }
+
+    /// The memory sizes of the entries that insert and remove a single stroke, in a document of `n_strokes` strokes.
+    fn single_stroke_entries_mem_sizes(n_strokes: usize) -> (usize, usize) {
+        let mut store = StrokeStore::default();
+
+        for i in 0..n_strokes {
+            let _ = store.insert_stroke(brushstroke(i as f64), None);
+        }
+        let _ = store.record(HistoryLabel::Import);
+        let key = store.insert_stroke(brushstroke(-10.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        let insert_mem_size = store.history.back().unwrap().mem_size;
+        let _ = store.remove_stroke(key);
+        let _ = store.record(HistoryLabel::Erase);
+        let remove_mem_size = store.history.back().unwrap().mem_size;
+
+        (insert_mem_size, remove_mem_size)
+    }
+
+    #[test]
+    fn single_stroke_entry_mem_size_independent_of_document_size() {
+        assert_eq!(
+            single_stroke_entries_mem_sizes(10),
+            single_stroke_entries_mem_sizes(1000)
+        );
+    }
+
+    #[test]
+    fn undo_reverts_unrecorded_changes() {
+        let mut store = StrokeStore::default();
+
+        let first = store.insert_stroke(brushstroke(0.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        let _ = store.insert_stroke(brushstroke(10.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+
+        // Changes that are not recorded yet
+        let _ = store.insert_stroke(brushstroke(20.0), None);
+        store.set_trashed_keys(&[first], true);
+
+        let _ = store.undo();
+        assert_eq!(store.stroke_keys_as_rendered(), vec![first]);
+        assert_eq!(store.chrono_counter, 1);
+
+        // The redone stroke is restored with a new key
+        let _ = store.redo();
+        assert_eq!(rendered_strokes_x(&store), vec![0.0, 10.0]);
+        assert_eq!(store.stroke_keys_unordered().len(), 2);
+    }
+
+    #[test]
+    fn update_latest_history_entry_merges_same_label() {
+        let mut store = StrokeStore::default();
+
+        let first = store.insert_stroke(brushstroke(0.0), None);
+        let _ = store.record(HistoryLabel::DrawStroke);
+        let second = store.insert_stroke(brushstroke(10.0), None);
+        // The latest entry has a different label, so a new one is recorded
+        let _ = store.update_latest_history_entry(HistoryLabel::Paste);
+        store.set_trashed_keys(&[first, second], true);
+        let _ = store.update_latest_history_entry(HistoryLabel::Paste);
+
+        assert_eq!(store.history.len(), 2);
+        assert_eq!(store.undo_label(), Some(HistoryLabel::Paste));
+
+        let _ = store.undo();
+        assert_eq!(store.stroke_keys_as_rendered(), vec![first]);
+    }
+
+    #[test]
+    fn mem_budget_drops_oldest_entries() {
+        let mut store = StrokeStore::default();
+
+        for i in 0..10 {
+            let _ = store.insert_stroke(brushstroke(i as f64), None);
+            let _ = store.record(HistoryLabel::DrawStroke);
+        }
+        assert_eq!(store.history.len(), 10);
+
+        let budget = store
+            .history
+            .iter()
+            .rev()
+            .take(3)
+            .map(|delta| delta.mem_size)
+            .sum::<usize>();
+        store.set_history_mem_budget(budget);
+        assert_eq!(store.history.len(), 3);
+        assert!(store.history_mem_size() <= budget);
+
+        // The latest entry is always kept
+        store.set_history_mem_budget(0);
+        assert_eq!(store.history.len(), 1);
+        assert!(store.can_undo());
+    }
+
+    #[test]
+    fn mem_budget_drops_redo_entries() {
+        let mut store = StrokeStore::default();
+
+        for i in 0..10 {
+            let _ = store.insert_stroke(brushstroke(i as f64), None);
+            let _ = store.record(HistoryLabel::DrawStroke);
+        }
+        for _ in 0..5 {
+            let _ = store.undo();
+        }
+        let budget = store
+            .history
+            .iter()
+            .take(7)
+            .map(|delta| delta.mem_size)
+            .sum::<usize>();
+
+        // The redo entries are dropped before the entries that can be undone
+        store.set_history_mem_budget(budget);
+        assert_eq!(store.history.len(), 7);
+        assert_eq!(store.live_index, 5);
+
+        store.set_history_mem_budget(0);
+        assert_eq!(store.history.len(), 1);
+        assert!(store.can_undo());
+        assert!(!store.can_redo());
+    }
+}
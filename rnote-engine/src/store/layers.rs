@@ -0,0 +1,559 @@
+// Imports
+use super::chrono_comp::StrokeLayer;
+use super::{StrokeKey, StrokeStore};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The properties of a single user layer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "layer_props")]
+pub struct LayerProps {
+    /// The layer name.
+    #[serde(rename = "name")]
+    pub name: String,
+    /// Whether the layer is hidden. Strokes on hidden layers are not rendered, selected or erased,
+    /// and are only exported into formats that keep the layers.
+    #[serde(rename = "hidden")]
+    pub hidden: bool,
+    /// Whether the layer is locked. Strokes on locked layers are rendered, but can't be selected or erased.
+    #[serde(rename = "locked")]
+    pub locked: bool,
+}
+
+impl Default for LayerProps {
+    fn default() -> Self {
+        Self {
+            name: String::from("Layer"),
+            hidden: false,
+            locked: false,
+        }
+    }
+}
+
+impl LayerProps {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            ..Default::default()
+        }
+    }
+}
+
+/// The user layers of the document.
+///
+/// The position of the layer properties in the vector corresponds to the index in [StrokeLayer::UserLayer].
+/// Layers with a higher index are drawn on top of layers with a lower index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, rename = "user_layers")]
+pub struct UserLayers {
+    #[serde(rename = "layers")]
+    layers: Vec<LayerProps>,
+    /// The index of the active layer, which new strokes are inserted into.
+    #[serde(rename = "active")]
+    active: u32,
+}
+
+impl Default for UserLayers {
+    fn default() -> Self {
+        Self {
+            layers: vec![LayerProps::new(Self::gen_layer_name(0))],
+            active: 0,
+        }
+    }
+}
+
+impl UserLayers {
+    fn gen_layer_name(index: u32) -> String {
+        format!("Layer {}", index + 1)
+    }
+
+    /// The number of user layers.
+    pub fn len(&self) -> u32 {
+        self.layers.len() as u32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The properties of the layer at the given index.
+    pub fn get(&self, index: u32) -> Option<&LayerProps> {
+        self.layers.get(index as usize)
+    }
+
+    /// Iterate over the layer properties, from bottom to top.
+    pub fn iter(&self) -> impl Iterator<Item = &LayerProps> {
+        self.layers.iter()
+    }
+
+    /// The index of the active layer.
+    pub fn active(&self) -> u32 {
+        self.active
+    }
+
+    /// Ensure that there are layer properties for at least `n_layers` layers,
+    /// and that the active layer index is valid.
+    fn ensure_n_layers(&mut self, n_layers: u32) {
+        while self.len() < n_layers.max(1) {
+            self.layers
+                .push(LayerProps::new(Self::gen_layer_name(self.len())));
+        }
+        self.active = self.active.min(self.len() - 1);
+    }
+}
+
+/// Systems that are related to the user layers.
+impl StrokeStore {
+    /// The user layers.
+    pub fn user_layers(&self) -> &UserLayers {
+        &self.layers
+    }
+
+    /// The index of the active user layer.
+    pub fn active_layer(&self) -> u32 {
+        self.layers.active
+    }
+
+    /// Set the active user layer, which new strokes are inserted into.
+    ///
+    /// Returns false if no layer exists for the index.
+    pub fn set_active_layer(&mut self, index: u32) -> bool {
+        if index >= self.layers.len() {
+            return false;
+        }
+        // avoid unnecessarily cloning the layers that are shared with the history
+        if index != self.layers.active {
+            Arc::make_mut(&mut self.layers).active = index;
+        }
+        true
+    }
+
+    /// Makes sure that layer properties exist for all user layers that are referenced by the chrono components.
+    ///
+    /// Needs to be called after loading strokes that might be on layers that don't have properties yet.
+    pub(crate) fn ensure_layers_for_chrono_components(&mut self) {
+        let n_layers = self
+            .chrono_components
+            .values()
+            .filter_map(|chrono_comp| match chrono_comp.layer {
+                StrokeLayer::UserLayer(index) => Some(index + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(1);
+
+        if n_layers > self.layers.len() || self.layers.active >= self.layers.len() {
+            Arc::make_mut(&mut self.layers).ensure_n_layers(n_layers);
+        }
+    }
+
+    /// The layer properties for the layer the stroke is on.
+    ///
+    /// None if the stroke is not on a user layer.
+    fn layer_props_for_key(&self, key: StrokeKey) -> Option<&LayerProps> {
+        match self.chrono_components.get(key)?.layer {
+            StrokeLayer::UserLayer(index) => self.layers.get(index),
+            _ => None,
+        }
+    }
+
+    /// Whether the stroke is on a hidden layer.
+    pub fn layer_hidden(&self, key: StrokeKey) -> bool {
+        self.layer_props_for_key(key)
+            .map(|props| props.hidden)
+            .unwrap_or(false)
+    }
+
+    /// Whether the stroke is on a locked layer.
+    pub fn layer_locked(&self, key: StrokeKey) -> bool {
+        self.layer_props_for_key(key)
+            .map(|props| props.locked)
+            .unwrap_or(false)
+    }
+
+    /// All keys of strokes that are on the given user layer, unordered. Includes trashed strokes.
+    pub fn keys_on_layer(&self, index: u32) -> Vec<StrokeKey> {
+        self.chrono_components
+            .iter()
+            .filter_map(|(key, chrono_comp)| {
+                if chrono_comp.layer == StrokeLayer::UserLayer(index) {
+                    Some(key)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Add a new user layer on top of all others and makes it the active layer.
+    ///
+    /// If no name is given, a name is generated. Returns the index of the new layer.
+    pub fn add_layer(&mut self, name: Option<String>) -> u32 {
+        let layers = Arc::make_mut(&mut self.layers);
+        let index = layers.len();
+        let name = name.unwrap_or_else(|| UserLayers::gen_layer_name(index));
+
+        layers.layers.push(LayerProps::new(name));
+        layers.active = index;
+
+        index
+    }
+
+    /// Permanently remove the user layer and all strokes on it.
+    ///
+    /// The last remaining layer can't be removed. Returns false if the layer was not removed.
+    pub fn remove_layer(&mut self, index: u32) -> bool {
+        if index >= self.layers.len() || self.layers.len() <= 1 {
+            return false;
+        }
+
+        for key in self.keys_on_layer(index) {
+            self.remove_stroke(key);
+        }
+        self.shift_layer_indices_above(index);
+
+        let layers = Arc::make_mut(&mut self.layers);
+        layers.layers.remove(index as usize);
+        if layers.active > index || layers.active >= layers.len() {
+            layers.active = layers.active.saturating_sub(1);
+        }
+
+        true
+    }
+
+    /// Rename the user layer.
+    pub fn rename_layer(&mut self, index: u32, name: String) -> bool {
+        let Some(props) = self.layer_props_mut(index) else {
+            return false;
+        };
+        props.name = name;
+        true
+    }
+
+    /// Set the user layer hidden or visible.
+    ///
+    /// Strokes on the layer are deselected when hiding it.
+    pub fn set_layer_hidden(&mut self, index: u32, hidden: bool) -> bool {
+        let Some(props) = self.layer_props_mut(index) else {
+            return false;
+        };
+        props.hidden = hidden;
+
+        if hidden {
+            self.deselect_keys_on_layer(index);
+        }
+        true
+    }
+
+    /// Set the user layer locked or unlocked.
+    ///
+    /// Strokes on the layer are deselected when locking it.
+    pub fn set_layer_locked(&mut self, index: u32, locked: bool) -> bool {
+        let Some(props) = self.layer_props_mut(index) else {
+            return false;
+        };
+        props.locked = locked;
+
+        if locked {
+            self.deselect_keys_on_layer(index);
+        }
+        true
+    }
+
+    /// Move the user layer from the index `from` to the index `to`, shifting the layers in between.
+    ///
+    /// The strokes are moved together with their layer. The active layer stays the same.
+    pub fn move_layer(&mut self, from: u32, to: u32) -> bool {
+        let n_layers = self.layers.len();
+        if from >= n_layers || to >= n_layers {
+            return false;
+        }
+        if from == to {
+            return true;
+        }
+
+        // Maps the old index of every layer to its new index
+        let map_index = |index: u32| -> u32 {
+            if index == from {
+                to
+            } else if from < to && index > from && index <= to {
+                index - 1
+            } else if from > to && index >= to && index < from {
+                index + 1
+            } else {
+                index
+            }
+        };
+
+        for chrono_comp in Arc::make_mut(&mut self.chrono_components).values_mut() {
+            if let StrokeLayer::UserLayer(index) = chrono_comp.layer {
+                Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(map_index(index));
+            }
+        }
+
+        let layers = Arc::make_mut(&mut self.layers);
+        let props = layers.layers.remove(from as usize);
+        layers.layers.insert(to as usize, props);
+        layers.active = map_index(layers.active);
+
+        true
+    }
+
+    /// Merge the user layer into the layer below it.
+    ///
+    /// The strokes of the merged layer are drawn on top of the strokes of the layer below,
+    /// and are deselected when that layer is hidden or locked.
+    /// Returns false if the layer is the bottom layer or does not exist.
+    pub fn merge_layer_down(&mut self, index: u32) -> bool {
+        if index == 0 || index >= self.layers.len() {
+            return false;
+        }
+
+        let mut keys = self.keys_on_layer(index);
+        // retain the drawing order of the merged strokes by placing them in chronological order at the end.
+        keys.sort_unstable_by_key(|&key| self.chrono_components.get(key).map(|c| c.t()));
+        for key in keys {
+            if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
+                Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(index - 1);
+            }
+            self.update_chrono_to_last(key);
+        }
+        self.shift_layer_indices_above(index);
+
+        let layers = Arc::make_mut(&mut self.layers);
+        layers.layers.remove(index as usize);
+        if layers.active >= index {
+            layers.active -= 1;
+        }
+
+        if self
+            .layers
+            .get(index - 1)
+            .map_or(false, |props| props.hidden || props.locked)
+        {
+            self.deselect_keys_on_layer(index - 1);
+        }
+        true
+    }
+
+    /// Move the strokes to the given user layer.
+    ///
+    /// Strokes are deselected when they are moved to a hidden or locked layer.
+    pub fn move_strokes_to_layer(&mut self, keys: &[StrokeKey], index: u32) -> bool {
+        if index >= self.layers.len() {
+            return false;
+        }
+
+        for &key in keys {
+            if let Some(chrono_comp) = Arc::make_mut(&mut self.chrono_components).get_mut(key) {
+                if matches!(chrono_comp.layer, StrokeLayer::UserLayer(_)) {
+                    Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(index);
+                }
+            }
+        }
+
+        if self
+            .layers
+            .get(index)
+            .map_or(false, |props| props.hidden || props.locked)
+        {
+            self.deselect_keys_on_layer(index);
+        }
+        true
+    }
+
+    fn layer_props_mut(&mut self, index: u32) -> Option<&mut LayerProps> {
+        if index >= self.layers.len() {
+            return None;
+        }
+        Arc::make_mut(&mut self.layers)
+            .layers
+            .get_mut(index as usize)
+    }
+
+    /// Decrement the layer index of all strokes on user layers above the given index.
+    fn shift_layer_indices_above(&mut self, index: u32) {
+        for chrono_comp in Arc::make_mut(&mut self.chrono_components).values_mut() {
+            if let StrokeLayer::UserLayer(stroke_layer) = chrono_comp.layer {
+                if stroke_layer > index {
+                    Arc::make_mut(chrono_comp).layer = StrokeLayer::UserLayer(stroke_layer - 1);
+                }
+            }
+        }
+    }
+
+    fn deselect_keys_on_layer(&mut self, index: u32) {
+        let keys = self
+            .keys_on_layer(index)
+            .into_iter()
+            .filter(|&key| self.selected(key).unwrap_or(false))
+            .collect::<Vec<StrokeKey>>();
+        self.set_selected_keys(&keys, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strokes::{BrushStroke, Stroke};
+    use rnote_compose::penpath::Element;
+    use rnote_compose::{PenPath, Style};
+
+    fn insert_brushstroke(store: &mut StrokeStore, layer: u32) -> StrokeKey {
+        let stroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            PenPath::new(Element::new(na::vector![0.0, 0.0], 0.5)),
+            Style::default(),
+        ));
+        store.insert_stroke(stroke, Some(StrokeLayer::UserLayer(layer)))
+    }
+
+    fn layer_names(store: &StrokeStore) -> Vec<&str> {
+        store
+            .user_layers()
+            .iter()
+            .map(|props| props.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn add_and_remove_layers() {
+        let mut store = StrokeStore::default();
+        let bottom = insert_brushstroke(&mut store, 0);
+
+        assert_eq!(store.add_layer(None), 1);
+        assert_eq!(store.add_layer(Some(String::from("Top"))), 2);
+        assert_eq!(layer_names(&store), vec!["Layer 1", "Layer 2", "Top"]);
+        assert_eq!(store.active_layer(), 2);
+
+        let middle = insert_brushstroke(&mut store, 1);
+        let top = insert_brushstroke(&mut store, 2);
+
+        assert!(store.remove_layer(1));
+        assert_eq!(layer_names(&store), vec!["Layer 1", "Top"]);
+        assert!(store.stroke_components.get(middle).is_none());
+        assert_eq!(store.stroke_layer(bottom), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.stroke_layer(top), Some(StrokeLayer::UserLayer(1)));
+        assert_eq!(store.active_layer(), 1);
+
+        // The last remaining layer can't be removed
+        assert!(store.remove_layer(1));
+        assert!(!store.remove_layer(0));
+        assert!(!store.remove_layer(5));
+        assert_eq!(store.user_layers().len(), 1);
+        assert_eq!(store.active_layer(), 0);
+    }
+
+    #[test]
+    fn move_layer_moves_strokes_along() {
+        let mut store = StrokeStore::default();
+        store.add_layer(None);
+        store.add_layer(None);
+        let keys = (0..3)
+            .map(|layer| insert_brushstroke(&mut store, layer))
+            .collect::<Vec<StrokeKey>>();
+        store.set_active_layer(0);
+
+        assert!(store.move_layer(0, 2));
+        assert_eq!(layer_names(&store), vec!["Layer 2", "Layer 3", "Layer 1"]);
+        assert_eq!(store.stroke_layer(keys[0]), Some(StrokeLayer::UserLayer(2)));
+        assert_eq!(store.stroke_layer(keys[1]), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.stroke_layer(keys[2]), Some(StrokeLayer::UserLayer(1)));
+        // The active layer follows the moved layer
+        assert_eq!(store.active_layer(), 2);
+
+        assert!(store.move_layer(2, 0));
+        assert_eq!(layer_names(&store), vec!["Layer 1", "Layer 2", "Layer 3"]);
+        assert_eq!(store.stroke_layer(keys[0]), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.active_layer(), 0);
+
+        assert!(!store.move_layer(0, 3));
+    }
+
+    #[test]
+    fn merge_layer_down_keeps_drawing_order() {
+        let mut store = StrokeStore::default();
+        store.add_layer(None);
+        // inserted first, but on the upper layer
+        let upper = insert_brushstroke(&mut store, 1);
+        let lower = insert_brushstroke(&mut store, 0);
+        assert_eq!(store.keys_sorted_chrono(), vec![lower, upper]);
+
+        assert!(!store.merge_layer_down(0));
+        assert!(store.merge_layer_down(1));
+        assert_eq!(store.user_layers().len(), 1);
+        assert_eq!(store.active_layer(), 0);
+        assert_eq!(store.stroke_layer(upper), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.keys_sorted_chrono(), vec![lower, upper]);
+    }
+
+    #[test]
+    fn hiding_and_locking_deselects_and_excludes_strokes() {
+        let mut store = StrokeStore::default();
+        let key = insert_brushstroke(&mut store, 0);
+
+        store.set_selected_keys(&[key], true);
+        assert!(store.set_layer_hidden(0, true));
+        assert!(store.layer_hidden(key));
+        assert_eq!(store.selected(key), Some(false));
+
+        assert!(store.set_layer_hidden(0, false));
+        store.set_selected_keys(&[key], true);
+        assert!(store.set_layer_locked(0, true));
+        assert!(store.layer_locked(key));
+        assert!(!store.layer_hidden(key));
+        assert_eq!(store.selected(key), Some(false));
+
+        assert!(!store.set_layer_locked(1, true));
+    }
+
+    #[test]
+    fn moving_strokes_to_hidden_or_locked_layers_deselects_them() {
+        let mut store = StrokeStore::default();
+        store.add_layer(None);
+        store.add_layer(None);
+        let first = insert_brushstroke(&mut store, 0);
+        let second = insert_brushstroke(&mut store, 0);
+        assert!(store.set_layer_hidden(1, true));
+        assert!(store.set_layer_locked(2, true));
+
+        store.set_selected_keys(&[first, second], true);
+        assert!(store.move_strokes_to_layer(&[first], 1));
+        assert_eq!(store.stroke_layer(first), Some(StrokeLayer::UserLayer(1)));
+        assert_eq!(store.selected(first), Some(false));
+        assert_eq!(store.selected(second), Some(true));
+
+        assert!(store.move_strokes_to_layer(&[second], 2));
+        assert_eq!(store.stroke_layer(second), Some(StrokeLayer::UserLayer(2)));
+        assert_eq!(store.selected(second), Some(false));
+
+        // Strokes stay selected when moved to a visible and unlocked layer
+        assert!(store.set_layer_hidden(1, false));
+        store.set_selected_keys(&[first], true);
+        assert!(store.move_strokes_to_layer(&[first], 0));
+        assert_eq!(store.selected(first), Some(true));
+
+        // The same applies to strokes merged into a hidden layer
+        assert!(store.set_layer_hidden(0, true));
+        store.set_selected_keys(&[first], false);
+        assert!(store.move_strokes_to_layer(&[first], 1));
+        store.set_selected_keys(&[first], true);
+        assert!(store.merge_layer_down(1));
+        assert_eq!(store.stroke_layer(first), Some(StrokeLayer::UserLayer(0)));
+        assert_eq!(store.selected(first), Some(false));
+    }
+
+    #[test]
+    fn ensure_layers_for_loaded_strokes() {
+        let mut store = StrokeStore::default();
+        let key = insert_brushstroke(&mut store, 3);
+        assert_eq!(store.user_layers().len(), 1);
+
+        store.ensure_layers_for_chrono_components();
+        assert_eq!(store.user_layers().len(), 4);
+        assert!(!store.layer_hidden(key));
+
+        assert!(store.move_strokes_to_layer(&[key], 1));
+        assert_eq!(store.stroke_layer(key), Some(StrokeLayer::UserLayer(1)));
+        assert!(!store.move_strokes_to_layer(&[key], 4));
+    }
+}
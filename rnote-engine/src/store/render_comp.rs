@@ -1,13 +1,19 @@
 // Imports
 use super::{Stroke, StrokeKey, StrokeStore};
+#[cfg(feature = "gtk")]
 use crate::engine::visual_debug;
 use crate::engine::{EngineTask, EngineTaskSender};
 use crate::strokes::strokebehaviour::GeneratedStrokeImages;
 use crate::strokes::StrokeBehaviour;
+#[cfg(feature = "gtk")]
 use crate::utils::{GdkRGBAHelpers, GrapheneRectHelpers};
-use crate::{render, DrawBehaviour, RnoteEngine};
+#[cfg(feature = "gtk")]
+use crate::RnoteEngine;
+use crate::{render, DrawBehaviour};
+#[cfg(feature = "gtk")]
 use gtk4::{gdk, graphene, gsk, prelude::*, Snapshot};
 use p2d::bounding_volume::{Aabb, BoundingVolume};
+#[cfg(feature = "gtk")]
 use rnote_compose::color;
 use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::shapes::ShapeBehaviour;
@@ -32,6 +38,8 @@ impl Default for RenderCompState {
 #[derive(Debug, Clone)]
 pub struct RenderComponent {
     pub(super) images: Vec<render::Image>,
+    /// The render nodes of the images, only needed for drawing to a GTK snapshot.
+    #[cfg(feature = "gtk")]
     pub(super) rendernodes: Vec<gsk::RenderNode>,
     pub(super) state: RenderCompState,
 }
@@ -41,11 +49,52 @@ impl Default for RenderComponent {
         Self {
             state: RenderCompState::default(),
             images: vec![],
+            #[cfg(feature = "gtk")]
             rendernodes: vec![],
         }
     }
 }
 
+impl RenderComponent {
+    /// Replace the rendered images.
+    pub(super) fn set_images(&mut self, images: Vec<render::Image>) -> anyhow::Result<()> {
+        #[cfg(feature = "gtk")]
+        {
+            self.rendernodes = render::Image::images_to_rendernodes(&images)?;
+        }
+        self.images = images;
+        Ok(())
+    }
+
+    /// Append to the rendered images.
+    pub(super) fn append_images(&mut self, mut images: Vec<render::Image>) -> anyhow::Result<()> {
+        #[cfg(feature = "gtk")]
+        {
+            self.rendernodes
+                .append(&mut render::Image::images_to_rendernodes(&images)?);
+        }
+        self.images.append(&mut images);
+        Ok(())
+    }
+
+    /// Update the rendering after the images were modified in place.
+    pub(super) fn images_modified(&mut self) -> anyhow::Result<()> {
+        #[cfg(feature = "gtk")]
+        {
+            self.rendernodes = render::Image::images_to_rendernodes(&self.images)?;
+        }
+        Ok(())
+    }
+
+    /// Clear the rendered images and mark the rendering as dirty.
+    pub(super) fn clear(&mut self) {
+        self.images.clear();
+        #[cfg(feature = "gtk")]
+        self.rendernodes.clear();
+        self.state = RenderCompState::Dirty;
+    }
+}
+
 impl StrokeStore {
     /// Rebuild the slotmap with empty render components with the keys returned from the stroke components.
     pub fn rebuild_render_components_slotmap(&mut self) {
@@ -148,10 +197,8 @@ impl StrokeStore {
 
             match stroke.gen_images(viewport, image_scale) {
                 Ok(GeneratedStrokeImages::Partial { images, viewport }) => {
-                    match render::Image::images_to_rendernodes(&images) {
-                        Ok(rendernodes) => {
-                            render_comp.rendernodes = rendernodes;
-                            render_comp.images = images;
+                    match render_comp.set_images(images) {
+                        Ok(()) => {
                             render_comp.state = RenderCompState::ForViewport(viewport);
                         }
                         Err(e) => {
@@ -160,19 +207,15 @@ impl StrokeStore {
                         }
                     }
                 }
-                Ok(GeneratedStrokeImages::Full(images)) => {
-                    match render::Image::images_to_rendernodes(&images) {
-                        Ok(rendernodes) => {
-                            render_comp.rendernodes = rendernodes;
-                            render_comp.images = images;
-                            render_comp.state = RenderCompState::Complete;
-                        }
-                        Err(e) => {
-                            render_comp.state = RenderCompState::Dirty;
-                            log::error!(" image_to_rendernode() failed in regenerate_rendering_for_stroke(), Err: {e:?}");
-                        }
+                Ok(GeneratedStrokeImages::Full(images)) => match render_comp.set_images(images) {
+                    Ok(()) => {
+                        render_comp.state = RenderCompState::Complete;
                     }
-                }
+                    Err(e) => {
+                        render_comp.state = RenderCompState::Dirty;
+                        log::error!(" image_to_rendernode() failed in regenerate_rendering_for_stroke(), Err: {e:?}");
+                    }
+                },
                 Err(e) => {
                     log::error!("generating images for stroke with key {key:?} failed, Err: {e:?}");
                 }
@@ -275,9 +318,7 @@ impl StrokeStore {
 
                 // skip and clear image buffer if stroke is not in viewport
                 if !viewport.intersects(&stroke_bounds) {
-                    render_comp.rendernodes = vec![];
-                    render_comp.images = vec![];
-                    render_comp.state = RenderCompState::Dirty;
+                    render_comp.clear();
                     continue;
                 }
 
@@ -332,9 +373,7 @@ impl StrokeStore {
     /// Clear all rendering for all strokes.
     pub fn clear_rendering(&mut self) {
         for (_key, render_comp) in self.render_components.iter_mut() {
-            render_comp.rendernodes = vec![];
-            render_comp.images = vec![];
-            render_comp.state = RenderCompState::Dirty;
+            render_comp.clear();
         }
     }
 
@@ -356,16 +395,12 @@ impl StrokeStore {
             match stroke.as_ref() {
                 Stroke::BrushStroke(brushstroke) => {
                     match brushstroke.gen_image_for_last_segments(n_last_segments, image_scale) {
-                        Ok(Some(image)) => match render::Image::images_to_rendernodes([&image]) {
-                            Ok(mut rendernodes) => {
-                                render_comp.rendernodes.append(&mut rendernodes);
-                                render_comp.images.push(image);
-                            }
-                            Err(e) => {
+                        Ok(Some(image)) => {
+                            if let Err(e) = render_comp.append_images(vec![image]) {
                                 render_comp.state = RenderCompState::Dirty;
                                 log::error!("failed to generated rendernodes in append_rendering_last_segments(), Err: {e:?}");
                             }
-                        },
+                        }
                         Ok(None) => {}
                         Err(e) => {
                             render_comp.state = RenderCompState::Dirty;
@@ -379,7 +414,8 @@ impl StrokeStore {
                 Stroke::ShapeStroke(_)
                 | Stroke::TextStroke(_)
                 | Stroke::VectorImage(_)
-                | Stroke::BitmapImage(_) => {
+                | Stroke::BitmapImage(_)
+                | Stroke::PdfPage(_) => {
                     self.regenerate_rendering_for_stroke_threaded(
                         tasks_tx,
                         key,
@@ -398,10 +434,8 @@ impl StrokeStore {
         if let Some(render_comp) = self.render_components.get_mut(key) {
             match images {
                 GeneratedStrokeImages::Partial { images, viewport } => {
-                    match render::Image::images_to_rendernodes(&images) {
-                        Ok(rendernodes) => {
-                            render_comp.rendernodes = rendernodes;
-                            render_comp.images = images;
+                    match render_comp.set_images(images) {
+                        Ok(()) => {
                             render_comp.state = RenderCompState::ForViewport(viewport);
                         }
                         Err(e) => {
@@ -410,19 +444,15 @@ impl StrokeStore {
                         }
                     }
                 }
-                GeneratedStrokeImages::Full(images) => {
-                    match render::Image::images_to_rendernodes(&images) {
-                        Ok(rendernodes) => {
-                            render_comp.rendernodes = rendernodes;
-                            render_comp.images = images;
-                            render_comp.state = RenderCompState::Complete;
-                        }
-                        Err(e) => {
-                            log::error!("failed to generate rendernodes in replace_rendering_with_images(), Err {e:?}");
-                            render_comp.state = RenderCompState::Dirty;
-                        }
+                GeneratedStrokeImages::Full(images) => match render_comp.set_images(images) {
+                    Ok(()) => {
+                        render_comp.state = RenderCompState::Complete;
                     }
-                }
+                    Err(e) => {
+                        log::error!("failed to generate rendernodes in replace_rendering_with_images(), Err {e:?}");
+                        render_comp.state = RenderCompState::Dirty;
+                    }
+                },
             }
         }
     }
@@ -434,62 +464,21 @@ impl StrokeStore {
         if let Some(render_comp) = self.render_components.get_mut(key) {
             match images {
                 GeneratedStrokeImages::Partial {
-                    mut images,
+                    images,
                     viewport: _,
                 }
-                | GeneratedStrokeImages::Full(mut images) => {
-                    match render::Image::images_to_rendernodes(&images) {
-                        Ok(mut rendernodes) => {
-                            render_comp.rendernodes.append(&mut rendernodes);
-                            render_comp.images.append(&mut images);
-                        }
-                        Err(e) => {
-                            log::error!("failed to generate rendernodes in append_rendering_images(), Err {e:?}");
-                            render_comp.state = RenderCompState::Dirty;
-                        }
+                | GeneratedStrokeImages::Full(images) => {
+                    if let Err(e) = render_comp.append_images(images) {
+                        log::error!(
+                            "failed to generate rendernodes in append_rendering_images(), Err {e:?}"
+                        );
+                        render_comp.state = RenderCompState::Dirty;
                     }
                 }
             }
         }
     }
 
-    /// Draw all strokes on the gtk snapshot.
-    pub fn draw_strokes_to_gtk_snapshot(
-        &self,
-        snapshot: &Snapshot,
-        doc_bounds: Aabb,
-        viewport: Aabb,
-    ) {
-        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
-
-        for key in self.stroke_keys_as_rendered_intersecting_bounds(viewport) {
-            if let (Some(stroke), Some(render_comp)) = (
-                self.stroke_components.get(key),
-                self.render_components.get(key),
-            ) {
-                // if the stroke currently does not have a rendering and is will create one,
-                // draw a placeholder filled rect
-                if render_comp.rendernodes.is_empty()
-                    && matches!(
-                        render_comp.state,
-                        RenderCompState::Dirty | RenderCompState::BusyRenderingInTask
-                    )
-                {
-                    snapshot.append_color(
-                        &gdk::RGBA::from_piet_color(color::GNOME_BRIGHTS[1].with_alpha(0.13)),
-                        &graphene::Rect::from_p2d_aabb(stroke.bounds()),
-                    );
-                }
-
-                for rendernode in render_comp.rendernodes.iter() {
-                    snapshot.append_node(rendernode);
-                }
-            }
-        }
-
-        snapshot.pop();
-    }
-
     /// Draw the strokes for the given keys on the [piet::RenderContext].
     ///
     /// This always draws all strokes for the given keys, even trashed ones.
@@ -528,6 +517,60 @@ impl StrokeStore {
             }
         }
     }
+}
+
+#[cfg(feature = "gtk")]
+impl StrokeStore {
+    /// Draw all strokes on the gtk snapshot.
+    pub fn draw_strokes_to_gtk_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        doc_bounds: Aabb,
+        viewport: Aabb,
+    ) {
+        self.draw_keys_to_gtk_snapshot(
+            snapshot,
+            &self.stroke_keys_as_rendered_intersecting_bounds(viewport),
+            doc_bounds,
+        );
+    }
+
+    /// Draw the current rendering of the strokes for the given keys to a GTK snapshot, in the order of the keys.
+    pub fn draw_keys_to_gtk_snapshot(
+        &self,
+        snapshot: &Snapshot,
+        keys: &[StrokeKey],
+        doc_bounds: Aabb,
+    ) {
+        snapshot.push_clip(&graphene::Rect::from_p2d_aabb(doc_bounds));
+
+        for &key in keys {
+            if let (Some(stroke), Some(render_comp)) = (
+                self.stroke_components.get(key),
+                self.render_components.get(key),
+            ) {
+                // if the stroke currently does not have a rendering and is will create one,
+                // draw a placeholder filled rect
+                if render_comp.rendernodes.is_empty()
+                    && matches!(
+                        render_comp.state,
+                        RenderCompState::Dirty | RenderCompState::BusyRenderingInTask
+                    )
+                {
+                    snapshot.append_color(
+                        &gdk::RGBA::from_piet_color(color::GNOME_BRIGHTS[1].with_alpha(0.13)),
+                        &graphene::Rect::from_p2d_aabb(stroke.bounds()),
+                    );
+                }
+
+                for rendernode in render_comp.rendernodes.iter() {
+                    snapshot.append_node(rendernode);
+                }
+            }
+        }
+
+        snapshot.pop();
+    }
 
     /// Draw bounds, positions, etc. for all strokes for visual debugging purposes.
     pub fn draw_debug_to_gtk_snapshot(
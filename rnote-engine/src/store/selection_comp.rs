@@ -118,8 +118,9 @@ impl StrokeStore {
         let new_selected = old_selected
             .iter()
             .filter_map(|&old_key| {
+                let layer = self.chrono_components.get(old_key).map(|c| c.layer);
                 let new_key =
-                    self.insert_stroke((**self.stroke_components.get(old_key)?).clone(), None);
+                    self.insert_stroke((**self.stroke_components.get(old_key)?).clone(), layer);
                 self.set_selected(new_key, true);
 
                 // duplicate and insert the render images of the old stroke to avoid flickering
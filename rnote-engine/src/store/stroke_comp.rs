@@ -2,8 +2,9 @@
 use super::render_comp::RenderCompState;
 use super::StrokeKey;
 use crate::engine::StrokeContent;
+use crate::strokes::pdfpage::EmbeddedPdf;
 use crate::strokes::{Stroke, StrokeBehaviour};
-use crate::{render, StrokeStore, WidgetFlags};
+use crate::{StrokeStore, WidgetFlags};
 use geo::intersects::Intersects;
 use geo::prelude::Contains;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
@@ -67,11 +68,11 @@ impl StrokeStore {
             .collect()
     }
 
-    /// Storke keys in the order that they should be rendered.
+    /// Stroke keys in the order that they should be rendered.
     pub fn stroke_keys_as_rendered(&self) -> Vec<StrokeKey> {
         self.keys_sorted_chrono()
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.layer_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -79,7 +80,7 @@ impl StrokeStore {
     pub fn stroke_keys_as_rendered_intersecting_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_intersecting_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.layer_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -87,7 +88,7 @@ impl StrokeStore {
     pub fn stroke_keys_as_rendered_in_bounds(&self, bounds: Aabb) -> Vec<StrokeKey> {
         self.keys_sorted_chrono_in_bounds(bounds)
             .into_iter()
-            .filter(|&key| !(self.trashed(key).unwrap_or(false)))
+            .filter(|&key| !(self.trashed(key).unwrap_or(false)) && !self.layer_hidden(key))
             .collect::<Vec<StrokeKey>>()
     }
 
@@ -207,13 +208,10 @@ impl StrokeStore {
                     image.translate(offset);
                 }
 
-                match render::Image::images_to_rendernodes(&render_comp.images) {
-                    Ok(rendernodes) => {
-                        render_comp.rendernodes = rendernodes;
-                    }
-                    Err(e) => log::error!(
-                        "images_to_rendernode() failed in translate_strokes_images() with Err: {e:?}"
-                    ),
+                if let Err(e) = render_comp.images_modified() {
+                    log::error!(
+                        "updating the rendering failed in translate_strokes_images() with Err: {e:?}"
+                    );
                 }
             }
         });
@@ -315,6 +313,121 @@ impl StrokeStore {
         widget_flags
     }
 
+    /// Smooth the brush strokes for the given keys by refitting their paths into cubic bezier segments.
+    ///
+    /// The input elements deviate at most by `tolerance` from the new paths, the pressure is preserved.
+    /// Strokes that are not brush strokes are ignored.
+    ///
+    /// The strokes then need to update their rendering.
+    pub fn smooth_brushstrokes(&mut self, keys: &[StrokeKey], tolerance: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        // Filter first, so that other strokes are not cloned out of the history by `make_mut()`
+        let brushstroke_keys = keys
+            .iter()
+            .copied()
+            .filter(|&key| matches!(self.get_stroke_ref(key), Some(Stroke::BrushStroke(_))))
+            .collect::<Vec<StrokeKey>>();
+        if brushstroke_keys.is_empty() {
+            return widget_flags;
+        }
+
+        let stroke_components = Arc::make_mut(&mut self.stroke_components);
+        for &key in brushstroke_keys.iter() {
+            if let Some(Stroke::BrushStroke(brushstroke)) =
+                stroke_components.get_mut(key).map(Arc::make_mut)
+            {
+                let path = brushstroke.path.fit_cubbez(tolerance);
+                brushstroke.replace_path(path);
+            }
+        }
+
+        self.update_geometry_for_strokes(&brushstroke_keys);
+        widget_flags.redraw = true;
+        widget_flags.store_modified = true;
+
+        widget_flags
+    }
+
+    /// Recognize shapes in the brush strokes for the given keys and replace them with the recognized shape strokes.
+    ///
+    /// The replaced strokes keep their keys, so their layer and selection state is preserved.
+    /// `tolerance` is the maximum deviation of a path from the shape, relative to the size of the path.
+    ///
+    /// The strokes then need to update their rendering.
+    pub fn recognize_shapes(&mut self, keys: &[StrokeKey], tolerance: f64) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let replaced_keys = keys
+            .iter()
+            .filter_map(|&key| {
+                let Some(Stroke::BrushStroke(brushstroke)) = self.get_stroke_ref(key) else {
+                    return None;
+                };
+                let shapestroke = brushstroke.recognize_shape(tolerance)?;
+                *self.get_stroke_mut(key)? = Stroke::ShapeStroke(shapestroke);
+                Some(key)
+            })
+            .collect::<Vec<StrokeKey>>();
+
+        if !replaced_keys.is_empty() {
+            self.update_geometry_for_strokes(&replaced_keys);
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+        }
+
+        widget_flags
+    }
+
+    /// Ungroup the vector images for the given keys into editable strokes, which replace the images.
+    ///
+    /// The new strokes are inserted into the layer of their image and selected if the image was selected.
+    /// See [crate::strokes::VectorImage::ungroup].
+    ///
+    /// The strokes then need to update their rendering.
+    pub fn ungroup_vectorimages(&mut self, keys: &[StrokeKey]) -> WidgetFlags {
+        let mut widget_flags = WidgetFlags::default();
+
+        let ungrouped = keys
+            .iter()
+            .filter_map(|&key| {
+                let Some(Stroke::VectorImage(vectorimage)) = self.get_stroke_ref(key) else {
+                    return None;
+                };
+                match vectorimage.ungroup() {
+                    Ok(strokes) => Some((key, strokes)),
+                    Err(e) => {
+                        log::error!(
+                            "ungrouping vectorimage failed in ungroup_vectorimages(), Err: {e:?}"
+                        );
+                        None
+                    }
+                }
+            })
+            .collect::<Vec<(StrokeKey, Vec<Stroke>)>>();
+
+        let mut inserted_keys = vec![];
+        for (key, strokes) in ungrouped {
+            let layer = self.stroke_layer(key);
+            let selected = self.selected(key).unwrap_or(false);
+            for stroke in strokes {
+                let new_key = self.insert_stroke(stroke, layer);
+                self.set_selected(new_key, selected);
+                inserted_keys.push(new_key);
+            }
+            self.set_selected(key, false);
+            self.set_trashed(key, true);
+        }
+
+        if !inserted_keys.is_empty() {
+            self.update_geometry_for_strokes(&inserted_keys);
+            widget_flags.redraw = true;
+            widget_flags.store_modified = true;
+        }
+
+        widget_flags
+    }
+
     /// Rotate the stroke rendering images.
     ///
     /// The strokes then need to update their rendering.
@@ -332,13 +445,10 @@ impl StrokeStore {
                     image.rotate(angle, center);
                 }
 
-                match render::Image::images_to_rendernodes(&render_comp.images) {
-                    Ok(rendernodes) => {
-                        render_comp.rendernodes = rendernodes;
-                    }
-                    Err(e) => log::error!(
-                        "images_to_rendernode() failed in rotate_strokes() with Err: {e:?}"
-                    ),
+                if let Err(e) = render_comp.images_modified() {
+                    log::error!(
+                        "updating the rendering failed in rotate_strokes() with Err: {e:?}"
+                    );
                 }
             }
         });
@@ -374,13 +484,10 @@ impl StrokeStore {
                     image.scale(scale);
                 }
 
-                match render::Image::images_to_rendernodes(&render_comp.images) {
-                    Ok(rendernodes) => {
-                        render_comp.rendernodes = rendernodes;
-                    }
-                    Err(e) => log::error!(
-                        "images_to_rendernode() failed in rotate_strokes() with Err: {e:?}"
-                    ),
+                if let Err(e) = render_comp.images_modified() {
+                    log::error!(
+                        "updating the rendering failed in rotate_strokes() with Err: {e:?}"
+                    );
                 }
             }
         });
@@ -484,13 +591,10 @@ impl StrokeStore {
                     image.translate(old_image_bounds.center().coords);
                 }
 
-                match render::Image::images_to_rendernodes(&render_comp.images) {
-                    Ok(rendernodes) => {
-                        render_comp.rendernodes = rendernodes;
-                    }
-                    Err(e) => log::error!(
-                        "images_to_rendernode() failed in resize_strokes() with Err: {e:?}"
-                    ),
+                if let Err(e) = render_comp.images_modified() {
+                    log::error!(
+                        "updating the rendering failed in resize_strokes() with Err: {e:?}"
+                    );
                 }
             }
         });
@@ -517,8 +621,8 @@ impl StrokeStore {
         self.keys_sorted_chrono_intersecting_bounds(viewport)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or on a hidden or locked layer
+                if self.trashed(key)? || self.layer_hidden(key) || self.layer_locked(key) {
                     return None;
                 }
 
@@ -568,8 +672,8 @@ impl StrokeStore {
         self.keys_sorted_chrono_intersecting_bounds(viewport)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or on a hidden or locked layer
+                if self.trashed(key)? || self.layer_hidden(key) || self.layer_locked(key) {
                     return None;
                 }
 
@@ -601,8 +705,8 @@ impl StrokeStore {
         self.keys_sorted_chrono_intersecting_bounds(viewport)
             .into_iter()
             .filter_map(|key| {
-                // skip if stroke is trashed
-                if self.trashed(key)? {
+                // skip if stroke is trashed or on a hidden or locked layer
+                if self.trashed(key)? || self.layer_hidden(key) || self.layer_locked(key) {
                     return None;
                 }
 
@@ -627,6 +731,8 @@ impl StrokeStore {
     }
 
     /// Return the keys for strokes where the given coord is inside at least one of their hitboxes.
+    ///
+    /// Strokes on locked layers are skipped.
     pub fn stroke_hitboxes_contain_coord(
         &self,
         viewport: Aabb,
@@ -635,6 +741,9 @@ impl StrokeStore {
         self.stroke_keys_as_rendered_intersecting_bounds(viewport)
             .into_iter()
             .filter(|&key| {
+                if self.layer_locked(key) {
+                    return false;
+                }
                 if let Some(stroke) = self.stroke_components.get(key) {
                     stroke
                         .hitboxes()
@@ -667,7 +776,7 @@ impl StrokeStore {
             .filter_map(|k| self.stroke_components.get(*k).map(Arc::clone))
             .collect();
 
-        StrokeContent { strokes }
+        StrokeContent::new(strokes)
     }
 
     /// Cut the strokes for the given keys and return them as stroke content.
@@ -681,7 +790,7 @@ impl StrokeStore {
             })
             .collect();
 
-        StrokeContent { strokes }
+        StrokeContent::new(strokes)
     }
 
     /// Paste the clipboard content as a selection.
@@ -701,13 +810,19 @@ impl StrokeStore {
             .strokes
             .iter()
             .fold(Aabb::new_invalid(), |acc, s| acc.merged(&s.bounds()));
+        let mut embedded_pdfs = clipboard_content.embedded_pdfs;
+        embedded_pdfs.extend(EmbeddedPdf::clipboard_pdfs());
 
         clipboard_content
             .strokes
             .into_iter()
             .map(|s| {
                 let offset = s.bounds().mins.coords - clipboard_bounds.mins.coords;
-                let key = self.insert_stroke((*s).clone(), None);
+                let mut stroke = (*s).clone();
+                if let Stroke::PdfPage(pdfpage) = &mut stroke {
+                    pdfpage.resolve_pdf(&embedded_pdfs);
+                }
+                let key = self.insert_stroke(stroke, None);
                 self.set_stroke_pos(key, pos);
                 self.translate_strokes(&[key], offset);
                 self.set_selected(key, true);
@@ -90,10 +90,15 @@ impl StrokeStore {
     }
 
     /// Trash strokes that collide with the given bounds.
+    ///
+    /// Strokes on locked layers are skipped.
     pub fn trash_colliding_strokes(&mut self, eraser_bounds: Aabb, viewport: Aabb) -> WidgetFlags {
         let mut widget_flags = WidgetFlags::default();
 
         self.stroke_keys_as_rendered_intersecting_bounds(viewport)
+            .into_iter()
+            .filter(|&key| !self.layer_locked(key))
+            .collect::<Vec<StrokeKey>>()
             .into_iter()
             .for_each(|key| {
                 let mut trash_current_stroke = false;
@@ -113,8 +118,10 @@ impl StrokeStore {
                             }
                         }
                         // Ignore other strokes when trashing with the Eraser
-                        Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {
-                        }
+                        Stroke::TextStroke(_)
+                        | Stroke::VectorImage(_)
+                        | Stroke::BitmapImage(_)
+                        | Stroke::PdfPage(_) => {}
                     }
                 }
 
@@ -130,6 +137,7 @@ impl StrokeStore {
 
     /// Remove colliding stroke segments with the given bounds.
     /// The stroke is then split. Strokes that don't have segments are trashed completely.
+    /// Strokes on locked layers are skipped.
     ///
     /// Returns the keys of all created or modified strokes.
     ///
@@ -145,6 +153,9 @@ impl StrokeStore {
         let new_strokes = self
             .stroke_keys_as_rendered_intersecting_bounds(viewport)
             .into_iter()
+            .filter(|&key| !self.layer_locked(key))
+            .collect::<Vec<StrokeKey>>()
+            .into_iter()
             .flat_map(|key| {
                 let Some(stroke) = Arc::make_mut(&mut self.stroke_components)
                     .get_mut(key)
@@ -229,7 +240,10 @@ impl StrokeStore {
                         }
                     }
                     // Ignore other strokes when trashing with the Eraser
-                    Stroke::TextStroke(_) | Stroke::VectorImage(_) | Stroke::BitmapImage(_) => {}
+                    Stroke::TextStroke(_)
+                    | Stroke::VectorImage(_)
+                    | Stroke::BitmapImage(_)
+                    | Stroke::PdfPage(_) => {}
                 }
 
                 if trash_current_stroke {
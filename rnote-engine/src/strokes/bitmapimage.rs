@@ -2,10 +2,11 @@
 use super::strokebehaviour::{self, GeneratedStrokeImages};
 use super::{Stroke, StrokeBehaviour};
 use crate::document::Format;
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PdfPageSource};
 use crate::render;
 use crate::DrawBehaviour;
 use anyhow::Context;
+use once_cell::sync::OnceCell;
 use p2d::bounding_volume::{Aabb, BoundingVolume};
 use piet::RenderContext;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
@@ -16,26 +17,38 @@ use rnote_compose::shapes::ShapeBehaviour;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::TransformBehaviour;
 use serde::{Deserialize, Serialize};
+use std::io;
 use std::ops::Range;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default, rename = "bitmapimage")]
 pub struct BitmapImage {
-    /// The bitmap image.
+    /// The encoded image, as it was imported.
     ///
-    /// The bounds field of the image should not be used to determine the stroke bounds.
-    /// Use rectangle.bounds() instead.
-    #[serde(rename = "image")]
-    pub image: render::Image,
+    /// Is (de)serialized with base64 encoding. Png, Jpeg and WebP images are kept as they are,
+    /// other formats are losslessly re-encoded as Png.
+    #[serde(rename = "encoded_image", with = "crate::utils::glib_bytes_base64")]
+    encoded_image: glib::Bytes,
     #[serde(rename = "rectangle")]
     pub rectangle: Rectangle,
+    /// The Pdf page the image was generated from, when it was imported as page background.
+    #[serde(rename = "pdf_page_source")]
+    pub pdf_page_source: Option<PdfPageSource>,
+    /// The decoded image, cached after it is decoded when drawing for the first time.
+    ///
+    /// The bounds field of the image should not be used to determine the stroke bounds.
+    /// Use rectangle.bounds() instead.
+    #[serde(skip)]
+    image: OnceCell<render::Image>,
 }
 
 impl Default for BitmapImage {
     fn default() -> Self {
         Self {
-            image: render::Image::default(),
+            encoded_image: glib::Bytes::from_owned(Vec::new()),
             rectangle: Rectangle::default(),
+            pdf_page_source: None,
+            image: OnceCell::new(),
         }
     }
 }
@@ -106,15 +119,16 @@ impl DrawBehaviour for BitmapImage {
     fn draw(&self, cx: &mut impl piet::RenderContext, _image_scale: f64) -> anyhow::Result<()> {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
-        let piet_image_format = piet::ImageFormat::try_from(self.image.memory_format)?;
+        let image = self.image()?;
+        let piet_image_format = piet::ImageFormat::try_from(image.memory_format)?;
 
         cx.transform(self.rectangle.transform.affine.to_kurbo());
 
         let piet_image = cx
             .make_image(
-                self.image.pixel_width as usize,
-                self.image.pixel_height as usize,
-                &self.image.data,
+                image.pixel_width as usize,
+                image.pixel_height as usize,
+                &image.data,
                 piet_image_format,
             )
             .map_err(|e| anyhow::anyhow!("{e:?}"))?;
@@ -152,23 +166,69 @@ impl TransformBehaviour for BitmapImage {
 }
 
 impl BitmapImage {
+    /// Create a new bitmap image from the encoded image bytes.
+    ///
+    /// Png, Jpeg and WebP images are kept as they are, other formats are losslessly re-encoded as Png.
+    /// Decoding is deferred until the image is drawn.
+    pub fn new(bytes: &[u8], rectangle: Rectangle) -> anyhow::Result<Self> {
+        let format = image::guess_format(bytes)?;
+
+        let (encoded_image, image) = match format {
+            image::ImageFormat::Png | image::ImageFormat::Jpeg | image::ImageFormat::WebP => {
+                (glib::Bytes::from(bytes), OnceCell::new())
+            }
+            _ => {
+                let decoded = render::Image::try_from_encoded_bytes(bytes)?;
+                let png_bytes = decoded
+                    .clone()
+                    .into_encoded_bytes(image::ImageOutputFormat::Png)?;
+                (
+                    glib::Bytes::from_owned(png_bytes),
+                    OnceCell::with_value(decoded),
+                )
+            }
+        };
+
+        Ok(Self {
+            encoded_image,
+            rectangle,
+            pdf_page_source: None,
+            image,
+        })
+    }
+
+    /// The encoded image.
+    pub fn encoded_image(&self) -> &glib::Bytes {
+        &self.encoded_image
+    }
+
+    /// The decoded image. It is decoded on first access and cached.
+    pub fn image(&self) -> anyhow::Result<&render::Image> {
+        self.image
+            .get_or_try_init(|| render::Image::try_from_encoded_bytes(&self.encoded_image))
+    }
+
     pub fn import_from_image_bytes(
         bytes: &[u8],
         pos: na::Vector2<f64>,
         size: Option<na::Vector2<f64>>,
     ) -> Result<Self, anyhow::Error> {
-        let image = render::Image::try_from_encoded_bytes(bytes)?;
-
-        let size = size.unwrap_or_else(|| {
-            na::vector![f64::from(image.pixel_width), f64::from(image.pixel_height)]
-        });
+        let size = match size {
+            Some(size) => size,
+            None => {
+                let (pixel_width, pixel_height) = image::io::Reader::new(io::Cursor::new(bytes))
+                    .with_guessed_format()?
+                    .into_dimensions()?;
+                na::vector![f64::from(pixel_width), f64::from(pixel_height)]
+            }
+        };
 
         let rectangle = Rectangle {
             cuboid: p2d::shape::Cuboid::new(size * 0.5),
             transform: Transform::new_w_isometry(na::Isometry2::new(pos + size * 0.5, 0.0)),
         };
 
-        Ok(Self { image, rectangle })
+        Self::new(bytes, rectangle)
     }
 
     pub fn import_from_pdf_bytes(
@@ -230,7 +290,10 @@ impl BitmapImage {
                             cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
                             cx.paint()?;
 
-                            page.render_for_printing(&cx);
+                            page.render_for_printing_with_options(
+                                &cx,
+                                pdf_import_prefs.poppler_print_flags(),
+                            );
 
                             // Draw outline around page
                             cx.set_source_rgba(
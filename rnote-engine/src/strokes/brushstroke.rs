@@ -1,6 +1,6 @@
 // Imports
 use super::strokebehaviour::GeneratedStrokeImages;
-use super::StrokeBehaviour;
+use super::{ShapeStroke, StrokeBehaviour};
 use crate::DrawBehaviour;
 use crate::{
     render::{self},
@@ -11,7 +11,8 @@ use piet::RenderContext;
 use rnote_compose::helpers::{AabbHelpers, Vector2Helpers};
 use rnote_compose::penpath::{Element, Segment};
 use rnote_compose::shapes::ShapeBehaviour;
-use rnote_compose::style::Composer;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::{Composer, PressureCurve};
 use rnote_compose::transform::TransformBehaviour;
 use rnote_compose::{PenPath, Style};
 use serde::{Deserialize, Serialize};
@@ -23,6 +24,11 @@ pub struct BrushStroke {
     pub path: PenPath,
     #[serde(default, rename = "style")]
     pub style: Style,
+    /// Whether the path is filled with the fill color of the smooth style.
+    ///
+    /// Only set for paths imported from Svgs, strokes drawn with the brush are never filled.
+    #[serde(default, rename = "filled")]
+    pub filled: bool,
     // since the path can have many hitboxes, we store them here and update them when the stroke geometry changes
     #[serde(skip)]
     hitboxes: Vec<Aabb>,
@@ -62,6 +68,8 @@ impl StrokeBehaviour for BrushStroke {
 
         let image_size_condition = bounds_extents[0] < IMAGES_SIZE_THRESHOLD / image_scale
             && bounds_extents[1] < IMAGES_SIZE_THRESHOLD / image_scale;
+        // the fill can't be split into the segments
+        let filled_condition = self.filled;
 
         //
         let stroke_width_condition = self.style.stroke_width()
@@ -69,12 +77,13 @@ impl StrokeBehaviour for BrushStroke {
             || self.style.stroke_width() > IMAGES_STROKE_WIDTH_BOUNDS_THRESHOLD * bounds_extents[1];
 
         // if these conditions evaluate true the stroke is rendered as a single imaeg
-        let images = if image_size_condition || stroke_width_condition {
+        let images = if image_size_condition || stroke_width_condition || filled_condition {
             // generate a single image when bounds are smaller than threshold
             match &self.style {
                 Style::Smooth(options) => {
                     let image = render::Image::gen_with_piet(
                         |piet_cx| {
+                            self.draw_fill(piet_cx, options);
                             self.path.draw_composed(piet_cx, options);
                             Ok(())
                         },
@@ -223,7 +232,10 @@ impl DrawBehaviour for BrushStroke {
         cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
 
         match &self.style {
-            Style::Smooth(options) => self.path.draw_composed(cx, options),
+            Style::Smooth(options) => {
+                self.draw_fill(cx, options);
+                self.path.draw_composed(cx, options);
+            }
             Style::Rough(_) => {
                 // Rough style currently unsupported for pen paths
                 unimplemented!()
@@ -274,6 +286,7 @@ impl BrushStroke {
         let mut new_brushstroke = Self {
             path,
             style,
+            filled: false,
             hitboxes: vec![],
         };
         new_brushstroke.update_geometry();
@@ -281,6 +294,54 @@ impl BrushStroke {
         new_brushstroke
     }
 
+    /// Fills the path with the fill color, if the stroke is filled.
+    fn draw_fill(&self, cx: &mut impl piet::RenderContext, options: &SmoothOptions) {
+        if !self.filled {
+            return;
+        }
+        if let Some(fill_color) = options.fill_color {
+            let fill_brush = cx.solid_brush(fill_color.into());
+            cx.fill(self.path.to_kurbo(), &fill_brush);
+        }
+    }
+
+    /// Recognizes a shape that the path resembles and converts it into a shape stroke.
+    ///
+    /// The shape stroke has the smooth style, with the stroke color of the brush stroke and its stroke width at the mean pressure.
+    /// `tolerance` is the maximum deviation of the path from the shape, relative to the size of the path.
+    pub fn recognize_shape(&self, tolerance: f64) -> Option<ShapeStroke> {
+        let shape = self.path.recognize_shape(tolerance)?;
+
+        let elements = self.path.clone().into_elements();
+        let mean_pressure =
+            elements.iter().map(|el| el.pressure).sum::<f64>() / elements.len() as f64;
+        let (stroke_width, stroke_color, pressure_curve) = match &self.style {
+            Style::Smooth(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                options.pressure_curve,
+            ),
+            Style::Rough(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                PressureCurve::Const,
+            ),
+            Style::Textured(options) => (
+                options.stroke_width,
+                options.stroke_color,
+                options.pressure_curve,
+            ),
+        };
+        let options = SmoothOptions {
+            stroke_width: pressure_curve.apply(stroke_width, mean_pressure),
+            stroke_color,
+            fill_color: None,
+            pressure_curve: PressureCurve::Const,
+        };
+
+        Some(ShapeStroke::new(shape, Style::Smooth(options)))
+    }
+
     pub fn push_segment(&mut self, segment: Segment) {
         self.path.segments.push(segment);
     }
@@ -0,0 +1,302 @@
+//! Golden image tests for the rendering of all stroke types and styles.
+//!
+//! Every stroke is rendered with [StrokeBehaviour::gen_images] onto a white background and compared
+//! against the reference Png with the same name in `misc/file-tests/render-goldens`.
+//! Run `RNOTE_UPDATE_GOLDENS=1 cargo test -p rnote-engine goldens` to regenerate the references
+//! after an intended rendering change, or to generate the reference of a new test. Missing references fail the test.
+
+// Imports
+use super::bitmapimage::BitmapImage;
+use super::brushstroke::BrushStroke;
+use super::shapestroke::ShapeStroke;
+use super::strokebehaviour::GeneratedStrokeImages;
+use super::textstroke::{FontStyle, RangedTextAttribute, TextAttribute, TextStroke, TextStyle};
+use super::vectorimage::VectorImage;
+use super::{Stroke, StrokeBehaviour};
+use crate::utils::goldens::{check_golden, UPDATE_GOLDENS_ENV};
+use crate::{render, DrawBehaviour};
+use p2d::bounding_volume::BoundingVolume;
+use piet::RenderContext;
+use rnote_compose::helpers::AabbHelpers;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::{Ellipse, Line, Rectangle, Shape, ShapeBehaviour};
+use rnote_compose::style::rough::roughoptions::FillStyle;
+use rnote_compose::style::rough::RoughOptions;
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::textured::TexturedOptions;
+use rnote_compose::{Color, PenPath, Style, Transform};
+use std::path::PathBuf;
+
+const RENDER_GOLDENS_DIR: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../misc/file-tests/render-goldens"
+);
+const IMAGE_SCALE: f64 = 1.0;
+const RENDER_MARGIN: f64 = 8.0;
+const SEED: u64 = 42;
+/// The perceived color difference in the range [0.0, 1.0] above which two pixels are considered different.
+const PIXEL_DIFF_THRESHOLD: f64 = 0.1;
+/// The ratio of different pixels that is tolerated, to allow for differences in anti-aliasing and font rendering.
+const DIFF_PIXELS_RATIO_MAX: f64 = 0.01;
+
+fn render_stroke(stroke: &Stroke) -> image::RgbaImage {
+    let bounds = stroke.bounds().loosened(RENDER_MARGIN);
+    let images = match stroke.gen_images(bounds, IMAGE_SCALE).unwrap() {
+        GeneratedStrokeImages::Partial { images, .. } | GeneratedStrokeImages::Full(images) => {
+            images
+        }
+    };
+
+    render::Image::gen_with_piet(
+        |piet_cx| {
+            piet_cx.fill(bounds.to_kurbo_rect(), &piet::Color::WHITE);
+            for image in images.iter() {
+                image.draw(piet_cx, IMAGE_SCALE)?;
+            }
+            Ok(())
+        },
+        bounds,
+        IMAGE_SCALE,
+    )
+    .unwrap()
+    .to_imgbuf()
+    .unwrap()
+}
+
+/// The perceived difference of two opaque pixels in the range [0.0, 1.0], using the YIQ color space.
+fn pixel_diff(a: &image::Rgba<u8>, b: &image::Rgba<u8>) -> f64 {
+    let yiq = |p: &image::Rgba<u8>| {
+        let (r, g, b) = (p[0] as f64, p[1] as f64, p[2] as f64);
+        (
+            0.29889531 * r + 0.58662247 * g + 0.11448223 * b,
+            0.59597799 * r - 0.27417610 * g - 0.32180189 * b,
+            0.21147017 * r - 0.52261711 * g + 0.31114694 * b,
+        )
+    };
+    let (ya, ia, qa) = yiq(a);
+    let (yb, ib, qb) = yiq(b);
+    // the maximum possible value of the weighted squared difference
+    const DIFF_MAX: f64 = 35215.0;
+
+    (0.5053 * (ya - yb).powi(2) + 0.299 * (ia - ib).powi(2) + 0.1957 * (qa - qb).powi(2)) / DIFF_MAX
+}
+
+fn check_render_golden(name: &str, stroke: Stroke) {
+    let rendered = render_stroke(&stroke);
+    let reference_file = PathBuf::from(RENDER_GOLDENS_DIR).join(format!("{name}.png"));
+
+    check_golden(
+        &reference_file,
+        |reference_file| Ok(rendered.save(reference_file)?),
+        |reference_file| {
+            let reference = image::open(reference_file).unwrap().into_rgba8();
+            assert_eq!(
+                rendered.dimensions(),
+                reference.dimensions(),
+                "rendered size of `{name}` does not match the reference"
+            );
+
+            let n_diff_pixels = rendered
+                .pixels()
+                .zip(reference.pixels())
+                .filter(|(a, b)| pixel_diff(a, b) > PIXEL_DIFF_THRESHOLD)
+                .count();
+            let diff_pixels_ratio =
+                n_diff_pixels as f64 / (rendered.width() as f64 * rendered.height() as f64);
+            assert!(
+                diff_pixels_ratio <= DIFF_PIXELS_RATIO_MAX,
+                "rendering of `{name}` differs from the reference in {:.2}% of the pixels, set {UPDATE_GOLDENS_ENV} to regenerate it",
+                diff_pixels_ratio * 100.0
+            );
+        },
+    );
+}
+
+fn wave_penpath() -> PenPath {
+    let element = |i: usize| {
+        let t = i as f64 / 40.0;
+        Element::new(
+            na::vector![
+                20.0 + 200.0 * t,
+                60.0 + 30.0 * (t * std::f64::consts::TAU).sin()
+            ],
+            0.2 + 0.8 * t,
+        )
+    };
+
+    PenPath::new_w_segments(
+        element(0),
+        (1..=40).map(|i| Segment::LineTo { end: element(i) }),
+    )
+}
+
+fn rectangle() -> Shape {
+    Shape::Rectangle(Rectangle::from_p2d_aabb(p2d::bounding_volume::Aabb::new(
+        na::point![20.0, 20.0],
+        na::point![180.0, 120.0],
+    )))
+}
+
+fn rough_style(fill_style: FillStyle) -> Style {
+    Style::Rough(RoughOptions {
+        stroke_color: Some(Color::BLACK),
+        stroke_width: 2.0,
+        fill_color: Some(Color::new(0.2, 0.4, 0.8, 1.0)),
+        fill_style,
+        seed: Some(SEED),
+        ..Default::default()
+    })
+}
+
+#[test]
+fn brushstroke_smooth() {
+    let style = Style::Smooth(SmoothOptions {
+        stroke_width: 6.0,
+        stroke_color: Some(Color::new(0.8, 0.1, 0.1, 1.0)),
+        ..Default::default()
+    });
+    check_render_golden(
+        "brushstroke-smooth",
+        Stroke::BrushStroke(BrushStroke::from_penpath(wave_penpath(), style)),
+    );
+}
+
+#[test]
+fn brushstroke_textured() {
+    let style = Style::Textured(TexturedOptions {
+        seed: Some(SEED),
+        stroke_width: 12.0,
+        stroke_color: Some(Color::new(0.1, 0.5, 0.2, 1.0)),
+        ..Default::default()
+    });
+    check_render_golden(
+        "brushstroke-textured",
+        Stroke::BrushStroke(BrushStroke::from_penpath(wave_penpath(), style)),
+    );
+}
+
+#[test]
+fn shapestroke_smooth() {
+    let style = Style::Smooth(SmoothOptions {
+        stroke_width: 4.0,
+        stroke_color: Some(Color::BLACK),
+        fill_color: Some(Color::new(0.9, 0.7, 0.1, 1.0)),
+        ..Default::default()
+    });
+    let ellipse = Shape::Ellipse(Ellipse {
+        radii: na::vector![80.0, 40.0],
+        transform: Transform::new_w_isometry(na::Isometry2::new(na::vector![100.0, 60.0], 0.0)),
+    });
+    let line = Shape::Line(Line {
+        start: na::vector![20.0, 20.0],
+        end: na::vector![180.0, 100.0],
+    });
+
+    check_render_golden(
+        "shapestroke-smooth-rectangle",
+        Stroke::ShapeStroke(ShapeStroke::new(rectangle(), style.clone())),
+    );
+    check_render_golden(
+        "shapestroke-smooth-ellipse",
+        Stroke::ShapeStroke(ShapeStroke::new(ellipse, style.clone())),
+    );
+    check_render_golden(
+        "shapestroke-smooth-line",
+        Stroke::ShapeStroke(ShapeStroke::new(line, style)),
+    );
+}
+
+#[test]
+fn shapestroke_rough() {
+    for (name, fill_style) in [
+        ("solid", FillStyle::Solid),
+        ("hachure", FillStyle::Hachure),
+        ("zig-zag", FillStyle::ZigZag),
+        ("zig-zag-line", FillStyle::ZigZagLine),
+        ("crosshatch", FillStyle::Crosshatch),
+        ("dots", FillStyle::Dots),
+        ("dashed", FillStyle::Dashed),
+    ] {
+        check_render_golden(
+            &format!("shapestroke-rough-{name}"),
+            Stroke::ShapeStroke(ShapeStroke::new(rectangle(), rough_style(fill_style))),
+        );
+    }
+}
+
+#[test]
+fn textstroke_ranged_attributes() {
+    let text = String::from("Rnote renders\nbold, italic, underlined and colored text.");
+    let ranged_text_attributes = vec![
+        RangedTextAttribute {
+            range: 14..18,
+            attribute: TextAttribute::FontWeight(800),
+        },
+        RangedTextAttribute {
+            range: 20..26,
+            attribute: TextAttribute::Style(FontStyle::Italic),
+        },
+        RangedTextAttribute {
+            range: 28..38,
+            attribute: TextAttribute::Underline(true),
+        },
+        RangedTextAttribute {
+            range: 43..50,
+            attribute: TextAttribute::TextColor(Color::new(0.8, 0.1, 0.1, 1.0)),
+        },
+        RangedTextAttribute {
+            range: 0..5,
+            attribute: TextAttribute::FontSize(40.0),
+        },
+    ];
+    let text_style = TextStyle {
+        font_size: 24.0,
+        max_width: Some(400.0),
+        ranged_text_attributes,
+        ..Default::default()
+    };
+
+    check_render_golden(
+        "textstroke-ranged-attributes",
+        Stroke::TextStroke(TextStroke::new(text, na::vector![20.0, 20.0], text_style)),
+    );
+}
+
+#[test]
+fn vectorimage() {
+    let svg_data = r##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="120" viewBox="0 0 200 120">
+    <rect x="10" y="10" width="100" height="60" fill="#3584e4" stroke="#1c71d8" stroke-width="4"/>
+    <circle cx="140" cy="70" r="40" fill="#e5a50a" fill-opacity="0.7"/>
+    <path d="M 10 110 C 60 60, 120 140, 190 90" fill="none" stroke="#000000" stroke-width="3"/>
+</svg>"##;
+
+    check_render_golden(
+        "vectorimage",
+        Stroke::VectorImage(
+            VectorImage::import_from_svg_data(svg_data, na::vector![20.0, 20.0], None).unwrap(),
+        ),
+    );
+}
+
+#[test]
+fn bitmapimage() {
+    let gradient = image::RgbaImage::from_fn(64, 48, |x, y| {
+        image::Rgba([(x * 4) as u8, (y * 5) as u8, 128, 255])
+    });
+    let mut png_bytes = std::io::Cursor::new(vec![]);
+    gradient
+        .write_to(&mut png_bytes, image::ImageOutputFormat::Png)
+        .unwrap();
+
+    check_render_golden(
+        "bitmapimage",
+        Stroke::BitmapImage(
+            BitmapImage::import_from_image_bytes(
+                png_bytes.get_ref(),
+                na::vector![20.0, 20.0],
+                Some(na::vector![128.0, 96.0]),
+            )
+            .unwrap(),
+        ),
+    );
+}
@@ -1,6 +1,9 @@
 // Modules
 pub mod bitmapimage;
 pub mod brushstroke;
+#[cfg(test)]
+mod goldens;
+pub mod pdfpage;
 pub mod shapestroke;
 pub mod stroke;
 pub mod strokebehaviour;
@@ -10,6 +13,7 @@ pub mod vectorimage;
 // Re-exports
 pub use bitmapimage::BitmapImage;
 pub use brushstroke::BrushStroke;
+pub use pdfpage::PdfPage;
 pub use shapestroke::ShapeStroke;
 pub use stroke::Stroke;
 pub use strokebehaviour::StrokeBehaviour;
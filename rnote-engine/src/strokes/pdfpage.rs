@@ -0,0 +1,483 @@
+// Imports
+use super::strokebehaviour::{self, GeneratedStrokeImages};
+use super::{Stroke, StrokeBehaviour};
+use crate::document::Format;
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PdfPageSource};
+use crate::render;
+use crate::DrawBehaviour;
+use p2d::bounding_volume::{Aabb, BoundingVolume};
+use rnote_compose::color;
+use rnote_compose::helpers::{AabbHelpers, Affine2Helpers};
+use rnote_compose::shapes::Rectangle;
+use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::transform::Transform;
+use rnote_compose::transform::TransformBehaviour;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::ops::Range;
+use std::sync::Mutex;
+
+thread_local! {
+    /// The Pdf that was last loaded for rendering on this thread, together with its id.
+    ///
+    /// Parsing the Pdf is expensive, and the pages of the same Pdf are usually rendered one after another.
+    static POPPLER_DOCUMENT_CACHE: RefCell<Option<(u64, poppler::Document)>> = RefCell::new(None);
+}
+
+/// The embedded Pdfs of the stroke content that was last copied to the clipboard.
+///
+/// The clipboard content only references them by id, so that the Pdfs are not serialized on every copy.
+static CLIPBOARD_EMBEDDED_PDFS: Mutex<Vec<EmbeddedPdf>> = Mutex::new(Vec::new());
+
+/// A Pdf that is embedded into the document once, and referenced by the Pdf pages imported from it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename = "embedded_pdf")]
+pub struct EmbeddedPdf {
+    /// The id which the Pdf pages reference. It is derived from the content of the Pdf.
+    #[serde(rename = "id")]
+    pub id: u64,
+    /// The Pdf bytes. Is (de)serialized with base64 encoding.
+    #[serde(rename = "bytes", with = "crate::utils::glib_bytes_base64")]
+    pub bytes: glib::Bytes,
+}
+
+impl EmbeddedPdf {
+    pub fn new(bytes: glib::Bytes) -> Self {
+        Self {
+            id: Self::content_id(&bytes),
+            bytes,
+        }
+    }
+
+    /// The id of the Pdf with the given bytes, the leading 64 bits of their Sha256 checksum.
+    ///
+    /// It is saved in the document, so it must stay the same across versions and platforms.
+    fn content_id(bytes: &glib::Bytes) -> u64 {
+        glib::compute_checksum_for_bytes(glib::ChecksumType::Sha256, bytes)
+            .and_then(|checksum| u64::from_str_radix(&checksum[..16], 16).ok())
+            .unwrap_or_default()
+    }
+
+    /// Replace the embedded Pdfs that pasted Pdf pages are resolved from.
+    pub fn set_clipboard_pdfs(embedded_pdfs: Vec<Self>) {
+        match CLIPBOARD_EMBEDDED_PDFS.lock() {
+            Ok(mut clipboard_pdfs) => *clipboard_pdfs = embedded_pdfs,
+            Err(e) => {
+                log::error!(
+                    "locking the clipboard pdfs failed in set_clipboard_pdfs(), Err: {e:?}"
+                );
+            }
+        }
+    }
+
+    /// The embedded Pdfs of the stroke content that was last copied to the clipboard.
+    pub fn clipboard_pdfs() -> Vec<Self> {
+        match CLIPBOARD_EMBEDDED_PDFS.lock() {
+            Ok(clipboard_pdfs) => clipboard_pdfs.clone(),
+            Err(e) => {
+                log::error!("locking the clipboard pdfs failed in clipboard_pdfs(), Err: {e:?}");
+                vec![]
+            }
+        }
+    }
+
+    /// Collect the embedded Pdfs referenced by the Pdf pages in the strokes, every one only once.
+    pub fn collect_from_strokes<'a>(strokes: impl IntoIterator<Item = &'a Stroke>) -> Vec<Self> {
+        let mut embedded_pdfs: Vec<Self> = vec![];
+
+        for stroke in strokes {
+            let Stroke::PdfPage(pdfpage) = stroke else {
+                continue;
+            };
+            let Some(bytes) = pdfpage.pdf_bytes.as_ref() else {
+                continue;
+            };
+            if embedded_pdfs.iter().all(|pdf| pdf.id != pdfpage.pdf_id) {
+                embedded_pdfs.push(Self {
+                    id: pdfpage.pdf_id,
+                    bytes: bytes.clone(),
+                });
+            }
+        }
+
+        embedded_pdfs
+    }
+}
+
+/// A page of an embedded Pdf, which is rendered on demand.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, rename = "pdfpage")]
+pub struct PdfPage {
+    /// The id of the embedded Pdf.
+    #[serde(rename = "pdf_id")]
+    pub pdf_id: u64,
+    /// The index of the page in the Pdf, starting from zero.
+    #[serde(rename = "page_index")]
+    pub page_index: u32,
+    #[serde(rename = "rectangle")]
+    pub rectangle: Rectangle,
+    /// Whether the annotations of the page are rendered.
+    #[serde(rename = "render_annotations")]
+    pub render_annotations: bool,
+    /// The text of the page, extracted when it is imported.
+    #[serde(rename = "text")]
+    pub text: String,
+    /// The Pdf file the page was imported from.
+    #[serde(rename = "pdf_page_source")]
+    pub pdf_page_source: Option<PdfPageSource>,
+    /// The bytes of the embedded Pdf, shared between all of its pages.
+    ///
+    /// Not (de)serialized with the page, the embedded Pdfs are stored once in the engine snapshot.
+    #[serde(skip)]
+    pdf_bytes: Option<glib::Bytes>,
+}
+
+impl Default for PdfPage {
+    fn default() -> Self {
+        Self {
+            pdf_id: 0,
+            page_index: 0,
+            rectangle: Rectangle::default(),
+            render_annotations: true,
+            text: String::default(),
+            pdf_page_source: None,
+            pdf_bytes: None,
+        }
+    }
+}
+
+impl StrokeBehaviour for PdfPage {
+    fn gen_svg(&self) -> Result<render::Svg, anyhow::Error> {
+        render::Svg::gen_with_cairo(|cx| self.draw_to_cairo(cx), self.bounds())
+    }
+
+    fn gen_images(
+        &self,
+        viewport: Aabb,
+        image_scale: f64,
+    ) -> Result<GeneratedStrokeImages, anyhow::Error> {
+        let bounds = self.bounds();
+
+        // The page is rendered at the current image scale, so only the visible part is generated
+        if viewport.contains(&bounds) {
+            Ok(GeneratedStrokeImages::Full(vec![
+                render::Image::gen_with_cairo(|cx| self.draw_to_cairo(cx), bounds, image_scale)?,
+            ]))
+        } else if let Some(intersection_bounds) = viewport.intersection(&bounds) {
+            Ok(GeneratedStrokeImages::Partial {
+                images: vec![render::Image::gen_with_cairo(
+                    |cx| self.draw_to_cairo(cx),
+                    intersection_bounds,
+                    image_scale,
+                )?],
+                viewport,
+            })
+        } else {
+            Ok(GeneratedStrokeImages::Partial {
+                images: vec![],
+                viewport,
+            })
+        }
+    }
+
+    fn draw_highlight(
+        &self,
+        cx: &mut impl piet::RenderContext,
+        total_zoom: f64,
+    ) -> anyhow::Result<()> {
+        const HIGHLIGHT_STROKE_WIDTH: f64 = 1.5;
+        cx.stroke(
+            self.bounds().to_kurbo_rect(),
+            &*strokebehaviour::STROKE_HIGHLIGHT_COLOR,
+            HIGHLIGHT_STROKE_WIDTH / total_zoom,
+        );
+        Ok(())
+    }
+
+    fn update_geometry(&mut self) {}
+}
+
+// Poppler can't render with piet, so the page is rendered into an image with cairo first.
+// When drawing onto cairo directly, use draw_to_cairo() instead to keep the page as vector graphics.
+impl DrawBehaviour for PdfPage {
+    fn draw(&self, cx: &mut impl piet::RenderContext, image_scale: f64) -> anyhow::Result<()> {
+        cx.save().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+
+        let image = render::Image::gen_with_cairo(
+            |cairo_cx| self.draw_to_cairo(cairo_cx),
+            self.bounds(),
+            image_scale,
+        )?;
+        image.draw(cx, image_scale)?;
+
+        cx.restore().map_err(|e| anyhow::anyhow!("{e:?}"))?;
+        Ok(())
+    }
+}
+
+impl ShapeBehaviour for PdfPage {
+    fn bounds(&self) -> Aabb {
+        self.rectangle.bounds()
+    }
+
+    fn hitboxes(&self) -> Vec<Aabb> {
+        vec![self.bounds()]
+    }
+}
+
+impl TransformBehaviour for PdfPage {
+    fn translate(&mut self, offset: na::Vector2<f64>) {
+        self.rectangle.translate(offset);
+    }
+
+    fn rotate(&mut self, angle: f64, center: na::Point2<f64>) {
+        self.rectangle.rotate(angle, center);
+    }
+
+    fn scale(&mut self, scale: na::Vector2<f64>) {
+        self.rectangle.scale(scale);
+    }
+}
+
+impl PdfPage {
+    /// The bytes of the embedded Pdf, None if they are not resolved.
+    pub fn pdf_bytes(&self) -> Option<&glib::Bytes> {
+        self.pdf_bytes.as_ref()
+    }
+
+    /// Set the bytes of the embedded Pdf from the given embedded Pdfs, if it is not already set.
+    pub fn resolve_pdf(&mut self, embedded_pdfs: &[EmbeddedPdf]) {
+        if self.pdf_bytes.is_some() {
+            return;
+        }
+        self.pdf_bytes = embedded_pdfs
+            .iter()
+            .find(|pdf| pdf.id == self.pdf_id)
+            .map(|pdf| pdf.bytes.clone());
+
+        if self.pdf_bytes.is_none() {
+            log::warn!(
+                "the embedded pdf with id {} of pdf page {} could not be found",
+                self.pdf_id,
+                self.page_index
+            );
+        }
+    }
+
+    /// Draw the page onto a cairo context, keeping it as vector graphics.
+    pub fn draw_to_cairo(&self, cx: &cairo::Context) -> anyhow::Result<()> {
+        let bytes = self.pdf_bytes.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("the embedded pdf with id {} is not available", self.pdf_id)
+        })?;
+        let print_flags = if self.render_annotations {
+            poppler::PrintFlags::ALL
+        } else {
+            poppler::PrintFlags::DOCUMENT
+        };
+
+        POPPLER_DOCUMENT_CACHE.with(|cache| -> anyhow::Result<()> {
+            let mut cache = cache.borrow_mut();
+            let doc = match &mut *cache {
+                Some((id, doc)) if *id == self.pdf_id => &*doc,
+                outdated => {
+                    let doc = poppler::Document::from_bytes(bytes, None)?;
+                    &outdated.insert((self.pdf_id, doc)).1
+                }
+            };
+            let page = doc.page(self.page_index as i32).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "the embedded pdf with id {} has no page with index {}",
+                    self.pdf_id,
+                    self.page_index
+                )
+            })?;
+            let intrinsic_size = page.size();
+            let half_extents = self.rectangle.cuboid.half_extents;
+            let coeffs = self.rectangle.transform.affine.to_kurbo().as_coeffs();
+
+            cx.save()?;
+            cx.transform(cairo::Matrix::new(
+                coeffs[0], coeffs[1], coeffs[2], coeffs[3], coeffs[4], coeffs[5],
+            ));
+            cx.translate(-half_extents[0], -half_extents[1]);
+            cx.scale(
+                2.0 * half_extents[0] / intrinsic_size.0,
+                2.0 * half_extents[1] / intrinsic_size.1,
+            );
+
+            // Set margin to white
+            cx.set_source_rgba(1.0, 1.0, 1.0, 1.0);
+            cx.rectangle(0.0, 0.0, intrinsic_size.0, intrinsic_size.1);
+            cx.fill()?;
+
+            page.render_for_printing_with_options(cx, print_flags);
+
+            // Draw outline around page
+            cx.set_source_rgba(
+                color::GNOME_REDS[4].as_rgba().0,
+                color::GNOME_REDS[4].as_rgba().1,
+                color::GNOME_REDS[4].as_rgba().2,
+                1.0,
+            );
+
+            let line_width = 1.0;
+            cx.set_line_width(line_width);
+            cx.rectangle(
+                line_width * 0.5,
+                line_width * 0.5,
+                intrinsic_size.0 - line_width,
+                intrinsic_size.1 - line_width,
+            );
+            cx.stroke()?;
+
+            cx.restore()?;
+            Ok(())
+        })
+    }
+
+    /// Import the pages of the Pdf bytes, which are embedded once and shared between the pages.
+    pub fn import_from_pdf_bytes(
+        to_be_read: &glib::Bytes,
+        pdf_import_prefs: PdfImportPrefs,
+        insert_pos: na::Vector2<f64>,
+        page_range: Option<Range<u32>>,
+        format: &Format,
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        let embedded_pdf = EmbeddedPdf::new(to_be_read.clone());
+        let doc = poppler::Document::from_bytes(&embedded_pdf.bytes, None)?;
+        let page_range = page_range.unwrap_or(0..doc.n_pages() as u32);
+
+        let page_width = format.width * (pdf_import_prefs.page_width_perc / 100.0);
+        // calculate the page zoom based on the width of the first page.
+        let page_zoom = if let Some(first_page) = doc.page(0) {
+            page_width / first_page.size().0
+        } else {
+            return Ok(vec![]);
+        };
+        let x = insert_pos[0];
+        let mut y = insert_pos[1];
+
+        Ok(page_range
+            .filter_map(|page_i| {
+                let pdf_page = Self::from_poppler_page(
+                    &embedded_pdf,
+                    &doc,
+                    page_i,
+                    na::vector![x, y],
+                    page_zoom,
+                    !pdf_import_prefs.annotations_as_strokes,
+                )?;
+
+                y += match pdf_import_prefs.page_spacing {
+                    PdfImportPageSpacing::Continuous => {
+                        pdf_page.rectangle.cuboid.half_extents[1] * 2.0
+                            + Stroke::IMPORT_OFFSET_DEFAULT[1] * 0.5
+                    }
+                    PdfImportPageSpacing::OnePerDocumentPage => format.height,
+                };
+
+                Some(pdf_page)
+            })
+            .collect())
+    }
+
+    /// Create the pages with the given indices, positions and widths from the embedded Pdf, which is parsed only once.
+    ///
+    /// Like when importing, the zoom of the pages is based on the width of the first page of the Pdf.
+    /// Pages that don't exist in the Pdf are skipped.
+    pub fn from_embedded_pdf_pages(
+        embedded_pdf: &EmbeddedPdf,
+        pages: &[(u32, na::Vector2<f64>, f64)],
+        render_annotations: bool,
+    ) -> Result<Vec<Self>, anyhow::Error> {
+        let doc = poppler::Document::from_bytes(&embedded_pdf.bytes, None)?;
+        let Some(first_page_width) = doc.page(0).map(|first_page| first_page.size().0) else {
+            return Ok(vec![]);
+        };
+
+        Ok(pages
+            .iter()
+            .filter_map(|&(page_i, pos, page_width)| {
+                Self::from_poppler_page(
+                    embedded_pdf,
+                    &doc,
+                    page_i,
+                    pos,
+                    page_width / first_page_width,
+                    render_annotations,
+                )
+            })
+            .collect())
+    }
+
+    fn from_poppler_page(
+        embedded_pdf: &EmbeddedPdf,
+        doc: &poppler::Document,
+        page_i: u32,
+        pos: na::Vector2<f64>,
+        page_zoom: f64,
+        render_annotations: bool,
+    ) -> Option<Self> {
+        let page = doc.page(page_i as i32)?;
+        let intrinsic_size = page.size();
+        let size = na::vector![intrinsic_size.0 * page_zoom, intrinsic_size.1 * page_zoom];
+
+        let rectangle = Rectangle {
+            cuboid: p2d::shape::Cuboid::new(size * 0.5),
+            transform: Transform::new_w_isometry(na::Isometry2::new(pos + size * 0.5, 0.0)),
+        };
+
+        Some(Self {
+            pdf_id: embedded_pdf.id,
+            page_index: page_i,
+            rectangle,
+            render_annotations,
+            // The text is extracted up front, so that it can be searched without rendering the page
+            text: page.text().map(|text| text.to_string()).unwrap_or_default(),
+            pdf_page_source: None,
+            pdf_bytes: Some(embedded_pdf.bytes.clone()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embed_pdf_once() {
+        let embedded_pdf = EmbeddedPdf::new(glib::Bytes::from_static(b"%PDF-1.7 test"));
+        let pdf_pages = (0..3)
+            .map(|page_index| {
+                Stroke::PdfPage(PdfPage {
+                    pdf_id: embedded_pdf.id,
+                    page_index,
+                    pdf_bytes: Some(embedded_pdf.bytes.clone()),
+                    ..Default::default()
+                })
+            })
+            .collect::<Vec<Stroke>>();
+
+        let embedded_pdfs = EmbeddedPdf::collect_from_strokes(&pdf_pages);
+        assert_eq!(embedded_pdfs.len(), 1);
+        assert_eq!(embedded_pdfs[0].id, embedded_pdf.id);
+
+        // The pages only reference the embedded pdf and get its bytes resolved when they are loaded
+        let json = serde_json::to_string(&pdf_pages[1]).unwrap();
+        let Stroke::PdfPage(mut pdf_page) = serde_json::from_str::<Stroke>(&json).unwrap() else {
+            panic!("deserialized stroke is not a pdf page");
+        };
+        assert_eq!(pdf_page.page_index, 1);
+        assert!(pdf_page.pdf_bytes.is_none());
+
+        pdf_page.resolve_pdf(&embedded_pdfs);
+        assert_eq!(pdf_page.pdf_bytes, Some(embedded_pdf.bytes));
+    }
+
+    #[test]
+    fn embedded_pdf_id_is_stable() {
+        // The leading 64 bits of the Sha256 checksum of the bytes
+        let embedded_pdf = EmbeddedPdf::new(glib::Bytes::from_static(b"%PDF-1.7 test"));
+        assert_eq!(embedded_pdf.id, 0xeddc31937fbdaf70);
+    }
+}
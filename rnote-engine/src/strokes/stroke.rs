@@ -1,10 +1,21 @@
 // Imports
 use super::bitmapimage::BitmapImage;
 use super::brushstroke::BrushStroke;
+use super::pdfpage::PdfPage;
 use super::shapestroke::ShapeStroke;
 use super::strokebehaviour::GeneratedStrokeImages;
+use super::textstroke::{RangedTextAttribute, TextAlignment, TextStyle};
 use super::vectorimage::VectorImage;
 use super::{StrokeBehaviour, TextStroke};
+use crate::engine::import::PdfPageSource;
+use crate::fileformats::excalidrawformat::{
+    ExcalidrawColor, ExcalidrawElement, ExcalidrawElementKind, ExcalidrawFile, ExcalidrawFillStyle,
+    ExcalidrawFreedraw, ExcalidrawLinear, ExcalidrawText,
+};
+use crate::fileformats::inkmlformat::{self, InkmlChannel, InkmlFile};
+use crate::fileformats::pdfannotformat::{
+    PdfAnnotFile, PdfAnnotation, PdfFreeTextAnnotation, PdfInkAnnotation, PdfMarkupKind,
+};
 use crate::fileformats::xoppformat::{self, XoppColor};
 use crate::store::chrono_comp::StrokeLayer;
 use crate::{render, RnoteEngine};
@@ -12,9 +23,12 @@ use crate::{utils, DrawBehaviour};
 use base64::Engine;
 use p2d::bounding_volume::Aabb;
 use rnote_compose::helpers::AabbHelpers;
-use rnote_compose::penpath::Element;
-use rnote_compose::shapes::{Rectangle, ShapeBehaviour};
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::{Arrow, Ellipse, Line, Rectangle, Shape, ShapeBehaviour};
+use rnote_compose::style::rough::roughoptions::FillStyle;
+use rnote_compose::style::rough::RoughOptions;
 use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::PressureCurve;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::TransformBehaviour;
 use rnote_compose::{Color, PenPath, Style};
@@ -33,6 +47,8 @@ pub enum Stroke {
     VectorImage(VectorImage),
     #[serde(rename = "bitmapimage")]
     BitmapImage(BitmapImage),
+    #[serde(rename = "pdfpage")]
+    PdfPage(PdfPage),
 }
 
 impl StrokeBehaviour for Stroke {
@@ -43,6 +59,7 @@ impl StrokeBehaviour for Stroke {
             Stroke::TextStroke(textstroke) => textstroke.gen_svg(),
             Stroke::VectorImage(vectorimage) => vectorimage.gen_svg(),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.gen_svg(),
+            Stroke::PdfPage(pdfpage) => pdfpage.gen_svg(),
         }
     }
 
@@ -57,6 +74,7 @@ impl StrokeBehaviour for Stroke {
             Stroke::TextStroke(textstroke) => textstroke.gen_images(viewport, image_scale),
             Stroke::VectorImage(vectorimage) => vectorimage.gen_images(viewport, image_scale),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.gen_images(viewport, image_scale),
+            Stroke::PdfPage(pdfpage) => pdfpage.gen_images(viewport, image_scale),
         }
     }
 
@@ -71,6 +89,7 @@ impl StrokeBehaviour for Stroke {
             Stroke::TextStroke(textstroke) => textstroke.draw_highlight(cx, total_zoom),
             Stroke::VectorImage(vectorimage) => vectorimage.draw_highlight(cx, total_zoom),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.draw_highlight(cx, total_zoom),
+            Stroke::PdfPage(pdfpage) => pdfpage.draw_highlight(cx, total_zoom),
         }
     }
 
@@ -81,6 +100,7 @@ impl StrokeBehaviour for Stroke {
             Stroke::TextStroke(textstroke) => textstroke.update_geometry(),
             Stroke::VectorImage(vectorimage) => vectorimage.update_geometry(),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.update_geometry(),
+            Stroke::PdfPage(pdfpage) => pdfpage.update_geometry(),
         }
     }
 }
@@ -93,6 +113,7 @@ impl DrawBehaviour for Stroke {
             Stroke::TextStroke(textstroke) => textstroke.draw(cx, image_scale),
             Stroke::VectorImage(vectorimage) => vectorimage.draw(cx, image_scale),
             Stroke::BitmapImage(bitmapimage) => bitmapimage.draw(cx, image_scale),
+            Stroke::PdfPage(pdfpage) => pdfpage.draw(cx, image_scale),
         }
     }
 }
@@ -105,6 +126,7 @@ impl ShapeBehaviour for Stroke {
             Self::TextStroke(textstroke) => textstroke.bounds(),
             Self::VectorImage(vectorimage) => vectorimage.bounds(),
             Self::BitmapImage(bitmapimage) => bitmapimage.bounds(),
+            Self::PdfPage(pdfpage) => pdfpage.bounds(),
         }
     }
 
@@ -115,6 +137,7 @@ impl ShapeBehaviour for Stroke {
             Self::TextStroke(textstroke) => textstroke.hitboxes(),
             Self::VectorImage(vectorimage) => vectorimage.hitboxes(),
             Self::BitmapImage(bitmapimage) => bitmapimage.hitboxes(),
+            Self::PdfPage(pdfpage) => pdfpage.hitboxes(),
         }
     }
 }
@@ -137,6 +160,9 @@ impl TransformBehaviour for Stroke {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.translate(offset);
             }
+            Self::PdfPage(pdfpage) => {
+                pdfpage.translate(offset);
+            }
         }
     }
 
@@ -157,6 +183,9 @@ impl TransformBehaviour for Stroke {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.rotate(angle, center);
             }
+            Self::PdfPage(pdfpage) => {
+                pdfpage.rotate(angle, center);
+            }
         }
     }
 
@@ -177,6 +206,9 @@ impl TransformBehaviour for Stroke {
             Self::BitmapImage(bitmapimage) => {
                 bitmapimage.scale(scale);
             }
+            Self::PdfPage(pdfpage) => {
+                pdfpage.scale(scale);
+            }
         }
     }
 }
@@ -191,8 +223,64 @@ impl Stroke {
             Stroke::ShapeStroke(_) => StrokeLayer::UserLayer(0),
             Stroke::TextStroke(_) => StrokeLayer::UserLayer(0),
             Stroke::VectorImage(_) | Stroke::BitmapImage(_) => StrokeLayer::Image,
+            Stroke::PdfPage(_) => StrokeLayer::Document,
         }
     }
+
+    /// The text content of the stroke, if it has any.
+    pub fn text_content(&self) -> Option<&str> {
+        match self {
+            Stroke::TextStroke(textstroke) => Some(textstroke.text.as_str()),
+            Stroke::VectorImage(vectorimage) => Some(vectorimage.text.as_str()),
+            Stroke::PdfPage(pdfpage) => Some(pdfpage.text.as_str()),
+            Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) | Stroke::BitmapImage(_) => None,
+        }
+    }
+
+    /// The Pdf page the stroke was generated from, for images that were imported as page backgrounds.
+    pub fn pdf_page_source(&self) -> Option<&PdfPageSource> {
+        match self {
+            Stroke::VectorImage(vectorimage) => vectorimage.pdf_page_source.as_ref(),
+            Stroke::BitmapImage(bitmapimage) => bitmapimage.pdf_page_source.as_ref(),
+            Stroke::PdfPage(pdfpage) => pdfpage.pdf_page_source.as_ref(),
+            Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) | Stroke::TextStroke(_) => None,
+        }
+    }
+
+    /// Set the Pdf page the stroke was generated from. Has no effect for strokes that are not images.
+    pub fn set_pdf_page_source(&mut self, pdf_page_source: Option<PdfPageSource>) {
+        match self {
+            Stroke::VectorImage(vectorimage) => vectorimage.pdf_page_source = pdf_page_source,
+            Stroke::BitmapImage(bitmapimage) => bitmapimage.pdf_page_source = pdf_page_source,
+            Stroke::PdfPage(pdfpage) => pdfpage.pdf_page_source = pdf_page_source,
+            Stroke::BrushStroke(_) | Stroke::ShapeStroke(_) | Stroke::TextStroke(_) => {}
+        }
+    }
+
+    /// An estimate of the memory the stroke occupies in bytes, excluding its rendering.
+    ///
+    /// Used to keep the history within its memory budget.
+    pub fn approx_mem_size(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + match self {
+                Stroke::BrushStroke(brushstroke) => {
+                    brushstroke.path.segments.len() * std::mem::size_of::<Segment>()
+                }
+                Stroke::ShapeStroke(_) => 0,
+                Stroke::TextStroke(textstroke) => {
+                    textstroke.text.len()
+                        + textstroke.text_style.font_family.len()
+                        + textstroke.text_style.ranged_text_attributes.len()
+                            * std::mem::size_of::<RangedTextAttribute>()
+                }
+                Stroke::VectorImage(vectorimage) => {
+                    vectorimage.svg_data.len() + vectorimage.text.len()
+                }
+                Stroke::BitmapImage(bitmapimage) => bitmapimage.encoded_image().len(),
+                Stroke::PdfPage(pdfpage) => pdfpage.text.len(),
+            }
+    }
+
     pub fn from_xoppstroke(
         stroke: xoppformat::XoppStroke,
         offset: na::Vector2<f64>,
@@ -315,9 +403,7 @@ impl Stroke {
             cuboid: p2d::shape::Cuboid::new(bounds.half_extents()),
             transform: Transform::new_w_isometry(na::Isometry2::new(bounds.center().coords, 0.0)),
         };
-        let image = render::Image::try_from_encoded_bytes(&bytes)?;
-
-        Ok(Stroke::BitmapImage(BitmapImage { image, rectangle }))
+        Ok(Stroke::BitmapImage(BitmapImage::new(&bytes, rectangle)?))
     }
 
     pub fn into_xopp(self, current_dpi: f64) -> Option<xoppformat::XoppStrokeType> {
@@ -547,6 +633,1094 @@ impl Stroke {
                     },
                 ))
             }
+            Stroke::PdfPage(pdfpage) => {
+                let png_data = match pdfpage.export_as_bitmapimage_bytes(
+                    image::ImageOutputFormat::Png,
+                    RnoteEngine::STROKE_EXPORT_IMAGE_SCALE,
+                ) {
+                    Ok(image_bytes) => image_bytes,
+                    Err(e) => {
+                        log::error!("export_as_bytes() failed for pdfpage in stroke to_xopp() with Err: {e:?}");
+                        return None;
+                    }
+                };
+
+                let bounds = pdfpage.bounds();
+
+                Some(xoppformat::XoppStrokeType::XoppImage(
+                    xoppformat::XoppImage {
+                        left: utils::convert_value_dpi(
+                            bounds.mins[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        top: utils::convert_value_dpi(
+                            bounds.mins[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        right: utils::convert_value_dpi(
+                            bounds.maxs[0],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        bottom: utils::convert_value_dpi(
+                            bounds.maxs[1],
+                            current_dpi,
+                            xoppformat::XoppFile::DPI,
+                        ),
+                        data: base64::engine::general_purpose::STANDARD.encode(png_data),
+                    },
+                ))
+            }
+        }
+    }
+    /// Converts an InkML trace into a brush stroke.
+    ///
+    /// The positions and the brush width are converted from the units of the trace format to the target dpi,
+    /// the force channel becomes the pen pressure.
+    pub fn from_inkml_trace(
+        trace: &inkmlformat::InkmlTrace,
+        ink: &inkmlformat::InkmlInk,
+        target_dpi: f64,
+    ) -> Result<Self, anyhow::Error> {
+        let (Some(x_i), Some(y_i)) = (ink.channel_index("X"), ink.channel_index("Y")) else {
+            return Err(anyhow::anyhow!(
+                "from_inkml_trace() failed, trace format has no `X` and `Y` channels"
+            ));
+        };
+        // Values with unknown units are taken as they are
+        let units_dpi = |units: Option<&str>| {
+            units
+                .and_then(InkmlChannel::units_per_inch)
+                .unwrap_or(target_dpi)
+        };
+        let x_dpi = units_dpi(ink.channels[x_i].units.as_deref());
+        let y_dpi = units_dpi(ink.channels[y_i].units.as_deref());
+
+        let force_i = ink.channel_index("F");
+        // Without a maximum, the force is normalized by the largest force of the trace if it exceeds 1.0
+        let force_max = force_i.map(|i| {
+            ink.channels[i].max.unwrap_or_else(|| {
+                trace
+                    .points
+                    .iter()
+                    .filter_map(|point| point.get(i).copied())
+                    .fold(1.0, f64::max)
+            })
+        });
+
+        let elements = trace
+            .points
+            .iter()
+            .filter_map(|point| {
+                let pos = na::vector![
+                    utils::convert_value_dpi(*point.get(x_i)?, x_dpi, target_dpi),
+                    utils::convert_value_dpi(*point.get(y_i)?, y_dpi, target_dpi)
+                ];
+                let pressure = force_i
+                    .zip(force_max)
+                    .filter(|&(_, max)| max > 0.0)
+                    .and_then(|(i, max)| Some(point.get(i)? / max))
+                    .unwrap_or(Element::PRESSURE_DEFAULT);
+
+                Some(Element::new(pos, pressure))
+            })
+            .collect::<Vec<Element>>();
+
+        let mut smooth_options = SmoothOptions::default();
+        if let Some(brush) = trace.brush_ref.as_deref().and_then(|id| ink.brush(id)) {
+            smooth_options.stroke_color = Some(utils::color_from_inkml(brush.color));
+            if let Some(width) = brush.width {
+                let width_dpi = brush
+                    .units
+                    .as_deref()
+                    .map_or(x_dpi, |units| units_dpi(Some(units)));
+                smooth_options.stroke_width =
+                    utils::convert_value_dpi(width, width_dpi, target_dpi);
+            }
+        }
+
+        let penpath = PenPath::try_from_elements(elements).ok_or_else(|| {
+            anyhow::anyhow!("from_inkml_trace() failed, failed to create pen path")
+        })?;
+
+        Ok(Stroke::BrushStroke(BrushStroke::from_penpath(
+            penpath,
+            Style::Smooth(smooth_options),
+        )))
+    }
+
+    /// Converts the stroke into an InkML trace and the brush it is drawn with.
+    ///
+    /// Only brush strokes can be represented as traces. The trace points have the values of the channels
+    /// of `InkmlFile::export_channels()`, their time starts at the given start time.
+    /// Curved segments are sampled into multiple points.
+    pub fn into_inkml(
+        self,
+        current_dpi: f64,
+        start_time: f64,
+    ) -> Option<(inkmlformat::InkmlTrace, inkmlformat::InkmlBrush)> {
+        let Stroke::BrushStroke(brushstroke) = self else {
+            return None;
+        };
+        // Return early if color is None
+        let (stroke_width, color) = match &brushstroke.style {
+            Style::Smooth(options) => (options.stroke_width, options.stroke_color?),
+            Style::Rough(options) => (options.stroke_width, options.stroke_color?),
+            Style::Textured(options) => (options.stroke_width, options.stroke_color?),
+        };
+
+        // The curved segments are sampled, so that their shape is kept
+        let points = brushstroke
+            .path
+            .sample_elements(utils::convert_value_dpi(
+                InkmlFile::EXPORT_SAMPLE_DIST,
+                InkmlFile::EXPORT_DPI,
+                current_dpi,
+            ))
+            .into_iter()
+            .enumerate()
+            .map(|(i, element)| {
+                let pos = utils::convert_coord_dpi(element.pos, current_dpi, InkmlFile::EXPORT_DPI);
+                vec![
+                    pos[0],
+                    pos[1],
+                    element.pressure,
+                    start_time + i as f64 * InkmlFile::EXPORT_POINT_INTERVAL,
+                ]
+            })
+            .collect::<Vec<Vec<f64>>>();
+
+        let brush = inkmlformat::InkmlBrush {
+            color: utils::inkmlcolor_from_color(color),
+            width: Some(utils::convert_value_dpi(
+                stroke_width,
+                current_dpi,
+                InkmlFile::EXPORT_DPI,
+            )),
+            units: Some(String::from("mm")),
+            ..Default::default()
+        };
+
+        Some((
+            inkmlformat::InkmlTrace {
+                points,
+                ..Default::default()
+            },
+            brush,
+        ))
+    }
+
+    /// Converts an Excalidraw element into strokes.
+    ///
+    /// Rectangles, ellipses, lines and arrows become shape strokes with the rough style,
+    /// or with the smooth style when they are drawn without roughness. Lines and arrows are split into
+    /// a shape for every segment. Freedraw paths become brush strokes and texts become text strokes.
+    /// Deleted and unsupported elements result in no strokes.
+    pub fn from_excalidraw_element(element: &ExcalidrawElement, target_dpi: f64) -> Vec<Self> {
+        if element.is_deleted {
+            return vec![];
+        }
+        let convert_value =
+            |value: f64| utils::convert_value_dpi(value, ExcalidrawFile::DPI, target_dpi);
+        let convert_coord = |coord: na::Vector2<f64>| {
+            utils::convert_coord_dpi(coord, ExcalidrawFile::DPI, target_dpi)
+        };
+        let convert_color = |css_color: &str| {
+            ExcalidrawColor::from_css_color(css_color).map(|color| {
+                let mut color = utils::color_from_excalidraw(color);
+                color.a *= element.opacity_factor();
+                color
+            })
+        };
+        let stroke_color = convert_color(&element.stroke_color);
+        let fill_color = convert_color(&element.background_color);
+        let stroke_width = convert_value(element.stroke_width);
+        let center = convert_coord(element.center());
+        let size = convert_coord(na::vector![element.width, element.height]).abs();
+
+        let shape_style = if element.roughness > 0.0 {
+            Style::Rough(RoughOptions {
+                stroke_color,
+                stroke_width,
+                fill_color,
+                fill_style: match element.fill_style {
+                    ExcalidrawFillStyle::Hachure => FillStyle::Hachure,
+                    ExcalidrawFillStyle::CrossHatch => FillStyle::Crosshatch,
+                    ExcalidrawFillStyle::Solid => FillStyle::Solid,
+                    ExcalidrawFillStyle::ZigZag => FillStyle::ZigZag,
+                },
+                roughness: element.roughness,
+                seed: Some(element.seed),
+                ..Default::default()
+            })
+        } else {
+            Style::Smooth(SmoothOptions {
+                stroke_width,
+                stroke_color,
+                fill_color,
+                ..Default::default()
+            })
+        };
+
+        let shapes = match &element.kind {
+            ExcalidrawElementKind::Rectangle => vec![Shape::Rectangle(Rectangle {
+                cuboid: p2d::shape::Cuboid::new(size * 0.5),
+                transform: Transform::new_w_isometry(na::Isometry2::new(center, element.angle)),
+            })],
+            ExcalidrawElementKind::Ellipse => vec![Shape::Ellipse(Ellipse {
+                radii: size * 0.5,
+                transform: Transform::new_w_isometry(na::Isometry2::new(center, element.angle)),
+            })],
+            ExcalidrawElementKind::Line(linear) => shapes_from_excalidraw_points(
+                element
+                    .absolute_points(&linear.points)
+                    .into_iter()
+                    .map(convert_coord)
+                    .collect(),
+                false,
+                false,
+            ),
+            ExcalidrawElementKind::Arrow(linear) => shapes_from_excalidraw_points(
+                element
+                    .absolute_points(&linear.points)
+                    .into_iter()
+                    .map(convert_coord)
+                    .collect(),
+                linear.start_arrowhead.is_some(),
+                linear.end_arrowhead.is_some(),
+            ),
+            ExcalidrawElementKind::Freedraw(freedraw) => {
+                let pressures = freedraw
+                    .pressures
+                    .iter()
+                    .copied()
+                    .chain(std::iter::repeat(Element::PRESSURE_DEFAULT));
+                let elements = element
+                    .absolute_points(&freedraw.points)
+                    .into_iter()
+                    .zip(pressures)
+                    .map(|(pos, pressure)| Element::new(convert_coord(pos), pressure))
+                    .collect::<Vec<Element>>();
+                let Some(penpath) = PenPath::try_from_elements(elements) else {
+                    return vec![];
+                };
+                let smooth_options = SmoothOptions {
+                    stroke_width: stroke_width * ExcalidrawFreedraw::STROKE_WIDTH_SIZE_FACTOR,
+                    stroke_color,
+                    // Without recorded pressures the path is drawn with a constant width
+                    pressure_curve: if freedraw.simulate_pressure || freedraw.pressures.is_empty() {
+                        PressureCurve::Const
+                    } else {
+                        PressureCurve::default()
+                    },
+                    ..Default::default()
+                };
+
+                return vec![Stroke::BrushStroke(BrushStroke::from_penpath(
+                    penpath,
+                    Style::Smooth(smooth_options),
+                ))];
+            }
+            ExcalidrawElementKind::Text(text) => {
+                let alignment = match text.text_align.as_str() {
+                    "center" => TextAlignment::Center,
+                    "right" => TextAlignment::End,
+                    _ => TextAlignment::Start,
+                };
+                let text_style = TextStyle {
+                    font_family: ExcalidrawText::font_family_name(text.font_family).map_or_else(
+                        || String::from(TextStyle::FONT_FAMILY_DEFAULT),
+                        String::from,
+                    ),
+                    font_size: convert_value(text.font_size),
+                    color: stroke_color.unwrap_or(TextStyle::FONT_COLOR_DEFAULT),
+                    // The width is only needed to align the lines
+                    max_width: if matches!(alignment, TextAlignment::Start) {
+                        None
+                    } else {
+                        Some(size[0])
+                    },
+                    alignment,
+                    ..Default::default()
+                };
+                let mut textstroke = TextStroke::new(
+                    text.text.clone(),
+                    convert_coord(na::vector![element.x, element.y]),
+                    text_style,
+                );
+                textstroke.rotate(element.angle, center.into());
+
+                return vec![Stroke::TextStroke(textstroke)];
+            }
+            ExcalidrawElementKind::Unsupported => vec![],
+        };
+
+        shapes
+            .into_iter()
+            .map(|shape| Stroke::ShapeStroke(ShapeStroke::new(shape, shape_style.clone())))
+            .collect()
+    }
+
+    /// Converts the stroke into an Excalidraw element.
+    ///
+    /// Shape strokes with the rough style keep their fill style, roughness and seed, other styles are exported
+    /// without roughness. Curves are approximated with lines. Images can't be represented and return None.
+    pub fn into_excalidraw(self, current_dpi: f64) -> Option<ExcalidrawElement> {
+        let convert_value =
+            |value: f64| utils::convert_value_dpi(value, current_dpi, ExcalidrawFile::DPI);
+        let convert_coord = |coord: na::Vector2<f64>| {
+            utils::convert_coord_dpi(coord, current_dpi, ExcalidrawFile::DPI)
+        };
+        let convert_color = |color: Option<Color>| {
+            color.map_or_else(
+                || String::from(ExcalidrawColor::TRANSPARENT),
+                |color| utils::excalidrawcolor_from_color(color).to_css_color(),
+            )
+        };
+        let mut element = ExcalidrawElement {
+            id: ExcalidrawElement::new_id(),
+            seed: ExcalidrawElement::new_seed(),
+            version_nonce: ExcalidrawElement::new_seed(),
+            ..Default::default()
+        };
+
+        match self {
+            Stroke::ShapeStroke(shapestroke) => {
+                match &shapestroke.style {
+                    Style::Rough(options) => {
+                        element.stroke_color = convert_color(options.stroke_color);
+                        element.background_color = convert_color(options.fill_color);
+                        element.fill_style = match options.fill_style {
+                            FillStyle::Solid => ExcalidrawFillStyle::Solid,
+                            FillStyle::Crosshatch => ExcalidrawFillStyle::CrossHatch,
+                            FillStyle::ZigZag | FillStyle::ZigZagLine => {
+                                ExcalidrawFillStyle::ZigZag
+                            }
+                            // Excalidraw has no dotted or dashed fills
+                            FillStyle::Hachure | FillStyle::Dots | FillStyle::Dashed => {
+                                ExcalidrawFillStyle::Hachure
+                            }
+                        };
+                        element.stroke_width = convert_value(options.stroke_width);
+                        element.roughness = options.roughness;
+                        if let Some(seed) = options.seed {
+                            element.seed = seed % ExcalidrawElement::SEED_MAX;
+                        }
+                    }
+                    Style::Smooth(options) => {
+                        element.stroke_color = convert_color(options.stroke_color);
+                        element.background_color = convert_color(options.fill_color);
+                        element.fill_style = ExcalidrawFillStyle::Solid;
+                        element.stroke_width = convert_value(options.stroke_width);
+                        element.roughness = 0.0;
+                    }
+                    Style::Textured(options) => {
+                        element.stroke_color = convert_color(options.stroke_color);
+                        element.stroke_width = convert_value(options.stroke_width);
+                        element.roughness = 0.0;
+                    }
+                }
+
+                match shapestroke.shape {
+                    Shape::Rectangle(rectangle) => {
+                        element.kind = ExcalidrawElementKind::Rectangle;
+                        set_excalidraw_box(
+                            &mut element,
+                            &rectangle.transform,
+                            na::Vector2::zeros(),
+                            rectangle.cuboid.half_extents,
+                            current_dpi,
+                        );
+                    }
+                    Shape::Ellipse(ellipse) => {
+                        element.kind = ExcalidrawElementKind::Ellipse;
+                        set_excalidraw_box(
+                            &mut element,
+                            &ellipse.transform,
+                            na::Vector2::zeros(),
+                            ellipse.radii,
+                            current_dpi,
+                        );
+                    }
+                    Shape::Line(line) => {
+                        let points = set_excalidraw_points(
+                            &mut element,
+                            &[convert_coord(line.start), convert_coord(line.end)],
+                        );
+                        element.kind = ExcalidrawElementKind::Line(ExcalidrawLinear {
+                            points,
+                            ..Default::default()
+                        });
+                    }
+                    Shape::Arrow(arrow) => {
+                        let points = set_excalidraw_points(
+                            &mut element,
+                            &[convert_coord(arrow.start), convert_coord(arrow.tip)],
+                        );
+                        element.kind = ExcalidrawElementKind::Arrow(ExcalidrawLinear {
+                            points,
+                            start_arrowhead: None,
+                            end_arrowhead: Some(String::from("arrow")),
+                        });
+                    }
+                    Shape::QuadraticBezier(quadbez) => {
+                        let points = set_excalidraw_points(
+                            &mut element,
+                            &points_from_lines(quadbez.approx_with_lines(CURVE_APPROX_SPLITS))
+                                .into_iter()
+                                .map(convert_coord)
+                                .collect::<Vec<na::Vector2<f64>>>(),
+                        );
+                        element.kind = ExcalidrawElementKind::Line(ExcalidrawLinear {
+                            points,
+                            ..Default::default()
+                        });
+                    }
+                    Shape::CubicBezier(cubbez) => {
+                        let points = set_excalidraw_points(
+                            &mut element,
+                            &points_from_lines(cubbez.approx_with_lines(CURVE_APPROX_SPLITS))
+                                .into_iter()
+                                .map(convert_coord)
+                                .collect::<Vec<na::Vector2<f64>>>(),
+                        );
+                        element.kind = ExcalidrawElementKind::Line(ExcalidrawLinear {
+                            points,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            Stroke::BrushStroke(brushstroke) => {
+                // Return early if color is None
+                let (stroke_width, stroke_color) = match &brushstroke.style {
+                    Style::Smooth(options) => (options.stroke_width, options.stroke_color?),
+                    Style::Rough(options) => (options.stroke_width, options.stroke_color?),
+                    Style::Textured(options) => (options.stroke_width, options.stroke_color?),
+                };
+                let path_elements = brushstroke.path.into_elements();
+                let points = set_excalidraw_points(
+                    &mut element,
+                    &path_elements
+                        .iter()
+                        .map(|path_element| convert_coord(path_element.pos))
+                        .collect::<Vec<na::Vector2<f64>>>(),
+                );
+
+                element.kind = ExcalidrawElementKind::Freedraw(ExcalidrawFreedraw {
+                    points,
+                    pressures: path_elements
+                        .iter()
+                        .map(|path_element| path_element.pressure)
+                        .collect(),
+                    simulate_pressure: false,
+                });
+                element.stroke_color = convert_color(Some(stroke_color));
+                element.stroke_width =
+                    convert_value(stroke_width) / ExcalidrawFreedraw::STROKE_WIDTH_SIZE_FACTOR;
+                element.roughness = 0.0;
+            }
+            Stroke::TextStroke(textstroke) => {
+                let size = textstroke
+                    .text_style
+                    .untransformed_size(&mut piet_cairo::CairoText::new(), textstroke.text.clone())
+                    .unwrap_or_else(|| na::Vector2::repeat(textstroke.text_style.font_size));
+                set_excalidraw_box(
+                    &mut element,
+                    &textstroke.transform,
+                    size * 0.5,
+                    size * 0.5,
+                    current_dpi,
+                );
+
+                element.stroke_color = convert_color(Some(textstroke.text_style.color));
+                element.kind = ExcalidrawElementKind::Text(ExcalidrawText {
+                    original_text: textstroke.text.clone(),
+                    text: textstroke.text,
+                    font_size: convert_value(textstroke.text_style.font_size),
+                    font_family: ExcalidrawText::font_family_id(&textstroke.text_style.font_family),
+                    text_align: String::from(match textstroke.text_style.alignment {
+                        TextAlignment::Start | TextAlignment::Fill => "left",
+                        TextAlignment::Center => "center",
+                        TextAlignment::End => "right",
+                    }),
+                    ..Default::default()
+                });
+            }
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) | Stroke::PdfPage(_) => return None,
         }
+
+        Some(element)
+    }
+
+    /// Converts the stroke into a native Pdf annotation.
+    ///
+    /// Brush strokes become ink annotations, which highlight the page content below when `highlight` is true.
+    /// Shape strokes become ink annotations of their outline. Text strokes become free text annotations. The transform maps the stroke coordinates
+    /// to the displayed coordinates of the Pdf page, in points.
+    pub fn into_pdf_annotation(
+        self,
+        transform: &na::Affine2<f64>,
+        highlight: bool,
+    ) -> Option<PdfAnnotation> {
+        let scale = transform
+            .matrix()
+            .fixed_view::<2, 2>(0, 0)
+            .determinant()
+            .abs()
+            .sqrt();
+
+        match self {
+            Stroke::BrushStroke(brushstroke) => {
+                // Return early if color is None
+                let (stroke_width, mut color) = match &brushstroke.style {
+                    Style::Smooth(options) => (options.stroke_width, options.stroke_color?),
+                    Style::Rough(options) => (options.stroke_width, options.stroke_color?),
+                    Style::Textured(options) => (options.stroke_width, options.stroke_color?),
+                };
+                if highlight {
+                    color.a = color.a.min(PdfAnnotFile::HIGHLIGHT_OPACITY);
+                }
+
+                Some(PdfAnnotation::Ink(PdfInkAnnotation {
+                    ink_list: pdf_ink_list(brushstroke.path.to_kurbo(), transform),
+                    color: utils::pdfcolor_from_color(color),
+                    width: stroke_width * scale,
+                    highlight,
+                }))
+            }
+            Stroke::ShapeStroke(shapestroke) => {
+                // Shapes without a stroke are outlined with their fill color
+                let (stroke_width, color) = match &shapestroke.style {
+                    Style::Smooth(options) => (
+                        options.stroke_width,
+                        options.stroke_color.or(options.fill_color)?,
+                    ),
+                    Style::Rough(options) => (
+                        options.stroke_width,
+                        options.stroke_color.or(options.fill_color)?,
+                    ),
+                    Style::Textured(options) => (options.stroke_width, options.stroke_color?),
+                };
+                let outline = match &shapestroke.shape {
+                    Shape::Line(line) => kurbo::Shape::to_path(&line.to_kurbo(), 0.0),
+                    Shape::Arrow(arrow) => {
+                        let arrow = arrow.to_kurbo(Some(stroke_width));
+                        let mut outline = kurbo::Shape::to_path(&arrow.stem, 0.0);
+                        outline.extend(arrow.tip_triangle);
+                        outline
+                    }
+                    Shape::Rectangle(rectangle) => rectangle.to_kurbo(),
+                    Shape::Ellipse(ellipse) => {
+                        kurbo::Shape::to_path(&ellipse.to_kurbo(), PDF_ANNOTATION_FLATTEN_TOLERANCE)
+                    }
+                    Shape::QuadraticBezier(quadbez) => {
+                        kurbo::Shape::to_path(&quadbez.to_kurbo(), 0.0)
+                    }
+                    Shape::CubicBezier(cubbez) => kurbo::Shape::to_path(&cubbez.to_kurbo(), 0.0),
+                };
+
+                Some(PdfAnnotation::Ink(PdfInkAnnotation {
+                    ink_list: pdf_ink_list(outline, transform),
+                    color: utils::pdfcolor_from_color(color),
+                    width: stroke_width * scale,
+                    highlight: false,
+                }))
+            }
+            Stroke::TextStroke(textstroke) => {
+                let bounds = textstroke.bounds();
+                let corners = [
+                    bounds.mins.coords,
+                    na::vector![bounds.maxs[0], bounds.mins[1]],
+                    bounds.maxs.coords,
+                    na::vector![bounds.mins[0], bounds.maxs[1]],
+                ]
+                .map(|corner| (transform * na::Point2::from(corner)).coords);
+                let (mins, maxs) = corners
+                    .iter()
+                    .skip(1)
+                    .fold((corners[0], corners[0]), |(mins, maxs), corner| {
+                        (mins.inf(corner), maxs.sup(corner))
+                    });
+
+                Some(PdfAnnotation::FreeText(PdfFreeTextAnnotation {
+                    upper_left: mins,
+                    size: maxs - mins,
+                    contents: textstroke.text,
+                    font_size: textstroke.text_style.font_size * scale,
+                    color: utils::pdfcolor_from_color(textstroke.text_style.color),
+                }))
+            }
+            Stroke::VectorImage(_) | Stroke::BitmapImage(_) | Stroke::PdfPage(_) => None,
+        }
+    }
+
+    /// Converts a native Pdf annotation into strokes.
+    ///
+    /// Ink annotations become brush strokes, one for each of their paths. Text markup annotations become
+    /// marker strokes in the highlighter layer, over, below or through each of the marked up text quadrilaterals.
+    /// Free text annotations become text strokes. The transform maps the displayed coordinates of the Pdf page
+    /// to the stroke coordinates.
+    pub fn from_pdf_annotation(
+        annotation: &PdfAnnotation,
+        transform: &na::Affine2<f64>,
+    ) -> Vec<(Self, Option<StrokeLayer>)> {
+        let scale = transform
+            .matrix()
+            .fixed_view::<2, 2>(0, 0)
+            .determinant()
+            .abs()
+            .sqrt();
+        let convert_coord = |pos: na::Vector2<f64>| (transform * na::Point2::from(pos)).coords;
+
+        match annotation {
+            PdfAnnotation::Ink(ink) => {
+                let layer = ink.highlight.then_some(StrokeLayer::Highlighter);
+                let smooth_options = SmoothOptions {
+                    stroke_width: ink.width * scale,
+                    stroke_color: Some(utils::color_from_pdf(ink.color)),
+                    pressure_curve: PressureCurve::Const,
+                    ..Default::default()
+                };
+
+                ink.ink_list
+                    .iter()
+                    .filter_map(|path| {
+                        let elements = path
+                            .iter()
+                            .map(|&pos| Element::new(convert_coord(pos), Element::PRESSURE_DEFAULT))
+                            .collect::<Vec<Element>>();
+                        let penpath = PenPath::try_from_elements(elements)?;
+
+                        Some((
+                            Stroke::BrushStroke(BrushStroke::from_penpath(
+                                penpath,
+                                Style::Smooth(smooth_options.clone()),
+                            )),
+                            layer,
+                        ))
+                    })
+                    .collect()
+            }
+            PdfAnnotation::Markup(markup) => {
+                let mut color = utils::color_from_pdf(markup.color);
+                if matches!(markup.kind, PdfMarkupKind::Highlight) {
+                    color.a = color.a.min(PdfAnnotFile::HIGHLIGHT_OPACITY);
+                }
+
+                markup
+                    .quads
+                    .iter()
+                    .filter_map(|&[upper_left, upper_right, lower_left, lower_right]| {
+                        let height = ((lower_left - upper_left).magnitude()
+                            + (lower_right - upper_right).magnitude())
+                            * 0.5;
+                        let (start, end, width) = match markup.kind {
+                            PdfMarkupKind::Highlight => (
+                                (upper_left + lower_left) * 0.5,
+                                (upper_right + lower_right) * 0.5,
+                                height,
+                            ),
+                            PdfMarkupKind::Underline => (
+                                lower_left,
+                                lower_right,
+                                height * PDF_MARKUP_LINE_WIDTH_FACTOR,
+                            ),
+                            PdfMarkupKind::StrikeOut => (
+                                (upper_left + lower_left) * 0.5,
+                                (upper_right + lower_right) * 0.5,
+                                height * PDF_MARKUP_LINE_WIDTH_FACTOR,
+                            ),
+                        };
+                        // Pull in the ends, so that the round caps don't extend beyond the marked up text
+                        let length = (end - start).magnitude();
+                        let inset = (end - start) * ((width * 0.5).min(length * 0.5) / length);
+                        if !inset.iter().all(|v| v.is_finite()) {
+                            return None;
+                        }
+                        let penpath = PenPath::try_from_elements([
+                            Element::new(convert_coord(start + inset), Element::PRESSURE_DEFAULT),
+                            Element::new(convert_coord(end - inset), Element::PRESSURE_DEFAULT),
+                        ])?;
+                        let smooth_options = SmoothOptions {
+                            stroke_width: width * scale,
+                            stroke_color: Some(color),
+                            pressure_curve: PressureCurve::Const,
+                            ..Default::default()
+                        };
+
+                        Some((
+                            Stroke::BrushStroke(BrushStroke::from_penpath(
+                                penpath,
+                                Style::Smooth(smooth_options),
+                            )),
+                            Some(StrokeLayer::Highlighter),
+                        ))
+                    })
+                    .collect()
+            }
+            PdfAnnotation::FreeText(free_text) => {
+                if free_text.contents.is_empty() {
+                    return vec![];
+                }
+                let text_style = TextStyle {
+                    font_size: free_text.font_size * scale,
+                    color: utils::color_from_pdf(free_text.color),
+                    max_width: Some(free_text.size[0] * scale),
+                    ..Default::default()
+                };
+
+                vec![(
+                    Stroke::TextStroke(TextStroke::new(
+                        free_text.contents.clone(),
+                        convert_coord(free_text.upper_left),
+                        text_style,
+                    )),
+                    None,
+                )]
+            }
+        }
+    }
+}
+
+/// The tolerance when flattening paths into the polylines of Pdf ink annotations.
+const PDF_ANNOTATION_FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// Flattens the path into the polylines of a Pdf ink annotation, transformed with the given transform.
+fn pdf_ink_list(path: kurbo::BezPath, transform: &na::Affine2<f64>) -> Vec<Vec<na::Vector2<f64>>> {
+    let mut ink_list: Vec<Vec<na::Vector2<f64>>> = vec![];
+
+    kurbo::flatten(path, PDF_ANNOTATION_FLATTEN_TOLERANCE, |el| {
+        let pos = match el {
+            kurbo::PathEl::MoveTo(pos) => {
+                ink_list.push(vec![]);
+                pos
+            }
+            kurbo::PathEl::LineTo(pos) => pos,
+            // Closed paths return to their start
+            kurbo::PathEl::ClosePath => {
+                let Some(start) = ink_list.last().and_then(|path| path.first().copied()) else {
+                    return;
+                };
+                if let Some(path) = ink_list.last_mut() {
+                    path.push(start);
+                }
+                return;
+            }
+            _ => return,
+        };
+        if let Some(path) = ink_list.last_mut() {
+            path.push((transform * na::point![pos.x, pos.y]).coords);
+        }
+    });
+
+    ink_list
+}
+
+/// The width of the strokes for underline and strike out annotations, relative to the height of the text.
+const PDF_MARKUP_LINE_WIDTH_FACTOR: f64 = 0.1;
+
+/// The number of lines curves are approximated with when they are exported to Excalidraw.
+const CURVE_APPROX_SPLITS: i32 = 20;
+
+/// The shapes for the segments of Excalidraw lines and arrows.
+///
+/// The first and last segments become arrows when the element has arrowheads on their ends.
+fn shapes_from_excalidraw_points(
+    points: Vec<na::Vector2<f64>>,
+    start_arrowhead: bool,
+    end_arrowhead: bool,
+) -> Vec<Shape> {
+    let n_segments = points.len().saturating_sub(1);
+
+    points
+        .windows(2)
+        .enumerate()
+        .flat_map(|(i, segment)| {
+            let (start, end) = (segment[0], segment[1]);
+            let first = i == 0 && start_arrowhead;
+            let last = i + 1 == n_segments && end_arrowhead;
+
+            match (first, last) {
+                (true, true) => {
+                    let middle = (start + end) * 0.5;
+                    vec![
+                        Shape::Arrow(Arrow::new(middle, start)),
+                        Shape::Arrow(Arrow::new(middle, end)),
+                    ]
+                }
+                (true, false) => vec![Shape::Arrow(Arrow::new(end, start))],
+                (false, true) => vec![Shape::Arrow(Arrow::new(start, end))],
+                (false, false) => vec![Shape::Line(Line { start, end })],
+            }
+        })
+        .collect()
+}
+
+/// The start of the lines and the end of the last line.
+fn points_from_lines(lines: Vec<Line>) -> Vec<na::Vector2<f64>> {
+    let last_end = lines.last().map(|line| line.end);
+    lines
+        .into_iter()
+        .map(|line| line.start)
+        .chain(last_end)
+        .collect()
+}
+
+/// Sets the position, size and angle of the Excalidraw element to the box with the given center and half extents,
+/// transformed by the transform.
+fn set_excalidraw_box(
+    element: &mut ExcalidrawElement,
+    transform: &Transform,
+    center: na::Vector2<f64>,
+    half_extents: na::Vector2<f64>,
+    current_dpi: f64,
+) {
+    let center = transform.transform_point(center.into()).coords;
+    let x_axis = transform.transform_vec(na::vector![half_extents[0], 0.0]);
+    let y_axis = transform.transform_vec(na::vector![0.0, half_extents[1]]);
+    let size = na::vector![x_axis.magnitude(), y_axis.magnitude()] * 2.0;
+    let pos = utils::convert_coord_dpi(center - size * 0.5, current_dpi, ExcalidrawFile::DPI);
+    let size = utils::convert_coord_dpi(size, current_dpi, ExcalidrawFile::DPI);
+
+    element.x = pos[0];
+    element.y = pos[1];
+    element.width = size[0];
+    element.height = size[1];
+    element.angle = x_axis[1].atan2(x_axis[0]);
+}
+
+/// Sets the position of the Excalidraw element to the first point and its size to the size of the bounds of the points.
+///
+/// Returns the points relative to the position.
+fn set_excalidraw_points(
+    element: &mut ExcalidrawElement,
+    points: &[na::Vector2<f64>],
+) -> Vec<[f64; 2]> {
+    let Some(&first) = points.first() else {
+        return vec![];
+    };
+    let (mins, maxs) = points.iter().fold((first, first), |(mins, maxs), point| {
+        (mins.inf(point), maxs.sup(point))
+    });
+
+    element.x = first[0];
+    element.y = first[1];
+    element.width = maxs[0] - mins[0];
+    element.height = maxs[1] - mins[1];
+    points
+        .iter()
+        .map(|point| [point[0] - first[0], point[1] - first[1]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inkml_samples_curved_segments() {
+        let path = PenPath::new_w_segments(
+            Element::new(na::vector![0.0, 0.0], 0.5),
+            [Segment::CubBezTo {
+                cp1: na::vector![0.0, 10.0],
+                cp2: na::vector![10.0, 10.0],
+                end: Element::new(na::vector![10.0, 0.0], 0.5),
+            }],
+        );
+        let stroke = Stroke::BrushStroke(BrushStroke::from_penpath(
+            path,
+            Style::Smooth(SmoothOptions::default()),
+        ));
+
+        let (trace, _) = stroke
+            .into_inkml(InkmlFile::EXPORT_DPI, 100.0)
+            .expect("brush strokes are converted into traces");
+
+        // The control points are not exported, but the curve is sampled
+        assert!(trace.points.len() > 2);
+        assert_eq!(trace.points.first().unwrap()[..2], [0.0, 0.0]);
+        assert_eq!(trace.points.last().unwrap()[..2], [10.0, 0.0]);
+        assert!(trace.points.iter().all(|point| point[1] <= 7.5 + 1e-9));
+        assert!(trace.points.iter().any(|point| point[1] > 5.0));
+        for (i, point) in trace.points.iter().enumerate() {
+            assert_eq!(
+                point[3],
+                100.0 + i as f64 * InkmlFile::EXPORT_POINT_INTERVAL
+            );
+        }
+    }
+
+    #[test]
+    fn excalidraw_rough_rectangle_roundtrip() {
+        let element = ExcalidrawElement {
+            kind: ExcalidrawElementKind::Rectangle,
+            x: 10.0,
+            y: 20.0,
+            width: 100.0,
+            height: 50.0,
+            stroke_color: String::from("#1e1e1e"),
+            background_color: String::from("#a5d8ff"),
+            fill_style: ExcalidrawFillStyle::CrossHatch,
+            roughness: 2.0,
+            seed: 42,
+            ..Default::default()
+        };
+
+        let strokes = Stroke::from_excalidraw_element(&element, ExcalidrawFile::DPI);
+        assert_eq!(strokes.len(), 1);
+        let Stroke::ShapeStroke(shapestroke) = &strokes[0] else {
+            panic!("rectangles are converted into shape strokes");
+        };
+        let Style::Rough(options) = &shapestroke.style else {
+            panic!("rectangles with roughness have the rough style");
+        };
+        assert_eq!(options.roughness, 2.0);
+        assert_eq!(options.fill_style, FillStyle::Crosshatch);
+        assert_eq!(options.seed, Some(42));
+        assert!(options.fill_color.is_some());
+
+        let exported = strokes[0]
+            .clone()
+            .into_excalidraw(ExcalidrawFile::DPI)
+            .unwrap();
+        assert!(matches!(exported.kind, ExcalidrawElementKind::Rectangle));
+        assert!((exported.x - 10.0).abs() < 1e-6);
+        assert!((exported.y - 20.0).abs() < 1e-6);
+        assert!((exported.width - 100.0).abs() < 1e-6);
+        assert!((exported.height - 50.0).abs() < 1e-6);
+        assert_eq!(exported.roughness, 2.0);
+        assert_eq!(exported.fill_style, ExcalidrawFillStyle::CrossHatch);
+        assert_eq!(exported.seed, 42);
+        assert_eq!(exported.stroke_color, "#1e1e1e");
+        assert_eq!(exported.background_color, "#a5d8ff");
+    }
+
+    #[test]
+    fn excalidraw_elements_without_roughness_are_smooth() {
+        let element = ExcalidrawElement {
+            kind: ExcalidrawElementKind::Ellipse,
+            width: 40.0,
+            height: 20.0,
+            roughness: 0.0,
+            ..Default::default()
+        };
+
+        let strokes = Stroke::from_excalidraw_element(&element, ExcalidrawFile::DPI);
+        assert_eq!(strokes.len(), 1);
+        assert!(matches!(
+            &strokes[0],
+            Stroke::ShapeStroke(ShapeStroke {
+                shape: Shape::Ellipse(_),
+                style: Style::Smooth(_),
+                ..
+            })
+        ));
+        let exported = strokes[0]
+            .clone()
+            .into_excalidraw(ExcalidrawFile::DPI)
+            .unwrap();
+        assert_eq!(exported.roughness, 0.0);
+    }
+
+    #[test]
+    fn excalidraw_arrow_split_into_segments() {
+        let element = ExcalidrawElement {
+            kind: ExcalidrawElementKind::Arrow(ExcalidrawLinear {
+                points: vec![[0.0, 0.0], [40.0, 0.0], [40.0, 30.0]],
+                start_arrowhead: None,
+                end_arrowhead: Some(String::from("arrow")),
+            }),
+            width: 40.0,
+            height: 30.0,
+            ..Default::default()
+        };
+
+        let shapes = Stroke::from_excalidraw_element(&element, ExcalidrawFile::DPI)
+            .into_iter()
+            .map(|stroke| match stroke {
+                Stroke::ShapeStroke(shapestroke) => shapestroke.shape,
+                _ => panic!("arrows are converted into shape strokes"),
+            })
+            .collect::<Vec<Shape>>();
+        assert_eq!(shapes.len(), 2);
+        assert!(matches!(shapes[0], Shape::Line(_)));
+        let Shape::Arrow(arrow) = &shapes[1] else {
+            panic!("the last segment has the arrowhead");
+        };
+        assert_eq!(arrow.start, na::vector![40.0, 0.0]);
+        assert_eq!(arrow.tip, na::vector![40.0, 30.0]);
+    }
+
+    #[test]
+    fn excalidraw_freedraw_roundtrip() {
+        let element = ExcalidrawElement {
+            kind: ExcalidrawElementKind::Freedraw(ExcalidrawFreedraw {
+                points: vec![[0.0, 0.0], [10.0, 5.0], [20.0, 0.0]],
+                pressures: vec![0.2, 0.5, 0.8],
+                simulate_pressure: false,
+            }),
+            x: 5.0,
+            y: 5.0,
+            width: 20.0,
+            height: 5.0,
+            ..Default::default()
+        };
+
+        let strokes = Stroke::from_excalidraw_element(&element, ExcalidrawFile::DPI);
+        assert_eq!(strokes.len(), 1);
+        assert!(matches!(strokes[0], Stroke::BrushStroke(_)));
+
+        let exported = strokes[0]
+            .clone()
+            .into_excalidraw(ExcalidrawFile::DPI)
+            .unwrap();
+        let ExcalidrawElementKind::Freedraw(freedraw) = &exported.kind else {
+            panic!("brush strokes are converted into freedraw elements");
+        };
+        assert_eq!(freedraw.points, vec![[0.0, 0.0], [10.0, 5.0], [20.0, 0.0]]);
+        assert_eq!(freedraw.pressures, vec![0.2, 0.5, 0.8]);
+        assert_eq!((exported.x, exported.y), (5.0, 5.0));
+        assert!((exported.stroke_width - element.stroke_width).abs() < 1e-6);
+    }
+
+    #[test]
+    fn excalidraw_deleted_and_unsupported_elements_skipped() {
+        let deleted = ExcalidrawElement {
+            kind: ExcalidrawElementKind::Rectangle,
+            is_deleted: true,
+            ..Default::default()
+        };
+        assert!(Stroke::from_excalidraw_element(&deleted, ExcalidrawFile::DPI).is_empty());
+        assert!(Stroke::from_excalidraw_element(
+            &ExcalidrawElement::default(),
+            ExcalidrawFile::DPI
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn shape_into_pdf_ink_annotation() {
+        let stroke = Stroke::ShapeStroke(ShapeStroke::new(
+            Shape::Rectangle(Rectangle::from_half_extents(
+                na::vector![10.0, 10.0],
+                na::vector![5.0, 5.0],
+            )),
+            Style::Smooth(SmoothOptions::default()),
+        ));
+        let transform = na::Affine2::from_matrix_unchecked(na::Matrix3::new_scaling(2.0));
+
+        let Some(PdfAnnotation::Ink(ink)) = stroke.into_pdf_annotation(&transform, false) else {
+            panic!("shape strokes are converted into ink annotations");
+        };
+        assert_eq!(ink.ink_list.len(), 1);
+        let outline = &ink.ink_list[0];
+        // The rectangle outline is closed
+        assert_eq!(outline.first(), outline.last());
+        assert!(outline.contains(&na::vector![10.0, 10.0]));
+        assert!(outline.contains(&na::vector![30.0, 30.0]));
+        assert_eq!(ink.width, SmoothOptions::default().stroke_width * 2.0);
     }
 }
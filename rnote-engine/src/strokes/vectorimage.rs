@@ -1,17 +1,22 @@
 // Imports
 use super::strokebehaviour::GeneratedStrokeImages;
-use super::{Stroke, StrokeBehaviour};
-use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs};
+use super::textstroke::TextStyle;
+use super::{BrushStroke, ShapeStroke, Stroke, StrokeBehaviour, TextStroke};
+use crate::engine::import::{PdfImportPageSpacing, PdfImportPrefs, PdfPageSource};
 use crate::{document::Format, strokes::strokebehaviour};
 use crate::{render, DrawBehaviour};
 use p2d::bounding_volume::Aabb;
 use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use rnote_compose::color;
 use rnote_compose::helpers::AabbHelpers;
-use rnote_compose::shapes::Rectangle;
-use rnote_compose::shapes::ShapeBehaviour;
+use rnote_compose::penpath::{Element, Segment};
+use rnote_compose::shapes::{
+    CubicBezier, Ellipse, Line, QuadraticBezier, Rectangle, Shape, ShapeBehaviour,
+};
+use rnote_compose::style::smooth::SmoothOptions;
+use rnote_compose::style::PressureCurve;
 use rnote_compose::transform::Transform;
 use rnote_compose::transform::TransformBehaviour;
+use rnote_compose::{color, Color, PenPath, Style};
 use serde::{Deserialize, Serialize};
 use std::ops::Range;
 use usvg::{TreeParsing, TreeTextToPath, TreeWriting};
@@ -28,6 +33,14 @@ pub struct VectorImage {
     pub intrinsic_size: na::Vector2<f64>,
     #[serde(rename = "rectangle")]
     pub rectangle: Rectangle,
+    /// The text content of the image, extracted on import. Used for searching.
+    ///
+    /// Text in the svg data itself is converted to paths, so it needs to be preserved separately.
+    #[serde(rename = "text")]
+    pub text: String,
+    /// The Pdf page the image was generated from, when it was imported as page background.
+    #[serde(rename = "pdf_page_source")]
+    pub pdf_page_source: Option<PdfPageSource>,
 }
 
 impl Default for VectorImage {
@@ -36,6 +49,8 @@ impl Default for VectorImage {
             svg_data: String::default(),
             intrinsic_size: na::Vector2::zeros(),
             rectangle: Rectangle::default(),
+            text: String::default(),
+            pdf_page_source: None,
         }
     }
 }
@@ -154,20 +169,10 @@ impl VectorImage {
         pos: na::Vector2<f64>,
         size: Option<na::Vector2<f64>>,
     ) -> Result<Self, anyhow::Error> {
-        let xml_options = usvg::XmlOptions {
-            id_prefix: Some(rnote_compose::utils::svg_random_id_prefix()),
-            coordinates_precision: 3,
-            transforms_precision: 4,
-            writer_opts: xmlwriter::Options {
-                use_single_quote: false,
-                indent: xmlwriter::Indent::None,
-                attributes_indent: xmlwriter::Indent::None,
-            },
-        };
-
         let mut svg_tree = usvg::Tree::from_str(svg_data, &usvg::Options::default())?;
+        let text = Self::extract_text_from_svg_tree(&svg_tree);
         svg_tree.convert_text(&render::USVG_FONTDB);
-        let svg_data = svg_tree.to_string(&xml_options);
+        let svg_data = svg_tree.to_string(&Self::usvg_xml_options());
         let intrinsic_size =
             na::vector![svg_tree.size.width() as f64, svg_tree.size.height() as f64];
 
@@ -190,9 +195,113 @@ impl VectorImage {
             svg_data,
             intrinsic_size,
             rectangle,
+            text,
+            pdf_page_source: None,
         })
     }
 
+    /// Import the svg data as editable strokes instead of a single vector image.
+    ///
+    /// Lines, rectangles, ellipses and bezier curves become shape strokes, other paths become brush strokes
+    /// and text becomes text strokes. Elements that can't be converted, like images or paths painted with gradients,
+    /// are kept in a vector image that is returned as the first stroke.
+    pub fn import_from_svg_data_as_strokes(
+        svg_data: &str,
+        pos: na::Vector2<f64>,
+    ) -> Result<Vec<Stroke>, anyhow::Error> {
+        let mut svg_tree = usvg::Tree::from_str(svg_data, &usvg::Options::default())?;
+        let intrinsic_size =
+            na::vector![svg_tree.size.width() as f64, svg_tree.size.height() as f64];
+        let transform =
+            na::Affine2::from_matrix_unchecked(na::Translation2::from(pos).to_homogeneous())
+                * svg_tree_user_space_transform(&svg_tree);
+
+        let strokes = convert_svg_tree_into_strokes(&svg_tree, transform);
+        svg_tree.convert_text(&render::USVG_FONTDB);
+
+        let remaining = if svg_tree_has_content(&svg_tree) {
+            Some(Stroke::VectorImage(Self {
+                svg_data: svg_tree.to_string(&Self::usvg_xml_options()),
+                intrinsic_size,
+                rectangle: Rectangle {
+                    cuboid: p2d::shape::Cuboid::new(intrinsic_size * 0.5),
+                    transform: Transform::new_w_isometry(na::Isometry2::new(
+                        pos + intrinsic_size * 0.5,
+                        0.0,
+                    )),
+                },
+                ..Default::default()
+            }))
+        } else {
+            None
+        };
+
+        Ok(remaining.into_iter().chain(strokes).collect())
+    }
+
+    /// Ungroup the vector image into editable strokes, the same way as in `import_from_svg_data_as_strokes()`.
+    ///
+    /// The text of the image was already converted to paths on import, so it becomes brush strokes.
+    pub fn ungroup(&self) -> Result<Vec<Stroke>, anyhow::Error> {
+        let mut svg_tree = usvg::Tree::from_str(&self.svg_data, &usvg::Options::default())?;
+        let half_extents = self.rectangle.cuboid.half_extents;
+        // The svg data is drawn scaled from its intrinsic size into the rectangle
+        let image_transform = na::Affine2::from_matrix_unchecked(
+            na::Translation2::from(-half_extents).to_homogeneous()
+                * na::Matrix3::new_nonuniform_scaling(&na::vector![
+                    2.0 * half_extents[0] / self.intrinsic_size[0],
+                    2.0 * half_extents[1] / self.intrinsic_size[1]
+                ]),
+        );
+        let transform = self.rectangle.transform.affine
+            * image_transform
+            * svg_tree_user_space_transform(&svg_tree);
+
+        let strokes = convert_svg_tree_into_strokes(&svg_tree, transform);
+
+        let remaining = if svg_tree_has_content(&svg_tree) {
+            Some(Stroke::VectorImage(Self {
+                svg_data: svg_tree.to_string(&Self::usvg_xml_options()),
+                ..self.clone()
+            }))
+        } else {
+            None
+        };
+
+        Ok(remaining.into_iter().chain(strokes).collect())
+    }
+
+    fn usvg_xml_options() -> usvg::XmlOptions {
+        usvg::XmlOptions {
+            id_prefix: Some(rnote_compose::utils::svg_random_id_prefix()),
+            coordinates_precision: 3,
+            transforms_precision: 4,
+            writer_opts: xmlwriter::Options {
+                use_single_quote: false,
+                indent: xmlwriter::Indent::None,
+                attributes_indent: xmlwriter::Indent::None,
+            },
+        }
+    }
+
+    /// Extract the text of all text elements in the svg tree, separated by newlines.
+    fn extract_text_from_svg_tree(svg_tree: &usvg::Tree) -> String {
+        svg_tree
+            .root
+            .descendants()
+            .filter_map(|node| match *node.borrow() {
+                usvg::NodeKind::Text(ref text) => Some(
+                    text.chunks
+                        .iter()
+                        .map(|chunk| chunk.text.as_str())
+                        .collect::<String>(),
+                ),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
     pub fn import_from_pdf_bytes(
         to_be_read: &[u8],
         pdf_import_prefs: PdfImportPrefs,
@@ -219,6 +328,9 @@ impl VectorImage {
             let width = intrinsic_size.0 * page_zoom;
             let height = intrinsic_size.1 * page_zoom;
 
+            // The text is lost when rendering the page to svg, so it is extracted separately
+            let text = page.text().map(|text| text.to_string()).unwrap_or_default();
+
             let res = move || -> anyhow::Result<String> {
                 let svg_stream: Vec<u8> = vec![];
 
@@ -247,7 +359,7 @@ impl VectorImage {
                     cx.paint()?;
 
                     // Render the poppler page
-                    page.render_for_printing(&cx);
+                    page.render_for_printing_with_options(&cx, pdf_import_prefs.poppler_print_flags());
 
                     // Draw outline around page
                     cx.set_source_rgba(color::GNOME_REDS[4].as_rgba().0, color::GNOME_REDS[4].as_rgba().1, color::GNOME_REDS[4].as_rgba().2, 1.0);
@@ -281,26 +393,26 @@ impl VectorImage {
             };
 
             match res() {
-                Ok(svg_data) => Some(render::Svg {
+                Ok(svg_data) => Some((render::Svg {
                     svg_data,
                     bounds,
-                }),
+                }, text)),
                 Err(e) => {
                     log::error!("importing page {page_i} from pdf failed with Err: {e:?}");
                     None
                 }
             }
-        }).collect::<Vec<render::Svg>>();
+        }).collect::<Vec<(render::Svg, String)>>();
 
         Ok(svgs
             .into_par_iter()
-            .filter_map(|svg| {
+            .filter_map(|(svg, text)| {
                 match Self::import_from_svg_data(
                     svg.svg_data.as_str(),
                     svg.bounds.mins.coords,
                     Some(svg.bounds.extents()),
                 ) {
-                    Ok(vectorimage) => Some(vectorimage),
+                    Ok(vectorimage) => Some(Self { text, ..vectorimage }),
                     Err(e) => {
                         log::error!("import_from_svg_data() failed failed in vectorimage import_from_pdf_bytes() with Err: {e:?}");
                         None
@@ -325,3 +437,580 @@ impl VectorImage {
         Ok(export_svg_data)
     }
 }
+
+/// The transform from the user space of the svg tree to the coordinates of its intrinsic size.
+///
+/// The aspect ratio of the view box is not preserved, the same as when rendering vector images.
+fn svg_tree_user_space_transform(svg_tree: &usvg::Tree) -> na::Affine2<f64> {
+    let view_box = svg_tree.view_box.rect;
+    let scale = na::vector![
+        svg_tree.size.width() as f64 / view_box.width() as f64,
+        svg_tree.size.height() as f64 / view_box.height() as f64
+    ];
+
+    na::Affine2::from_matrix_unchecked(
+        na::Matrix3::new_nonuniform_scaling(&scale)
+            * na::Translation2::new(-view_box.x() as f64, -view_box.y() as f64).to_homogeneous(),
+    )
+}
+
+/// Whether the svg tree has any content left that is drawn.
+fn svg_tree_has_content(svg_tree: &usvg::Tree) -> bool {
+    svg_tree
+        .root
+        .descendants()
+        .any(|node| !matches!(*node.borrow(), usvg::NodeKind::Group(_)))
+}
+
+/// Converts the paths and texts of the svg tree into strokes and removes them from the tree.
+///
+/// The transform maps the user space of the svg to the coordinates of the strokes.
+fn convert_svg_tree_into_strokes(
+    svg_tree: &usvg::Tree,
+    transform: na::Affine2<f64>,
+) -> Vec<Stroke> {
+    let nodes = svg_tree.root.descendants().collect::<Vec<usvg::Node>>();
+    let mut strokes = vec![];
+
+    for node in nodes {
+        // Clipped, masked or filtered content can't be represented by strokes
+        let has_effects = node.ancestors().any(|ancestor| match *ancestor.borrow() {
+            usvg::NodeKind::Group(ref group) => {
+                group.clip_path.is_some() || group.mask.is_some() || !group.filters.is_empty()
+            }
+            _ => false,
+        });
+        if has_effects {
+            continue;
+        }
+        // The node transform includes the transforms of all its ancestors
+        let (node_transform, opacity) = node.ancestors().fold(
+            (na::Affine2::identity(), 1.0),
+            |(acc, opacity), ancestor| {
+                let ancestor = ancestor.borrow();
+                let opacity = match *ancestor {
+                    usvg::NodeKind::Group(ref group) => opacity * group.opacity.get() as f64,
+                    _ => opacity,
+                };
+                (
+                    affine_from_usvg_transform(ancestor.transform()) * acc,
+                    opacity,
+                )
+            },
+        );
+        let converted = match *node.borrow() {
+            usvg::NodeKind::Path(ref path) => {
+                strokes_from_usvg_path(path, transform * node_transform, opacity)
+            }
+            usvg::NodeKind::Text(ref text) => Some(strokes_from_usvg_text(
+                text,
+                transform * node_transform,
+                opacity,
+            )),
+            _ => None,
+        };
+
+        if let Some(converted) = converted {
+            strokes.extend(converted);
+            node.detach();
+        }
+    }
+
+    strokes
+}
+
+fn affine_from_usvg_transform(transform: usvg::Transform) -> na::Affine2<f64> {
+    na::Affine2::from_matrix_unchecked(na::Matrix3::new(
+        transform.sx as f64,
+        transform.kx as f64,
+        transform.tx as f64,
+        transform.ky as f64,
+        transform.sy as f64,
+        transform.ty as f64,
+        0.0,
+        0.0,
+        1.0,
+    ))
+}
+
+/// The mean scale factor of the transform.
+fn affine_mean_scale(transform: &na::Affine2<f64>) -> f64 {
+    transform
+        .matrix()
+        .fixed_view::<2, 2>(0, 0)
+        .determinant()
+        .abs()
+        .sqrt()
+}
+
+/// The rotation angle of the transformed x axis.
+fn affine_angle(transform: &na::Affine2<f64>) -> f64 {
+    let x_axis = transform * na::Vector2::x();
+    x_axis[1].atan2(x_axis[0])
+}
+
+/// The color of the paint, None for gradients and patterns.
+fn color_from_usvg_paint(paint: &usvg::Paint, opacity: f64) -> Option<Color> {
+    match paint {
+        usvg::Paint::Color(color) => Some(Color::new(
+            f64::from(color.red) / 255.0,
+            f64::from(color.green) / 255.0,
+            f64::from(color.blue) / 255.0,
+            opacity,
+        )),
+        _ => None,
+    }
+}
+
+/// A subpath of a svg path, in the coordinates of the strokes.
+struct SvgSubpath {
+    start: na::Vector2<f64>,
+    segments: Vec<SvgSegment>,
+    closed: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SvgSegment {
+    Line {
+        end: na::Vector2<f64>,
+    },
+    Quad {
+        cp: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+    },
+    Cubic {
+        cp1: na::Vector2<f64>,
+        cp2: na::Vector2<f64>,
+        end: na::Vector2<f64>,
+    },
+}
+
+impl SvgSegment {
+    fn end(&self) -> na::Vector2<f64> {
+        match self {
+            SvgSegment::Line { end } => *end,
+            SvgSegment::Quad { end, .. } => *end,
+            SvgSegment::Cubic { end, .. } => *end,
+        }
+    }
+}
+
+impl SvgSubpath {
+    /// The relative tolerance when recognizing shapes.
+    const SHAPE_TOLERANCE: f64 = 1e-3;
+
+    fn from_usvg_path_data(
+        data: &usvg::tiny_skia_path::Path,
+        transform: na::Affine2<f64>,
+    ) -> Vec<Self> {
+        let map = |point: usvg::tiny_skia_path::Point| -> na::Vector2<f64> {
+            (transform * na::point![point.x as f64, point.y as f64]).coords
+        };
+        let mut subpaths: Vec<Self> = vec![];
+
+        for segment in data.segments() {
+            match segment {
+                usvg::tiny_skia_path::PathSegment::MoveTo(point) => subpaths.push(Self {
+                    start: map(point),
+                    segments: vec![],
+                    closed: false,
+                }),
+                usvg::tiny_skia_path::PathSegment::Close => {
+                    if let Some(subpath) = subpaths.last_mut() {
+                        subpath.closed = true;
+                    }
+                }
+                segment => {
+                    let Some(subpath) = subpaths.last_mut() else {
+                        continue;
+                    };
+                    subpath.segments.push(match segment {
+                        usvg::tiny_skia_path::PathSegment::QuadTo(cp, end) => SvgSegment::Quad {
+                            cp: map(cp),
+                            end: map(end),
+                        },
+                        usvg::tiny_skia_path::PathSegment::CubicTo(cp1, cp2, end) => {
+                            SvgSegment::Cubic {
+                                cp1: map(cp1),
+                                cp2: map(cp2),
+                                end: map(end),
+                            }
+                        }
+                        usvg::tiny_skia_path::PathSegment::LineTo(end)
+                        | usvg::tiny_skia_path::PathSegment::MoveTo(end) => {
+                            SvgSegment::Line { end: map(end) }
+                        }
+                        usvg::tiny_skia_path::PathSegment::Close => unreachable!(),
+                    });
+                }
+            }
+        }
+
+        subpaths
+    }
+
+    /// The on-curve points of the subpath, without the end of the last segment if it returns to the start.
+    fn points(&self) -> Vec<na::Vector2<f64>> {
+        let mut points = std::iter::once(self.start)
+            .chain(self.segments.iter().map(|segment| segment.end()))
+            .collect::<Vec<na::Vector2<f64>>>();
+        if points.len() > 1 && points.last() == Some(&self.start) {
+            points.pop();
+        }
+        points
+    }
+
+    fn approx_eq(&self, first: na::Vector2<f64>, second: na::Vector2<f64>, size: f64) -> bool {
+        (first - second).magnitude() <= size * Self::SHAPE_TOLERANCE
+    }
+
+    /// Recognizes lines, rectangles, ellipses and sequences of bezier curves.
+    fn recognize_shapes(&self, filled: bool) -> Option<Vec<Shape>> {
+        let points = self.points();
+        let all_lines = self
+            .segments
+            .iter()
+            .all(|segment| matches!(segment, SvgSegment::Line { .. }));
+        let all_cubics = self
+            .segments
+            .iter()
+            .all(|segment| matches!(segment, SvgSegment::Cubic { .. }));
+
+        if !self.closed && self.segments.len() == 1 && all_lines {
+            return Some(vec![Shape::Line(Line {
+                start: self.start,
+                end: self.segments[0].end(),
+            })]);
+        }
+        if self.closed && all_lines && points.len() == 4 {
+            let edges = [
+                points[1] - points[0],
+                points[2] - points[1],
+                points[3] - points[2],
+                points[0] - points[3],
+            ];
+            let size = edges[0].magnitude().max(edges[1].magnitude());
+            if self.approx_eq(edges[0], -edges[2], size)
+                && self.approx_eq(edges[1], -edges[3], size)
+                && edges[0].dot(&edges[1]).abs() <= size.powi(2) * Self::SHAPE_TOLERANCE
+            {
+                let center = (points[0] + points[2]) * 0.5;
+                return Some(vec![Shape::Rectangle(Rectangle {
+                    cuboid: p2d::shape::Cuboid::new(
+                        na::vector![edges[0].magnitude(), edges[1].magnitude()] * 0.5,
+                    ),
+                    transform: Transform::new_w_isometry(na::Isometry2::new(
+                        center,
+                        edges[0][1].atan2(edges[0][0]),
+                    )),
+                })]);
+            }
+        }
+        if self.closed && all_cubics && self.segments.len() == 4 && points.len() == 4 {
+            // Ellipses are converted into four arcs, starting at the end of an axis
+            let center = (points[0] + points[2]) * 0.5;
+            let (axis_0, axis_1) = (points[0] - center, points[1] - center);
+            let size = axis_0.magnitude().max(axis_1.magnitude());
+            if self.approx_eq(center, (points[1] + points[3]) * 0.5, size)
+                && axis_0.dot(&axis_1).abs() <= size.powi(2) * Self::SHAPE_TOLERANCE
+            {
+                return Some(vec![Shape::Ellipse(Ellipse {
+                    radii: na::vector![axis_0.magnitude(), axis_1.magnitude()],
+                    transform: Transform::new_w_isometry(na::Isometry2::new(
+                        center,
+                        axis_0[1].atan2(axis_0[0]),
+                    )),
+                })]);
+            }
+        }
+        // Curves can't be filled as a whole, so only unfilled curves are split into a sequence of shapes
+        if !filled
+            && !self.closed
+            && !self.segments.is_empty()
+            && self
+                .segments
+                .iter()
+                .all(|segment| !matches!(segment, SvgSegment::Line { .. }))
+        {
+            let mut start = self.start;
+            return Some(
+                self.segments
+                    .iter()
+                    .map(|segment| {
+                        let shape = match *segment {
+                            SvgSegment::Quad { cp, end } => {
+                                Shape::QuadraticBezier(QuadraticBezier { start, cp, end })
+                            }
+                            SvgSegment::Cubic { cp1, cp2, end } => {
+                                Shape::CubicBezier(CubicBezier {
+                                    start,
+                                    cp1,
+                                    cp2,
+                                    end,
+                                })
+                            }
+                            SvgSegment::Line { end } => Shape::Line(Line { start, end }),
+                        };
+                        start = segment.end();
+                        shape
+                    })
+                    .collect(),
+            );
+        }
+
+        None
+    }
+
+    fn into_penpath(self) -> PenPath {
+        let element = |pos: na::Vector2<f64>| Element::new(pos, Element::PRESSURE_DEFAULT);
+        let close = (self.closed
+            && self.segments.last().map(|segment| segment.end()) != Some(self.start))
+        .then_some(Segment::LineTo {
+            end: element(self.start),
+        });
+
+        PenPath::new_w_segments(
+            element(self.start),
+            self.segments
+                .into_iter()
+                .map(|segment| match segment {
+                    SvgSegment::Line { end } => Segment::LineTo { end: element(end) },
+                    SvgSegment::Quad { cp, end } => Segment::QuadBezTo {
+                        cp,
+                        end: element(end),
+                    },
+                    SvgSegment::Cubic { cp1, cp2, end } => Segment::CubBezTo {
+                        cp1,
+                        cp2,
+                        end: element(end),
+                    },
+                })
+                .chain(close),
+        )
+    }
+}
+
+/// Converts the path into shape strokes or brush strokes, a stroke for every subpath.
+///
+/// Returns None if the path can't be represented by strokes.
+fn strokes_from_usvg_path(
+    path: &usvg::Path,
+    transform: na::Affine2<f64>,
+    opacity: f64,
+) -> Option<Vec<Stroke>> {
+    let fill_color = match &path.fill {
+        Some(fill) => Some(color_from_usvg_paint(
+            &fill.paint,
+            fill.opacity.get() as f64 * opacity,
+        )?),
+        None => None,
+    };
+    let (stroke_color, stroke_width) = match &path.stroke {
+        Some(stroke) => (
+            Some(color_from_usvg_paint(
+                &stroke.paint,
+                stroke.opacity.get() as f64 * opacity,
+            )?),
+            stroke.width.get() as f64 * affine_mean_scale(&transform),
+        ),
+        None => (None, SmoothOptions::default().stroke_width),
+    };
+    let subpaths = SvgSubpath::from_usvg_path_data(&path.data, transform);
+    // Filled subpaths can't cut holes into each other
+    if fill_color.is_some() && subpaths.len() > 1 {
+        return None;
+    }
+    let style = Style::Smooth(SmoothOptions {
+        stroke_width,
+        stroke_color,
+        fill_color,
+        pressure_curve: PressureCurve::Const,
+    });
+
+    Some(
+        subpaths
+            .into_iter()
+            .flat_map(
+                |subpath| match subpath.recognize_shapes(fill_color.is_some()) {
+                    Some(shapes) => shapes
+                        .into_iter()
+                        .map(|shape| Stroke::ShapeStroke(ShapeStroke::new(shape, style.clone())))
+                        .collect::<Vec<Stroke>>(),
+                    None => {
+                        let mut brushstroke =
+                            BrushStroke::from_penpath(subpath.into_penpath(), style.clone());
+                        brushstroke.filled = fill_color.is_some();
+                        vec![Stroke::BrushStroke(brushstroke)]
+                    }
+                },
+            )
+            .collect(),
+    )
+}
+
+/// Converts the text into text strokes, a stroke for every text chunk.
+fn strokes_from_usvg_text(
+    text: &usvg::Text,
+    transform: na::Affine2<f64>,
+    opacity: f64,
+) -> Vec<Stroke> {
+    /// The distance from the baseline to the top of the text, relative to the font size.
+    const ASCENT_FACTOR: f64 = 0.8;
+    let scale = affine_mean_scale(&transform);
+    let angle = affine_angle(&transform);
+
+    text.chunks
+        .iter()
+        .filter_map(|chunk| {
+            let span = chunk.spans.first()?;
+            let color = span
+                .fill
+                .as_ref()
+                .and_then(|fill| {
+                    color_from_usvg_paint(&fill.paint, fill.opacity.get() as f64 * opacity)
+                })
+                .unwrap_or(TextStyle::FONT_COLOR_DEFAULT);
+            let font_size = span.font_size.get() as f64 * scale;
+            // The chunk position is on the baseline of the text
+            let baseline_pos = (transform
+                * na::point![chunk.x.unwrap_or(0.0) as f64, chunk.y.unwrap_or(0.0) as f64])
+            .coords;
+            let upper_left_pos = baseline_pos
+                - na::Rotation2::new(angle) * na::vector![0.0, font_size * ASCENT_FACTOR];
+
+            let text_style = TextStyle {
+                font_family: span
+                    .font
+                    .families
+                    .first()
+                    .cloned()
+                    .unwrap_or_else(|| String::from(TextStyle::FONT_FAMILY_DEFAULT)),
+                font_size,
+                font_weight: span.font.weight,
+                color,
+                ..Default::default()
+            };
+            let mut textstroke = TextStroke::new(chunk.text.clone(), upper_left_pos, text_style);
+            textstroke.rotate(angle, upper_left_pos.into());
+
+            Some(Stroke::TextStroke(textstroke))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHAPES_SVG: &str = r##"<svg xmlns="http://www.w3.org/2000/svg" width="200" height="100" viewBox="0 0 200 100">
+    <defs>
+        <linearGradient id="gradient">
+            <stop offset="0" stop-color="#ff0000"/>
+            <stop offset="1" stop-color="#0000ff"/>
+        </linearGradient>
+    </defs>
+    <rect x="10" y="20" width="100" height="50" fill="#ff0000"/>
+    <line x1="0" y1="0" x2="50" y2="50" stroke="#000000" stroke-width="2"/>
+    <ellipse cx="150" cy="50" rx="20" ry="10" fill="none" stroke="#0000ff"/>
+    <path d="M0 0 L10 0 L5 10 Z" fill="#00ff00"/>
+    <rect x="0" y="80" width="20" height="20" fill="url(#gradient)"/>
+</svg>"##;
+
+    fn approx_eq(first: na::Vector2<f64>, second: na::Vector2<f64>) -> bool {
+        (first - second).magnitude() < 1e-3
+    }
+
+    #[test]
+    fn import_svg_as_strokes() {
+        let strokes =
+            VectorImage::import_from_svg_data_as_strokes(SHAPES_SVG, na::vector![100.0, 0.0])
+                .unwrap();
+        assert_eq!(strokes.len(), 5);
+
+        // The rectangle painted with a gradient can't be converted and is kept in a vector image
+        assert!(matches!(strokes[0], Stroke::VectorImage(_)));
+
+        let Stroke::ShapeStroke(ShapeStroke {
+            shape: Shape::Rectangle(rectangle),
+            style: Style::Smooth(options),
+            ..
+        }) = &strokes[1] else {
+            panic!("the rect is not converted into a rectangle shape stroke");
+        };
+        assert!(approx_eq(
+            rectangle
+                .transform
+                .transform_point(na::point![0.0, 0.0])
+                .coords,
+            na::vector![160.0, 45.0]
+        ));
+        assert!(approx_eq(
+            rectangle.cuboid.half_extents,
+            na::vector![50.0, 25.0]
+        ));
+        assert_eq!(options.fill_color, Some(Color::new(1.0, 0.0, 0.0, 1.0)));
+        assert_eq!(options.stroke_color, None);
+
+        let Stroke::ShapeStroke(ShapeStroke {
+            shape: Shape::Line(line),
+            style: Style::Smooth(options),
+            ..
+        }) = &strokes[2] else {
+            panic!("the line is not converted into a line shape stroke");
+        };
+        assert!(approx_eq(line.start, na::vector![100.0, 0.0]));
+        assert!(approx_eq(line.end, na::vector![150.0, 50.0]));
+        assert_eq!(options.stroke_width, 2.0);
+
+        let Stroke::ShapeStroke(ShapeStroke {
+            shape: Shape::Ellipse(ellipse),
+            ..
+        }) = &strokes[3] else {
+            panic!("the ellipse is not converted into an ellipse shape stroke");
+        };
+        assert!(approx_eq(ellipse.radii, na::vector![20.0, 10.0]));
+
+        // Filled paths that aren't shapes become filled brush strokes
+        let Stroke::BrushStroke(brushstroke) = &strokes[4] else {
+            panic!("the filled path is not converted into a brush stroke");
+        };
+        assert!(brushstroke.filled);
+    }
+
+    #[test]
+    fn ungroup_vector_image() {
+        let vectorimage = VectorImage::import_from_svg_data(
+            SHAPES_SVG,
+            na::vector![100.0, 100.0],
+            Some(na::vector![400.0, 200.0]),
+        )
+        .unwrap();
+
+        let strokes = vectorimage.ungroup().unwrap();
+        assert_eq!(strokes.len(), 5);
+        let Stroke::VectorImage(remaining) = &strokes[0] else {
+            panic!("the content that can't be converted is not kept in a vector image");
+        };
+        // The remaining vector image keeps the bounds of the ungrouped image
+        assert_eq!(remaining.rectangle.bounds(), vectorimage.rectangle.bounds());
+
+        // The strokes are scaled with the image
+        let Stroke::ShapeStroke(ShapeStroke {
+            shape: Shape::Rectangle(rectangle),
+            ..
+        }) = &strokes[1] else {
+            panic!("the rect is not converted into a rectangle shape stroke");
+        };
+        assert!(approx_eq(
+            rectangle
+                .transform
+                .transform_point(na::point![0.0, 0.0])
+                .coords,
+            na::vector![220.0, 190.0]
+        ));
+        assert!(approx_eq(
+            rectangle.cuboid.half_extents,
+            na::vector![100.0, 50.0]
+        ));
+    }
+}
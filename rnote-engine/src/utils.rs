@@ -1,12 +1,16 @@
 // Imports
-use crate::fileformats::xoppformat;
+use crate::document::background::PatternStyle;
+use crate::fileformats::{excalidrawformat, inkmlformat, pdfannotformat, xoppformat};
 use geo::line_string;
+#[cfg(feature = "gtk")]
 use gtk4::{gdk, graphene, gsk};
 use p2d::bounding_volume::Aabb;
 use rnote_compose::Color;
+#[cfg(feature = "gtk")]
 use rnote_compose::Transform;
 use std::ops::Range;
 
+#[cfg(feature = "gtk")]
 pub trait GdkRGBAHelpers
 where
     Self: Sized,
@@ -17,6 +21,7 @@ where
     fn into_piet_color(self) -> piet::Color;
 }
 
+#[cfg(feature = "gtk")]
 impl GdkRGBAHelpers for gdk::RGBA {
     fn from_compose_color(color: rnote_compose::Color) -> Self {
         gdk::RGBA::new(
@@ -68,6 +73,84 @@ pub fn xoppcolor_from_color(color: Color) -> xoppformat::XoppColor {
     }
 }
 
+pub fn color_from_inkml(inkml_color: inkmlformat::InkmlColor) -> Color {
+    Color {
+        r: f64::from(inkml_color.red) / 255.0,
+        g: f64::from(inkml_color.green) / 255.0,
+        b: f64::from(inkml_color.blue) / 255.0,
+        a: f64::from(inkml_color.alpha) / 255.0,
+    }
+}
+
+pub fn inkmlcolor_from_color(color: Color) -> inkmlformat::InkmlColor {
+    inkmlformat::InkmlColor {
+        red: (color.r * 255.0).floor() as u8,
+        green: (color.g * 255.0).floor() as u8,
+        blue: (color.b * 255.0).floor() as u8,
+        alpha: (color.a * 255.0).floor() as u8,
+    }
+}
+
+pub fn color_from_excalidraw(excalidraw_color: excalidrawformat::ExcalidrawColor) -> Color {
+    Color {
+        r: f64::from(excalidraw_color.red) / 255.0,
+        g: f64::from(excalidraw_color.green) / 255.0,
+        b: f64::from(excalidraw_color.blue) / 255.0,
+        a: f64::from(excalidraw_color.alpha) / 255.0,
+    }
+}
+
+pub fn excalidrawcolor_from_color(color: Color) -> excalidrawformat::ExcalidrawColor {
+    excalidrawformat::ExcalidrawColor {
+        red: (color.r * 255.0).floor() as u8,
+        green: (color.g * 255.0).floor() as u8,
+        blue: (color.b * 255.0).floor() as u8,
+        alpha: (color.a * 255.0).floor() as u8,
+    }
+}
+
+pub fn color_from_pdf(pdf_color: pdfannotformat::PdfColor) -> Color {
+    Color {
+        r: pdf_color.red,
+        g: pdf_color.green,
+        b: pdf_color.blue,
+        a: pdf_color.alpha,
+    }
+}
+
+pub fn pdfcolor_from_color(color: Color) -> pdfannotformat::PdfColor {
+    pdfannotformat::PdfColor {
+        red: color.r,
+        green: color.g,
+        blue: color.b,
+        alpha: color.a,
+    }
+}
+
+/// The closest Xopp background style for the pattern style. The pattern sizes are not preserved.
+pub fn xopp_bg_style_from_pattern_style(
+    pattern: PatternStyle,
+) -> xoppformat::XoppBackgroundSolidStyle {
+    match pattern {
+        PatternStyle::None | PatternStyle::HexagonGrid => {
+            xoppformat::XoppBackgroundSolidStyle::Plain
+        }
+        PatternStyle::Lines => xoppformat::XoppBackgroundSolidStyle::Ruled,
+        // Xopp lined backgrounds have a margin line
+        PatternStyle::LinesMargin | PatternStyle::CornellNotes => {
+            xoppformat::XoppBackgroundSolidStyle::Lined
+        }
+        PatternStyle::Grid | PatternStyle::GridMajorLines => {
+            xoppformat::XoppBackgroundSolidStyle::Graph
+        }
+        PatternStyle::Dots => xoppformat::XoppBackgroundSolidStyle::Dotted,
+        PatternStyle::IsometricGrid => xoppformat::XoppBackgroundSolidStyle::IsometricGraph,
+        PatternStyle::IsometricDots => xoppformat::XoppBackgroundSolidStyle::IsometricDotted,
+        PatternStyle::Staves => xoppformat::XoppBackgroundSolidStyle::Staves,
+    }
+}
+
+#[cfg(feature = "gtk")]
 pub trait GrapheneRectHelpers
 where
     Self: Sized,
@@ -75,6 +158,7 @@ where
     fn from_p2d_aabb(aabb: Aabb) -> Self;
 }
 
+#[cfg(feature = "gtk")]
 impl GrapheneRectHelpers for graphene::Rect {
     fn from_p2d_aabb(aabb: Aabb) -> Self {
         graphene::Rect::new(
@@ -106,6 +190,7 @@ pub fn convert_coord_dpi(
     (coord / current_dpi) * target_dpi
 }
 
+#[cfg(feature = "gtk")]
 pub fn transform_to_gsk(transform: &Transform) -> gsk::Transform {
     gsk::Transform::new().matrix(&graphene::Matrix::from_2d(
         transform.affine[(0, 0)],
@@ -154,3 +239,39 @@ pub mod glib_bytes_base64 {
         rnote_compose::serialize::sliceu8_base64::deserialize(d).map(glib::Bytes::from_owned)
     }
 }
+
+/// Checking test output against golden files that are committed to the repository.
+#[cfg(test)]
+pub(crate) mod goldens {
+    use std::path::Path;
+
+    /// When set, the golden files are regenerated from the test output instead of compared against it.
+    pub(crate) const UPDATE_GOLDENS_ENV: &str = "RNOTE_UPDATE_GOLDENS";
+
+    /// Check the test output against the golden file.
+    ///
+    /// When [UPDATE_GOLDENS_ENV] is set, `write` is called to regenerate the golden file.
+    /// Otherwise the golden file must exist and `compare` is called with it.
+    /// This way the source tree is only written to when explicitly requested.
+    pub(crate) fn check_golden(
+        golden_file: &Path,
+        write: impl FnOnce(&Path) -> anyhow::Result<()>,
+        compare: impl FnOnce(&Path),
+    ) {
+        if std::env::var_os(UPDATE_GOLDENS_ENV).is_some() {
+            if let Some(dir) = golden_file.parent() {
+                std::fs::create_dir_all(dir).unwrap();
+            }
+            write(golden_file).unwrap();
+            eprintln!("wrote golden file {}", golden_file.display());
+            return;
+        }
+
+        assert!(
+            golden_file.exists(),
+            "golden file {} is missing, set {UPDATE_GOLDENS_ENV} to generate it",
+            golden_file.display()
+        );
+        compare(golden_file);
+    }
+}
@@ -1,3 +1,6 @@
+// Imports
+use crate::store::HistoryLabel;
+
 /// Flags returned to the UI widget that holds the engine.
 #[must_use]
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
@@ -22,6 +25,10 @@ pub struct WidgetFlags {
     pub hide_undo: Option<bool>,
     /// Is Some when redo button visibility should be changed. Is None if should not be changed.
     pub hide_redo: Option<bool>,
+    /// Is Some when the label of the operation that is undone next has changed. Holds None when nothing can be undone.
+    pub undo_label: Option<Option<HistoryLabel>>,
+    /// Is Some when the label of the operation that is redone next has changed. Holds None when nothing can be redone.
+    pub redo_label: Option<Option<HistoryLabel>>,
     /// Changes whether text preprocessing in the UI toolkit should be enabled.
     /// Meaning, when enabled instead of key events, text events are then emitted
     /// for regular unicode text. Used when writing text with the typewriter.
@@ -41,6 +48,8 @@ impl Default for WidgetFlags {
             deselect_color_setters: false,
             hide_undo: None,
             hide_redo: None,
+            undo_label: None,
+            redo_label: None,
             enable_text_preprocessing: None,
         }
     }
@@ -63,6 +72,12 @@ impl WidgetFlags {
         if other.hide_redo.is_some() {
             self.hide_redo = other.hide_redo;
         }
+        if other.undo_label.is_some() {
+            self.undo_label = other.undo_label;
+        }
+        if other.redo_label.is_some() {
+            self.redo_label = other.redo_label;
+        }
         if other.enable_text_preprocessing.is_some() {
             self.enable_text_preprocessing = other.enable_text_preprocessing;
         }
@@ -9,12 +9,15 @@ use piet::RenderContext;
 use rnote_compose::helpers::{SplitOrder, Vector2Helpers};
 use rnote_compose::penevents::ShortcutKey;
 use rnote_engine::document::Layout;
-use rnote_engine::engine::StrokeContent;
+use rnote_engine::engine::{ReplayState, StrokeContent};
+use rnote_engine::pens::pensconfig::BrushConfig;
 use rnote_engine::pens::PenStyle;
+use rnote_engine::store::HistoryLabel;
 use rnote_engine::{render, Camera, DrawBehaviour, RnoteEngine, WidgetFlags};
+use std::cell::Cell;
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const CLIPBOARD_INPUT_STREAM_BUFSIZE: usize = 4096;
 
@@ -103,10 +106,18 @@ impl RnAppWindow {
         self.add_action(&action_resize_to_fit_strokes);
         let action_return_origin_page = gio::SimpleAction::new("return-origin-page", None);
         self.add_action(&action_return_origin_page);
+        let action_replay_doc =
+            gio::SimpleAction::new_stateful("replay-doc", None, false.to_variant());
+        self.add_action(&action_replay_doc);
         let action_selection_trash = gio::SimpleAction::new("selection-trash", None);
         self.add_action(&action_selection_trash);
         let action_selection_duplicate = gio::SimpleAction::new("selection-duplicate", None);
         self.add_action(&action_selection_duplicate);
+        let action_selection_recognize_shapes =
+            gio::SimpleAction::new("selection-recognize-shapes", None);
+        self.add_action(&action_selection_recognize_shapes);
+        let action_selection_ungroup = gio::SimpleAction::new("selection-ungroup", None);
+        self.add_action(&action_selection_ungroup);
         let action_selection_select_all = gio::SimpleAction::new("selection-select-all", None);
         self.add_action(&action_selection_select_all);
         let action_selection_deselect_all = gio::SimpleAction::new("selection-deselect-all", None);
@@ -405,7 +416,7 @@ impl RnAppWindow {
                 canvas.engine_mut().store.set_trashed_keys(&selection_keys, true);
                 widget_flags.merge(canvas.engine_mut().current_pen_update_state());
                 widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
-                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                widget_flags.merge(canvas.engine_mut().record(HistoryLabel::TrashSelection));
                 canvas.update_rendering_current_viewport();
 
                 appwindow.handle_widget_flags(widget_flags, &canvas);
@@ -422,13 +433,71 @@ impl RnAppWindow {
                 canvas.engine_mut().store.update_geometry_for_strokes(&new_selected);
                 widget_flags.merge(canvas.engine_mut().current_pen_update_state());
                 widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
-                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                widget_flags.merge(canvas.engine_mut().record(HistoryLabel::DuplicateSelection));
                 canvas.update_rendering_current_viewport();
 
                 appwindow.handle_widget_flags(widget_flags, &canvas);
             }),
         );
 
+        // Recognize shapes in selection
+        action_selection_recognize_shapes.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_recognize_shapes, _| {
+                let canvas = appwindow.active_tab().canvas();
+
+                let widget_flags = canvas
+                    .engine_mut()
+                    .recognize_shapes_in_selection(BrushConfig::SHAPE_RECOGNITION_TOLERANCE);
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Ungroup the vector images in selection into strokes
+        action_selection_ungroup.connect_activate(
+            clone!(@weak self as appwindow => move |_action_selection_ungroup, _| {
+                let canvas = appwindow.active_tab().canvas();
+
+                let widget_flags = canvas.engine_mut().ungroup_vectorimages_in_selection();
+                appwindow.handle_widget_flags(widget_flags, &canvas);
+            }),
+        );
+
+        // Replay the document in chronological order
+        action_replay_doc.connect_change_state(
+            clone!(@weak self as appwindow => move |action_replay_doc, state_request| {
+                let requested_state = state_request.unwrap().get::<bool>().unwrap();
+                let canvas = appwindow.active_tab().canvas();
+
+                if requested_state {
+                    let widget_flags = canvas.engine_mut().replay_start(ReplayState::SPEED_DEFAULT);
+                    appwindow.handle_widget_flags(widget_flags, &canvas);
+
+                    let last_frame_time = Cell::new(None);
+                    canvas.add_tick_callback(
+                        clone!(@weak appwindow, @weak action_replay_doc => @default-return glib::source::Continue(false), move |canvas, frame_clock| {
+                            if canvas.engine_ref().replay.is_none() {
+                                action_replay_doc.set_state(false.to_variant());
+                                return glib::source::Continue(false);
+                            }
+                            let frame_time = frame_clock.frame_time();
+                            let elapsed = last_frame_time
+                                .replace(Some(frame_time))
+                                .map(|last: i64| Duration::from_micros((frame_time - last).max(0) as u64))
+                                .unwrap_or_default();
+
+                            let widget_flags = canvas.engine_mut().replay_advance(elapsed);
+                            appwindow.handle_widget_flags(widget_flags, canvas);
+                            glib::source::Continue(true)
+                        }),
+                    );
+                } else {
+                    let widget_flags = canvas.engine_mut().replay_stop();
+                    appwindow.handle_widget_flags(widget_flags, &canvas);
+                }
+                action_replay_doc.set_state(requested_state.to_variant());
+            }),
+        );
+
         // select all strokes
         action_selection_select_all.connect_activate(
             clone!(@weak self as appwindow => move |_action_selection_select_all, _| {
@@ -440,7 +509,7 @@ impl RnAppWindow {
                 widget_flags.merge(canvas.engine_mut().change_pen_style(PenStyle::Selector));
                 widget_flags.merge(canvas.engine_mut().current_pen_update_state());
                 widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
-                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                widget_flags.merge(canvas.engine_mut().record(HistoryLabel::Select));
                 canvas.update_rendering_current_viewport();
 
                 appwindow.handle_widget_flags(widget_flags, &canvas);
@@ -458,7 +527,7 @@ impl RnAppWindow {
                 widget_flags.merge(canvas.engine_mut().change_pen_style(PenStyle::Selector));
                 widget_flags.merge(canvas.engine_mut().current_pen_update_state());
                 widget_flags.merge(canvas.engine_mut().doc_resize_autoexpand());
-                widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                widget_flags.merge(canvas.engine_mut().record(HistoryLabel::Deselect));
                 canvas.update_rendering_current_viewport();
 
                 appwindow.handle_widget_flags(widget_flags, &canvas);
@@ -476,7 +545,7 @@ impl RnAppWindow {
         action_undo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
             let canvas = appwindow.active_tab().canvas();
 
-            let widget_flags = canvas.engine_mut().undo(Instant::now());
+            let widget_flags = canvas.engine_mut().undo();
             canvas.update_rendering_current_viewport();
 
             appwindow.handle_widget_flags(widget_flags, &canvas);
@@ -486,7 +555,7 @@ impl RnAppWindow {
         action_redo_stroke.connect_activate(clone!(@weak self as appwindow => move |_,_| {
             let canvas = appwindow.active_tab().canvas();
 
-            let widget_flags = canvas.engine_mut().redo(Instant::now());
+            let widget_flags = canvas.engine_mut().redo();
             canvas.update_rendering_current_viewport();
             appwindow.handle_widget_flags(widget_flags, &canvas);
         }));
@@ -555,7 +624,7 @@ impl RnAppWindow {
 
                 let mut widget_flags = WidgetFlags::default();
                 if canvas.engine_mut().doc_remove_page_fixed_size() {
-                    widget_flags.merge(canvas.engine_mut().record(Instant::now()));
+                    widget_flags.merge(canvas.engine_mut().record(HistoryLabel::DeletePage));
                     canvas.update_rendering_current_viewport();
                 }
                 appwindow.handle_widget_flags(widget_flags, &canvas);
@@ -646,11 +715,10 @@ impl RnAppWindow {
 
             let background_svg = if with_background {
                 canvas.engine_ref().document
-                    .background
-                    .gen_svg(doc_bounds, true)
+                    .gen_background_svg(doc_bounds, true)
                     .map_err(|e| {
                         log::error!(
-                            "background.gen_svg() failed in in the print document action, with Err: {e:?}"
+                            "gen_background_svg() failed in in the print document action, with Err: {e:?}"
                         )
                     })
                     .ok()
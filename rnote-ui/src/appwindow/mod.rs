@@ -209,7 +209,11 @@ impl RnAppWindow {
     }
 
     // Returns true if the flags indicate that any loop that handles the flags should be quit. (usually an async event loop)
-    pub(crate) fn handle_widget_flags(&self, widget_flags: WidgetFlags, canvas: &RnCanvas) {
+    pub(crate) fn handle_widget_flags(&self, mut widget_flags: WidgetFlags, canvas: &RnCanvas) {
+        if widget_flags.store_modified {
+            // The matches of the current search might have changed
+            widget_flags.merge(canvas.engine_mut().search_refresh());
+        }
         if widget_flags.redraw {
             canvas.queue_draw();
         }
@@ -256,6 +260,12 @@ impl RnAppWindow {
         if let Some(hide_redo) = widget_flags.hide_redo {
             self.overlays().redo_button().set_sensitive(!hide_redo);
         }
+        if let Some(undo_label) = widget_flags.undo_label {
+            self.overlays().set_undo_label(undo_label);
+        }
+        if let Some(redo_label) = widget_flags.redo_label {
+            self.overlays().set_redo_label(redo_label);
+        }
         if let Some(enable_text_preprocessing) = widget_flags.enable_text_preprocessing {
             canvas.set_text_preprocessing(enable_text_preprocessing);
         }
@@ -486,11 +496,28 @@ impl RnAppWindow {
                     }
                 }
             }
-            crate::utils::FileType::VectorImageFile | crate::utils::FileType::BitmapImageFile => {
+            crate::utils::FileType::VectorImageFile => {
+                glib::MainContext::default().spawn_local(
+                    clone!(@strong input_file, @weak self as appwindow => async move {
+                        let canvas =
+                            appwindow.active_tab().canvas();
+
+                        dialogs::import::dialog_import_svg_w_prefs(
+                            &appwindow,
+                            &canvas,
+                            input_file,
+                            target_pos,
+                        ).await;
+                    }),
+                );
+            }
+            crate::utils::FileType::BitmapImageFile
+            | crate::utils::FileType::InkmlFile
+            | crate::utils::FileType::ExcalidrawFile => {
                 if let Err(e) =
                     self.load_in_file(input_file, target_pos, &self.active_tab().canvas())
                 {
-                    log::error!("failed to load in file with FileType::VectorImageFile / FileType::BitmapImageFile / FileType::Pdf, {e:?}");
+                    log::error!("failed to load in file with FileType::BitmapImageFile / FileType::InkmlFile / FileType::ExcalidrawFile, {e:?}");
                 }
             }
             crate::utils::FileType::XoppFile => {
@@ -583,7 +610,7 @@ impl RnAppWindow {
                 crate::utils::FileType::XoppFile => {
                     match file.load_bytes_future().await {
                         Ok((bytes, _)) => {
-                            if let Err(e) = canvas.load_in_xopp_bytes(bytes.to_vec()).await {
+                            if let Err(e) = canvas.load_in_xopp_bytes(bytes.to_vec(), file.path()).await {
                                 log::error!("load_in_xopp_bytes() failed with Err: {e:?}");
                                 appwindow.overlays().dispatch_toast_error(&gettext("Opening Xournal++ file failed"));
                             }
@@ -594,7 +621,7 @@ impl RnAppWindow {
                 crate::utils::FileType::PdfFile => {
                     match file.load_bytes_future().await {
                         Ok((bytes, _)) => {
-                            if let Err(e) = canvas.load_in_pdf_bytes(bytes.to_vec(), target_pos, None).await {
+                            if let Err(e) = canvas.load_in_pdf_bytes(bytes.to_vec(), target_pos, None, file.path()).await {
                                 log::error!("load_in_pdf_bytes() failed with Err: {e:?}");
                                 appwindow.overlays().dispatch_toast_error(&gettext("Opening Pdf file failed"));
                             }
@@ -602,6 +629,28 @@ impl RnAppWindow {
                         Err(e) => log::error!("failed to load bytes, Err: {e:?}"),
                     }
                 }
+                crate::utils::FileType::InkmlFile => {
+                    match file.load_bytes_future().await {
+                        Ok((bytes, _)) => {
+                            if let Err(e) = canvas.load_in_inkml_bytes(bytes.to_vec(), target_pos).await {
+                                log::error!("load_in_inkml_bytes() failed with Err: {e:?}");
+                                appwindow.overlays().dispatch_toast_error(&gettext("Opening InkML file failed"));
+                            }
+                        }
+                        Err(e) => log::error!("failed to load bytes, Err: {e:?}"),
+                    }
+                }
+                crate::utils::FileType::ExcalidrawFile => {
+                    match file.load_bytes_future().await {
+                        Ok((bytes, _)) => {
+                            if let Err(e) = canvas.load_in_excalidraw_bytes(bytes.to_vec(), target_pos).await {
+                                log::error!("load_in_excalidraw_bytes() failed with Err: {e:?}");
+                                appwindow.overlays().dispatch_toast_error(&gettext("Opening Excalidraw file failed"));
+                            }
+                        }
+                        Err(e) => log::error!("failed to load bytes, Err: {e:?}"),
+                    }
+                }
                 crate::utils::FileType::Folder => {
                     log::error!("tried to open a folder as a file");
                     appwindow.overlays()
@@ -633,9 +682,13 @@ impl RnAppWindow {
         // Undo / redo
         let can_undo = canvas.engine_ref().can_undo();
         let can_redo = canvas.engine_ref().can_redo();
+        let undo_label = canvas.engine_ref().undo_label();
+        let redo_label = canvas.engine_ref().redo_label();
 
         self.overlays().undo_button().set_sensitive(can_undo);
         self.overlays().redo_button().set_sensitive(can_redo);
+        self.overlays().set_undo_label(undo_label);
+        self.overlays().set_redo_label(redo_label);
 
         // we change the state through the actions, because they themselves hold state. ( e.g. used to display tickboxes for boolean actions )
         adw::prelude::ActionGroupExt::activate_action(
@@ -46,17 +46,19 @@ mod imp {
         ) -> (i32, i32, i32, i32) {
             let canvas = widget.downcast_ref::<RnCanvas>().unwrap();
             let total_zoom = canvas.engine_ref().camera.total_zoom();
-            let document = canvas.engine_ref().document;
+            let (doc_width, doc_height) = {
+                let engine = canvas.engine_ref();
+                (engine.document.width, engine.document.height)
+            };
 
             if orientation == Orientation::Horizontal {
-                let natural_width = (document.width * total_zoom
-                    + 2.0 * Camera::OVERSHOOT_HORIZONTAL)
-                    .ceil() as i32;
+                let natural_width =
+                    (doc_width * total_zoom + 2.0 * Camera::OVERSHOOT_HORIZONTAL).ceil() as i32;
 
                 (0, natural_width, -1, -1)
             } else {
                 let natural_height =
-                    (document.height * total_zoom + 2.0 * Camera::OVERSHOOT_VERTICAL).ceil() as i32;
+                    (doc_height * total_zoom + 2.0 * Camera::OVERSHOOT_VERTICAL).ceil() as i32;
 
                 (0, natural_height, -1, -1)
             }
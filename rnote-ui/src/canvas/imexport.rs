@@ -7,7 +7,7 @@ use rnote_engine::engine::export::{DocExportPrefs, DocPagesExportPrefs, Selectio
 use rnote_engine::engine::{EngineSnapshot, StrokeContent};
 use rnote_engine::strokes::Stroke;
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 impl RnCanvas {
     pub(crate) async fn load_in_rnote_bytes<P>(
@@ -71,16 +71,27 @@ impl RnCanvas {
                 ])
         });
 
+        let import_as_strokes = self
+            .engine_ref()
+            .import_prefs
+            .svg_import_prefs
+            .import_as_strokes;
+
         // we need the split the import operation between generate_vectorimage_from_bytes() which returns a receiver and import_generated_strokes(),
         // to avoid borrowing the entire engine refcell while awaiting the stroke
-        let vectorimage_receiver = self
-            .engine_mut()
-            .generate_vectorimage_from_bytes(pos, bytes);
-        let vectorimage = vectorimage_receiver.await??;
+        let strokes = if import_as_strokes {
+            let strokes_receiver = self
+                .engine_mut()
+                .generate_svg_strokes_from_bytes(pos, bytes);
+            strokes_receiver.await??
+        } else {
+            let vectorimage_receiver = self
+                .engine_mut()
+                .generate_vectorimage_from_bytes(pos, bytes);
+            vec![(Stroke::VectorImage(vectorimage_receiver.await??), None)]
+        };
 
-        let widget_flags = self
-            .engine_mut()
-            .import_generated_strokes(vec![(Stroke::VectorImage(vectorimage), None)]);
+        let widget_flags = self.engine_mut().import_generated_strokes(strokes);
 
         self.emit_handle_widget_flags(widget_flags);
         Ok(())
@@ -119,11 +130,82 @@ impl RnCanvas {
         Ok(())
     }
 
-    pub(crate) async fn load_in_xopp_bytes(&self, bytes: Vec<u8>) -> anyhow::Result<()> {
+    pub(crate) async fn load_in_inkml_bytes(
+        &self,
+        bytes: Vec<u8>,
+        // In the coordinate space of the doc
+        target_pos: Option<na::Vector2<f64>>,
+    ) -> anyhow::Result<()> {
+        let pos = target_pos.unwrap_or_else(|| {
+            self.engine_ref()
+                .camera
+                .transform()
+                .inverse()
+                .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
+                .coords
+                .maxs(&na::vector![
+                    self.engine_ref().document.x,
+                    self.engine_ref().document.y
+                ])
+        });
+
+        let strokes_receiver = self
+            .engine_mut()
+            .generate_inkml_strokes_from_bytes(pos, bytes);
+        let strokes = strokes_receiver.await??;
+
+        let widget_flags = self.engine_mut().import_generated_strokes(strokes);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
+    pub(crate) async fn load_in_excalidraw_bytes(
+        &self,
+        bytes: Vec<u8>,
+        // In the coordinate space of the doc
+        target_pos: Option<na::Vector2<f64>>,
+    ) -> anyhow::Result<()> {
+        let pos = target_pos.unwrap_or_else(|| {
+            self.engine_ref()
+                .camera
+                .transform()
+                .inverse()
+                .transform_point(&na::Point2::from(Stroke::IMPORT_OFFSET_DEFAULT))
+                .coords
+                .maxs(&na::vector![
+                    self.engine_ref().document.x,
+                    self.engine_ref().document.y
+                ])
+        });
+
+        let strokes_receiver = self
+            .engine_mut()
+            .generate_excalidraw_strokes_from_bytes(pos, bytes);
+        let strokes = strokes_receiver.await??;
+
+        let widget_flags = self.engine_mut().import_generated_strokes(strokes);
+
+        self.emit_handle_widget_flags(widget_flags);
+        Ok(())
+    }
+
+    pub(crate) async fn load_in_xopp_bytes<P>(
+        &self,
+        bytes: Vec<u8>,
+        file_path: Option<P>,
+    ) -> anyhow::Result<()>
+    where
+        P: AsRef<Path>,
+    {
         let xopp_import_prefs = self.engine_mut().import_prefs.xopp_import_prefs;
 
-        let engine_snapshot =
-            EngineSnapshot::load_from_xopp_bytes(bytes, xopp_import_prefs).await?;
+        let engine_snapshot = EngineSnapshot::load_from_xopp_bytes(
+            bytes,
+            xopp_import_prefs,
+            file_path.map(|p| p.as_ref().to_path_buf()),
+        )
+        .await?;
 
         let mut widget_flags = self.engine_mut().load_snapshot(engine_snapshot);
 
@@ -147,6 +229,7 @@ impl RnCanvas {
         bytes: Vec<u8>,
         target_pos: Option<na::Vector2<f64>>,
         page_range: Option<Range<u32>>,
+        file_path: Option<PathBuf>,
     ) -> anyhow::Result<()> {
         let pos = target_pos.unwrap_or_else(|| {
             self.engine_ref()
@@ -163,7 +246,7 @@ impl RnCanvas {
 
         let strokes_receiver = self
             .engine_mut()
-            .generate_pdf_pages_from_bytes(bytes, pos, page_range);
+            .generate_pdf_pages_from_bytes(bytes, pos, page_range, file_path);
         let strokes = strokes_receiver.await??;
 
         let widget_flags = self.engine_mut().import_generated_strokes(strokes);
@@ -20,7 +20,7 @@ use p2d::bounding_volume::Aabb;
 use rnote_compose::helpers::AabbHelpers;
 use rnote_compose::penevents::PenState;
 use rnote_engine::utils::GrapheneRectHelpers;
-use rnote_engine::Document;
+use rnote_engine::{Camera, Document};
 use rnote_engine::{RnoteEngine, WidgetFlags};
 use std::cell::{Cell, Ref, RefCell, RefMut};
 
@@ -539,9 +539,9 @@ pub(crate) static OUTPUT_FILE_NEW_SUBTITLE: once_cell::sync::Lazy<String> =
 
 impl RnCanvas {
     // Sets the canvas zoom scroll step in % for one unit of the event controller delta
-    pub(crate) const ZOOM_SCROLL_STEP: f64 = 0.1;
+    pub(crate) const ZOOM_SCROLL_STEP: f64 = Camera::ZOOM_STEP;
     /// A small margin added to the document width, when zooming to fit document width
-    pub(crate) const ZOOM_FIT_WIDTH_MARGIN: f64 = 32.0;
+    pub(crate) const ZOOM_FIT_WIDTH_MARGIN: f64 = Camera::ZOOM_FIT_WIDTH_MARGIN;
 
     pub(crate) fn new() -> Self {
         glib::Object::new()
@@ -91,6 +91,15 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
     let with_pattern_switch: Switch = builder.object("export_doc_with_pattern_switch").unwrap();
     let export_format_row: adw::ComboRow = builder.object("export_doc_export_format_row").unwrap();
     let page_order_row: adw::ComboRow = builder.object("export_doc_page_order_row").unwrap();
+    let bitmap_scalefactor_row: adw::ActionRow =
+        builder.object("export_doc_bitmap_scalefactor_row").unwrap();
+    let bitmap_scalefactor_spinbutton: SpinButton = builder
+        .object("export_doc_bitmap_scalefactor_spinbutton")
+        .unwrap();
+    let jpeg_quality_row: adw::ActionRow = builder.object("export_doc_jpeg_quality_row").unwrap();
+    let jpeg_quality_spinbutton: SpinButton = builder
+        .object("export_doc_jpeg_quality_spinbutton")
+        .unwrap();
     let export_file_label: Label = builder.object("export_doc_export_file_label").unwrap();
     let export_file_button: Button = builder.object("export_doc_export_file_button").unwrap();
 
@@ -111,6 +120,13 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
             || initial_doc_export_prefs.export_format == DocExportFormat::Xopp)
             && (doc_layout == Layout::SemiInfinite || doc_layout == Layout::Infinite),
     );
+    bitmap_scalefactor_row.set_sensitive(
+        initial_doc_export_prefs.export_format == DocExportFormat::Png
+            || initial_doc_export_prefs.export_format == DocExportFormat::Jpeg,
+    );
+    bitmap_scalefactor_spinbutton.set_value(initial_doc_export_prefs.bitmap_scalefactor);
+    jpeg_quality_row.set_sensitive(initial_doc_export_prefs.export_format == DocExportFormat::Jpeg);
+    jpeg_quality_spinbutton.set_value(initial_doc_export_prefs.jpeg_quality as f64);
     button_confirm.set_sensitive(false);
 
     // Update prefs
@@ -160,13 +176,17 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
         canvas.engine_mut().export_prefs.doc_export_prefs.with_pattern = with_pattern_switch.is_active();
     }));
 
-    export_format_row.connect_selected_notify(clone!(@strong selected_file, @weak export_file_label, @weak page_order_row, @weak button_confirm, @weak canvas, @weak appwindow => move |row| {
+    export_format_row.connect_selected_notify(clone!(@strong selected_file, @weak export_file_label, @weak page_order_row, @weak bitmap_scalefactor_row, @weak jpeg_quality_row, @weak button_confirm, @weak canvas, @weak appwindow => move |row| {
         let export_format = DocExportFormat::try_from(row.selected()).unwrap();
         canvas.engine_mut().export_prefs.doc_export_prefs.export_format = export_format;
 
         // enable page direction row when export format is finite and document layout is infinite (i.e. layout will be split into pages)
         let doc_layout = canvas.engine_ref().document.layout;
         page_order_row.set_sensitive((export_format == DocExportFormat::Pdf || export_format == DocExportFormat::Xopp) && (doc_layout == Layout::SemiInfinite || doc_layout == Layout::Infinite));
+        // Set the bitmap scalefactor sensitive only when exporting to a bitmap image
+        bitmap_scalefactor_row.set_sensitive(export_format == DocExportFormat::Png || export_format == DocExportFormat::Jpeg);
+        // Set the jpeg quality pref only sensitive when jpeg is actually selected
+        jpeg_quality_row.set_sensitive(export_format == DocExportFormat::Jpeg);
 
         // force the user to pick another file
         export_file_label.set_label(&gettext("- no file selected -"));
@@ -179,6 +199,14 @@ pub(crate) async fn dialog_export_doc_w_prefs(appwindow: &RnAppWindow, canvas: &
         canvas.engine_mut().export_prefs.doc_export_prefs.page_order = page_order;
     }));
 
+    bitmap_scalefactor_spinbutton.connect_value_changed(clone!(@weak canvas, @weak appwindow => move |bitmap_scalefactor_spinbutton| {
+        canvas.engine_mut().export_prefs.doc_export_prefs.bitmap_scalefactor = bitmap_scalefactor_spinbutton.value();
+    }));
+
+    jpeg_quality_spinbutton.connect_value_changed(clone!(@weak canvas, @weak appwindow => move |jpeg_quality_spinbutton| {
+        canvas.engine_mut().export_prefs.doc_export_prefs.jpeg_quality = jpeg_quality_spinbutton.value().clamp(1.0, 100.0) as u8;
+    }));
+
     let response = dialog.run_future().await;
     dialog.close();
     match response {
@@ -239,6 +267,32 @@ fn create_filedialog_export_doc(
             filter.add_suffix("xopp");
             filter.set_name(Some(&gettext("Xopp")));
         }
+        DocExportFormat::Png => {
+            filter.add_mime_type("image/png");
+            filter.add_suffix("png");
+            filter.set_name(Some(&gettext("Png")));
+        }
+        DocExportFormat::Jpeg => {
+            filter.add_mime_type("image/jpeg");
+            filter.add_suffix("jpg");
+            filter.add_suffix("jpeg");
+            filter.set_name(Some(&gettext("Jpeg")));
+        }
+        DocExportFormat::Inkml => {
+            filter.add_mime_type("application/inkml+xml");
+            filter.add_suffix("inkml");
+            filter.set_name(Some(&gettext("InkML")));
+        }
+        DocExportFormat::Excalidraw => {
+            filter.add_mime_type("application/vnd.excalidraw+json");
+            filter.add_suffix("excalidraw");
+            filter.set_name(Some(&gettext("Excalidraw")));
+        }
+        DocExportFormat::AnnotatedPdf => {
+            filter.add_mime_type("application/pdf");
+            filter.add_suffix("pdf");
+            filter.set_name(Some(&gettext("Pdf")));
+        }
     }
     let file_ext = doc_export_prefs.export_format.file_ext();
     let file_name = crate::utils::default_file_title_for_export(
@@ -728,6 +782,16 @@ fn create_filedialog_export_selection(
             filter.add_suffix("jpeg");
             filter.set_name(Some(&gettext("Jpeg")));
         }
+        SelectionExportFormat::Inkml => {
+            filter.add_mime_type("application/inkml+xml");
+            filter.add_suffix("inkml");
+            filter.set_name(Some(&gettext("InkML")));
+        }
+        SelectionExportFormat::Excalidraw => {
+            filter.add_mime_type("application/vnd.excalidraw+json");
+            filter.add_suffix("excalidraw");
+            filter.set_name(Some(&gettext("Excalidraw")));
+        }
     }
     let file_ext = selection_export_prefs.export_format.file_ext();
     let file_name = crate::utils::default_file_title_for_export(
@@ -8,7 +8,7 @@ use adw::prelude::*;
 use gettextrs::gettext;
 use gtk4::{
     gio, glib, glib::clone, Builder, Dialog, FileDialog, FileFilter, Label, ResponseType,
-    SpinButton, ToggleButton,
+    SpinButton, Switch, ToggleButton,
 };
 use num_traits::ToPrimitive;
 use rnote_engine::engine::import::{PdfImportPageSpacing, PdfImportPagesType};
@@ -101,17 +101,25 @@ pub(crate) async fn filedialog_open_doc(appwindow: &RnAppWindow) {
 pub(crate) async fn filedialog_import_file(appwindow: &RnAppWindow) {
     let filter = FileFilter::new();
     filter.add_mime_type("application/x-xopp");
+    filter.add_mime_type("application/x-xoj");
     filter.add_mime_type("application/pdf");
     filter.add_mime_type("image/svg+xml");
     filter.add_mime_type("image/png");
     filter.add_mime_type("image/jpeg");
+    filter.add_mime_type("application/inkml+xml");
+    filter.add_mime_type("application/vnd.excalidraw+json");
     filter.add_suffix("xopp");
+    filter.add_suffix("xoj");
     filter.add_suffix("pdf");
     filter.add_suffix("svg");
     filter.add_suffix("png");
     filter.add_suffix("jpg");
     filter.add_suffix("jpeg");
-    filter.set_name(Some(&gettext("Jpg, Pdf, Png, Svg, Xopp")));
+    filter.add_suffix("inkml");
+    filter.add_suffix("excalidraw");
+    filter.set_name(Some(&gettext(
+        "Excalidraw, InkML, Jpg, Pdf, Png, Svg, Xoj, Xopp",
+    )));
 
     let dialog = FileDialog::builder()
         .title(gettext("Import File"))
@@ -156,11 +164,16 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         builder.object("pdf_import_as_bitmap_toggle").unwrap();
     let pdf_import_as_vector_toggle: ToggleButton =
         builder.object("pdf_import_as_vector_toggle").unwrap();
+    let pdf_import_as_embedded_toggle: ToggleButton =
+        builder.object("pdf_import_as_embedded_toggle").unwrap();
     let pdf_import_bitmap_scalefactor_row: adw::ActionRow =
         builder.object("pdf_import_bitmap_scalefactor_row").unwrap();
     let pdf_import_bitmap_scalefactor_spinbutton: SpinButton = builder
         .object("pdf_import_bitmap_scalefactor_spinbutton")
         .unwrap();
+    let pdf_import_annotations_as_strokes_switch: Switch = builder
+        .object("pdf_import_annotations_as_strokes_switch")
+        .unwrap();
 
     dialog.set_transient_for(Some(appwindow));
 
@@ -179,9 +192,14 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
             pdf_import_as_vector_toggle.set_active(true);
             pdf_import_bitmap_scalefactor_row.set_sensitive(false);
         }
+        PdfImportPagesType::Embedded => {
+            pdf_import_as_embedded_toggle.set_active(true);
+            pdf_import_bitmap_scalefactor_row.set_sensitive(false);
+        }
     }
     pdf_import_page_spacing_row.set_selected(pdf_import_prefs.page_spacing.to_u32().unwrap());
     pdf_import_bitmap_scalefactor_spinbutton.set_value(pdf_import_prefs.bitmap_scalefactor);
+    pdf_import_annotations_as_strokes_switch.set_active(pdf_import_prefs.annotations_as_strokes);
 
     pdf_page_start_spinbutton
         .bind_property("value", &pdf_page_end_spinbutton.adjustment(), "lower")
@@ -202,6 +220,15 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         }),
     );
 
+    pdf_import_as_embedded_toggle.connect_toggled(
+        clone!(@weak pdf_import_bitmap_scalefactor_row, @weak canvas, @weak appwindow => move |toggle| {
+            if toggle.is_active() {
+                canvas.engine_mut().import_prefs.pdf_import_prefs.pages_type = PdfImportPagesType::Embedded;
+                pdf_import_bitmap_scalefactor_row.set_sensitive(false);
+            }
+        }),
+    );
+
     pdf_import_as_bitmap_toggle.connect_toggled(
         clone!(@weak pdf_import_bitmap_scalefactor_row, @weak canvas, @weak appwindow => move |toggle| {
             if toggle.is_active() {
@@ -229,6 +256,10 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
         }),
     );
 
+    pdf_import_annotations_as_strokes_switch.connect_active_notify(clone!(@weak canvas => move |switch| {
+        canvas.engine_mut().import_prefs.pdf_import_prefs.annotations_as_strokes = switch.is_active();
+    }));
+
     if let Ok(poppler_doc) =
         poppler::Document::from_gfile(&input_file, None, None::<&gio::Cancellable>)
     {
@@ -293,7 +324,12 @@ pub(crate) async fn dialog_import_pdf_w_prefs(
             let result = input_file.load_bytes_future().await;
             if let Ok((file_bytes, _)) = result {
                 if let Err(e) = canvas
-                    .load_in_pdf_bytes(file_bytes.to_vec(), target_pos, Some(page_range))
+                    .load_in_pdf_bytes(
+                        file_bytes.to_vec(),
+                        target_pos,
+                        Some(page_range),
+                        input_file.path(),
+                    )
                     .await
                 {
                     appwindow
@@ -344,7 +380,10 @@ pub(crate) async fn dialog_import_xopp_w_prefs(
 
             let result = input_file.load_bytes_future().await;
             if let Ok((file_bytes, _)) = result {
-                if let Err(e) = canvas.load_in_xopp_bytes(file_bytes.to_vec()).await {
+                if let Err(e) = canvas
+                    .load_in_xopp_bytes(file_bytes.to_vec(), input_file.path())
+                    .await
+                {
                     appwindow
                         .overlays()
                         .dispatch_toast_error(&gettext("Opening Xournal++ file failed"));
@@ -361,3 +400,56 @@ pub(crate) async fn dialog_import_xopp_w_prefs(
         }
     }
 }
+
+pub(crate) async fn dialog_import_svg_w_prefs(
+    appwindow: &RnAppWindow,
+    canvas: &RnCanvas,
+    input_file: gio::File,
+    target_pos: Option<na::Vector2<f64>>,
+) {
+    let builder = Builder::from_resource(
+        (String::from(config::APP_IDPATH) + "ui/dialogs/import.ui").as_str(),
+    );
+    let dialog: Dialog = builder.object("dialog_import_svg_w_prefs").unwrap();
+    let as_strokes_switch: Switch = builder.object("svg_import_as_strokes_switch").unwrap();
+
+    dialog.set_transient_for(Some(appwindow));
+
+    let svg_import_prefs = canvas.engine_ref().import_prefs.svg_import_prefs;
+
+    // Set initial widget state for preference
+    as_strokes_switch.set_active(svg_import_prefs.import_as_strokes);
+
+    // Update preferences
+    as_strokes_switch.connect_active_notify(clone!(@weak canvas => move |switch| {
+        canvas.engine_mut().import_prefs.svg_import_prefs.import_as_strokes = switch.is_active();
+    }));
+
+    let response = dialog.run_future().await;
+    dialog.close();
+    match response {
+        ResponseType::Apply => {
+            appwindow.overlays().start_pulsing_progressbar();
+
+            let result = input_file.load_bytes_future().await;
+            if let Ok((file_bytes, _)) = result {
+                if let Err(e) = canvas
+                    .load_in_vectorimage_bytes(file_bytes.to_vec(), target_pos)
+                    .await
+                {
+                    appwindow
+                        .overlays()
+                        .dispatch_toast_error(&gettext("Opening vector image file failed"));
+                    log::error!(
+                        "load_in_vectorimage_bytes() failed in dialog import svg with Err: {e:?}"
+                    );
+                }
+            }
+
+            appwindow.overlays().finish_progressbar();
+        }
+        _ => {
+            // Cancel
+        }
+    }
+}
@@ -2,15 +2,16 @@
 use crate::canvaswrapper::RnCanvasWrapper;
 use crate::RnPensSideBar;
 use crate::{dialogs, RnAppWindow, RnColorPicker};
+use gettextrs::gettext;
 use gtk4::{
     gio, glib, glib::clone, prelude::*, subclass::prelude::*, Button, CompositeTemplate, Overlay,
     ProgressBar, ScrolledWindow, ToggleButton, Widget,
 };
 use rnote_engine::engine::EngineViewMut;
 use rnote_engine::pens::{Pen, PenStyle};
+use rnote_engine::store::HistoryLabel;
 use rnote_engine::utils::GdkRGBAHelpers;
 use std::cell::RefCell;
-use std::time::Instant;
 
 mod imp {
     use super::*;
@@ -287,7 +288,7 @@ impl RnOverlays {
                             let selection_keys = engine.store.selection_keys_unordered();
                             if !selection_keys.is_empty() {
                                 let mut widget_flags = engine.store.change_stroke_colors(&selection_keys, stroke_color);
-                                widget_flags.merge(engine.record(Instant::now()));
+                                widget_flags.merge(engine.record(HistoryLabel::ChangeStrokeColor));
                                 engine.update_content_rendering_current_viewport();
                                 appwindow.handle_widget_flags(widget_flags, &canvas);
                             }
@@ -318,7 +319,7 @@ impl RnOverlays {
                         let selection_keys = engine.store.selection_keys_unordered();
                         if !selection_keys.is_empty() {
                             let mut widget_flags = engine.store.change_fill_colors(&selection_keys, fill_color);
-                            widget_flags.merge(engine.record(Instant::now()));
+                            widget_flags.merge(engine.record(HistoryLabel::ChangeFillColor));
                             engine.update_content_rendering_current_viewport();
                             appwindow.handle_widget_flags(widget_flags, &canvas);
                         }
@@ -426,6 +427,26 @@ impl RnOverlays {
         }));
     }
 
+    /// Shows the operation that is undone next in the tooltip of the undo button.
+    pub(crate) fn set_undo_label(&self, label: Option<HistoryLabel>) {
+        let tooltip = match label {
+            // {} is replaced with the operation, e.g. "Undo Move Selection"
+            Some(label) => gettext("Undo {}").replace("{}", &history_label_display(label)),
+            None => gettext("Undo"),
+        };
+        self.undo_button().set_tooltip_text(Some(&tooltip));
+    }
+
+    /// Shows the operation that is redone next in the tooltip of the redo button.
+    pub(crate) fn set_redo_label(&self, label: Option<HistoryLabel>) {
+        let tooltip = match label {
+            // {} is replaced with the operation, e.g. "Redo Move Selection"
+            Some(label) => gettext("Redo {}").replace("{}", &history_label_display(label)),
+            None => gettext("Redo"),
+        };
+        self.redo_button().set_tooltip_text(Some(&tooltip));
+    }
+
     pub(crate) fn start_pulsing_progressbar(&self) {
         const PROGRESS_BAR_PULSE_INTERVAL: std::time::Duration =
             std::time::Duration::from_millis(300);
@@ -539,3 +560,47 @@ impl RnOverlays {
         toast
     }
 }
+
+/// The display name of the recorded operation.
+fn history_label_display(label: HistoryLabel) -> String {
+    match label {
+        HistoryLabel::DrawStroke => gettext("Draw Stroke"),
+        HistoryLabel::DrawShape => gettext("Draw Shape"),
+        HistoryLabel::Erase => gettext("Erase"),
+        HistoryLabel::InsertSpace => gettext("Insert Space"),
+        HistoryLabel::Select => gettext("Select"),
+        HistoryLabel::Deselect => gettext("Deselect"),
+        HistoryLabel::MoveSelection => gettext("Move Selection"),
+        HistoryLabel::RotateSelection => gettext("Rotate Selection"),
+        HistoryLabel::ResizeSelection => gettext("Resize Selection"),
+        HistoryLabel::DuplicateSelection => gettext("Duplicate Selection"),
+        HistoryLabel::TrashSelection => gettext("Delete Selection"),
+        HistoryLabel::CutSelection => gettext("Cut Selection"),
+        HistoryLabel::ChangeStrokeColor => gettext("Change Stroke Color"),
+        HistoryLabel::ChangeFillColor => gettext("Change Fill Color"),
+        HistoryLabel::SmoothStrokes => gettext("Smooth Strokes"),
+        HistoryLabel::RecognizeShapes => gettext("Recognize Shapes"),
+        HistoryLabel::UngroupVectorImages => gettext("Ungroup Images"),
+        HistoryLabel::InsertText => gettext("Insert Text"),
+        HistoryLabel::EditText => gettext("Edit Text"),
+        HistoryLabel::CutText => gettext("Cut Text"),
+        HistoryLabel::MoveText => gettext("Move Text"),
+        HistoryLabel::AdjustTextWidth => gettext("Adjust Text Width"),
+        HistoryLabel::ChangeTextStyle => gettext("Change Text Style"),
+        HistoryLabel::Import => gettext("Import"),
+        HistoryLabel::Paste => gettext("Paste"),
+        HistoryLabel::AddLayer => gettext("Add Layer"),
+        HistoryLabel::RemoveLayer => gettext("Remove Layer"),
+        HistoryLabel::RenameLayer => gettext("Rename Layer"),
+        HistoryLabel::ChangeLayerVisibility => gettext("Change Layer Visibility"),
+        HistoryLabel::ChangeLayerLock => gettext("Change Layer Lock"),
+        HistoryLabel::MoveLayer => gettext("Move Layer"),
+        HistoryLabel::MergeLayerDown => gettext("Merge Layer Down"),
+        HistoryLabel::MoveToLayer => gettext("Move to Layer"),
+        HistoryLabel::InsertPage => gettext("Insert Page"),
+        HistoryLabel::DeletePage => gettext("Delete Page"),
+        HistoryLabel::DuplicatePage => gettext("Duplicate Page"),
+        HistoryLabel::MovePage => gettext("Move Page"),
+        HistoryLabel::ChangePageBackground => gettext("Change Page Background"),
+    }
+}
@@ -3,7 +3,7 @@ use crate::{RnAppWindow, RnCanvasWrapper, RnStrokeWidthPicker};
 use adw::prelude::*;
 use gtk4::{
     glib, glib::clone, subclass::prelude::*, CompositeTemplate, ListBox, MenuButton, Popover,
-    SpinButton,
+    SpinButton, Switch,
 };
 use num_traits::cast::ToPrimitive;
 use rnote_compose::builders::PenPathBuilderType;
@@ -41,6 +41,8 @@ mod imp {
         #[template_child]
         pub(crate) brush_buildertype_modeled: TemplateChild<adw::ActionRow>,
         #[template_child]
+        pub(crate) brush_shape_recognition_switch: TemplateChild<Switch>,
+        #[template_child]
         pub(crate) solidstyle_pressure_curves_row: TemplateChild<adw::ComboRow>,
         #[template_child]
         pub(crate) texturedstyle_density_spinbutton: TemplateChild<SpinButton>,
@@ -255,6 +257,13 @@ impl RnBrushPage {
             }),
         );
 
+        // Shape recognition
+        imp.brush_shape_recognition_switch.get().connect_state_notify(
+            clone!(@weak appwindow => move |switch| {
+                appwindow.active_tab().canvas().engine_mut().pens_config.brush_config.shape_recognition = switch.state();
+            }),
+        );
+
         // Solid style
         // Pressure curve
         imp.solidstyle_pressure_curves_row.get().connect_selected_notify(clone!(@weak self as brushpage, @weak appwindow => move |_smoothstyle_pressure_curves_row| {
@@ -302,6 +311,8 @@ impl RnBrushPage {
 
         self.set_brush_style(brush_config.style);
         self.set_buildertype(brush_config.builder_type);
+        imp.brush_shape_recognition_switch
+            .set_state(brush_config.shape_recognition);
 
         match brush_config.style {
             BrushStyle::Marker => {
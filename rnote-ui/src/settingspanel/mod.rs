@@ -18,6 +18,7 @@ use num_traits::ToPrimitive;
 use rnote_compose::penevents::ShortcutKey;
 use rnote_engine::document::background::PatternStyle;
 use rnote_engine::document::format::{self, Format, PredefinedFormat};
+use rnote_engine::engine::import::PdfImportPagesType;
 use rnote_engine::utils::GdkRGBAHelpers;
 use std::cell::RefCell;
 
@@ -91,6 +92,12 @@ mod imp {
         #[template_child]
         pub(crate) doc_background_pattern_height_unitentry: TemplateChild<RnUnitEntry>,
         #[template_child]
+        pub(crate) doc_background_major_line_interval_row: TemplateChild<adw::ActionRow>,
+        #[template_child]
+        pub(crate) doc_background_major_line_interval_spinbutton: TemplateChild<SpinButton>,
+        #[template_child]
+        pub(crate) import_pdf_pages_type_row: TemplateChild<adw::ComboRow>,
+        #[template_child]
         pub(crate) penshortcut_stylus_button_primary_row: TemplateChild<RnPenShortcutRow>,
         #[template_child]
         pub(crate) penshortcut_stylus_button_secondary_row: TemplateChild<RnPenShortcutRow>,
@@ -334,6 +341,19 @@ impl RnSettingsPanel {
             .set_selected(position);
     }
 
+    pub(crate) fn pdf_import_pages_type(&self) -> PdfImportPagesType {
+        PdfImportPagesType::try_from(self.imp().import_pdf_pages_type_row.get().selected()).unwrap()
+    }
+
+    pub(crate) fn set_pdf_import_pages_type(&self, pages_type: PdfImportPagesType) {
+        let position = pages_type.to_u32().unwrap();
+
+        self.imp()
+            .import_pdf_pages_type_row
+            .get()
+            .set_selected(position);
+    }
+
     pub(crate) fn set_format_orientation(&self, orientation: format::Orientation) {
         if orientation == format::Orientation::Portrait {
             self.imp()
@@ -374,6 +394,7 @@ impl RnSettingsPanel {
         self.refresh_general_ui(active_tab);
         self.refresh_format_ui(active_tab);
         self.refresh_doc_ui(active_tab);
+        self.refresh_import_ui(active_tab);
         self.refresh_shortcuts_ui(active_tab);
     }
 
@@ -421,6 +442,15 @@ impl RnSettingsPanel {
             .set_dpi(format.dpi);
         imp.doc_background_pattern_height_unitentry
             .set_value_in_px(background.pattern_size[1]);
+        imp.doc_background_major_line_interval_spinbutton
+            .set_value(f64::from(background.major_line_interval));
+    }
+
+    fn refresh_import_ui(&self, active_tab: &RnCanvasWrapper) {
+        let canvas = active_tab.canvas();
+        let pages_type = canvas.engine_ref().import_prefs.pdf_import_prefs.pages_type;
+
+        self.set_pdf_import_pages_type(pages_type);
     }
 
     fn refresh_shortcuts_ui(&self, active_tab: &RnCanvasWrapper) {
@@ -466,6 +496,7 @@ impl RnSettingsPanel {
         self.setup_general(appwindow);
         self.setup_format(appwindow);
         self.setup_doc(appwindow);
+        self.setup_import(appwindow);
         self.setup_shortcuts(appwindow);
     }
 
@@ -657,7 +688,20 @@ impl RnSettingsPanel {
                     settings_panel.imp().doc_background_pattern_width_unitentry.set_sensitive(false);
                     settings_panel.imp().doc_background_pattern_height_unitentry.set_sensitive(true);
                 },
+                PatternStyle::Staves | PatternStyle::CornellNotes | PatternStyle::LinesMargin => {
+                    settings_panel.imp().doc_background_pattern_width_unitentry.set_sensitive(false);
+                    settings_panel.imp().doc_background_pattern_height_unitentry.set_sensitive(true);
+                },
+                PatternStyle::GridMajorLines => {
+                    settings_panel.imp().doc_background_pattern_width_unitentry.set_sensitive(true);
+                    settings_panel.imp().doc_background_pattern_height_unitentry.set_sensitive(true);
+                },
+                PatternStyle::HexagonGrid => {
+                    settings_panel.imp().doc_background_pattern_width_unitentry.set_sensitive(false);
+                    settings_panel.imp().doc_background_pattern_height_unitentry.set_sensitive(true);
+                },
             }
+            settings_panel.imp().doc_background_major_line_interval_row.set_sensitive(pattern == PatternStyle::GridMajorLines);
 
             canvas.background_regenerate_pattern();
             canvas.update_rendering_current_viewport();
@@ -699,6 +743,26 @@ impl RnSettingsPanel {
                         canvas.update_rendering_current_viewport();
                 }),
             );
+
+        imp.doc_background_major_line_interval_spinbutton
+            .get()
+            .connect_value_changed(clone!(@weak appwindow => move |spinbutton| {
+                let canvas = appwindow.active_tab().canvas();
+
+                canvas.engine_mut().document.background.major_line_interval = spinbutton.value().round() as u32;
+                canvas.background_regenerate_pattern();
+                canvas.update_rendering_current_viewport();
+            }));
+    }
+
+    fn setup_import(&self, appwindow: &RnAppWindow) {
+        let imp = self.imp();
+
+        imp.import_pdf_pages_type_row.get().connect_selected_item_notify(clone!(@weak self as settings_panel, @weak appwindow => move |_| {
+            let pages_type = settings_panel.pdf_import_pages_type();
+
+            appwindow.active_tab().canvas().engine_mut().import_prefs.pdf_import_prefs.pages_type = pages_type;
+        }));
     }
 
     fn setup_shortcuts(&self, appwindow: &RnAppWindow) {
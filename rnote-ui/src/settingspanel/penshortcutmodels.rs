@@ -5,27 +5,129 @@ use gtk4::{
     StringList,
 };
 use gtk4::{Align, StringObject};
+use num_traits::ToPrimitive;
+use rnote_engine::pens::shortcuts::{ShortcutAction, ShortcutMode};
 use rnote_engine::pens::PenStyle;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
+/// The pen styles that can be changed to, in the order of their `u32` representation.
+const PEN_STYLES: [PenStyle; 6] = [
+    PenStyle::Brush,
+    PenStyle::Shaper,
+    PenStyle::Typewriter,
+    PenStyle::Eraser,
+    PenStyle::Selector,
+    PenStyle::Tools,
+];
+
+/// The shortcut actions besides changing the pen style and their list item names.
+///
+/// They are listed after the pen styles.
+const OTHER_ACTIONS: [(ShortcutAction, &str); 11] = [
+    (ShortcutAction::Undo, "undo"),
+    (ShortcutAction::Redo, "redo"),
+    (ShortcutAction::CycleBrushColor, "cycle-brush-color"),
+    (ShortcutAction::IncreaseStrokeWidth, "increase-stroke-width"),
+    (ShortcutAction::DecreaseStrokeWidth, "decrease-stroke-width"),
+    (ShortcutAction::ToggleEraserStyle, "toggle-eraser-style"),
+    (ShortcutAction::ZoomIn, "zoom-in"),
+    (ShortcutAction::ZoomOut, "zoom-out"),
+    (ShortcutAction::ZoomFitWidth, "zoom-fit-width"),
+    (ShortcutAction::NextPage, "next-page"),
+    (ShortcutAction::PreviousPage, "previous-page"),
+];
+
+/// The shortcut action at the given position of the list model.
+///
+/// `mode` is used when the position is a pen style.
+pub(crate) fn shortcut_action_at_position(
+    position: u32,
+    mode: ShortcutMode,
+) -> Option<ShortcutAction> {
+    if let Ok(style) = PenStyle::try_from(position) {
+        return Some(ShortcutAction::ChangePenStyle { style, mode });
+    }
+    let other_position = position.checked_sub(PEN_STYLES.len() as u32)?;
+
+    OTHER_ACTIONS
+        .get(other_position as usize)
+        .map(|(action, _)| *action)
+}
+
+/// The position of the shortcut action in the list model.
+pub(crate) fn shortcut_action_position(action: ShortcutAction) -> u32 {
+    match action {
+        ShortcutAction::ChangePenStyle { style, .. } => style.to_u32().unwrap(),
+        action => {
+            let other_position = OTHER_ACTIONS
+                .iter()
+                .position(|(other_action, _)| *other_action == action)
+                .unwrap();
+
+            (PEN_STYLES.len() + other_position) as u32
+        }
+    }
+}
+
+/// The label and icon name for the list item name.
+fn list_item_label_icon_name(name: &str) -> (String, String) {
+    if let Ok(pen_style) = PenStyle::from_str(name) {
+        let label = match pen_style {
+            PenStyle::Brush => gettext("Brush"),
+            PenStyle::Shaper => gettext("Shaper"),
+            PenStyle::Typewriter => gettext("Typewriter"),
+            PenStyle::Eraser => gettext("Eraser"),
+            PenStyle::Selector => gettext("Selector"),
+            PenStyle::Tools => gettext("Tools"),
+        };
+        return (label, pen_style.icon_name());
+    }
+
+    let action = OTHER_ACTIONS
+        .iter()
+        .find(|(_, other_name)| *other_name == name)
+        .map(|(action, _)| *action)
+        .unwrap();
+    let (label, icon_name) = match action {
+        ShortcutAction::Undo => (gettext("Undo"), "edit-undo-symbolic"),
+        ShortcutAction::Redo => (gettext("Redo"), "edit-redo-symbolic"),
+        ShortcutAction::CycleBrushColor => (gettext("Next Brush Color"), "stroke-color-symbolic"),
+        ShortcutAction::IncreaseStrokeWidth => (gettext("Increase Stroke Width"), "plus-symbolic"),
+        ShortcutAction::DecreaseStrokeWidth => (gettext("Decrease Stroke Width"), "minus-symbolic"),
+        ShortcutAction::ToggleEraserStyle => (
+            gettext("Toggle Eraser Style"),
+            "pen-eraser-split-colliding-strokes-symbolic",
+        ),
+        ShortcutAction::ZoomIn => (gettext("Zoom In"), "zoom-in-symbolic"),
+        ShortcutAction::ZoomOut => (gettext("Zoom Out"), "zoom-out-symbolic"),
+        ShortcutAction::ZoomFitWidth => (gettext("Zoom to Fit Width"), "zoom-fit-width-symbolic"),
+        ShortcutAction::NextPage => (gettext("Next Page"), "down-symbolic"),
+        ShortcutAction::PreviousPage => (gettext("Previous Page"), "up-symbolic"),
+        ShortcutAction::ChangePenStyle { .. } => unreachable!(),
+    };
+
+    (label, String::from(icon_name))
+}
+
 #[derive(Debug, Clone)]
-pub(crate) struct ChangePenStyleListModel(StringList);
+pub(crate) struct ShortcutActionListModel(StringList);
 
-impl Default for ChangePenStyleListModel {
+impl Default for ShortcutActionListModel {
     fn default() -> Self {
-        Self(StringList::new(&[
-            &PenStyle::Brush.to_string(),
-            &PenStyle::Shaper.to_string(),
-            &PenStyle::Typewriter.to_string(),
-            &PenStyle::Eraser.to_string(),
-            &PenStyle::Selector.to_string(),
-            &PenStyle::Tools.to_string(),
-        ]))
+        let pen_style_names = PEN_STYLES.map(|style| style.to_string());
+
+        Self(StringList::new(
+            &pen_style_names
+                .iter()
+                .map(|name| name.as_str())
+                .chain(OTHER_ACTIONS.iter().map(|(_, name)| *name))
+                .collect::<Vec<&str>>(),
+        ))
     }
 }
 
-impl Deref for ChangePenStyleListModel {
+impl Deref for ShortcutActionListModel {
     type Target = StringList;
 
     fn deref(&self) -> &Self::Target {
@@ -33,16 +135,16 @@ impl Deref for ChangePenStyleListModel {
     }
 }
 
-impl DerefMut for ChangePenStyleListModel {
+impl DerefMut for ShortcutActionListModel {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct ChangePenStyleListFactory(SignalListItemFactory);
+pub(crate) struct ShortcutActionListFactory(SignalListItemFactory);
 
-impl Default for ChangePenStyleListFactory {
+impl Default for ShortcutActionListFactory {
     fn default() -> Self {
         let factory = SignalListItemFactory::new();
         factory.connect_setup(move |_factory, list_item| {
@@ -63,28 +165,19 @@ impl Default for ChangePenStyleListFactory {
         });
         factory.connect_bind(move |_factory, list_item| {
             let list_item = list_item.downcast_ref::<ListItem>().unwrap();
-            let pen_style = PenStyle::from_str(
+            let (label, icon_name) = list_item_label_icon_name(
                 &list_item
                     .item()
                     .unwrap()
                     .downcast::<StringObject>()
                     .unwrap()
                     .string(),
-            )
-            .unwrap();
+            );
             let item_box = list_item.child().unwrap().downcast::<gtk4::Box>().unwrap();
 
             let mut child = item_box.first_child();
             while let Some(ref next_child) = child {
                 if next_child.type_() == Label::static_type() {
-                    let label = match pen_style {
-                        PenStyle::Brush => gettext("Brush"),
-                        PenStyle::Shaper => gettext("Shaper"),
-                        PenStyle::Typewriter => gettext("Typewriter"),
-                        PenStyle::Eraser => gettext("Eraser"),
-                        PenStyle::Selector => gettext("Selector"),
-                        PenStyle::Tools => gettext("Tools"),
-                    };
                     next_child
                         .downcast_ref::<Label>()
                         .unwrap()
@@ -93,7 +186,7 @@ impl Default for ChangePenStyleListFactory {
                     next_child
                         .downcast_ref::<Image>()
                         .unwrap()
-                        .set_icon_name(Some(pen_style.icon_name().as_str()));
+                        .set_icon_name(Some(icon_name.as_str()));
                 }
 
                 child = next_child.next_sibling();
@@ -103,7 +196,7 @@ impl Default for ChangePenStyleListFactory {
     }
 }
 
-impl Deref for ChangePenStyleListFactory {
+impl Deref for ShortcutActionListFactory {
     type Target = SignalListItemFactory;
 
     fn deref(&self) -> &Self::Target {
@@ -111,16 +204,16 @@ impl Deref for ChangePenStyleListFactory {
     }
 }
 
-impl DerefMut for ChangePenStyleListFactory {
+impl DerefMut for ShortcutActionListFactory {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
 }
 
 #[derive(Debug, Clone)]
-pub(crate) struct ChangePenStyleIconFactory(SignalListItemFactory);
+pub(crate) struct ShortcutActionIconFactory(SignalListItemFactory);
 
-impl Default for ChangePenStyleIconFactory {
+impl Default for ShortcutActionIconFactory {
     fn default() -> Self {
         let factory = SignalListItemFactory::new();
         factory.connect_setup(move |_factory, list_item| {
@@ -131,26 +224,23 @@ impl Default for ChangePenStyleIconFactory {
         });
         factory.connect_bind(move |_factory, list_item| {
             let list_item = list_item.downcast_ref::<ListItem>().unwrap();
-            let pen_style = PenStyle::from_str(
+            let (label, icon_name) = list_item_label_icon_name(
                 &list_item
                     .item()
                     .unwrap()
                     .downcast::<StringObject>()
                     .unwrap()
                     .string(),
-            )
-            .unwrap();
+            );
             let image = list_item.child().unwrap().downcast::<Image>().unwrap();
-            image
-                .downcast_ref::<Image>()
-                .unwrap()
-                .set_icon_name(Some(pen_style.icon_name().as_str()));
+            image.set_icon_name(Some(icon_name.as_str()));
+            image.set_tooltip_text(Some(&label));
         });
         Self(factory)
     }
 }
 
-impl Deref for ChangePenStyleIconFactory {
+impl Deref for ShortcutActionIconFactory {
     type Target = SignalListItemFactory;
 
     fn deref(&self) -> &Self::Target {
@@ -158,7 +248,7 @@ impl Deref for ChangePenStyleIconFactory {
     }
 }
 
-impl DerefMut for ChangePenStyleIconFactory {
+impl DerefMut for ShortcutActionIconFactory {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.0
     }
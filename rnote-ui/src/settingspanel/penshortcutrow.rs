@@ -1,6 +1,7 @@
 // Imports
 use super::penshortcutmodels::{
-    ChangePenStyleIconFactory, ChangePenStyleListFactory, ChangePenStyleListModel,
+    shortcut_action_at_position, shortcut_action_position, ShortcutActionIconFactory,
+    ShortcutActionListFactory, ShortcutActionListModel,
 };
 use adw::{prelude::*, subclass::prelude::*};
 use gtk4::{glib, glib::clone, glib::subclass::*, CompositeTemplate, DropDown};
@@ -18,7 +19,7 @@ mod imp {
     #[template(resource = "/com/github/flxzt/rnote/ui/penshortcutrow.ui")]
     pub(crate) struct RnPenShortcutRow {
         pub(crate) action: RefCell<ShortcutAction>,
-        pub(crate) changepenstyle_model: ChangePenStyleListModel,
+        pub(crate) action_model: ShortcutActionListModel,
 
         #[template_child]
         pub(crate) mode_dropdown: TemplateChild<DropDown>,
@@ -31,7 +32,7 @@ mod imp {
                     style: PenStyle::Eraser,
                     mode: ShortcutMode::Temporary,
                 }),
-                changepenstyle_model: ChangePenStyleListModel::default(),
+                action_model: ShortcutActionListModel::default(),
 
                 mode_dropdown: TemplateChild::default(),
             }
@@ -59,30 +60,31 @@ mod imp {
             self.parent_constructed();
             let obj = self.obj();
 
-            let list_factory = ChangePenStyleListFactory::default();
-            let icon_factory = ChangePenStyleIconFactory::default();
+            let list_factory = ShortcutActionListFactory::default();
+            let icon_factory = ShortcutActionIconFactory::default();
 
-            obj.set_model(Some(&*self.changepenstyle_model));
+            obj.set_model(Some(&*self.action_model));
             obj.set_list_factory(Some(&*list_factory));
             obj.set_factory(Some(&*icon_factory));
 
             obj.connect_selected_item_notify(move |row| {
-                let new_pen_style = row.pen_style();
-
-                match &mut *row.imp().action.borrow_mut() {
-                    ShortcutAction::ChangePenStyle { style, .. } => {
-                        *style = new_pen_style;
-                    }
-                }
+                // keep the mode of the current action when switching between pen styles
+                let mode = match row.action() {
+                    ShortcutAction::ChangePenStyle { mode, .. } => mode,
+                    _ => row.shortcut_mode(),
+                };
+                let Some(new_action) = shortcut_action_at_position(row.selected(), mode) else {
+                    return;
+                };
+
+                *row.imp().action.borrow_mut() = new_action;
                 row.emit_by_name::<()>("action-changed", &[]);
             });
 
             self.mode_dropdown.get().connect_selected_notify(
                 clone!(@weak obj as penshortcutrow => move |_| {
-                    match &mut *penshortcutrow.imp().action.borrow_mut() {
-                        ShortcutAction::ChangePenStyle { mode, .. } => {
-                            *mode = penshortcutrow.shortcut_mode();
-                        }
+                    if let ShortcutAction::ChangePenStyle { mode, .. } = &mut *penshortcutrow.imp().action.borrow_mut() {
+                        *mode = penshortcutrow.shortcut_mode();
                     }
                     penshortcutrow.emit_by_name::<()>("action-changed", &[]);
                 }),
@@ -142,14 +144,6 @@ impl RnPenShortcutRow {
         self.emit_by_name::<()>("action-changed", &[]);
     }
 
-    pub(crate) fn pen_style(&self) -> PenStyle {
-        PenStyle::try_from(self.selected()).unwrap()
-    }
-
-    pub(crate) fn set_pen_style(&self, style: PenStyle) {
-        self.set_selected(style.to_u32().unwrap())
-    }
-
     pub(crate) fn shortcut_mode(&self) -> ShortcutMode {
         ShortcutMode::try_from(self.imp().mode_dropdown.selected()).unwrap()
     }
@@ -161,11 +155,15 @@ impl RnPenShortcutRow {
     }
 
     fn update_ui(&self) {
-        match self.action() {
-            ShortcutAction::ChangePenStyle { style, mode } => {
-                self.set_pen_style(style);
-                self.set_shortcut_mode(mode);
-            }
+        let action = self.action();
+        self.set_selected(shortcut_action_position(action));
+
+        // the mode only applies to changing the pen style
+        if let ShortcutAction::ChangePenStyle { mode, .. } = action {
+            self.set_shortcut_mode(mode);
+            self.imp().mode_dropdown.set_sensitive(true);
+        } else {
+            self.imp().mode_dropdown.set_sensitive(false);
         }
     }
 }
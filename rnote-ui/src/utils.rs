@@ -18,6 +18,8 @@ pub(crate) enum FileType {
     BitmapImageFile,
     XoppFile,
     PdfFile,
+    InkmlFile,
+    ExcalidrawFile,
     Unsupported,
 }
 
@@ -41,12 +43,18 @@ impl FileType {
                             "image/png" | "image/jpeg" => {
                                 return Self::BitmapImageFile;
                             }
-                            "application/x-xopp" => {
+                            "application/x-xopp" | "application/x-xoj" => {
                                 return Self::XoppFile;
                             }
                             "application/pdf" => {
                                 return Self::PdfFile;
                             }
+                            "application/inkml+xml" => {
+                                return Self::InkmlFile;
+                            }
+                            "application/vnd.excalidraw+json" => {
+                                return Self::ExcalidrawFile;
+                            }
                             _ => {}
                         }
                     }
@@ -76,12 +84,18 @@ impl FileType {
                     "jpg" | "jpeg" | "png" => {
                         return Self::BitmapImageFile;
                     }
-                    "xopp" => {
+                    "xopp" | "xoj" => {
                         return Self::XoppFile;
                     }
                     "pdf" => {
                         return Self::PdfFile;
                     }
+                    "inkml" => {
+                        return Self::InkmlFile;
+                    }
+                    "excalidraw" => {
+                        return Self::ExcalidrawFile;
+                    }
                     _ => {}
                 }
             }
@@ -323,11 +323,12 @@ impl RnWorkspaceBrowser {
         filefilter.add_mime_type("image/svg+xml");
         filefilter.add_mime_type("image/png");
         filefilter.add_mime_type("image/jpeg");
-        filefilter.add_mime_type("application/x-xopp");
+        filefilter.add_mime_type("application/x-xoj");
         filefilter.add_mime_type("inode/directory");
         filefilter.add_suffix("rnote");
         filefilter.add_suffix("pdf");
         filefilter.add_suffix("xopp");
+        filefilter.add_suffix("xoj");
         filefilter.add_suffix("svg");
         filefilter.add_suffix("png");
         filefilter.add_suffix("jpg");